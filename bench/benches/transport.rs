@@ -0,0 +1,35 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Calls over new connections compared to calls over kept-alive ones.
+
+extern crate bench;
+#[macro_use]
+extern crate criterion;
+extern crate dashcore_rpc;
+
+use criterion::{BenchmarkId, Criterion};
+use dashcore_rpc::{Client, HttpTransport, RpcApi, DEFAULT_POOL_SIZE};
+
+fn transport(c: &mut Criterion) {
+    let url = bench::mock_server("1000");
+    let mut group = c.benchmark_group("getblockcount");
+    for &pool_size in &[0, DEFAULT_POOL_SIZE] {
+        let transport = HttpTransport::new(&url, None, None).unwrap().with_pool_size(pool_size);
+        let client = Client::from_transport(transport);
+        group.bench_with_input(BenchmarkId::new("pool_size", pool_size), &client, |b, client| {
+            b.iter(|| client.get_block_count().unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, transport);
+criterion_main!(benches);
@@ -0,0 +1,71 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Helpers for the benchmarks.
+
+extern crate serde_json;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+/// Start a JSON-RPC server on localhost that answers every request with the
+/// given result, and return its URL.
+///
+/// It keeps connections alive unless the client asks it to close them.
+pub fn mock_server(result: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            thread::spawn(move || serve(stream, result));
+        }
+    });
+    url
+}
+
+/// Answer the requests on a connection until it is closed.
+fn serve(stream: TcpStream, result: &str) {
+    let mut reader = BufReader::new(&stream);
+    loop {
+        let mut length = 0;
+        let mut close = false;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            let lower = line.to_ascii_lowercase();
+            if lower.starts_with("content-length:") {
+                length = lower[15..].trim().parse().unwrap();
+            } else if lower.starts_with("connection:") {
+                close = lower.contains("close");
+            } else if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let response = format!(r#"{{"result":{},"error":null,"id":{}}}"#, result, request["id"]);
+        let mut stream = &stream;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            response.len(),
+            response
+        )
+        .unwrap();
+        if close {
+            return;
+        }
+    }
+}
@@ -1,5 +1,7 @@
 extern crate dashcore_rpc;
 
+use dashcore_rpc::dashcore::hashes::hex::FromHex;
+use dashcore_rpc::dashcore::BlockHash;
 use dashcore_rpc::{Auth, Client, RpcApi};
 
 fn main() {
@@ -31,15 +33,15 @@ fn main() {
 
 
     // Get masternode list
-    let mn_list = rpc.get_masternode_list(Some("json"), None).unwrap();
+    let mn_list = rpc.get_masternode_list_json(None).unwrap();
     println!("\n\nMasternode List: \n{:?}", mn_list);
 
     // Get masternode outputs
-    let mn_outputs = rpc.get_masternode_outputs().unwrap();
+    let mn_outputs = rpc.masternode_outputs().unwrap();
     println!("\n\nMasternode Outputs: \n{:?}", mn_outputs);
 
     // Get masternode payments 
-    let mn_payments = rpc.get_masternode_payments(None, None).unwrap();
+    let mn_payments = rpc.masternode_payments(None, None).unwrap();
     println!("\n\nMasternode Payments: \n{:?}", mn_payments);
 
     // Get masternode status
@@ -91,7 +93,10 @@ fn main() {
     println!("\nQuorum memberof: \n{:?}", quorum_memberof);
 
     // Get Quorum rotationinfo
-    let quorum_rotationinfo = rpc.get_quorum_rotationinfo("0000012197b7ca6360af3756c6a49c217dbbdf8b595fd55e0fcef7ffcd546044", None, None).unwrap();
+    let rotation_block_hash =
+        BlockHash::from_hex("0000012197b7ca6360af3756c6a49c217dbbdf8b595fd55e0fcef7ffcd546044")
+            .unwrap();
+    let quorum_rotationinfo = rpc.get_quorum_rotationinfo(&rotation_block_hash, None, &[]).unwrap();
     println!("\nQuorum rotationinfo: \n{:?}", quorum_rotationinfo);
 
     // Get Quorum selectquorum
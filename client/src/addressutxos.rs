@@ -0,0 +1,240 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Following the unspent outputs of external addresses with the address
+//! index.
+
+use std::collections::BTreeMap;
+
+use dashcore::{Address, Amount, OutPoint};
+
+use client::{Result, RpcApi};
+
+/// An unspent output of an [AddressUtxoSet].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AddressUtxoEntry {
+    pub address: Address,
+    pub amount: Amount,
+    /// The height of the block of the transaction, `None` while it is in the
+    /// mempool.
+    pub height: Option<u32>,
+}
+
+/// A change of an [AddressUtxoSet] found by [AddressUtxoSet::sync].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UtxoChange {
+    /// A new output paying an address, mined or in the mempool.
+    Added(OutPoint, AddressUtxoEntry),
+    /// An output that was in the mempool was mined.
+    Confirmed(OutPoint, AddressUtxoEntry),
+    /// An output that was spent, by a mined or a mempool transaction, or
+    /// whose transaction left the chain or the mempool.
+    Removed(OutPoint, AddressUtxoEntry),
+}
+
+/// The unspent outputs of addresses that are not in the wallet of the node,
+/// including those of mempool transactions.
+///
+/// The outputs are fetched with `getaddressutxos` and `getaddressmempool`,
+/// so tracking a balance needs neither importing the addresses nor
+/// rescanning. The node needs the address index, enabled with
+/// `-addressindex`. Outputs spent by mempool transactions are left out.
+#[derive(Clone, Debug, Default)]
+pub struct AddressUtxoSet {
+    addresses: Vec<Address>,
+    utxos: BTreeMap<OutPoint, AddressUtxoEntry>,
+}
+
+impl AddressUtxoSet {
+    pub fn new(addresses: Vec<Address>) -> AddressUtxoSet {
+        AddressUtxoSet {
+            addresses,
+            utxos: BTreeMap::new(),
+        }
+    }
+
+    /// Add an address, its outputs are fetched by the next sync.
+    pub fn add_address(&mut self, address: Address) {
+        if !self.addresses.contains(&address) {
+            self.addresses.push(address);
+        }
+    }
+
+    pub fn addresses(&self) -> &[Address] {
+        &self.addresses
+    }
+
+    /// The unspent outputs as of the last sync.
+    pub fn utxos(&self) -> &BTreeMap<OutPoint, AddressUtxoEntry> {
+        &self.utxos
+    }
+
+    /// The sum of the unspent outputs, including those in the mempool.
+    pub fn balance(&self) -> Amount {
+        self.utxos.values().fold(Amount::ZERO, |sum, u| sum + u.amount)
+    }
+
+    /// The sum of the mined unspent outputs.
+    pub fn confirmed_balance(&self) -> Amount {
+        let confirmed = self.utxos.values().filter(|u| u.height.is_some());
+        confirmed.fold(Amount::ZERO, |sum, u| sum + u.amount)
+    }
+
+    /// Fetch the unspent outputs of the addresses again and return how they
+    /// changed since the last sync.
+    pub fn sync<C: RpcApi>(&mut self, rpc: &C) -> Result<Vec<UtxoChange>> {
+        let addresses: Vec<&Address> = self.addresses.iter().collect();
+        let mut utxos = BTreeMap::new();
+        if !addresses.is_empty() {
+            for utxo in rpc.get_address_utxos(&addresses)? {
+                let entry = AddressUtxoEntry {
+                    address: utxo.address,
+                    amount: utxo.satoshis,
+                    height: Some(utxo.height),
+                };
+                utxos.insert(OutPoint::new(utxo.txid, utxo.output_index), entry);
+            }
+            let mempool = rpc.get_address_mempool(&addresses)?;
+            for delta in &mempool {
+                if delta.satoshis.is_positive() {
+                    let entry = AddressUtxoEntry {
+                        address: delta.address.clone(),
+                        amount: Amount::from_sat(delta.satoshis.as_sat() as u64),
+                        height: None,
+                    };
+                    utxos.insert(OutPoint::new(delta.txid, delta.index), entry);
+                }
+            }
+            // Spends are applied last, as a mempool transaction may spend the
+            // output of another one.
+            for delta in &mempool {
+                if let (Some(txid), Some(vout)) = (delta.prevtxid, delta.prevout) {
+                    utxos.remove(&OutPoint::new(txid, vout));
+                }
+            }
+        }
+
+        let mut changes = Vec::new();
+        for (outpoint, entry) in &self.utxos {
+            if !utxos.contains_key(outpoint) {
+                changes.push(UtxoChange::Removed(*outpoint, entry.clone()));
+            }
+        }
+        for (outpoint, entry) in &utxos {
+            match self.utxos.get(outpoint) {
+                None => changes.push(UtxoChange::Added(*outpoint, entry.clone())),
+                Some(old) if old.height.is_none() && entry.height.is_some() => {
+                    changes.push(UtxoChange::Confirmed(*outpoint, entry.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        self.utxos = utxos;
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use dashcore::Txid;
+    use serde_json;
+    use std::cell::RefCell;
+    use testutil::{address, MockRpc};
+
+    /// A node with the address index answering with the queued results.
+    struct MockNode {
+        utxos: RefCell<Vec<String>>,
+        mempool: RefCell<Vec<String>>,
+    }
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, _args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "getaddressutxos" => self.utxos.borrow_mut().remove(0),
+                "getaddressmempool" => self.mempool.borrow_mut().remove(0),
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    fn utxo(tx: u8, satoshis: u64) -> String {
+        format!(
+            r#"{{"address": "{}", "txid": "{}", "outputIndex": 0, "script": "51",
+                 "satoshis": {}, "height": 10}}"#,
+            address(),
+            Txid::hash(&[tx]),
+            satoshis
+        )
+    }
+
+    fn delta(tx: u8, satoshis: i64, spent: Option<u8>) -> String {
+        let prevout = match spent {
+            Some(spent) => format!(r#", "prevtxid": "{}", "prevout": 0"#, Txid::hash(&[spent])),
+            None => String::new(),
+        };
+        format!(
+            r#"{{"address": "{}", "txid": "{}", "index": 0, "satoshis": {},
+                 "timestamp": 0{}}}"#,
+            address(),
+            Txid::hash(&[tx]),
+            satoshis,
+            prevout
+        )
+    }
+
+    #[test]
+    fn test_sync() {
+        let node = MockNode {
+            utxos: RefCell::new(vec![
+                format!("[{}]", utxo(1, 1000)),
+                format!("[{}, {}]", utxo(1, 1000), utxo(2, 2000)),
+            ]),
+            mempool: RefCell::new(vec![
+                format!("[{}]", delta(2, 2000, None)),
+                format!("[{}, {}]", delta(3, 3000, None), delta(3, -1000, Some(1))),
+            ]),
+        };
+        let mut set = AddressUtxoSet::new(vec![address()]);
+        set.add_address(address());
+        assert_eq!(set.addresses().len(), 1);
+
+        let changes = set.sync(&node).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|c| match *c {
+            UtxoChange::Added(..) => true,
+            _ => false,
+        }));
+        assert_eq!(set.balance(), Amount::from_sat(3000));
+        assert_eq!(set.confirmed_balance(), Amount::from_sat(1000));
+
+        // The mempool output got mined, and a new mempool transaction spends
+        // the first output.
+        let mut changes = set.sync(&node).unwrap();
+        changes.sort_by_key(|c| format!("{:?}", c));
+        let outpoint = |tx: u8| OutPoint::new(Txid::hash(&[tx]), 0);
+        let entry = |amount: u64, height: Option<u32>| AddressUtxoEntry {
+            address: address(),
+            amount: Amount::from_sat(amount),
+            height,
+        };
+        let mut expected = vec![
+            UtxoChange::Removed(outpoint(1), entry(1000, Some(10))),
+            UtxoChange::Confirmed(outpoint(2), entry(2000, Some(10))),
+            UtxoChange::Added(outpoint(3), entry(3000, None)),
+        ];
+        expected.sort_by_key(|c| format!("{:?}", c));
+        assert_eq!(changes, expected);
+        assert_eq!(set.balance(), Amount::from_sat(5000));
+        assert_eq!(set.confirmed_balance(), Amount::from_sat(2000));
+    }
+}
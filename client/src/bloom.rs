@@ -0,0 +1,275 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! BIP37 bloom filters, as used by the `getmerkleblocks` RPC.
+
+use std::cmp;
+use std::f64::consts::LN_2;
+
+use dashcore;
+use dashcore::hashes::hex::ToHex;
+use dashcore::util::address::Payload;
+use dashcore::{Address, OutPoint};
+
+/// The maximum size of a filter in bytes, see `MAX_BLOOM_FILTER_SIZE` in Dash Core.
+pub const MAX_BLOOM_FILTER_SIZE: usize = 36000;
+/// The maximum number of hash functions, see `MAX_HASH_FUNCS` in Dash Core.
+pub const MAX_HASH_FUNCS: u32 = 50;
+
+/// Controls how the node updates the filter when it finds a match.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum BloomFlags {
+    /// Never update the filter.
+    None = 0,
+    /// Add the outpoints of all matched outputs to the filter.
+    All = 1,
+    /// Only add outpoints of matched pay-to-pubkey and multisig outputs.
+    PubkeyOnly = 2,
+}
+
+/// A BIP37 bloom filter.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BloomFilter {
+    data: Vec<u8>,
+    hash_funcs: u32,
+    tweak: u32,
+    flags: BloomFlags,
+}
+
+impl BloomFilter {
+    /// Create an empty filter sized for `elements` items with the given
+    /// false positive rate.
+    pub fn new(elements: usize, fp_rate: f64, tweak: u32, flags: BloomFlags) -> BloomFilter {
+        let elements = cmp::max(elements, 1) as f64;
+        let bits = (-1.0 / (LN_2 * LN_2) * elements * fp_rate.ln()) as usize;
+        let size = cmp::max(cmp::min(bits, MAX_BLOOM_FILTER_SIZE * 8) / 8, 1);
+        let hash_funcs = (size as f64 * 8.0 / elements * LN_2) as u32;
+        BloomFilter {
+            data: vec![0; size],
+            hash_funcs: cmp::min(hash_funcs, MAX_HASH_FUNCS),
+            tweak,
+            flags,
+        }
+    }
+
+    /// Start building a filter from addresses and outpoints.
+    pub fn builder() -> BloomFilterBuilder {
+        BloomFilterBuilder::default()
+    }
+
+    fn bit_index(&self, hash_num: u32, data: &[u8]) -> usize {
+        let seed = hash_num.wrapping_mul(0xfba4c795).wrapping_add(self.tweak);
+        murmur3(seed, data) as usize % (self.data.len() * 8)
+    }
+
+    /// Add raw data to the filter.
+    pub fn insert(&mut self, data: &[u8]) {
+        for i in 0..self.hash_funcs {
+            let idx = self.bit_index(i, data);
+            self.data[idx >> 3] |= 1 << (7 & idx);
+        }
+    }
+
+    /// Check whether the filter matches the given data.
+    pub fn contains(&self, data: &[u8]) -> bool {
+        (0..self.hash_funcs).all(|i| {
+            let idx = self.bit_index(i, data);
+            self.data[idx >> 3] & (1 << (7 & idx)) != 0
+        })
+    }
+
+    /// Add an address to the filter, so that outputs paying to it match.
+    pub fn insert_address(&mut self, address: &Address) {
+        self.insert(&address_element(address));
+    }
+
+    /// Add an outpoint to the filter, so that transactions spending it match.
+    pub fn insert_outpoint(&mut self, outpoint: &OutPoint) {
+        self.insert(&dashcore::consensus::encode::serialize(outpoint));
+    }
+
+    /// The filter in its network serialization.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut ret = dashcore::consensus::encode::serialize(&self.data);
+        ret.extend_from_slice(&self.hash_funcs.to_le_bytes());
+        ret.extend_from_slice(&self.tweak.to_le_bytes());
+        ret.push(self.flags as u8);
+        ret
+    }
+
+    /// The hex encoded filter, as expected by `getmerkleblocks`.
+    pub fn to_hex(&self) -> String {
+        self.serialize().to_hex()
+    }
+}
+
+/// A builder for [BloomFilter]s.
+#[derive(Clone, Debug)]
+pub struct BloomFilterBuilder {
+    elements: Vec<Vec<u8>>,
+    fp_rate: f64,
+    tweak: u32,
+    flags: BloomFlags,
+}
+
+impl Default for BloomFilterBuilder {
+    fn default() -> BloomFilterBuilder {
+        BloomFilterBuilder {
+            elements: vec![],
+            fp_rate: 0.001,
+            tweak: 0,
+            flags: BloomFlags::None,
+        }
+    }
+}
+
+impl BloomFilterBuilder {
+    /// Set the false positive rate, defaults to 0.001.
+    pub fn false_positive_rate(mut self, fp_rate: f64) -> Self {
+        self.fp_rate = fp_rate;
+        self
+    }
+
+    /// Set the tweak that is added to the hash function seeds, defaults to 0.
+    pub fn tweak(mut self, tweak: u32) -> Self {
+        self.tweak = tweak;
+        self
+    }
+
+    /// Set the update flags, defaults to [BloomFlags::None].
+    pub fn flags(mut self, flags: BloomFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Match outputs paying to `address`.
+    pub fn add_address(mut self, address: &Address) -> Self {
+        self.elements.push(address_element(address));
+        self
+    }
+
+    /// Match transactions spending `outpoint`.
+    pub fn add_outpoint(mut self, outpoint: &OutPoint) -> Self {
+        self.elements.push(dashcore::consensus::encode::serialize(outpoint));
+        self
+    }
+
+    /// Match arbitrary data, such as a public key or a txid.
+    pub fn add_data(mut self, data: &[u8]) -> Self {
+        self.elements.push(data.to_vec());
+        self
+    }
+
+    pub fn build(self) -> BloomFilter {
+        let mut filter =
+            BloomFilter::new(self.elements.len(), self.fp_rate, self.tweak, self.flags);
+        for element in &self.elements {
+            filter.insert(element);
+        }
+        filter
+    }
+}
+
+/// The data pushed in the output script of an address, which is what the
+/// node matches against.
+fn address_element(address: &Address) -> Vec<u8> {
+    match address.payload {
+        Payload::PubkeyHash(ref hash) => hash[..].to_vec(),
+        Payload::ScriptHash(ref hash) => hash[..].to_vec(),
+        _ => address.script_pubkey().to_bytes(),
+    }
+}
+
+/// MurmurHash3 (x86_32), the hash function used by BIP37.
+fn murmur3(seed: u32, data: &[u8]) -> u32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+
+    let mut h1 = seed;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k1 = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k1 = k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        h1 ^= k1;
+        h1 = h1.rotate_left(13).wrapping_mul(5).wrapping_add(0xe6546b64);
+    }
+
+    let tail = chunks.remainder();
+    let mut k1 = 0u32;
+    if tail.len() >= 3 {
+        k1 ^= (tail[2] as u32) << 16;
+    }
+    if tail.len() >= 2 {
+        k1 ^= (tail[1] as u32) << 8;
+    }
+    if !tail.is_empty() {
+        k1 ^= tail[0] as u32;
+        h1 ^= k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    h1 ^= data.len() as u32;
+    h1 ^= h1 >> 16;
+    h1 = h1.wrapping_mul(0x85ebca6b);
+    h1 ^= h1 >> 13;
+    h1 = h1.wrapping_mul(0xc2b2ae35);
+    h1 ^= h1 >> 16;
+    h1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::hex::FromHex;
+
+    #[test]
+    fn test_murmur3() {
+        let vectors: &[(u32, u32, &str)] = &[
+            (0x00000000, 0x00000000, ""),
+            (0x6a396f08, 0xfba4c795, ""),
+            (0x81f16f39, 0xffffffff, ""),
+            (0x514e28b7, 0x00000000, "00"),
+            (0xea3f0b17, 0xfba4c795, "00"),
+            (0xfd6cf10d, 0x00000000, "ff"),
+            (0x16c6b7ab, 0x00000000, "0011"),
+            (0x8eb51c3d, 0x00000000, "001122"),
+            (0xb4471bf8, 0x00000000, "00112233"),
+            (0xe2301fa8, 0x00000000, "0011223344"),
+        ];
+        for &(expected, seed, data) in vectors {
+            assert_eq!(murmur3(seed, &Vec::<u8>::from_hex(data).unwrap()), expected);
+        }
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let elem1 = Vec::<u8>::from_hex("99108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap();
+        let elem2 = Vec::<u8>::from_hex("b5a2c786d9ef4658287ced5914b37a1b4aa32eee").unwrap();
+        let elem3 = Vec::<u8>::from_hex("b9300670b4c5366e95b2699e8b18bc75e5f729c5").unwrap();
+        let other = Vec::<u8>::from_hex("19108ad8ed9bb6274d3980bab5a85c048f0950c8").unwrap();
+
+        let mut filter = BloomFilter::new(3, 0.01, 0, BloomFlags::All);
+        filter.insert(&elem1);
+        assert!(filter.contains(&elem1));
+        assert!(!filter.contains(&other));
+        filter.insert(&elem2);
+        filter.insert(&elem3);
+        assert_eq!(filter.to_hex(), "03614e9b050000000000000001");
+
+        let built = BloomFilter::builder()
+            .false_positive_rate(0.01)
+            .tweak(2147483649)
+            .flags(BloomFlags::All)
+            .add_data(&elem1)
+            .add_data(&elem2)
+            .add_data(&elem3)
+            .build();
+        assert_eq!(built.to_hex(), "03ce4299050000000100008001");
+    }
+}
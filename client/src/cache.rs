@@ -0,0 +1,278 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Caching of immutable call results.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use dashcore::{BlockHash, BlockHeader, Transaction, Txid};
+use serde;
+use serde_json;
+
+use capabilities::Capabilities;
+use client::{Result, RpcApi};
+
+/// The default number of transactions a [CachedClient] keeps.
+pub const DEFAULT_TX_CACHE_CAPACITY: usize = 1000;
+
+/// The default number of block headers a [CachedClient] keeps.
+pub const DEFAULT_HEADER_CACHE_CAPACITY: usize = 10_000;
+
+/// A least recently used cache.
+struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    /// The keys by the tick they were last used at.
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Lru<K, V> {
+    fn new(capacity: usize) -> Lru<K, V> {
+        Lru {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.1);
+        self.order.insert(self.tick, key.clone());
+        entry.1 = self.tick;
+        Some(entry.0.clone())
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.tick += 1;
+        if let Some((_, tick)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.order.remove(&tick);
+        }
+        self.order.insert(self.tick, key);
+        self.evict();
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        match self.entries.remove(key) {
+            Some((_, tick)) => self.order.remove(&tick).is_some(),
+            None => false,
+        }
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let oldest = *self.order.keys().next().expect("order and entries are in sync");
+            let key = self.order.remove(&oldest).expect("the key was just found");
+            self.entries.remove(&key);
+        }
+    }
+}
+
+/// A client that caches the transactions and block headers it fetches.
+///
+/// Both never change for a given txid or block hash, so they can be cached
+/// for as long as they are useful, like when an indexer resolves the inputs
+/// of many transactions spending the same parents. All other calls are
+/// passed through to the inner client.
+pub struct CachedClient<C> {
+    inner: C,
+    txs: Mutex<Lru<Txid, Transaction>>,
+    headers: Mutex<Lru<BlockHash, BlockHeader>>,
+}
+
+impl<C: RpcApi> CachedClient<C> {
+    pub fn new(inner: C) -> CachedClient<C> {
+        CachedClient {
+            inner,
+            txs: Mutex::new(Lru::new(DEFAULT_TX_CACHE_CAPACITY)),
+            headers: Mutex::new(Lru::new(DEFAULT_HEADER_CACHE_CAPACITY)),
+        }
+    }
+
+    /// Set the number of transactions to keep, zero disables their caching.
+    pub fn with_tx_capacity(self, capacity: usize) -> Self {
+        self.txs.lock().unwrap().set_capacity(capacity);
+        self
+    }
+
+    /// Set the number of block headers to keep, zero disables their caching.
+    pub fn with_header_capacity(self, capacity: usize) -> Self {
+        self.headers.lock().unwrap().set_capacity(capacity);
+        self
+    }
+
+    /// The inner client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// Drop a transaction from the cache, returns whether it was cached.
+    pub fn invalidate_tx(&self, txid: &Txid) -> bool {
+        self.txs.lock().unwrap().remove(txid)
+    }
+
+    /// Drop a block header from the cache, returns whether it was cached.
+    pub fn invalidate_header(&self, hash: &BlockHash) -> bool {
+        self.headers.lock().unwrap().remove(hash)
+    }
+
+    /// Drop all the cached transactions and block headers.
+    pub fn clear(&self) {
+        self.txs.lock().unwrap().clear();
+        self.headers.lock().unwrap().clear();
+    }
+}
+
+impl<C: RpcApi> RpcApi for CachedClient<C> {
+    fn call<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        cmd: &str,
+        args: &[serde_json::Value],
+    ) -> Result<T> {
+        self.inner.call(cmd, args)
+    }
+
+    fn call_batch<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        calls: &[(&str, Vec<serde_json::Value>)],
+    ) -> Result<Vec<Result<T>>> {
+        self.inner.call_batch(calls)
+    }
+
+    fn node_version(&self) -> Result<usize> {
+        self.inner.node_version()
+    }
+
+    fn capabilities(&self) -> Result<Capabilities> {
+        self.inner.capabilities()
+    }
+
+    fn get_block_header(&self, hash: &BlockHash) -> Result<BlockHeader> {
+        if let Some(header) = self.headers.lock().unwrap().get(hash) {
+            return Ok(header);
+        }
+        let header = self.inner.get_block_header(hash)?;
+        self.headers.lock().unwrap().insert(*hash, header);
+        Ok(header)
+    }
+
+    fn get_raw_transaction(
+        &self,
+        txid: &Txid,
+        block_hash: Option<&BlockHash>,
+    ) -> Result<Transaction> {
+        if let Some(tx) = self.txs.lock().unwrap().get(txid) {
+            return Ok(tx);
+        }
+        let tx = self.inner.get_raw_transaction(txid, block_hash)?;
+        self.txs.lock().unwrap().insert(*txid, tx.clone());
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::hex::ToHex;
+    use dashcore::hashes::Hash;
+    use std::cell::Cell;
+    use testutil::MockRpc;
+
+    const TX: &str = "0200000001586bd02815cf5faabfec986a4e50d25dbee089bd2758621e61c5fab06c334af0000000006b483045022100e85425f6d7c589972ee061413bcf08dc8c8e589ce37b217535a42af924f0e4d602205c9ba9cb14ef15513c9d946fa1c4b797883e748e8c32171bdf6166583946e35c012103dae30a4d7870cd87b45dd53e6012f71318fdd059c1c2623b8cc73f8af287bb2dfeffffff021dc4260c010000001976a914f602e88b2b5901d8aab15ebe4a97cf92ec6e03b388ac00e1f505000000001976a914687ffeffe8cf4e4c038da46a9b1d37db385a472d88acfd211500";
+
+    /// A node that counts the transactions and headers it returns.
+    #[derive(Default)]
+    struct CountingNode {
+        txs: Cell<usize>,
+        headers: Cell<usize>,
+    }
+
+    impl MockRpc for CountingNode {
+        fn respond(&self, cmd: &str, _: &[serde_json::Value]) -> Result<String> {
+            let hex = match cmd {
+                "getrawtransaction" => {
+                    self.txs.set(self.txs.get() + 1);
+                    TX.to_owned()
+                }
+                "getblockheader" => {
+                    self.headers.set(self.headers.get() + 1);
+                    [0u8; 80].to_hex()
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(format!(r#""{}""#, hex))
+        }
+    }
+
+    #[test]
+    fn test_lru() {
+        let mut lru = Lru::new(2);
+        lru.insert(1, "a");
+        lru.insert(2, "b");
+        assert_eq!(lru.get(&1), Some("a"));
+        lru.insert(3, "c");
+        assert_eq!(lru.get(&2), None);
+        assert_eq!(lru.get(&1), Some("a"));
+        assert_eq!(lru.get(&3), Some("c"));
+        lru.insert(3, "d");
+        assert_eq!(lru.entries.len(), 2);
+        assert_eq!(lru.order.len(), 2);
+        assert!(lru.remove(&1));
+        assert!(!lru.remove(&1));
+        lru.set_capacity(0);
+        assert_eq!(lru.get(&3), None);
+        lru.insert(4, "e");
+        assert!(lru.order.is_empty());
+    }
+
+    #[test]
+    fn test_cached_client() {
+        let client = CachedClient::new(CountingNode::default()).with_tx_capacity(1);
+        let (txid_a, txid_b) = (Txid::hash(&[0]), Txid::hash(&[1]));
+        let tx = client.get_raw_transaction(&txid_a, None).unwrap();
+        assert_eq!(client.get_raw_transaction(&txid_a, None).unwrap(), tx);
+        assert_eq!(client.inner().txs.get(), 1);
+
+        // The second transaction evicts the first one.
+        client.get_raw_transaction(&txid_b, None).unwrap();
+        client.get_raw_transaction(&txid_a, None).unwrap();
+        assert_eq!(client.inner().txs.get(), 3);
+        assert!(client.invalidate_tx(&txid_a));
+        client.get_raw_transaction(&txid_a, None).unwrap();
+        assert_eq!(client.inner().txs.get(), 4);
+
+        let hash = BlockHash::hash(&[0]);
+        client.get_block_header(&hash).unwrap();
+        client.get_block_header(&hash).unwrap();
+        assert_eq!(client.inner().headers.get(), 1);
+        client.clear();
+        client.get_block_header(&hash).unwrap();
+        assert_eq!(client.into_inner().headers.get(), 2);
+    }
+}
@@ -0,0 +1,74 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Probing which calls a node supports.
+
+use std::collections::HashSet;
+
+/// The calls a node supports, as listed by `help`, see
+/// `RpcApi::capabilities`.
+///
+/// Useful to check for Dash specific calls like `quorum` or `protx` when
+/// the same code also targets other backends.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Capabilities {
+    commands: HashSet<String>,
+}
+
+impl Capabilities {
+    /// Parse the output of `help`, which lists one call per line with its
+    /// arguments, grouped under "== Category ==" headers.
+    pub fn from_help(help: &str) -> Capabilities {
+        let commands = help
+            .lines()
+            .filter(|l| !l.starts_with("=="))
+            .filter_map(|l| l.split_whitespace().next())
+            .map(|c| c.to_owned())
+            .collect();
+        Capabilities {
+            commands,
+        }
+    }
+
+    /// Whether the node supports the given call.
+    pub fn supports(&self, command: &str) -> bool {
+        self.commands.contains(command)
+    }
+
+    /// All the calls the node supports, in no particular order.
+    pub fn commands(&self) -> impl Iterator<Item = &str> {
+        self.commands.iter().map(|c| c.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_help() {
+        let help = "\
+== Blockchain ==
+getbestblockhash
+getblock \"blockhash\" ( verbosity )
+
+== Evo ==
+protx \"command\" ...
+quorum \"command\" ...
+";
+        let caps = Capabilities::from_help(help);
+        assert!(caps.supports("getblock"));
+        assert!(caps.supports("quorum"));
+        assert!(!caps.supports("Blockchain"));
+        assert!(!caps.supports("=="));
+        assert!(!caps.supports("getblockfilter"));
+        assert_eq!(caps.commands().count(), 4);
+    }
+}
@@ -0,0 +1,804 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Chain traversal helpers.
+
+use std::cmp;
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::{Bound, RangeBounds};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use serde_json;
+
+use dashcore::{Block, BlockHash};
+
+use client::{Result, RpcApi};
+use error::Error;
+use json;
+use operations::OperationHandle;
+use raw::RawResult;
+
+/// The default number of blocks a [BlockIter] fetches per batch.
+pub const DEFAULT_BATCH_SIZE: usize = 16;
+
+/// An iterator over the blocks of the main chain, see [RpcApi::iter_blocks].
+///
+/// Blocks are fetched lazily in batches. Every block is checked to build on
+/// the previously returned one, so a chain reorganization during the
+/// iteration results in an [Error::Reorg] for the first height that no
+/// longer connects, after which the iterator stops.
+///
+/// Without an end height, the iterator returns `None` once it caught up with
+/// the chain tip. It can be polled again later to continue with new blocks.
+///
+/// See [BlockIter::operation] to report the progress through an
+/// [OperationHandle] and to cancel the iteration.
+pub struct BlockIter<'a, C: 'a> {
+    rpc: &'a C,
+    next_height: u64,
+    /// The last height to return, inclusive.
+    end_height: Option<u64>,
+    batch_size: usize,
+    buffer: VecDeque<(u64, Block)>,
+    last_hash: Option<BlockHash>,
+    done: bool,
+    operation: Option<OperationHandle>,
+}
+
+impl<'a, C: RpcApi> BlockIter<'a, C> {
+    pub fn new<R: RangeBounds<u64>>(rpc: &'a C, heights: R) -> BlockIter<'a, C> {
+        let next_height = match heights.start_bound() {
+            Bound::Included(&h) => h,
+            Bound::Excluded(&h) => h + 1,
+            Bound::Unbounded => 0,
+        };
+        let (end_height, empty) = match heights.end_bound() {
+            Bound::Included(&h) => (Some(h), h < next_height),
+            Bound::Excluded(&h) => (h.checked_sub(1), h <= next_height),
+            Bound::Unbounded => (None, false),
+        };
+        BlockIter {
+            rpc,
+            next_height,
+            end_height,
+            batch_size: DEFAULT_BATCH_SIZE,
+            buffer: VecDeque::new(),
+            last_hash: None,
+            done: empty,
+            operation: None,
+        }
+    }
+
+    /// Set the number of blocks fetched per batch.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = cmp::max(batch_size, 1);
+        self
+    }
+
+    /// Check that the first block builds on the given block, which is useful
+    /// when resuming from a previously processed block.
+    pub fn after(mut self, prev_hash: BlockHash) -> Self {
+        self.last_hash = Some(prev_hash);
+        self
+    }
+
+    /// Report the progress of the iteration to `operation`.
+    ///
+    /// With an end height, the total is the number of blocks left to return.
+    /// Once cancellation is requested, the iterator returns
+    /// [Error::Cancelled] and stops. The operation is finished when the
+    /// block at the end height was returned, and fails when the iterator
+    /// fails.
+    pub fn operation(mut self, operation: OperationHandle) -> Self {
+        if let Some(end) = self.end_height {
+            let left = (end + 1).saturating_sub(self.next_height) + self.buffer.len() as u64;
+            operation.set_total(if self.done {
+                0
+            } else {
+                left
+            });
+        }
+        self.operation = Some(operation);
+        self
+    }
+
+    /// The hash of the last block returned by the iterator.
+    pub fn last_hash(&self) -> Option<BlockHash> {
+        self.last_hash
+    }
+
+    fn next_block(&mut self) -> Option<Result<(u64, Block)>> {
+        if self.done {
+            return None;
+        }
+
+        if self.buffer.is_empty() {
+            if let Err(e) = self.fill() {
+                self.done = true;
+                return Some(Err(e));
+            }
+            if self.buffer.is_empty() {
+                self.done = self.end_height.map_or(false, |end| self.next_height > end);
+                return None;
+            }
+        }
+
+        let (height, block) = self.buffer.pop_front().unwrap();
+        if let Some(prev) = self.last_hash {
+            if block.header.prev_blockhash != prev {
+                self.done = true;
+                self.buffer.clear();
+                return Some(Err(Error::Reorg(height)));
+            }
+        }
+        self.last_hash = Some(block.block_hash());
+        Some(Ok((height, block)))
+    }
+
+    /// Fetch the next batch of blocks, up to the chain tip.
+    fn fill(&mut self) -> Result<()> {
+        let tip = self.rpc.get_block_count()?;
+        let mut last = cmp::min(tip, self.next_height + self.batch_size as u64 - 1);
+        if let Some(end) = self.end_height {
+            last = cmp::min(last, end);
+        }
+        if self.next_height > last {
+            return Ok(());
+        }
+
+        let blocks = fetch_blocks(self.rpc, self.next_height, last)?;
+        self.buffer.extend((self.next_height..).zip(blocks));
+        self.next_height = last + 1;
+        Ok(())
+    }
+}
+
+/// Fetch the blocks of the main chain from height `first` to `last`,
+/// inclusive, with one batch of `getblockhash` and one of `getblock` calls.
+fn fetch_blocks<C: RpcApi>(rpc: &C, first: u64, last: u64) -> Result<Vec<Block>> {
+    let calls: Vec<_> = (first..=last).map(|h| ("getblockhash", vec![h.into()])).collect();
+    let hashes: Vec<BlockHash> = rpc.call_batch(&calls)?.into_iter().collect::<Result<_>>()?;
+
+    let verbosity = serde_json::to_value(json::BlockVerbosity::Raw)?;
+    let mut calls = Vec::with_capacity(hashes.len());
+    for hash in &hashes {
+        calls.push(("getblock", vec![serde_json::to_value(hash)?, verbosity.clone()]));
+    }
+    let blocks = rpc.call_batch::<RawResult<Block>>(&calls)?;
+    hashes.iter().zip(blocks).map(|(hash, block)| block?.decode("getblock", Some(hash))).collect()
+}
+
+impl<'a, C: RpcApi> Iterator for BlockIter<'a, C> {
+    type Item = Result<(u64, Block)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = if !self.done && is_cancelled(&self.operation) {
+            self.done = true;
+            self.buffer.clear();
+            Some(Err(Error::Cancelled))
+        } else {
+            self.next_block()
+        };
+        track(&mut self.operation, &item, self.done, self.end_height);
+        item
+    }
+}
+
+fn is_cancelled(operation: &Option<OperationHandle>) -> bool {
+    operation.as_ref().map_or(false, |op| op.is_cancelled())
+}
+
+/// Report a result of a block iterator to its operation, if any.
+fn track(
+    operation: &mut Option<OperationHandle>,
+    item: &Option<Result<(u64, Block)>>,
+    done: bool,
+    end_height: Option<u64>,
+) {
+    match *item {
+        Some(Ok((height, _))) => {
+            if let Some(ref op) = *operation {
+                op.add_done(1);
+            }
+            if Some(height) == end_height {
+                if let Some(op) = operation.take() {
+                    op.finish();
+                }
+            }
+        }
+        // Dropping the handle marks the operation as failed or cancelled.
+        Some(Err(_)) => drop(operation.take()),
+        None if done => {
+            if let Some(op) = operation.take() {
+                op.finish();
+            }
+        }
+        None => {}
+    }
+}
+
+/// The progress shared by a [ParallelBlockIter] and its threads.
+#[derive(Debug)]
+struct Progress {
+    /// The first height not claimed by a thread yet.
+    next_claim: u64,
+    /// The first height not returned by the iterator yet.
+    next_height: u64,
+    stopped: bool,
+}
+
+#[derive(Debug)]
+struct SharedProgress {
+    progress: Mutex<Progress>,
+    changed: Condvar,
+}
+
+/// An iterator over the blocks of the main chain that downloads several
+/// batches in parallel, see [get_blocks_parallel].
+///
+/// Like [BlockIter], it returns the blocks in height order and fails with
+/// [Error::Reorg] when a block no longer builds on the previous one, after
+/// which it stops. See [ParallelBlockIter::operation] to report progress and
+/// to cancel the download.
+#[derive(Debug)]
+pub struct ParallelBlockIter {
+    shared: Arc<SharedProgress>,
+    receiver: Receiver<(u64, Result<Vec<Block>>)>,
+    /// The downloaded batches that are not next in line, by first height.
+    pending: BTreeMap<u64, Result<Vec<Block>>>,
+    buffer: VecDeque<(u64, Block)>,
+    next_height: u64,
+    end_height: u64,
+    last_hash: Option<BlockHash>,
+    done: bool,
+    operation: Option<OperationHandle>,
+}
+
+/// Download the blocks of the main chain in the given range of heights with
+/// `concurrency` threads, each fetching a batch of [DEFAULT_BATCH_SIZE]
+/// blocks at a time.
+///
+/// Compared to [RpcApi::iter_blocks], which waits for each batch before
+/// requesting the next one, this keeps up to `concurrency` batches in flight,
+/// which is much faster for an initial indexing. The threads download at
+/// most a few batches ahead of the block returned last, so a slow consumer
+/// doesn't buffer the whole range. Without an end height, the range ends at
+/// the chain tip when this is called.
+///
+/// Dropping the iterator stops the threads after their current batch.
+pub fn get_blocks_parallel<C, R>(
+    rpc: Arc<C>,
+    heights: R,
+    concurrency: usize,
+) -> Result<ParallelBlockIter>
+where
+    C: RpcApi + Send + Sync + 'static,
+    R: RangeBounds<u64>,
+{
+    let next_height = match heights.start_bound() {
+        Bound::Included(&h) => h,
+        Bound::Excluded(&h) => h + 1,
+        Bound::Unbounded => 0,
+    };
+    let (end_height, empty) = match heights.end_bound() {
+        Bound::Included(&h) => (h, h < next_height),
+        Bound::Excluded(&h) => (h.saturating_sub(1), h <= next_height),
+        Bound::Unbounded => {
+            let tip = rpc.get_block_count()?;
+            (tip, tip < next_height)
+        }
+    };
+
+    let concurrency = cmp::max(concurrency, 1);
+    let shared = Arc::new(SharedProgress {
+        progress: Mutex::new(Progress {
+            next_claim: next_height,
+            next_height,
+            stopped: empty,
+        }),
+        changed: Condvar::new(),
+    });
+    let (sender, receiver) = mpsc::sync_channel(concurrency);
+    let window = 2 * concurrency as u64 * DEFAULT_BATCH_SIZE as u64;
+    for _ in 0..concurrency {
+        let (rpc, shared, sender) = (rpc.clone(), shared.clone(), sender.clone());
+        thread::spawn(move || download_batches(&*rpc, &shared, &sender, end_height, window));
+    }
+    Ok(ParallelBlockIter {
+        shared,
+        receiver,
+        pending: BTreeMap::new(),
+        buffer: VecDeque::new(),
+        next_height,
+        end_height,
+        last_hash: None,
+        done: empty,
+        operation: None,
+    })
+}
+
+/// Claim and download batches until all are claimed or the iterator is
+/// dropped, without getting more than `window` heights ahead of it.
+fn download_batches<C: RpcApi>(
+    rpc: &C,
+    shared: &SharedProgress,
+    sender: &SyncSender<(u64, Result<Vec<Block>>)>,
+    end_height: u64,
+    window: u64,
+) {
+    loop {
+        let first = {
+            let mut progress = shared.progress.lock().unwrap();
+            loop {
+                if progress.stopped || progress.next_claim > end_height {
+                    return;
+                }
+                if progress.next_claim < progress.next_height + window {
+                    break;
+                }
+                progress = shared.changed.wait(progress).unwrap();
+            }
+            let first = progress.next_claim;
+            progress.next_claim = first + DEFAULT_BATCH_SIZE as u64;
+            first
+        };
+        let last = cmp::min(end_height, first + DEFAULT_BATCH_SIZE as u64 - 1);
+        if sender.send((first, fetch_blocks(rpc, first, last))).is_err() {
+            return;
+        }
+    }
+}
+
+impl ParallelBlockIter {
+    /// Report the progress of the download to `operation`, like
+    /// [BlockIter::operation]. Cancellation also stops the threads after
+    /// their current batch.
+    pub fn operation(mut self, operation: OperationHandle) -> Self {
+        let left = (self.end_height + 1).saturating_sub(self.next_height);
+        let left = left + self.buffer.len() as u64;
+        operation.set_total(if self.done {
+            0
+        } else {
+            left
+        });
+        self.operation = Some(operation);
+        self
+    }
+
+    /// The hash of the last block returned by the iterator.
+    pub fn last_hash(&self) -> Option<BlockHash> {
+        self.last_hash
+    }
+
+    fn next_block(&mut self) -> Option<Result<(u64, Block)>> {
+        if self.done {
+            return None;
+        }
+
+        if self.buffer.is_empty() {
+            if self.next_height > self.end_height {
+                self.done = true;
+                return None;
+            }
+            if let Err(e) = self.fill() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+
+        let (height, block) = self.buffer.pop_front().unwrap();
+        if let Some(prev) = self.last_hash {
+            if block.header.prev_blockhash != prev {
+                self.done = true;
+                self.buffer.clear();
+                return Some(Err(Error::Reorg(height)));
+            }
+        }
+        self.last_hash = Some(block.block_hash());
+        Some(Ok((height, block)))
+    }
+
+    /// Stop the download threads after their current batch.
+    fn stop(&self) {
+        self.shared.progress.lock().unwrap().stopped = true;
+        self.shared.changed.notify_all();
+    }
+
+    /// Wait for the batch starting at the next height.
+    fn fill(&mut self) -> Result<()> {
+        let blocks = loop {
+            if let Some(blocks) = self.pending.remove(&self.next_height) {
+                break blocks;
+            }
+            let (first, blocks) = self.receiver.recv().expect("a block download thread panicked");
+            self.pending.insert(first, blocks);
+        }?;
+        self.buffer.extend((self.next_height..).zip(blocks));
+        self.next_height += DEFAULT_BATCH_SIZE as u64;
+        self.shared.progress.lock().unwrap().next_height = self.next_height;
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+}
+
+impl Iterator for ParallelBlockIter {
+    type Item = Result<(u64, Block)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = if !self.done && is_cancelled(&self.operation) {
+            self.done = true;
+            self.buffer.clear();
+            self.stop();
+            Some(Err(Error::Cancelled))
+        } else {
+            self.next_block()
+        };
+        track(&mut self.operation, &item, self.done, Some(self.end_height));
+        item
+    }
+}
+
+impl Drop for ParallelBlockIter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A change to the main chain observed by a [ChainTracker].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ChainEvent {
+    /// The block was added to the main chain.
+    BlockConnected {
+        height: u64,
+        hash: BlockHash,
+    },
+    /// The block was removed from the main chain by a reorganization.
+    BlockDisconnected {
+        height: u64,
+        hash: BlockHash,
+    },
+}
+
+/// Follows the node's main chain by polling and reports which blocks were
+/// connected and disconnected in between polls.
+///
+/// The tracker remembers the hashes of the last `depth` blocks. On every
+/// [ChainTracker::poll], tracked blocks that are no longer part of the main
+/// chain are reported as disconnected, from the highest down to the fork
+/// point, followed by all new blocks as connected in ascending order.
+#[derive(Clone, Debug)]
+pub struct ChainTracker {
+    depth: usize,
+    blocks: VecDeque<(u64, BlockHash)>,
+}
+
+impl ChainTracker {
+    /// Create a tracker remembering the last `depth` blocks, which is the
+    /// deepest reorganization it can follow.
+    ///
+    /// The first poll starts tracking at the current tip without emitting
+    /// any events.
+    pub fn new(depth: usize) -> ChainTracker {
+        ChainTracker {
+            depth: cmp::max(depth, 1),
+            blocks: VecDeque::new(),
+        }
+    }
+
+    /// Create a tracker that continues from a previously processed block.
+    pub fn starting_at(depth: usize, height: u64, hash: BlockHash) -> ChainTracker {
+        let mut tracker = ChainTracker::new(depth);
+        tracker.blocks.push_back((height, hash));
+        tracker
+    }
+
+    /// The height and hash of the last block known to the tracker.
+    pub fn tip(&self) -> Option<(u64, BlockHash)> {
+        self.blocks.back().cloned()
+    }
+
+    /// Compare the tracked blocks against the node's current chain and
+    /// return what changed since the last poll.
+    ///
+    /// If none of the tracked blocks are part of the main chain anymore, the
+    /// reorganization is deeper than the tracker can follow. In that case
+    /// [Error::Reorg] is returned with the lowest tracked height and the
+    /// tracker starts over at the current tip with the next poll.
+    pub fn poll<C: RpcApi>(&mut self, rpc: &C) -> Result<Vec<ChainEvent>> {
+        let tip = rpc.get_block_count()?;
+        if self.blocks.is_empty() {
+            self.blocks.push_back((tip, rpc.get_block_hash(tip)?));
+            return Ok(vec![]);
+        }
+
+        let mut events = vec![];
+        let mut lowest = 0;
+        while let Some(&(height, hash)) = self.blocks.back() {
+            if height <= tip && rpc.get_block_hash(height)? == hash {
+                break;
+            }
+            self.blocks.pop_back();
+            lowest = height;
+            events.push(ChainEvent::BlockDisconnected {
+                height,
+                hash,
+            });
+        }
+        let fork_height = match self.blocks.back() {
+            Some(&(height, _)) => height,
+            None => return Err(Error::Reorg(lowest)),
+        };
+
+        let heights: Vec<u64> = (fork_height + 1..=tip).collect();
+        for chunk in heights.chunks(DEFAULT_BATCH_SIZE) {
+            let calls: Vec<_> = chunk.iter().map(|&h| ("getblockhash", vec![h.into()])).collect();
+            for (&height, hash) in chunk.iter().zip(rpc.call_batch(&calls)?) {
+                let hash = hash?;
+                self.blocks.push_back((height, hash));
+                events.push(ChainEvent::BlockConnected {
+                    height,
+                    hash,
+                });
+            }
+        }
+        while self.blocks.len() > self.depth {
+            self.blocks.pop_front();
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    use dashcore::consensus::encode::serialize;
+    use dashcore::hashes::hex::ToHex;
+    use dashcore::BlockHeader;
+    use operations::{OperationState, Operations};
+    use testutil::MockRpc;
+
+    /// A fake node serving the blocks of an in-memory chain.
+    pub struct MockChain {
+        /// The current main chain.
+        pub chain: RefCell<Vec<Block>>,
+        /// All blocks ever seen, including stale ones.
+        pub known: RefCell<Vec<Block>>,
+    }
+
+    impl MockChain {
+        pub fn new(len: usize) -> MockChain {
+            let chain = build_chain(Default::default(), len, 0);
+            MockChain {
+                known: RefCell::new(chain.clone()),
+                chain: RefCell::new(chain),
+            }
+        }
+
+        /// Replace the blocks from `height` onwards by `len` new blocks.
+        pub fn reorg(&self, height: usize, len: usize) {
+            let mut chain = self.chain.borrow_mut();
+            chain.truncate(height);
+            let fork = build_chain(chain[height - 1].block_hash(), len, 1000 + height as u32);
+            self.known.borrow_mut().extend(fork.iter().cloned());
+            chain.extend(fork);
+        }
+
+        /// Add `len` blocks to the tip.
+        pub fn extend(&self, len: usize) {
+            let mut chain = self.chain.borrow_mut();
+            let tip = chain.last().unwrap().block_hash();
+            let blocks = build_chain(tip, len, 2000 + chain.len() as u32);
+            self.known.borrow_mut().extend(blocks.iter().cloned());
+            chain.extend(blocks);
+        }
+
+        pub fn hash(&self, height: usize) -> BlockHash {
+            self.chain.borrow()[height].block_hash()
+        }
+    }
+
+    pub fn build_chain(mut prev: BlockHash, len: usize, nonce: u32) -> Vec<Block> {
+        let mut blocks = Vec::with_capacity(len);
+        for i in 0..len {
+            let block = Block {
+                header: BlockHeader {
+                    version: 1,
+                    prev_blockhash: prev,
+                    merkle_root: Default::default(),
+                    time: 0,
+                    bits: 0,
+                    nonce: nonce + i as u32,
+                },
+                txdata: vec![],
+            };
+            prev = block.block_hash();
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    impl MockRpc for MockChain {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let chain = self.chain.borrow();
+            let value = match cmd {
+                "getblockcount" => serde_json::to_value(chain.len() - 1)?,
+                "getblockhash" => {
+                    let height = args[0].as_u64().unwrap() as usize;
+                    serde_json::to_value(chain[height].block_hash())?
+                }
+                "getblock" => {
+                    let hash: BlockHash = serde_json::from_value(args[0].clone())?;
+                    let known = self.known.borrow();
+                    let block = known.iter().find(|b| b.block_hash() == hash).unwrap();
+                    serialize(block).to_hex().into()
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_iter_blocks() {
+        let rpc = MockChain::new(10);
+        let heights: Vec<_> = rpc.iter_blocks(3..).batch_size(4).map(|r| r.unwrap().0).collect();
+        assert_eq!(heights, (3..10).collect::<Vec<_>>());
+
+        let blocks: Vec<_> = rpc.iter_blocks(..=2).map(|r| r.unwrap()).collect();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[2].1.block_hash(), rpc.hash(2));
+        assert_eq!(rpc.iter_blocks(5..5).count(), 0);
+
+        // Unbounded iterators pick up new blocks.
+        let mut iter = rpc.iter_blocks(8..);
+        assert_eq!(iter.by_ref().count(), 2);
+        rpc.extend(2);
+        assert_eq!(iter.next().unwrap().unwrap().0, 10);
+    }
+
+    #[test]
+    fn test_iter_blocks_reorg() {
+        let rpc = MockChain::new(10);
+        let mut iter = rpc.iter_blocks(0..).batch_size(4);
+        for _ in 0..4 {
+            iter.next().unwrap().unwrap();
+        }
+        rpc.reorg(3, 7);
+        match iter.next() {
+            Some(Err(Error::Reorg(4))) => {}
+            r => panic!("unexpected result: {:?}", r.map(|r| r.map(|b| b.0))),
+        }
+        assert!(iter.next().is_none());
+
+        let resumed = rpc.iter_blocks(3..).after(rpc.hash(2)).count();
+        assert_eq!(resumed, 7);
+    }
+
+    /// A [MockChain] that can be shared between threads.
+    struct SyncChain(Mutex<MockChain>);
+
+    impl MockRpc for SyncChain {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            self.0.lock().unwrap().respond(cmd, args)
+        }
+    }
+
+    #[test]
+    fn test_get_blocks_parallel() {
+        let rpc = Arc::new(SyncChain(Mutex::new(MockChain::new(100))));
+        let blocks: Vec<_> = get_blocks_parallel(rpc.clone(), 3.., 4).unwrap().collect();
+        assert_eq!(blocks.len(), 97);
+        for (height, block) in (3..).zip(blocks) {
+            let (h, block) = block.unwrap();
+            assert_eq!(h, height);
+            assert_eq!(block.block_hash(), rpc.0.lock().unwrap().hash(height as usize));
+        }
+        assert_eq!(get_blocks_parallel(rpc.clone(), ..=40, 3).unwrap().count(), 41);
+        assert_eq!(get_blocks_parallel(rpc.clone(), 5..5, 3).unwrap().count(), 0);
+
+        // Dropping the iterator early stops the threads.
+        let mut iter = get_blocks_parallel(rpc.clone(), .., 2).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap().0, 0);
+        assert_eq!(iter.last_hash(), Some(rpc.0.lock().unwrap().hash(0)));
+
+        let ops = Operations::new();
+        let op = ops.register("download");
+        let id = op.id();
+        let mut iter = get_blocks_parallel(rpc.clone(), 10..=59, 2).unwrap().operation(op);
+        assert_eq!(ops.get(id).unwrap().total, Some(50));
+        assert_eq!(iter.by_ref().take(20).count(), 20);
+        assert_eq!(ops.get(id).unwrap().done, 20);
+        ops.cancel(id);
+        match iter.next() {
+            Some(Err(Error::Cancelled)) => {}
+            r => panic!("unexpected result: {:?}", r.map(|r| r.map(|b| b.0))),
+        }
+        assert!(iter.next().is_none());
+        assert_eq!(ops.get(id).unwrap().state, OperationState::Cancelled);
+    }
+
+    #[test]
+    fn test_iter_blocks_operation() {
+        let rpc = MockChain::new(10);
+        let ops = Operations::new();
+        let op = ops.register("backfill");
+        let id = op.id();
+        let iter = rpc.iter_blocks(2..8).operation(op);
+        assert_eq!(ops.get(id).unwrap().total, Some(6));
+        assert_eq!(iter.count(), 6);
+        let info = ops.get(id).unwrap();
+        assert_eq!((info.state, info.done), (OperationState::Finished, 6));
+
+        let op = ops.register("backfill");
+        let id = op.id();
+        let mut iter = rpc.iter_blocks(0..).batch_size(4).operation(op);
+        assert_eq!(iter.by_ref().take(4).count(), 4);
+        rpc.reorg(3, 7);
+        assert!(iter.next().unwrap().is_err());
+        assert_eq!(ops.get(id).unwrap().state, OperationState::Failed);
+    }
+
+    #[test]
+    fn test_chain_tracker() {
+        let rpc = MockChain::new(10);
+        let mut tracker = ChainTracker::new(5);
+        assert!(tracker.poll(&rpc).unwrap().is_empty());
+        assert_eq!(tracker.tip(), Some((9, rpc.hash(9))));
+        assert!(tracker.poll(&rpc).unwrap().is_empty());
+
+        rpc.extend(3);
+        let connected: Vec<_> = (10..13)
+            .map(|height| ChainEvent::BlockConnected {
+                height,
+                hash: rpc.hash(height as usize),
+            })
+            .collect();
+        assert_eq!(tracker.poll(&rpc).unwrap(), connected);
+
+        let stale = (rpc.hash(11), rpc.hash(12));
+        rpc.reorg(11, 3);
+        let events = tracker.poll(&rpc).unwrap();
+        assert_eq!(events.len(), 5);
+        assert_eq!(
+            events[0],
+            ChainEvent::BlockDisconnected {
+                height: 12,
+                hash: stale.1,
+            }
+        );
+        assert_eq!(
+            events[1],
+            ChainEvent::BlockDisconnected {
+                height: 11,
+                hash: stale.0,
+            }
+        );
+        assert_eq!(
+            events[4],
+            ChainEvent::BlockConnected {
+                height: 13,
+                hash: rpc.hash(13),
+            }
+        );
+
+        // Deeper than the tracked window.
+        rpc.reorg(2, 20);
+        match tracker.poll(&rpc) {
+            Err(Error::Reorg(9)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(tracker.poll(&rpc).unwrap().is_empty());
+        assert_eq!(tracker.tip(), Some((21, rpc.hash(21))));
+    }
+}
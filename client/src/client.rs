@@ -11,8 +11,12 @@
 use std::collections::HashMap;
 use std::fs::File;
 use std::iter::FromIterator;
+use std::ops::RangeBounds;
 use std::path::PathBuf;
-use std::{fmt, result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use std::{fmt, iter, result};
 
 use dashcore;
 use jsonrpc;
@@ -21,19 +25,111 @@ use serde_json;
 
 use dashcore::hashes::hex::{FromHex, ToHex};
 use dashcore::secp256k1::ecdsa::Signature;
+use dashcore::util::bip158;
+use dashcore::util::merkleblock::MerkleBlock;
 use dashcore::{
-    Address, Amount, Block, BlockHeader, OutPoint, PrivateKey, PublicKey, Script, Transaction,
+    Address, Amount, Block, BlockHeader, Network, OutPoint, PrivateKey, PublicKey, Script,
+    Transaction,
 };
 use log::Level::{Debug, Trace, Warn};
 
+use bloom::BloomFilter;
+use capabilities::Capabilities;
+use chain::BlockIter;
+use coinselect::CoinSelector;
+use collateral::{self, CollateralProof};
+use conflicts::{self, ConflictReport};
 use error::*;
+use export::{self, ExportRecord};
+use governance::{self, Superblock, VoteTally};
+use health::{self, LatencyStats};
 use json;
+use masternodes::{MasternodeList, MasternodeListDiffs};
+use mempool::{FeeHistogram, FeeHistogramBucket, MempoolEntries};
+#[cfg(feature = "metrics")]
+use metrics::{RpcMetrics, BATCH_METHOD_LABEL};
+use mining;
+use ownership::{self, ClassifiedOutput};
+use payment::{self, VerifiedPayment};
+use prevouts::TxWithPrevouts;
 use queryable;
+use quorum::{self, QuorumVerification};
+use ratelimit::{InFlightLimit, RateLimiter};
+use raw::{decode_hex, decode_result, RawResult};
+use signing::{self, Signer, SigningInput};
+use transport::{HttpTransport, Proxy};
+use txout;
+use warmup;
+use workflow::{self, CreateTransactionOptions};
 
 /// Crate-specific Result type, shorthand for `std::result::Result` with our
 /// crate-specific Error type;
 pub type Result<T> = result::Result<T, Error>;
 
+/// The node version that added `signrawtransactionwithwallet` and
+/// `signrawtransactionwithkey`, deprecating `signrawtransaction`, Dash Core
+/// 0.17.
+const SIGN_WITH_WALLET_VERSION: usize = 170000;
+/// The node version that deprecated `generate` for `generatetoaddress`, Dash
+/// Core 0.17.
+const GENERATE_DEPRECATED_VERSION: usize = 170000;
+/// The node version that added `getblockfilter`, Dash Core 18.0.
+const BLOCK_FILTER_VERSION: usize = 180000;
+/// The node version that replaced the `allowhighfees` argument of
+/// `sendrawtransaction` with `maxfeerate`.
+const MAX_FEE_RATE_VERSION: usize = 170000;
+/// The most txids `gettxchainlocks` takes per call.
+const MAX_TX_CHAIN_LOCKS_TXIDS: usize = 100;
+
+/// Ask the node for the network of its chain.
+fn detect_network<C: RpcApi>(rpc: &C) -> Result<Network> {
+    #[derive(Deserialize)]
+    struct Response {
+        pub chain: String,
+    }
+    let res: Response = rpc.call("getblockchaininfo", &[])?;
+    network_from_chain(&res.chain).ok_or(Error::UnexpectedStructure)
+}
+
+/// The network of a chain name as reported by `getblockchaininfo`.
+fn network_from_chain(chain: &str) -> Option<Network> {
+    match chain {
+        "main" => Some(Network::Dash),
+        "test" => Some(Network::Testnet),
+        "regtest" => Some(Network::Regtest),
+        // Devnets are reported as "devnet" or "devnet-<name>".
+        c if c.starts_with("devnet") => Some(Network::Devnet),
+        _ => None,
+    }
+}
+
+/// Check that an address of the network `address` can be used on a node of
+/// the network `node`.
+///
+/// Testnet, devnets and regtest share their address prefixes, so an address
+/// of one of them is valid on all of them.
+fn check_network(address: Network, node: Network) -> Result<()> {
+    if (address == Network::Dash) != (node == Network::Dash) {
+        return Err(Error::NetworkMismatch {
+            address,
+            node,
+        });
+    }
+    Ok(())
+}
+
+/// Check that `header` is the header of the block `requested`.
+fn verify_block_header(requested: &dashcore::BlockHash, header: &BlockHeader) -> Result<()> {
+    let found = header.block_hash();
+    if found != *requested {
+        return Err(Error::BlockHashMismatch {
+            requested: *requested,
+            found,
+        });
+    }
+    Ok(())
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct JsonOutPoint {
     pub txid: dashcore::Txid,
@@ -223,6 +319,18 @@ pub trait RpcApi: Sized {
         args: &[serde_json::Value],
     ) -> Result<T>;
 
+    /// Call several rpcs returning the same type, in a single round trip if
+    /// the implementation supports batching.
+    ///
+    /// The default implementation makes the calls one after another. The
+    /// outer error is for failures of the batch as a whole.
+    fn call_batch<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        calls: &[(&str, Vec<serde_json::Value>)],
+    ) -> Result<Vec<Result<T>>> {
+        Ok(calls.iter().map(|&(cmd, ref args)| self.call(cmd, args)).collect())
+    }
+
     /// Query an object implementing `Querable` type
     fn get_by_id<T: queryable::Queryable<Self>>(
         &self,
@@ -244,6 +352,84 @@ pub trait RpcApi: Sized {
         Ok(res.version)
     }
 
+    /// The version of the connected node.
+    ///
+    /// The wrappers of calls that were renamed or removed in some node
+    /// version use it to pick the call the node supports. This defaults to
+    /// [RpcApi::version], [Client] only asks the node once.
+    fn node_version(&self) -> Result<usize> {
+        self.version()
+    }
+
+    /// The network of the chain the node runs, parsed from
+    /// `getblockchaininfo`.
+    ///
+    /// [Client] only asks the node once.
+    fn network(&self) -> Result<Network> {
+        detect_network(self)
+    }
+
+    /// Check that `address` belongs to the chain of the node.
+    ///
+    /// The wrappers of calls that take an [Address] call this first, so an
+    /// address of the wrong network fails with [Error::NetworkMismatch]
+    /// instead of an "Invalid address" error of the node. This defaults to
+    /// no check, [Client] checks once enabled with
+    /// [Client::with_address_validation].
+    fn check_address_network(&self, _address: &Address) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check that `header`, returned by the node for the block `requested`,
+    /// hashes to `requested`.
+    ///
+    /// The wrappers of `getblock` and `getblockheader` call this before
+    /// returning, so a corrupted response fails with
+    /// [Error::BlockHashMismatch]. This defaults to no check, [Client] checks
+    /// once enabled with [Client::with_block_verification].
+    fn check_block_header(
+        &self,
+        _requested: &dashcore::BlockHash,
+        _header: &BlockHeader,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Like [RpcApi::check_block_header], but also checks the transactions
+    /// of `block` against the merkle root of its header.
+    fn check_block(&self, _requested: &dashcore::BlockHash, _block: &Block) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether [RpcApi::check_block_header] and [RpcApi::check_block] check
+    /// anything, so wrappers only decode a result for them when they do.
+    fn verifies_blocks(&self) -> bool {
+        false
+    }
+
+    /// Check the fee of the raw transaction `hex` before it is broadcast.
+    ///
+    /// [RpcApi::send_raw_transaction] calls this first, so a transaction
+    /// paying an unreasonable fee fails with [Error::FeeTooHigh] instead of
+    /// being broadcast. This defaults to no check, [Client] checks once a
+    /// maximum fee is set with [Client::with_max_fee].
+    fn check_transaction_fee(&self, _hex: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// The calls the node supports, parsed from `help`.
+    ///
+    /// [Client] only asks the node once.
+    fn capabilities(&self) -> Result<Capabilities> {
+        let help: String = self.call("help", &[])?;
+        Ok(Capabilities::from_help(&help))
+    }
+
+    /// Add an `nrequired`-of-`keys.len()` multisig address to the wallet.
+    ///
+    /// Dash Core takes no `address_type`, a node rejects the call when one
+    /// is given.
+    #[deprecated(note = "use add_multisig")]
     fn add_multisig_address(
         &self,
         nrequired: usize,
@@ -260,6 +446,27 @@ pub trait RpcApi: Sized {
         self.call("addmultisigaddress", handle_defaults(&mut args, &[into_json("")?, null()]))
     }
 
+    /// Add an `nrequired`-of-`keys.len()` multisig address to the wallet.
+    fn add_multisig(
+        &self,
+        nrequired: usize,
+        keys: &[json::PubKeyOrAddress],
+        label: Option<&str>,
+    ) -> Result<json::AddMultiSigAddressResult> {
+        let mut args = [into_json(nrequired)?, into_json(keys)?, opt_into_json(label)?];
+        self.call("addmultisigaddress", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// Create an `nrequired`-of-`keys.len()` multisig address without adding
+    /// it to the wallet.
+    fn create_multisig(
+        &self,
+        nrequired: usize,
+        keys: &[PublicKey],
+    ) -> Result<json::CreateMultiSigResult> {
+        self.call("createmultisig", &[into_json(nrequired)?, into_json(keys)?])
+    }
+
     fn load_wallet(&self, wallet: &str) -> Result<json::LoadWalletResult> {
         self.call("loadwallet", &[wallet.into()])
     }
@@ -290,10 +497,38 @@ pub trait RpcApi: Sized {
         )
     }
 
+    /// Create a descriptor wallet, whose keys and scripts are kept as output
+    /// descriptors, see [RpcApi::import_descriptors].
+    fn create_descriptor_wallet(
+        &self,
+        wallet: &str,
+        disable_private_keys: Option<bool>,
+        blank: Option<bool>,
+        passphrase: Option<&str>,
+    ) -> Result<json::LoadWalletResult> {
+        let mut args = [
+            wallet.into(),
+            opt_into_json(disable_private_keys)?,
+            opt_into_json(blank)?,
+            opt_into_json(passphrase)?,
+            false.into(),
+            true.into(),
+        ];
+        let defaults = [false.into(), false.into(), into_json("")?, false.into(), true.into()];
+        self.call("createwallet", handle_defaults(&mut args, &defaults))
+    }
+
     fn list_wallets(&self) -> Result<Vec<String>> {
         self.call("listwallets", &[])
     }
 
+    /// List the names of the wallets in the wallet directory of the node,
+    /// whether they are loaded or not.
+    fn list_wallet_dir(&self) -> Result<Vec<String>> {
+        let result: json::ListWalletDirResult = self.call("listwalletdir", &[])?;
+        Ok(result.wallets.into_iter().map(|w| w.name).collect())
+    }
+
     fn get_wallet_info(&self) -> Result<json::GetWalletInfoResult> {
         self.call("getwalletinfo", &[])
     }
@@ -304,9 +539,16 @@ pub trait RpcApi: Sized {
     }
 
     fn dump_private_key(&self, address: &Address) -> Result<PrivateKey> {
+        self.check_address_network(address)?;
         self.call("dumpprivkey", &[address.to_string().into()])
     }
 
+    /// Dump all wallet keys to `filename` on the node, see `WalletDump` to
+    /// read the dump back.
+    fn dump_wallet(&self, filename: &str) -> Result<json::DumpWalletResult> {
+        self.call("dumpwallet", &[into_json(filename)?])
+    }
+
     fn encrypt_wallet(&self, passphrase: &str) -> Result<()> {
         self.call("encryptwallet", &[into_json(passphrase)?])
     }
@@ -320,31 +562,86 @@ pub trait RpcApi: Sized {
     }
 
     fn get_block(&self, hash: &dashcore::BlockHash) -> Result<Block> {
-        let hex: String = self.call("getblock", &[into_json(hash)?, 0.into()])?;
-        let bytes: Vec<u8> = FromHex::from_hex(&hex)?;
-        Ok(dashcore::consensus::encode::deserialize(&bytes)?)
+        let raw: RawResult<Block> =
+            self.call("getblock", &[into_json(hash)?, into_json(json::BlockVerbosity::Raw)?])?;
+        let block = raw.decode("getblock", Some(hash))?;
+        self.check_block(hash, &block)?;
+        Ok(block)
     }
 
     fn get_block_hex(&self, hash: &dashcore::BlockHash) -> Result<String> {
-        self.call("getblock", &[into_json(hash)?, 0.into()])
+        let hex: String =
+            self.call("getblock", &[into_json(hash)?, into_json(json::BlockVerbosity::Raw)?])?;
+        if self.verifies_blocks() {
+            // The header is the first 80 bytes of the block.
+            let header = hex.get(..160).ok_or(Error::UnexpectedStructure)?;
+            self.check_block_header(hash, &decode_result("getblock", Some(hash), header)?)?;
+        }
+        Ok(hex)
     }
 
     fn get_block_info(&self, hash: &dashcore::BlockHash) -> Result<json::GetBlockResult> {
-        self.call("getblock", &[into_json(hash)?, 1.into()])
+        self.call("getblock", &[into_json(hash)?, into_json(json::BlockVerbosity::Info)?])
+    }
+
+    /// Get a block with the given verbosity.
+    fn get_block_with_verbosity(
+        &self,
+        hash: &dashcore::BlockHash,
+        verbosity: json::BlockVerbosity,
+    ) -> Result<json::GetBlockVerbosityResult> {
+        Ok(match verbosity {
+            json::BlockVerbosity::Raw => json::GetBlockVerbosityResult::Raw(self.get_block(hash)?),
+            json::BlockVerbosity::Info => {
+                json::GetBlockVerbosityResult::Info(self.get_block_info(hash)?)
+            }
+            json::BlockVerbosity::Full => json::GetBlockVerbosityResult::Full(
+                self.call("getblock", &[into_json(hash)?, into_json(verbosity)?])?,
+            ),
+        })
     }
     //TODO(stevenroose) add getblock_txs
 
     fn get_block_header(&self, hash: &dashcore::BlockHash) -> Result<BlockHeader> {
-        let hex: String = self.call("getblockheader", &[into_json(hash)?, false.into()])?;
-        let bytes: Vec<u8> = FromHex::from_hex(&hex)?;
-        Ok(dashcore::consensus::encode::deserialize(&bytes)?)
+        let raw: RawResult<BlockHeader> =
+            self.call("getblockheader", &[into_json(hash)?, false.into()])?;
+        let header = raw.decode("getblockheader", Some(hash))?;
+        self.check_block_header(hash, &header)?;
+        Ok(header)
     }
 
     fn get_block_header_info(
         &self,
         hash: &dashcore::BlockHash,
     ) -> Result<json::GetBlockHeaderResult> {
-        self.call("getblockheader", &[into_json(hash)?, true.into()])
+        let info: json::GetBlockHeaderResult =
+            self.call("getblockheader", &[into_json(hash)?, true.into()])?;
+        if self.verifies_blocks() {
+            let header = info.block_header().ok_or(Error::UnexpectedStructure)?;
+            self.check_block_header(hash, &header)?;
+        }
+        Ok(info)
+    }
+
+    /// Get up to `count` merkle blocks, starting at `block_hash`, containing
+    /// the transactions matching `filter`.
+    fn get_merkle_blocks(
+        &self,
+        filter: &BloomFilter,
+        block_hash: &dashcore::BlockHash,
+        count: Option<u32>,
+    ) -> Result<Vec<MerkleBlock>> {
+        let mut args = [filter.to_hex().into(), into_json(block_hash)?, opt_into_json(count)?];
+        let blocks: Vec<RawResult<MerkleBlock>> =
+            self.call("getmerkleblocks", handle_defaults(&mut args, &[null()]))?;
+        blocks.into_iter().map(|b| b.decode("getmerkleblocks", Some(block_hash))).collect()
+    }
+
+    /// Iterate over the blocks of the main chain in the given range of
+    /// heights, see [BlockIter]. See `get_blocks_parallel` to download many
+    /// blocks faster with several threads.
+    fn iter_blocks<R: RangeBounds<u64>>(&self, heights: R) -> BlockIter<Self> {
+        BlockIter::new(self, heights)
     }
 
     fn get_mining_info(&self) -> Result<json::GetMiningInfoResult> {
@@ -374,6 +671,33 @@ pub trait RpcApi: Sized {
         )
     }
 
+    /// Submit a serialized block, fails with [Error::BlockRejected] if the
+    /// node doesn't accept it.
+    fn submit_block_bytes(&self, block: &[u8]) -> Result<()> {
+        let rejected: Option<String> = self.call("submitblock", &[block.to_hex().into()])?;
+        match rejected {
+            Some(reason) => Err(Error::BlockRejected(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Mine a block on a regtest node with the transactions of
+    /// `getblocktemplate` and `transactions`, which don't need to be in the
+    /// mempool, paying the reward to `address`.
+    ///
+    /// The block is built with a
+    /// [BlockAssembler](mining::BlockAssembler) and submitted with
+    /// `submitblock`. The fees of `transactions` are not claimed. Fails with
+    /// [Error::RegtestOnly] on other networks.
+    fn mine_block_with_transactions(
+        &self,
+        address: &Address,
+        transactions: &[Transaction],
+    ) -> Result<dashcore::BlockHash> {
+        self.check_address_network(address)?;
+        mining::mine_block_with_transactions(self, address.script_pubkey(), transactions)
+    }
+
     /// Returns a data structure containing various state info regarding
     /// blockchain processing.
     fn get_blockchain_info(&self) -> Result<json::GetBlockchainInfoResult> {
@@ -381,7 +705,7 @@ pub trait RpcApi: Sized {
         // The softfork fields are not backwards compatible:
         // - 0.18.x returns a "softforks" array and a "bip9_softforks" map.
         // - 0.19.x returns a "softforks" map.
-        Ok(if self.version()? < 190000 {
+        Ok(if self.node_version()? < 190000 {
             use Error::UnexpectedStructure as err;
 
             // First, remove both incompatible softfork fields.
@@ -479,10 +803,34 @@ pub trait RpcApi: Sized {
         txid: &dashcore::Txid,
         block_hash: Option<&dashcore::BlockHash>,
     ) -> Result<Transaction> {
-        let mut args = [into_json(txid)?, into_json(false)?, opt_into_json(block_hash)?];
-        let hex: String = self.call("getrawtransaction", handle_defaults(&mut args, &[null()]))?;
-        let bytes: Vec<u8> = FromHex::from_hex(&hex)?;
-        Ok(dashcore::consensus::encode::deserialize(&bytes)?)
+        let mut args =
+            [into_json(txid)?, into_json(json::TxVerbosity::Raw)?, opt_into_json(block_hash)?];
+        let raw: RawResult<Transaction> =
+            self.call("getrawtransaction", handle_defaults(&mut args, &[null()]))?;
+        raw.decode("getrawtransaction", Some(txid))
+    }
+
+    /// Get a transaction together with the outputs its inputs spend, see
+    /// [TxWithPrevouts::resolve].
+    fn get_tx_with_prevouts(&self, txid: &dashcore::Txid) -> Result<TxWithPrevouts> {
+        TxWithPrevouts::resolve(self, self.get_raw_transaction(txid, None)?)
+    }
+
+    /// Get the fee paid by a confirmed or mempool transaction, computed from
+    /// the outputs its inputs spend, zero for a coinbase transaction. The fee
+    /// of an asset unlock transaction, which spends nothing, is read from its
+    /// payload.
+    fn get_tx_fee(&self, txid: &dashcore::Txid) -> Result<Amount> {
+        let tx = self.get_tx_with_prevouts(txid)?;
+        if tx.tx.is_coin_base() {
+            return Ok(Amount::ZERO);
+        }
+        if tx.is_asset_unlock() {
+            let info = self.get_raw_transaction_info(txid, None)?;
+            let payload = info.asset_unlock_payload().ok_or(Error::UnexpectedStructure)??;
+            return Ok(Amount::from_sat(u64::from(payload.fee)));
+        }
+        tx.fee().ok_or(Error::UnexpectedStructure)
     }
 
     fn get_raw_transaction_hex(
@@ -490,7 +838,8 @@ pub trait RpcApi: Sized {
         txid: &dashcore::Txid,
         block_hash: Option<&dashcore::BlockHash>,
     ) -> Result<String> {
-        let mut args = [into_json(txid)?, into_json(false)?, opt_into_json(block_hash)?];
+        let mut args =
+            [into_json(txid)?, into_json(json::TxVerbosity::Raw)?, opt_into_json(block_hash)?];
         self.call("getrawtransaction", handle_defaults(&mut args, &[null()]))
     }
 
@@ -499,39 +848,223 @@ pub trait RpcApi: Sized {
         txid: &dashcore::Txid,
         block_hash: Option<&dashcore::BlockHash>,
     ) -> Result<json::GetRawTransactionResult> {
-        let mut args = [into_json(txid)?, into_json(true)?, opt_into_json(block_hash)?];
+        let mut args =
+            [into_json(txid)?, into_json(json::TxVerbosity::Info)?, opt_into_json(block_hash)?];
         self.call("getrawtransaction", handle_defaults(&mut args, &[null()]))
     }
 
+    /// Get how final a transaction is, taking InstantSend locks and
+    /// ChainLocks into account.
+    fn get_tx_confirmation_status(
+        &self,
+        txid: &dashcore::Txid,
+    ) -> Result<json::ConfirmationStatus> {
+        Ok(self.get_raw_transaction_info(txid, None)?.confirmation_status())
+    }
+
+    /// Get the ChainLock status of many transactions at once, in the order of
+    /// `txids`, which is much cheaper than a verbose `getrawtransaction` per
+    /// transaction.
+    ///
+    /// The transactions are queried in calls of at most 100 txids.
+    fn get_tx_chain_locks(
+        &self,
+        txids: &[dashcore::Txid],
+    ) -> Result<Vec<json::GetTxChainLocksResultEntry>> {
+        let mut statuses = Vec::with_capacity(txids.len());
+        for chunk in txids.chunks(MAX_TX_CHAIN_LOCKS_TXIDS) {
+            let chunk: Vec<json::GetTxChainLocksResultEntry> =
+                self.call("gettxchainlocks", &[into_json(chunk)?])?;
+            statuses.extend(chunk);
+        }
+        Ok(statuses)
+    }
+
+    /// Get the compact block filter of a block, the basic filter by default.
+    ///
+    /// The node needs the filter index, enabled with `-blockfilterindex`.
     fn get_block_filter(
         &self,
         block_hash: &dashcore::BlockHash,
+        filter_type: Option<json::BlockFilterType>,
     ) -> Result<json::GetBlockFilterResult> {
-        self.call("getblockfilter", &[into_json(block_hash)?])
+        let version = self.node_version()?;
+        if version < BLOCK_FILTER_VERSION {
+            return Err(Error::UnsupportedByNode {
+                method: "getblockfilter",
+                version,
+            });
+        }
+        let mut args = [into_json(block_hash)?, opt_into_json(filter_type)?];
+        self.call("getblockfilter", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// Whether the basic block filter of a block matches any of `scripts`,
+    /// to find the blocks relevant to a wallet without downloading them.
+    ///
+    /// As filters are probabilistic, a match can be a false positive.
+    fn block_filter_matches(
+        &self,
+        block_hash: &dashcore::BlockHash,
+        scripts: &[Script],
+    ) -> Result<bool> {
+        let filter = self.get_block_filter(block_hash, None)?;
+        filter.match_any_script(block_hash, scripts).map_err(|e| match e {
+            bip158::Error::Io(e) => Error::Io(e),
+            bip158::Error::UtxoMissing(outpoint) => Error::MissingPrevout(outpoint),
+        })
+    }
+
+    /// The status of the enabled indexes of the node, like `txindex` or
+    /// `basic block filter index`, by name. `name` only returns that index,
+    /// nothing if it is disabled.
+    ///
+    /// Fails with [Error::UnsupportedByNode] on nodes without `getindexinfo`.
+    fn get_index_info(&self, name: Option<&str>) -> Result<HashMap<String, json::IndexInfo>> {
+        if !self.capabilities()?.supports("getindexinfo") {
+            return Err(Error::UnsupportedByNode {
+                method: "getindexinfo",
+                version: self.node_version()?,
+            });
+        }
+        let mut args = [opt_into_json(name)?];
+        self.call("getindexinfo", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// Check that the indexes `names` are enabled and synced, so an
+    /// application can refuse to start against a node that lacks them.
+    ///
+    /// Fails with [Error::IndexUnavailable] for the first index that is not.
+    fn require_indexes(&self, names: &[&str]) -> Result<()> {
+        let indexes = self.get_index_info(None)?;
+        for name in names {
+            match indexes.get(*name) {
+                Some(index) if index.synced => {}
+                index => {
+                    return Err(Error::IndexUnavailable {
+                        name: name.to_string(),
+                        best_block_height: index.map(|i| i.best_block_height),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the changes of the balances of addresses by mined transactions,
+    /// optionally only those of the blocks in the `(start, end)` height range.
+    ///
+    /// The node needs the address index, enabled with `-addressindex`.
+    fn get_address_deltas(
+        &self,
+        addresses: &[&Address],
+        heights: Option<(u32, u32)>,
+    ) -> Result<Vec<json::AddressDelta>> {
+        #[derive(Serialize)]
+        struct Argument<'a> {
+            addresses: &'a [&'a Address],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            start: Option<u32>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            end: Option<u32>,
+        }
+
+        for address in addresses {
+            self.check_address_network(address)?;
+        }
+        self.call(
+            "getaddressdeltas",
+            &[into_json(Argument {
+                addresses: addresses,
+                start: heights.map(|h| h.0),
+                end: heights.map(|h| h.1),
+            })?],
+        )
+    }
+
+    /// Get the unspent outputs paying addresses, not counting the mempool.
+    ///
+    /// The node needs the address index, enabled with `-addressindex`.
+    fn get_address_utxos(&self, addresses: &[&Address]) -> Result<Vec<json::AddressUtxo>> {
+        #[derive(Serialize)]
+        struct Argument<'a> {
+            addresses: &'a [&'a Address],
+        }
+
+        for address in addresses {
+            self.check_address_network(address)?;
+        }
+        self.call(
+            "getaddressutxos",
+            &[into_json(Argument {
+                addresses: addresses,
+            })?],
+        )
+    }
+
+    /// Get the changes of the balances of addresses by mempool transactions.
+    ///
+    /// The node needs the address index, enabled with `-addressindex`.
+    fn get_address_mempool(
+        &self,
+        addresses: &[&Address],
+    ) -> Result<Vec<json::AddressMempoolEntry>> {
+        #[derive(Serialize)]
+        struct Argument<'a> {
+            addresses: &'a [&'a Address],
+        }
+
+        for address in addresses {
+            self.check_address_network(address)?;
+        }
+        self.call(
+            "getaddressmempool",
+            &[into_json(Argument {
+                addresses: addresses,
+            })?],
+        )
     }
 
+    /// The balance of the wallet, counting transactions with at least
+    /// `minconf` confirmations and, with `include_watchonly`, the
+    /// watch-only addresses.
     fn get_balance(
         &self,
         minconf: Option<usize>,
         include_watchonly: Option<bool>,
     ) -> Result<Amount> {
-        let mut args = ["*".into(), opt_into_json(minconf)?, opt_into_json(include_watchonly)?];
-        Ok(Amount::from_btc(
-            self.call("getbalance", handle_defaults(&mut args, &[0.into(), null()]))?,
-        )?)
+        // Dash Core takes an `addlocked` flag before `include_watchonly`.
+        let mut args =
+            ["*".into(), opt_into_json(minconf)?, null(), opt_into_json(include_watchonly)?];
+        let defaults = [0.into(), false.into(), null()];
+        Ok(Amount::from_btc(self.call("getbalance", handle_defaults(&mut args, &defaults))?)?)
     }
 
     fn get_balances(&self) -> Result<json::GetBalancesResult> {
         Ok(self.call("getbalances", &[])?)
     }
 
+    /// The total amount received by `address` in transactions with at
+    /// least `minconf` confirmations, one by default.
     fn get_received_by_address(&self, address: &Address, minconf: Option<u32>) -> Result<Amount> {
+        self.check_address_network(address)?;
         let mut args = [address.to_string().into(), opt_into_json(minconf)?];
         Ok(Amount::from_btc(
             self.call("getreceivedbyaddress", handle_defaults(&mut args, &[null()]))?,
         )?)
     }
 
+    /// The total amount received by the addresses of `label` in transactions
+    /// with at least `minconf` confirmations, one by default.
+    fn get_received_by_label(&self, label: &str, minconf: Option<u32>) -> Result<Amount> {
+        let mut args = [label.into(), opt_into_json(minconf)?];
+        Ok(Amount::from_btc(
+            self.call("getreceivedbylabel", handle_defaults(&mut args, &[null()]))?,
+        )?)
+    }
+
+    /// Get a wallet transaction, also one of watch-only addresses with
+    /// `include_watchonly`.
     fn get_transaction(
         &self,
         txid: &dashcore::Txid,
@@ -541,6 +1074,19 @@ pub trait RpcApi: Sized {
         self.call("gettransaction", handle_defaults(&mut args, &[null()]))
     }
 
+    /// Report the wallet transactions conflicting with `txid` and whether
+    /// the mempool takes it, to detect double spends.
+    ///
+    /// The conflicts are the `walletconflicts` of `gettransaction`, the
+    /// mempool is checked with `testmempoolaccept` unless the transaction is
+    /// mined.
+    fn get_conflicts(&self, txid: &dashcore::Txid) -> Result<ConflictReport> {
+        conflicts::get_conflicts(self, txid)
+    }
+
+    /// List the wallet transactions of `label`, all of them if `None`, most
+    /// recent last. Those of watch-only addresses are only listed with
+    /// `include_watchonly`, see `involved_watch_only` of their details.
     fn list_transactions(
         &self,
         label: Option<&str>,
@@ -557,6 +1103,29 @@ pub trait RpcApi: Sized {
         self.call("listtransactions", handle_defaults(&mut args, &[10.into(), 0.into(), null()]))
     }
 
+    /// Export all the transactions of the wallet, or those of `label`, as
+    /// records for bookkeeping, oldest first.
+    ///
+    /// The transactions are fetched with `listtransactions`, by pages of
+    /// [export::EXPORT_PAGE_SIZE]. There is a record per output received or
+    /// sent, see [ExportRecord::write_csv] to write them as CSV.
+    fn export_transactions(
+        &self,
+        label: Option<&str>,
+        include_watchonly: Option<bool>,
+    ) -> Result<Vec<ExportRecord>> {
+        export::export_transactions(self, label, include_watchonly)
+    }
+
+    /// List the wallet transactions of the blocks after `blockhash`, all
+    /// of them if `None`, and of the mempool. Those of watch-only addresses
+    /// are only listed with `include_watchonly`.
+    ///
+    /// With `include_removed`, the default, the transactions of blocks that
+    /// were disconnected since `blockhash` by a reorganization are listed in
+    /// `removed`. Pass the `lastblock` of the result to the next call to
+    /// list the transactions of the blocks that may still be reorganized
+    /// again, those with fewer than `target_confirmations` confirmations.
     fn list_since_block(
         &self,
         blockhash: Option<&dashcore::BlockHash>,
@@ -583,6 +1152,49 @@ pub trait RpcApi: Sized {
         opt_result(self.call("gettxout", handle_defaults(&mut args, &[null()]))?)
     }
 
+    /// Get an output like [RpcApi::get_tx_out], together with the chain tip
+    /// it was evaluated at.
+    ///
+    /// The tip is read before and after the output, fails with
+    /// [Error::TipChanged] if a block was connected or disconnected in
+    /// between. See [RpcApi::get_tx_outs_at_tip] to retry.
+    fn get_tx_out_at_tip(
+        &self,
+        txid: &dashcore::Txid,
+        vout: u32,
+        include_mempool: Option<bool>,
+    ) -> Result<txout::TxOutAtTip> {
+        let outpoint = dashcore::OutPoint::new(*txid, vout);
+        match txout::read_at_tip(self, &[outpoint], include_mempool)? {
+            Some(mut read) => Ok(txout::TxOutAtTip {
+                tip: read.tip,
+                output: read.outputs.remove(0),
+            }),
+            None => Err(Error::TipChanged {
+                attempts: 1,
+            }),
+        }
+    }
+
+    /// Get several outputs evaluated at the same chain tip, so that for
+    /// example a balance summed from them is consistent.
+    ///
+    /// The outputs are read in one batch, which is read again while the tip
+    /// changes during the read, up to `max_attempts` times, by default
+    /// [DEFAULT_TIP_READ_ATTEMPTS](::DEFAULT_TIP_READ_ATTEMPTS), and at least
+    /// once. Fails with [Error::TipChanged] if the tip changed on every
+    /// attempt. Only the chain is pinned, outputs created or spent by the
+    /// mempool can change between two reads.
+    fn get_tx_outs_at_tip(
+        &self,
+        outpoints: &[dashcore::OutPoint],
+        include_mempool: Option<bool>,
+        max_attempts: Option<usize>,
+    ) -> Result<txout::TxOutsAtTip> {
+        let attempts = max_attempts.unwrap_or(txout::DEFAULT_TIP_READ_ATTEMPTS);
+        txout::read_at_tip_with_retries(self, outpoints, include_mempool, attempts)
+    }
+
     fn get_tx_out_proof(
         &self,
         txids: &[dashcore::Txid],
@@ -593,6 +1205,25 @@ pub trait RpcApi: Sized {
         Ok(FromHex::from_hex(&hex)?)
     }
 
+    /// Verify that the transaction `txid` pays at least `min_amount` to
+    /// `address` with at least `min_confirmations` confirmations.
+    ///
+    /// The transaction is proven to be in its block with `gettxoutproof`,
+    /// whose merkle proof is checked here against the header of the block in
+    /// the chain of the node. Fails with [Error::InvalidPayment] when the
+    /// payment does not meet the requirements. Needs the transaction index
+    /// unless the transaction has an unspent output.
+    fn verify_payment(
+        &self,
+        txid: &dashcore::Txid,
+        address: &Address,
+        min_amount: Amount,
+        min_confirmations: u32,
+    ) -> Result<VerifiedPayment> {
+        self.check_address_network(address)?;
+        payment::verify_payment(self, txid, address, min_amount, min_confirmations)
+    }
+
     fn import_public_key(
         &self,
         pubkey: &PublicKey,
@@ -619,6 +1250,7 @@ pub trait RpcApi: Sized {
         label: Option<&str>,
         rescan: Option<bool>,
     ) -> Result<()> {
+        self.check_address_network(address)?;
         let mut args = [address.to_string().into(), opt_into_json(label)?, opt_into_json(rescan)?];
         self.call("importaddress", handle_defaults(&mut args, &[into_json("")?, null()]))
     }
@@ -655,7 +1287,40 @@ pub trait RpcApi: Sized {
         self.call("importmulti", handle_defaults(&mut args, &[null()]))
     }
 
+    /// Import descriptors into a descriptor wallet, the descriptors need
+    /// their checksum, see `with_descriptor_checksum`.
+    fn import_descriptors(
+        &self,
+        requests: &[json::ImportDescriptorsRequest],
+    ) -> Result<Vec<json::ImportMultiResult>> {
+        self.call("importdescriptors", &[into_json(requests)?])
+    }
+
+    /// List the descriptors of a descriptor wallet, with their private keys
+    /// if `private`.
+    fn list_descriptors(&self, private: Option<bool>) -> Result<json::ListDescriptorsResult> {
+        let mut args = [opt_into_json(private)?];
+        self.call("listdescriptors", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// Import the outputs of `rawtx` paying the wallet, which must already
+    /// have the addresses or scripts, like imported without a rescan.
+    ///
+    /// `txout_proof`, from [RpcApi::get_tx_out_proof], proves that the
+    /// transaction is mined, so the funds are imported without the rescan a
+    /// pruned node can't do.
+    fn import_pruned_funds<R: RawTx>(&self, rawtx: R, txout_proof: &[u8]) -> Result<()> {
+        self.call("importprunedfunds", &[rawtx.raw_hex().into(), txout_proof.to_hex().into()])
+    }
+
+    /// Remove a transaction imported with [RpcApi::import_pruned_funds] from
+    /// the wallet.
+    fn remove_pruned_funds(&self, txid: &dashcore::Txid) -> Result<()> {
+        self.call("removeprunedfunds", &[into_json(txid)?])
+    }
+
     fn set_label(&self, address: &Address, label: &str) -> Result<()> {
+        self.check_address_network(address)?;
         self.call("setlabel", &[address.to_string().into(), label.into()])
     }
 
@@ -664,6 +1329,41 @@ pub trait RpcApi: Sized {
         self.call("keypoolrefill", handle_defaults(&mut args, &[null()]))
     }
 
+    /// Set or generate a new HD wallet seed. Setting a new seed flushes the
+    /// keypool unless `new_keypool` is false.
+    fn set_hd_seed(&self, new_keypool: Option<bool>, seed: Option<&PrivateKey>) -> Result<()> {
+        let mut args = [opt_into_json(new_keypool)?, opt_into_json(seed.map(|s| s.to_string()))?];
+        self.call("sethdseed", handle_defaults(&mut args, &[true.into(), null()]))
+    }
+
+    /// Upgrade a non-HD wallet to HD. A new BIP39 mnemonic is generated
+    /// unless one is provided.
+    fn upgrade_to_hd(
+        &self,
+        mnemonic: Option<&str>,
+        mnemonic_passphrase: Option<&str>,
+        wallet_passphrase: Option<&str>,
+        rescan: Option<bool>,
+    ) -> Result<bool> {
+        let mut args = [
+            opt_into_json(mnemonic)?,
+            opt_into_json(mnemonic_passphrase)?,
+            opt_into_json(wallet_passphrase)?,
+            opt_into_json(rescan)?,
+        ];
+        let defaults = [into_json("")?, into_json("")?, into_json("")?, null()];
+        self.call("upgradetohd", handle_defaults(&mut args, &defaults))
+    }
+
+    /// Dump the HD seed and BIP39 mnemonic of the wallet.
+    ///
+    /// The result holds the wallet secrets in plain text, so this call is
+    /// only available with the `dump-secrets` feature.
+    #[cfg(feature = "dump-secrets")]
+    fn dump_hd_info(&self) -> Result<json::DumpHdInfoResult> {
+        self.call("dumphdinfo", &[])
+    }
+
     fn list_unspent(
         &self,
         minconf: Option<usize>,
@@ -672,6 +1372,9 @@ pub trait RpcApi: Sized {
         include_unsafe: Option<bool>,
         query_options: Option<json::ListUnspentQueryOptions>,
     ) -> Result<Vec<json::ListUnspentResultEntry>> {
+        for address in addresses.unwrap_or(&[]) {
+            self.check_address_network(address)?;
+        }
         let mut args = [
             opt_into_json(minconf)?,
             opt_into_json(maxconf)?,
@@ -705,6 +1408,19 @@ pub trait RpcApi: Sized {
         self.call("lockunspent", &[true.into()])
     }
 
+    /// List the unspent outputs that are locked with `lockunspent`.
+    fn list_lock_unspent(&self) -> Result<Vec<OutPoint>> {
+        let outputs: Vec<JsonOutPoint> = self.call("listlockunspent", &[])?;
+        Ok(outputs.into_iter().map(|o| o.into()).collect())
+    }
+
+    /// A [CoinSelector] over the unspent outputs of the wallet with at least
+    /// `minconf` confirmations, excluding the locked ones.
+    fn coin_selector(&self, minconf: Option<usize>) -> Result<CoinSelector> {
+        let unspent = self.list_unspent(minconf, None, None, None, None)?;
+        Ok(CoinSelector::new(unspent).without(&self.list_lock_unspent()?))
+    }
+
     fn list_received_by_address(
         &self,
         address_filter: Option<&Address>,
@@ -712,13 +1428,18 @@ pub trait RpcApi: Sized {
         include_empty: Option<bool>,
         include_watchonly: Option<bool>,
     ) -> Result<Vec<json::ListReceivedByAddressResult>> {
+        if let Some(address) = address_filter {
+            self.check_address_network(address)?;
+        }
+        // Dash Core takes an `addlocked` flag before `include_empty`.
         let mut args = [
             opt_into_json(minconf)?,
+            null(),
             opt_into_json(include_empty)?,
             opt_into_json(include_watchonly)?,
             opt_into_json(address_filter)?,
         ];
-        let defaults = [1.into(), false.into(), false.into(), null()];
+        let defaults = [1.into(), false.into(), false.into(), false.into(), null()];
         self.call("listreceivedbyaddress", handle_defaults(&mut args, &defaults))
     }
 
@@ -750,8 +1471,61 @@ pub trait RpcApi: Sized {
         replaceable: Option<bool>,
     ) -> Result<Transaction> {
         let hex: String = self.create_raw_transaction_hex(utxos, outs, locktime, replaceable)?;
-        let bytes: Vec<u8> = FromHex::from_hex(&hex)?;
-        Ok(dashcore::consensus::encode::deserialize(&bytes)?)
+        decode_result("createrawtransaction", None, &hex)
+    }
+
+    /// Create a transaction paying `recipients` from the wallet, signed and
+    /// ready to broadcast.
+    ///
+    /// The inputs are selected from `listunspent` with a [CoinSelector], or by
+    /// the node with `fundrawtransaction` if the options say so, and the
+    /// transaction is signed with `signrawtransactionwithwallet`. Fails with
+    /// [Error::IncompleteSignature] if the wallet can't sign every input.
+    fn create_and_sign_transaction(
+        &self,
+        recipients: &[(Address, Amount)],
+        options: &CreateTransactionOptions,
+    ) -> Result<Transaction> {
+        workflow::create_and_sign_transaction(self, recipients, options)
+    }
+
+    /// Create a transaction paying `recipients` from the wallet, signed by
+    /// an external `signer`, like a hardware wallet, ready to broadcast.
+    ///
+    /// The wallet only needs to watch the addresses of the signer. The inputs
+    /// are always selected by the node with `fundrawtransaction`, including
+    /// the watch-only outputs, the minconf option is ignored. The signer gets
+    /// the derivation paths the wallet knows, see
+    /// [RpcApi::get_signing_inputs].
+    fn create_and_sign_transaction_with_signer<S: Signer + ?Sized>(
+        &self,
+        recipients: &[(Address, Amount)],
+        options: &CreateTransactionOptions,
+        signer: &S,
+    ) -> Result<Transaction> {
+        workflow::create_and_sign_transaction_with_signer(self, recipients, options, signer)
+    }
+
+    /// The inputs of `tx` for a [Signer] to sign, with the outputs they spend
+    /// and the derivation paths of their keys the wallet knows.
+    fn get_signing_inputs(&self, tx: &Transaction) -> Result<Vec<SigningInput>> {
+        signing::get_signing_inputs(self, tx)
+    }
+
+    /// Create a transaction with an `OP_RETURN` output carrying `data`,
+    /// funded and signed by the wallet, to anchor or timestamp the data in
+    /// the chain.
+    ///
+    /// The inputs are always selected by the node with `fundrawtransaction`,
+    /// the minconf option is ignored. Fails with [Error::DataTooLarge] if
+    /// `data` is larger than
+    /// [MAX_OP_RETURN_DATA](workflow::MAX_OP_RETURN_DATA).
+    fn create_data_transaction(
+        &self,
+        data: &[u8],
+        options: &CreateTransactionOptions,
+    ) -> Result<Transaction> {
+        workflow::create_data_transaction(self, data, options)
     }
 
     fn fund_raw_transaction<R: RawTx>(
@@ -765,6 +1539,10 @@ pub trait RpcApi: Sized {
         self.call("fundrawtransaction", handle_defaults(&mut args, &defaults))
     }
 
+    /// Sign a transaction with `signrawtransaction`.
+    ///
+    /// Nodes that replaced it get `signrawtransactionwithkey` when
+    /// `private_keys` are given and `signrawtransactionwithwallet` otherwise.
     #[deprecated]
     fn sign_raw_transaction<R: RawTx>(
         &self,
@@ -773,27 +1551,37 @@ pub trait RpcApi: Sized {
         private_keys: Option<&[PrivateKey]>,
         sighash_type: Option<json::SigHashType>,
     ) -> Result<json::SignRawTransactionResult> {
-        let mut args = [
-            tx.raw_hex().into(),
-            opt_into_json(utxos)?,
-            opt_into_json(private_keys)?,
-            opt_into_json(sighash_type)?,
-        ];
-        let defaults = [empty_arr(), empty_arr(), null()];
-        self.call("signrawtransaction", handle_defaults(&mut args, &defaults))
+        if self.node_version()? >= SIGN_WITH_WALLET_VERSION {
+            return match private_keys {
+                Some(keys) => self.sign_raw_transaction_with_key(tx, keys, utxos, sighash_type),
+                None => self.sign_raw_transaction_with_wallet(tx, utxos, sighash_type),
+            };
+        }
+        sign_raw_transaction_legacy(self, tx, utxos, private_keys, sighash_type)
     }
 
+    /// Sign a transaction with the keys of the wallet.
+    ///
+    /// Nodes that predate `signrawtransactionwithwallet` get
+    /// `signrawtransaction` instead.
     fn sign_raw_transaction_with_wallet<R: RawTx>(
         &self,
         tx: R,
         utxos: Option<&[json::SignRawTransactionInput]>,
         sighash_type: Option<json::SigHashType>,
     ) -> Result<json::SignRawTransactionResult> {
+        if self.node_version()? < SIGN_WITH_WALLET_VERSION {
+            return sign_raw_transaction_legacy(self, tx, utxos, None, sighash_type);
+        }
         let mut args = [tx.raw_hex().into(), opt_into_json(utxos)?, opt_into_json(sighash_type)?];
         let defaults = [empty_arr(), null()];
         self.call("signrawtransactionwithwallet", handle_defaults(&mut args, &defaults))
     }
 
+    /// Sign a transaction with the given keys.
+    ///
+    /// Nodes that predate `signrawtransactionwithkey` get
+    /// `signrawtransaction` instead.
     fn sign_raw_transaction_with_key<R: RawTx>(
         &self,
         tx: R,
@@ -801,6 +1589,9 @@ pub trait RpcApi: Sized {
         prevtxs: Option<&[json::SignRawTransactionInput]>,
         sighash_type: Option<json::SigHashType>,
     ) -> Result<json::SignRawTransactionResult> {
+        if self.node_version()? < SIGN_WITH_WALLET_VERSION {
+            return sign_raw_transaction_legacy(self, tx, prevtxs, Some(privkeys), sighash_type);
+        }
         let mut args = [
             tx.raw_hex().into(),
             into_json(privkeys)?,
@@ -824,16 +1615,43 @@ pub trait RpcApi: Sized {
         self.call("stop", &[])
     }
 
+    /// Sign `message` with the key of `address`, which must be in the
+    /// wallet. Returns the signature encoded in base64.
+    fn sign_message(&self, address: &Address, message: &str) -> Result<String> {
+        self.check_address_network(address)?;
+        self.call("signmessage", &[address.to_string().into(), into_json(message)?])
+    }
+
+    /// Sign `message` with `key`, without a wallet. Returns the signature
+    /// encoded in base64.
+    fn sign_message_with_priv_key(&self, key: &PrivateKey, message: &str) -> Result<String> {
+        self.call("signmessagewithprivkey", &[key.to_wif().into(), into_json(message)?])
+    }
+
     fn verify_message(
         &self,
         address: &Address,
         signature: &Signature,
         message: &str,
     ) -> Result<bool> {
+        self.check_address_network(address)?;
         let args = [address.to_string().into(), signature.to_string().into(), into_json(message)?];
         self.call("verifymessage", &args)
     }
 
+    /// Check that `signature`, encoded in base64 like the result of
+    /// [RpcApi::sign_message], is one of `message` by the key of `address`.
+    fn verify_message_base64(
+        &self,
+        address: &Address,
+        signature: &str,
+        message: &str,
+    ) -> Result<bool> {
+        self.check_address_network(address)?;
+        let args = [address.to_string().into(), into_json(signature)?, into_json(message)?];
+        self.call("verifymessage", &args)
+    }
+
     /// Generate new address under own control
     fn get_new_address(
         &self,
@@ -843,10 +1661,44 @@ pub trait RpcApi: Sized {
         self.call("getnewaddress", &[opt_into_json(label)?, opt_into_json(address_type)?])
     }
 
+    /// Like [RpcApi::get_new_address], but returns the address as given by
+    /// the node, for chains whose address prefixes [Address] doesn't know,
+    /// like a devnet with custom prefixes.
+    fn get_new_address_str(
+        &self,
+        label: Option<&str>,
+        address_type: Option<json::AddressType>,
+    ) -> Result<String> {
+        self.call("getnewaddress", &[opt_into_json(label)?, opt_into_json(address_type)?])
+    }
+
+    /// Get a new address of the wallet for receiving change.
+    fn get_raw_change_address(&self) -> Result<Address> {
+        self.call("getrawchangeaddress", &[])
+    }
+
+    /// Check whether `address` is a valid address of the chain of the node.
+    ///
+    /// Takes the address as given by a user, so an invalid one is reported
+    /// in the result instead of failing to parse.
+    fn validate_address(&self, address: &str) -> Result<json::ValidateAddressResult> {
+        self.call("validateaddress", &[into_json(address)?])
+    }
+
     fn get_address_info(&self, address: &Address) -> Result<json::GetAddressInfoResult> {
+        self.check_address_network(address)?;
         self.call("getaddressinfo", &[address.to_string().into()])
     }
 
+    /// Classify the outputs of `tx` by who owns them: the wallet, as change
+    /// or not, or someone else.
+    ///
+    /// The addresses of the outputs are looked up with one batch of
+    /// `getaddressinfo` calls.
+    fn classify_outputs(&self, tx: &Transaction) -> Result<Vec<ClassifiedOutput>> {
+        ownership::classify_outputs(self, tx)
+    }
+
     /// Mine `block_num` blocks and pay coinbase to `address`
     ///
     /// Returns hashes of the generated blocks
@@ -855,15 +1707,52 @@ pub trait RpcApi: Sized {
         block_num: u64,
         address: &Address,
     ) -> Result<Vec<dashcore::BlockHash>> {
+        self.check_address_network(address)?;
         self.call("generatetoaddress", &[block_num.into(), address.to_string().into()])
     }
 
     /// Mine up to block_num blocks immediately (before the RPC call returns)
     /// to an address in the wallet.
+    ///
+    /// Nodes that deprecated `generate` mine to a new address of the wallet
+    /// with `generatetoaddress` instead.
     fn generate(&self, block_num: u64, maxtries: Option<u64>) -> Result<Vec<dashcore::BlockHash>> {
+        if self.node_version()? >= GENERATE_DEPRECATED_VERSION {
+            let address = self.get_new_address(None, None)?;
+            let mut args = [block_num.into(), address.to_string().into(), opt_into_json(maxtries)?];
+            return self.call("generatetoaddress", handle_defaults(&mut args, &[null()]));
+        }
         self.call("generate", &[block_num.into(), opt_into_json(maxtries)?])
     }
 
+    /// Set the time of the node to `timestamp`, or back to the system time
+    /// if `timestamp` is zero. Only regtest nodes support it.
+    fn set_mock_time(&self, timestamp: u64) -> Result<()> {
+        self.call("setmocktime", &[timestamp.into()])
+    }
+
+    /// Move the time of a regtest node `seconds` past the time of the tip
+    /// with `setmocktime`, then mine `block_num` blocks to `address`.
+    ///
+    /// Time-dependent behavior, like spork activation or the InstantSend
+    /// timeouts, can be tested this way. The mock time stays set, see
+    /// [RpcApi::set_mock_time] to reset it. Fails with [Error::RegtestOnly]
+    /// on other networks.
+    fn advance_time_and_generate(
+        &self,
+        seconds: u64,
+        block_num: u64,
+        address: &Address,
+    ) -> Result<Vec<dashcore::BlockHash>> {
+        let network = self.network()?;
+        if network != Network::Regtest {
+            return Err(Error::RegtestOnly(network));
+        }
+        let tip = self.get_block_header(&self.get_best_block_hash()?)?;
+        self.set_mock_time(u64::from(tip.time) + seconds)?;
+        self.generate_to_address(block_num, address)
+    }
+
     /// Mark a block as invalid by `block_hash`
     fn invalidate_block(&self, block_hash: &dashcore::BlockHash) -> Result<()> {
         self.call("invalidateblock", &[into_json(block_hash)?])
@@ -884,12 +1773,69 @@ pub trait RpcApi: Sized {
         self.call("getmempoolentry", &[into_json(txid)?])
     }
 
+    /// Iterate over the entries of all transactions in the mempool, fetching
+    /// them in batches, see [MempoolEntries].
+    fn iter_mempool_entries(&self) -> Result<MempoolEntries<Self>> {
+        MempoolEntries::new(self)
+    }
+
+    /// Build a histogram of the fee rates in the mempool, with buckets
+    /// `bucket_size` duffs per kB wide.
+    fn mempool_fee_histogram(&self, bucket_size: u64) -> Result<Vec<FeeHistogramBucket>> {
+        let mut histogram = FeeHistogram::new(bucket_size);
+        for entry in self.iter_mempool_entries()? {
+            histogram.add(&entry?.1);
+        }
+        Ok(histogram.buckets())
+    }
+
     /// Get information about all known tips in the block tree, including the
     /// main chain as well as stale branches.
     fn get_chain_tips(&self) -> Result<json::GetChainTipsResult> {
         self.call("getchaintips", &[])
     }
 
+    /// Get the best ChainLock known to the node, `None` if it knows none.
+    fn get_best_chain_lock(&self) -> Result<Option<json::GetBestChainLockResult>> {
+        match self.call("getbestchainlock", &[]) {
+            Ok(chain_lock) => Ok(Some(chain_lock)),
+            Err(Error::JsonRpc(jsonrpc::Error::Rpc(ref e))) if e.code == RPC_INTERNAL_ERROR => {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Submit a ChainLock of the block `block_hash` at `block_height`, with
+    /// its BLS `signature`, like one received by Platform.
+    ///
+    /// Returns the height of the best ChainLock of the node afterwards, which
+    /// can be higher than `block_height`.
+    fn submit_chain_lock(
+        &self,
+        block_hash: &dashcore::BlockHash,
+        signature: &[u8],
+        block_height: u32,
+    ) -> Result<u32> {
+        let args = [into_json(block_hash)?, signature.to_hex().into(), block_height.into()];
+        self.call("submitchainlock", &args)
+    }
+
+    /// Verify the blockchain database, returns true if it is valid.
+    ///
+    /// `check_level` ranges from 0 to 4 and `num_blocks` of 0 checks all
+    /// blocks.
+    fn verify_chain(&self, check_level: Option<u32>, num_blocks: Option<u32>) -> Result<bool> {
+        let mut args = [opt_into_json(check_level)?, opt_into_json(num_blocks)?];
+        self.call("verifychain", handle_defaults(&mut args, &[3.into(), null()]))
+    }
+
+    /// Prune the blockchain up to `height`, which can also be a UNIX
+    /// timestamp. Returns the height of the last pruned block.
+    fn prune_blockchain(&self, height: u64) -> Result<u64> {
+        self.call("pruneblockchain", &[height.into()])
+    }
+
     fn send_to_address(
         &self,
         address: &Address,
@@ -900,9 +1846,36 @@ pub trait RpcApi: Sized {
         replaceable: Option<bool>,
         confirmation_target: Option<u32>,
         estimate_mode: Option<json::EstimateMode>,
+    ) -> Result<dashcore::Txid> {
+        self.check_address_network(address)?;
+        self.send_to_address_str(
+            &address.to_string(),
+            amount,
+            comment,
+            comment_to,
+            subtract_fee,
+            replaceable,
+            confirmation_target,
+            estimate_mode,
+        )
+    }
+
+    /// Like [RpcApi::send_to_address], but takes the address as a string
+    /// that is passed to the node as is, for chains whose address prefixes
+    /// [Address] doesn't know. The network of the address is not checked.
+    fn send_to_address_str(
+        &self,
+        address: &str,
+        amount: Amount,
+        comment: Option<&str>,
+        comment_to: Option<&str>,
+        subtract_fee: Option<bool>,
+        replaceable: Option<bool>,
+        confirmation_target: Option<u32>,
+        estimate_mode: Option<json::EstimateMode>,
     ) -> Result<dashcore::Txid> {
         let mut args = [
-            address.to_string().into(),
+            into_json(address)?,
             into_json(amount.as_btc())?,
             opt_into_json(comment)?,
             opt_into_json(comment_to)?,
@@ -920,6 +1893,91 @@ pub trait RpcApi: Sized {
         )
     }
 
+    /// Send an amount to an address using the legacy InstantSend RPC.
+    ///
+    /// Only available on Dash Core before v0.15, newer nodes lock
+    /// transactions automatically. See `send_to_address_instant`.
+    fn instant_send_to_address(
+        &self,
+        address: &Address,
+        amount: Amount,
+        comment: Option<&str>,
+        comment_to: Option<&str>,
+        subtract_fee: Option<bool>,
+    ) -> Result<dashcore::Txid> {
+        self.check_address_network(address)?;
+        let mut args = [
+            address.to_string().into(),
+            into_json(amount.as_btc())?,
+            opt_into_json(comment)?,
+            opt_into_json(comment_to)?,
+            opt_into_json(subtract_fee)?,
+        ];
+        self.call(
+            "instantsendtoaddress",
+            handle_defaults(&mut args, &["".into(), "".into(), null()]),
+        )
+    }
+
+    /// Send an amount to an address and have it InstantSend locked.
+    ///
+    /// Nodes before v0.15 need the dedicated "instantsendtoaddress" RPC,
+    /// newer ones lock every eligible transaction automatically. The result
+    /// reports which of both was used.
+    fn send_to_address_instant(
+        &self,
+        address: &Address,
+        amount: Amount,
+        comment: Option<&str>,
+        comment_to: Option<&str>,
+        subtract_fee: Option<bool>,
+    ) -> Result<json::SendResult> {
+        if self.node_version()? < 150000 {
+            let txid =
+                self.instant_send_to_address(address, amount, comment, comment_to, subtract_fee)?;
+            Ok(json::SendResult {
+                txid,
+                instant_send: json::InstantSendPath::Legacy,
+            })
+        } else {
+            let txid = self.send_to_address(
+                address,
+                amount,
+                comment,
+                comment_to,
+                subtract_fee,
+                None,
+                None,
+                None,
+            )?;
+            Ok(json::SendResult {
+                txid,
+                instant_send: json::InstantSendPath::Automatic,
+            })
+        }
+    }
+
+    /// Send an amount to an address with the wallet and wait until the
+    /// transaction is locked by InstantSend, the usual flow of a fast
+    /// payment, and return its txid.
+    ///
+    /// The wallet is polled every
+    /// [INSTANT_LOCK_POLL_INTERVAL](workflow::INSTANT_LOCK_POLL_INTERVAL). A
+    /// transaction mined in a ChainLocked block before it was locked counts
+    /// as locked. Fails with [Error::InstantLockTimeout], which holds the txid
+    /// of the transaction that was sent, when it is not locked within
+    /// `timeout`. See
+    /// [send_and_wait_for_islock](::send_and_wait_for_islock) to wait as a
+    /// cancellable operation.
+    fn send_and_wait_for_islock(
+        &self,
+        address: &Address,
+        amount: Amount,
+        timeout: Duration,
+    ) -> Result<dashcore::Txid> {
+        workflow::send_and_wait_for_islock(self, address, amount, timeout, None)
+    }
+
     /// Attempts to add a node to the addnode list.
     /// Nodes added using addnode (or -connect) are protected from DoS disconnection and are not required to be full nodes/support SegWit as other outbound peers are (though such peers will not be synced from).
     fn add_node(&self, addr: &str) -> Result<()> {
@@ -955,6 +2013,10 @@ pub trait RpcApi: Sized {
     }
 
     /// Return known addresses which can potentially be used to find new nodes in the network
+    ///
+    /// Returns `count` addresses, 1 by default, or all of them with 0, as
+    /// the node knows them from gossip: when they were last seen, with which
+    /// services and on which network.
     fn get_node_addresses(
         &self,
         count: Option<usize>,
@@ -1007,12 +2069,56 @@ pub trait RpcApi: Sized {
     ///
     /// Ping command is handled in queue with all other commands, so it
     /// measures processing backlog, not just network ping.
+    ///
+    /// Being one of the cheapest calls, that doesn't touch the chain state,
+    /// it also serves as a health check of the node.
     fn ping(&self) -> Result<()> {
         self.call("ping", &[])
     }
 
-    fn send_raw_transaction<R: RawTx>(&self, tx: R) -> Result<dashcore::Txid> {
-        self.call("sendrawtransaction", &[tx.raw_hex().into()])
+    /// Measure the round-trip time of calls to the node with `samples`
+    /// consecutive [RpcApi::ping] calls, at least one, for health checks or
+    /// to pick the fastest of several nodes.
+    fn measure_latency(&self, samples: usize) -> Result<LatencyStats> {
+        health::measure_latency(self, samples)
+    }
+
+    /// Wait until the node has finished starting up and answers calls.
+    ///
+    /// While a node loads its block index or wallet, calls fail with
+    /// [Error::NodeWarmingUp]. This polls the node every
+    /// `WARMUP_POLL_INTERVAL`, also while it refuses connections because its
+    /// RPC server is not listening yet. Fails with [Error::Timeout] if the
+    /// node is not ready within `timeout`. See
+    /// [wait_until_warmed_up](::wait_until_warmed_up) to wait as a
+    /// cancellable operation.
+    fn wait_until_warmed_up(&self, timeout: Duration) -> Result<()> {
+        warmup::wait_until_warmed_up(self, timeout, None)
+    }
+
+    /// Broadcast a signed transaction.
+    ///
+    /// The node rejects transactions paying a fee rate higher than
+    /// `max_fee_rate` per kB, 0.1 DASH per kB by default, and accepts any fee
+    /// rate with zero.
+    fn send_raw_transaction<R: RawTx>(
+        &self,
+        tx: R,
+        max_fee_rate: Option<Amount>,
+    ) -> Result<dashcore::Txid> {
+        let hex = tx.raw_hex();
+        self.check_transaction_fee(&hex)?;
+        if max_fee_rate.is_some() {
+            let version = self.node_version()?;
+            if version < MAX_FEE_RATE_VERSION {
+                return Err(Error::UnsupportedByNode {
+                    method: "sendrawtransaction",
+                    version,
+                });
+            }
+        }
+        let mut args = [hex.into(), opt_into_json(max_fee_rate.map(|r| r.as_btc()))?];
+        self.call("sendrawtransaction", handle_defaults(&mut args, &[null()]))
     }
 
     fn estimate_smart_fee(
@@ -1110,6 +2216,24 @@ pub trait RpcApi: Sized {
         self.call("finalizepsbt", handle_defaults(&mut args, &[true.into()]))
     }
 
+    /// Add the outputs spent by the inputs of a PSBT, from the UTXO set and
+    /// the mempool of the node, so a PSBT created offline can be signed
+    /// elsewhere.
+    ///
+    /// The scripts and keys of the `descriptors` that match the spent
+    /// outputs are added as well, which needs no wallet.
+    fn utxo_update_psbt(
+        &self,
+        psbt: &str,
+        descriptors: Option<&[json::ScanTxOutRequest]>,
+    ) -> Result<String> {
+        let mut args = [into_json(psbt)?, opt_into_json(descriptors)?];
+        self.call("utxoupdatepsbt", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// Derive the addresses of a descriptor with its checksum, see
+    /// `with_descriptor_checksum`, those of the child indexes of `range` for
+    /// a ranged one.
     fn derive_addresses(&self, descriptor: &str, range: Option<[u32; 2]>) -> Result<Vec<Address>> {
         let mut args = [into_json(descriptor)?, opt_into_json(range)?];
         self.call("deriveaddresses", handle_defaults(&mut args, &[null()]))
@@ -1138,6 +2262,12 @@ pub trait RpcApi: Sized {
         self.call("gettxoutsetinfo", &[])
     }
 
+    /// Write the UTXO set to `path` on the node, see `UtxoSnapshot` to read
+    /// the snapshot back. A relative path is relative to the data directory.
+    fn dump_tx_out_set(&self, path: &str) -> Result<json::DumpTxOutSetResult> {
+        self.call("dumptxoutset", &[into_json(path)?])
+    }
+
     /// Returns information about network traffic, including bytes in, bytes out,
     /// and current time.
     fn get_net_totals(&self) -> Result<json::GetNetTotalsResult> {
@@ -1155,6 +2285,12 @@ pub trait RpcApi: Sized {
         self.call("uptime", &[])
     }
 
+    /// Returns the active ZMQ notifications and the addresses they are
+    /// published on.
+    fn get_zmq_notifications(&self) -> Result<Vec<json::GetZmqNotificationsResult>> {
+        self.call("getzmqnotifications", &[])
+    }
+
     fn scan_tx_out_set_blocking(
         &self,
         descriptors: &[json::ScanTxOutRequest],
@@ -1171,21 +2307,121 @@ pub trait RpcApi: Sized {
 
 
     /// Returns a list of known masternodes
-     fn get_masternode_list(&self, mode: Option<&str>, filter: Option<&str>) -> Result<HashMap<String, json::Masternode>>{ 
+    #[deprecated(note = "use get_masternode_list_mode")]
+    fn get_masternode_list(
+        &self,
+        mode: Option<&str>,
+        filter: Option<&str>,
+    ) -> Result<HashMap<String, json::Masternode>> {
+        let mut args = ["list".into(), opt_into_json(mode)?, opt_into_json(filter)?];
+        self.call("masternode", handle_defaults(&mut args, &["json".into(), null()]))
+    }
+
+    /// Returns the details of all known masternodes, keyed by collateral outpoint
+    ///
+    /// `filter` only keeps masternodes matching a partial string.
+    fn get_masternode_list_json(
+        &self,
+        filter: Option<&str>,
+    ) -> Result<HashMap<String, json::Masternode>> {
+        let mode = json::MasternodeListMode::Json;
         let mut args = ["list".into(), into_json(mode)?, opt_into_json(filter)?];
-        self.call::<HashMap<String, json::Masternode>>("masternode", handle_defaults(&mut args, &["json".into(), null()])) 
+        self.call("masternode", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// Returns a single projection of all known masternodes, keyed by collateral
+    /// outpoint
+    ///
+    /// `filter` only keeps masternodes matching a partial string.
+    fn get_masternode_list_mode(
+        &self,
+        mode: json::MasternodeListMode,
+        filter: Option<&str>,
+    ) -> Result<HashMap<String, json::MasternodeListValue>> {
+        let mut args = ["list".into(), into_json(mode)?, opt_into_json(filter)?];
+        self.call("masternode", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// The outputs of the wallet that can be the collateral of a masternode.
+    fn masternode_outputs(&self) -> Result<Vec<dashcore::OutPoint>> {
+        let outputs: json::GetMasternodeOutputsResult =
+            self.call("masternode", &["outputs".into()])?;
+        Ok(outputs.0)
+    }
+
+    /// The collateral outputs of the wallet, as a map from txid to output
+    /// index.
+    #[deprecated(note = "use masternode_outputs")]
+    fn get_masternode_outputs(&self) -> Result<HashMap<String, String>> {
+        let outputs = self.masternode_outputs()?;
+        Ok(outputs.iter().map(|o| (o.txid.to_string(), o.vout.to_string())).collect())
+    }
+
+    /// Prove that the wallet controls the masternode collateral `outpoint`
+    /// by signing `message` with the key of its address.
+    ///
+    /// The collateral must be listed by [RpcApi::masternode_outputs]. Fails
+    /// with [Error::InvalidCollateralProof] otherwise, or if it doesn't pay
+    /// to a single address.
+    fn prove_collateral_ownership(
+        &self,
+        outpoint: &dashcore::OutPoint,
+        message: &str,
+    ) -> Result<CollateralProof> {
+        collateral::prove(self, outpoint, message)
     }
 
-    /// Returns masternode compatible outputs
-    fn get_masternode_outputs(&self) -> Result<HashMap<String, String>>{ 
-            let mut args = ["outputs".into()];
-            self.call::<HashMap<String, String>>("masternode", handle_defaults(&mut args, &[null()])) 
+    /// Verify a masternode collateral ownership proof, as created by
+    /// [RpcApi::prove_collateral_ownership] on another node, and return the
+    /// amount of the collateral.
+    ///
+    /// The collateral must be unspent, hold the collateral amount of a
+    /// masternode or evonode and pay to the address of the proof, whose key
+    /// must have signed the message. Fails with
+    /// [Error::InvalidCollateralProof] otherwise.
+    fn verify_collateral_proof(&self, proof: &CollateralProof) -> Result<Amount> {
+        collateral::verify(self, proof)
     }
 
-    /// Returns an array of deterministic masternodes and their payments for the specified block
-    fn get_masternode_payments(&self, block_hash: Option<&str>, count: Option<&str>) -> Result<Vec<json::GetMasternodePaymentsResult>>{ 
-            let mut args = ["payments".into(), opt_into_json(block_hash)?, opt_into_json(count)?];
-            self.call::<Vec<json::GetMasternodePaymentsResult>>("masternode", handle_defaults(&mut args, &[null(), null()])) 
+    /// The masternode payments of `count` blocks starting at `block_hash`,
+    /// the chain tip by default. A negative `count` goes back from the block.
+    fn masternode_payments(
+        &self,
+        block_hash: Option<&dashcore::BlockHash>,
+        count: Option<i32>,
+    ) -> Result<Vec<json::GetMasternodePaymentsResult>> {
+        let mut args = ["payments".into(), opt_into_json(block_hash)?, opt_into_json(count)?];
+        self.call("masternode", handle_defaults(&mut args, &[null(), null()]))
+    }
+
+    /// Returns an array of deterministic masternodes and their payments for
+    /// the specified block.
+    #[deprecated(note = "use masternode_payments")]
+    fn get_masternode_payments(
+        &self,
+        block_hash: Option<&str>,
+        count: Option<&str>,
+    ) -> Result<Vec<json::GetMasternodePaymentsResult>> {
+        let block_hash = match block_hash {
+            Some(hash) => Some(hash.parse::<dashcore::BlockHash>()?),
+            None => None,
+        };
+        let count = match count {
+            Some(count) => Some(serde_json::from_str(count)?),
+            None => None,
+        };
+        self.masternode_payments(block_hash.as_ref(), count)
+    }
+
+    /// The masternode expected to be paid in the next block.
+    fn masternode_current(&self) -> Result<json::GetMasternodePayeeResult> {
+        self.call("masternode", &["current".into()])
+    }
+
+    /// The masternode expected to be paid ten blocks after the chain tip,
+    /// the height it is expected at is in the result.
+    fn masternode_winner(&self) -> Result<json::GetMasternodePayeeResult> {
+        self.call("masternode", &["winner".into()])
     }
 
     /// Returns masternode status information
@@ -1221,9 +2457,19 @@ pub trait RpcApi: Sized {
     }
 
     /// Returns information about a specific quorum
-    fn get_quorum_info(&self, llmq_type: u8, quorum_hash: &str, include_sk_share: Option<bool>) -> Result<json::QuorumInfoResult> {
-            let mut args = ["info".into(), into_json(llmq_type)?, into_json(quorum_hash)?, opt_into_json(include_sk_share)?];
-            self.call::<json::QuorumInfoResult>("quorum", handle_defaults(&mut args, &[null()]))
+    fn get_quorum_info(
+        &self,
+        llmq_type: json::LLMQType,
+        quorum_hash: &json::QuorumHash,
+        include_sk_share: Option<bool>,
+    ) -> Result<json::QuorumInfoResult> {
+        let mut args = [
+            "info".into(),
+            into_json(llmq_type)?,
+            into_json(quorum_hash)?,
+            opt_into_json(include_sk_share)?,
+        ];
+        self.call::<json::QuorumInfoResult>("quorum", handle_defaults(&mut args, &[null()]))
     }
 
     /// Returns the status of the current DKG process
@@ -1233,54 +2479,193 @@ pub trait RpcApi: Sized {
     }  
 
     /// Requests threshold-signing for a message
-    fn get_quorum_sign(&self, llmq_type: u8, id: &str, msg_hash: &str, quorum_hash: Option<&str>, submit: Option<bool>) -> Result<json::QuorumSignResult> {
-            let mut args = ["sign".into(), into_json(llmq_type)?, into_json(id)?, into_json(msg_hash)?, opt_into_json(quorum_hash)?, opt_into_json(submit)?];
-            self.call::<json::QuorumSignResult>("quorum", handle_defaults(&mut args, &[null()]))
+    fn get_quorum_sign(
+        &self,
+        llmq_type: json::LLMQType,
+        id: &str,
+        msg_hash: &str,
+        quorum_hash: Option<&json::QuorumHash>,
+        submit: Option<bool>,
+    ) -> Result<json::QuorumSignResult> {
+        let mut args = [
+            "sign".into(),
+            into_json(llmq_type)?,
+            into_json(id)?,
+            into_json(msg_hash)?,
+            opt_into_json(quorum_hash)?,
+            opt_into_json(submit)?,
+        ];
+        self.call::<json::QuorumSignResult>("quorum", handle_defaults(&mut args, &[null()]))
     }
 
     /// Returns the recovered signature for a previous threshold-signing message request
-    fn get_quorum_getrecsig(&self, llmq_type: u8, id: &str, msg_hash: &str) -> Result<json::QuorumSignature> {
-        let mut args = ["getrecsig".into(), into_json(llmq_type)?, into_json(id)?, into_json(msg_hash)?];
+    fn get_quorum_getrecsig(
+        &self,
+        llmq_type: json::LLMQType,
+        id: &str,
+        msg_hash: &str,
+    ) -> Result<json::QuorumSignature> {
+        let mut args =
+            ["getrecsig".into(), into_json(llmq_type)?, into_json(id)?, into_json(msg_hash)?];
         self.call::<json::QuorumSignature>("quorum", handle_defaults(&mut args, &[null()]))
     }
 
     /// Checks for a recovered signature for a previous threshold-signing message request
-    fn get_quorum_hasrecsig(&self, llmq_type: u8, id: &str, msg_hash: &str) -> Result<bool> {
-        let mut args = ["hasrecsig".into(), into_json(llmq_type)?, into_json(id)?, into_json(msg_hash)?];
+    fn get_quorum_hasrecsig(
+        &self,
+        llmq_type: json::LLMQType,
+        id: &str,
+        msg_hash: &str,
+    ) -> Result<bool> {
+        let mut args =
+            ["hasrecsig".into(), into_json(llmq_type)?, into_json(id)?, into_json(msg_hash)?];
         self.call::<bool>("quorum", handle_defaults(&mut args, &[null()]))
     }
 
     /// Checks if there is a conflict for a threshold-signing message request
-    fn get_quorum_isconflicting(&self, llmq_type: u8, id: &str, msg_hash: &str) -> Result<bool> {
-        let mut args = ["isconflicting".into(), into_json(llmq_type)?, into_json(id)?, into_json(msg_hash)?];
+    fn get_quorum_isconflicting(
+        &self,
+        llmq_type: json::LLMQType,
+        id: &str,
+        msg_hash: &str,
+    ) -> Result<bool> {
+        let mut args =
+            ["isconflicting".into(), into_json(llmq_type)?, into_json(id)?, into_json(msg_hash)?];
         self.call::<bool>("quorum", handle_defaults(&mut args, &[null()]))
     }
 
     /// Checks which quorums the given masternode is a member of
-    fn get_quorum_memberof(&self, pro_tx_hash: &str, scan_quorums_count: Option<u8>) -> Result<json::QuorumMemberOfResult> {
-        let mut args = ["memberof".into(), into_json(pro_tx_hash)?, opt_into_json(scan_quorums_count)?];
+    fn get_quorum_memberof(
+        &self,
+        pro_tx_hash: &json::ProTxHash,
+        scan_quorums_count: Option<u8>,
+    ) -> Result<json::QuorumMemberOfResult> {
+        let mut args =
+            ["memberof".into(), into_json(pro_tx_hash)?, opt_into_json(scan_quorums_count)?];
         self.call::<json::QuorumMemberOfResult>("quorum", handle_defaults(&mut args, &[null()]))
     }
 
-    /// Returns quorum rotation information
-    fn get_quorum_rotationinfo(&self, block_request_hash: &str, extra_share: Option<bool>, base_block_hash: Option<&str>) -> Result<json::QuorumRotationInfo> {
-        let mut args = ["rotationinfo".into(), into_json(block_request_hash)?, opt_into_json(extra_share)?, opt_into_json(base_block_hash)?];
-        self.call::<json::QuorumRotationInfo>("quorum", handle_defaults(&mut args, &[false.into(), "".into(), null()]))
+    /// Sign a message with the quorum and wait for the recovered signature.
+    ///
+    /// Signing is asynchronous: the node submits its signature share and the
+    /// signature is only recovered once enough members of the quorum signed.
+    /// Fails with [Error::QuorumSignRefused] when the node refuses to sign,
+    /// like when it is not a member of the quorum, and with [Error::Timeout]
+    /// when the signature is not recovered within `timeout`. See
+    /// [quorum_sign_and_wait](::quorum_sign_and_wait) to wait as a
+    /// cancellable operation.
+    fn quorum_sign_and_wait(
+        &self,
+        llmq_type: json::LLMQType,
+        id: &str,
+        msg_hash: &str,
+        timeout: Duration,
+    ) -> Result<json::QuorumSignature> {
+        quorum::quorum_sign_and_wait(self, llmq_type, id, msg_hash, timeout, None)
+    }
+
+    /// Verify a quorum signature against the quorum that should have signed
+    /// the request `id`, selected with `quorum selectquorum`.
+    ///
+    /// Returns whether the signature is valid and the hash of the quorum it
+    /// was verified against.
+    fn quorum_select_and_verify(
+        &self,
+        llmq_type: json::LLMQType,
+        id: &str,
+        msg_hash: &str,
+        signature: &str,
+    ) -> Result<QuorumVerification> {
+        quorum::quorum_select_and_verify(self, llmq_type, id, msg_hash, signature)
+    }
+
+    /// Returns quorum rotation information, as sent in the `qrinfo` P2P message
+    ///
+    /// The masternode list diffs are calculated against the most recent of
+    /// `base_block_hashes`, or against the genesis block if none is given.
+    fn get_quorum_rotationinfo(
+        &self,
+        block_request_hash: &dashcore::BlockHash,
+        extra_share: Option<bool>,
+        base_block_hashes: &[dashcore::BlockHash],
+    ) -> Result<json::QuorumRotationInfo> {
+        let mut args = vec![
+            "rotationinfo".into(),
+            into_json(block_request_hash)?,
+            into_json(extra_share.unwrap_or(false))?,
+        ];
+        for hash in base_block_hashes {
+            args.push(into_json(hash)?);
+        }
+        self.call::<json::QuorumRotationInfo>("quorum", &args)
     }
 
     /// Returns information about the quorum that would/should sign a request
-    fn get_quorum_selectquorum(&self, llmq_type: u8, id: &str) -> Result<json::SelectQuorumResult> {
+    fn get_quorum_selectquorum(
+        &self,
+        llmq_type: json::LLMQType,
+        id: &str,
+    ) -> Result<json::SelectQuorumResult> {
         let mut args = ["selectquorum".into(), into_json(llmq_type)?, into_json(id)?];
         self.call::<json::SelectQuorumResult>("quorum", handle_defaults(&mut args, &[null()]))
     }
 
     /// Tests if a quorum signature is valid for a request id and a message hash
-    fn get_quorum_verify(&self, llmq_type: u8, id: &str, msg_hash: &str, signature: &str, quorum_hash: Option<&str>, sign_height: Option<u32>) -> Result<bool> {
-            let mut args = ["verify".into(), into_json(llmq_type)?, into_json(id)?, into_json(msg_hash)?, into_json(signature)?, opt_into_json(quorum_hash)?, opt_into_json(sign_height)?];
-            self.call::<bool>("quorum", handle_defaults(&mut args, &[null()]))
+    fn get_quorum_verify(
+        &self,
+        llmq_type: json::LLMQType,
+        id: &str,
+        msg_hash: &str,
+        signature: &str,
+        quorum_hash: Option<&json::QuorumHash>,
+        sign_height: Option<u32>,
+    ) -> Result<bool> {
+        let mut args = [
+            "verify".into(),
+            into_json(llmq_type)?,
+            into_json(id)?,
+            into_json(msg_hash)?,
+            into_json(signature)?,
+            opt_into_json(quorum_hash)?,
+            opt_into_json(sign_height)?,
+        ];
+        self.call::<bool>("quorum", handle_defaults(&mut args, &[null()]))
     }
 
     
+    // --------------------------- Credit pool -------------------------------
+
+    /// Returns the balance of the Platform credit pool at the given block, or
+    /// at the chain tip.
+    ///
+    /// Returns `None` for blocks before the credit pool was activated.
+    fn get_credit_pool_balance(
+        &self,
+        block_hash: Option<&dashcore::BlockHash>,
+    ) -> Result<Option<Amount>> {
+        let block_hash = match block_hash {
+            Some(hash) => *hash,
+            None => self.get_best_block_hash()?,
+        };
+        let info = self.get_block_info(&block_hash)?;
+        Ok(info.cb_tx.and_then(|cb_tx| cb_tx.credit_pool_balance))
+    }
+
+    /// Get the status of asset unlocks, withdrawals of Platform credits, by
+    /// their index.
+    ///
+    /// With a `height`, a withdrawal is only reported as ChainLocked if it
+    /// was at that height and as unknown otherwise, so Platform gets the same
+    /// answer from every node.
+    fn get_asset_unlock_statuses(
+        &self,
+        indexes: &[u64],
+        height: Option<u32>,
+    ) -> Result<Vec<json::AssetUnlockStatusResult>> {
+        let mut args = [into_json(indexes)?, opt_into_json(height)?];
+        self.call("getassetunlockstatuses", handle_defaults(&mut args, &[null()]))
+    }
+
     // --------------------------- ProTx -------------------------------
    
     /// Returns a diff and a proof between two masternode list
@@ -1289,8 +2674,37 @@ pub trait RpcApi: Sized {
         self.call::<json::MasternodeListDiff>("protx", handle_defaults(&mut args, &[null()]))
     }
 
+    /// Fetch the simplified masternode list at the chain tip, see
+    /// [MasternodeList::sync] to keep it up to date.
+    fn masternode_list(&self) -> Result<MasternodeList> {
+        MasternodeList::bootstrap(self)
+    }
+
+    /// Iterate over the masternode list diffs of the blocks of the main chain
+    /// in the given range of heights, each against its parent, see
+    /// [MasternodeListDiffs].
+    fn iter_protx_diffs<R: RangeBounds<u32>>(&self, heights: R) -> MasternodeListDiffs<Self> {
+        MasternodeListDiffs::new(self, heights)
+    }
+
+    /// Override the result of the MNAUTH handshake of the peer `node_id`, as
+    /// if it authenticated as the masternode `pro_tx_hash` with the BLS
+    /// operator key `public_key`.
+    ///
+    /// Only available on regtest, to test masternode connections like the
+    /// ones of Platform.
+    fn mnauth(
+        &self,
+        node_id: u64,
+        pro_tx_hash: &json::ProTxHash,
+        public_key: &[u8],
+    ) -> Result<bool> {
+        let args = [node_id.into(), into_json(pro_tx_hash)?, public_key.to_hex().into()];
+        self.call("mnauth", &args)
+    }
+
     /// Returns a returns detailed information about a deterministic masternode
-    fn get_protx_info(&self, protx_hash: &str) -> Result<json::ProTxInfo> {
+    fn get_protx_info(&self, protx_hash: &json::ProTxHash) -> Result<json::ProTxInfo> {
         let mut args = ["info".into(), into_json(protx_hash)?];
         self.call::<json::ProTxInfo>("protx", handle_defaults(&mut args, &[null()]))
     }
@@ -1302,8 +2716,32 @@ pub trait RpcApi: Sized {
     }
 
     /// Creates a ProRegTx referencing an existing collateral and and sends it to the network
-    fn get_protx_register(&self, collateral_hash: &str, collateral_index: u32, ip_and_port: &str, owner_address: &str, operator_pub_key: &str, voting_address: &str, operator_reward: u32, payout_address: &str, fee_source_address: Option<&str>, submit: Option<bool>) -> Result<json::ProRegTxHash> {
-        let mut args = ["register".into(), into_json(collateral_hash)?, into_json(collateral_index)?, into_json(ip_and_port)?, into_json(owner_address)?, into_json(operator_pub_key)?, into_json(voting_address)?, into_json(operator_reward)?, into_json(payout_address)?, opt_into_json(fee_source_address)?, opt_into_json(submit)?];
+    fn get_protx_register(
+        &self,
+        collateral_hash: &dashcore::Txid,
+        collateral_index: u32,
+        ip_and_port: &str,
+        owner_address: &str,
+        operator_pub_key: &str,
+        voting_address: &str,
+        operator_reward: u32,
+        payout_address: &str,
+        fee_source_address: Option<&str>,
+        submit: Option<bool>,
+    ) -> Result<json::ProRegTxHash> {
+        let mut args = [
+            "register".into(),
+            into_json(collateral_hash)?,
+            into_json(collateral_index)?,
+            into_json(ip_and_port)?,
+            into_json(owner_address)?,
+            into_json(operator_pub_key)?,
+            into_json(voting_address)?,
+            into_json(operator_reward)?,
+            into_json(payout_address)?,
+            opt_into_json(fee_source_address)?,
+            opt_into_json(submit)?,
+        ];
         self.call::<json::ProRegTxHash>("protx", handle_defaults(&mut args, &[null()]))
     }
 
@@ -1313,11 +2751,207 @@ pub trait RpcApi: Sized {
         self.call::<json::ProRegTxHash>("protx", handle_defaults(&mut args, &[null()]))
     }
 
+    /// Creates a ProRegTx for an Evo masternode referencing an existing collateral and
+    /// sends it to the network. Available since Dash Core v19.
+    fn get_protx_register_evo(
+        &self,
+        collateral_hash: &dashcore::Txid,
+        collateral_index: u32,
+        ip_and_port: &str,
+        owner_address: &str,
+        operator_pub_key: &str,
+        voting_address: &str,
+        operator_reward: u32,
+        payout_address: &str,
+        platform_node_id: &str,
+        platform_p2p_port: u16,
+        platform_http_port: u16,
+        fee_source_address: Option<&str>,
+        submit: Option<bool>,
+    ) -> Result<json::ProRegTxHash> {
+        let mut args = [
+            "register_evo".into(),
+            into_json(collateral_hash)?,
+            into_json(collateral_index)?,
+            into_json(ip_and_port)?,
+            into_json(owner_address)?,
+            into_json(operator_pub_key)?,
+            into_json(voting_address)?,
+            into_json(operator_reward)?,
+            into_json(payout_address)?,
+            into_json(platform_node_id)?,
+            into_json(platform_p2p_port)?,
+            into_json(platform_http_port)?,
+            opt_into_json(fee_source_address)?,
+            opt_into_json(submit)?,
+        ];
+        self.call("protx", handle_defaults(&mut args, &["".into(), null()]))
+    }
+
+    /// Creates and funds a ProRegTx for an Evo masternode with the 4,000 DASH collateral
+    /// and sends it to the network. Available since Dash Core v19.
+    fn get_protx_register_fund_evo(
+        &self,
+        collateral_address: &str,
+        ip_and_port: &str,
+        owner_address: &str,
+        operator_pub_key: &str,
+        voting_address: &str,
+        operator_reward: u32,
+        payout_address: &str,
+        platform_node_id: &str,
+        platform_p2p_port: u16,
+        platform_http_port: u16,
+        fund_address: Option<&str>,
+        submit: Option<bool>,
+    ) -> Result<json::ProRegTxHash> {
+        let mut args = [
+            "register_fund_evo".into(),
+            into_json(collateral_address)?,
+            into_json(ip_and_port)?,
+            into_json(owner_address)?,
+            into_json(operator_pub_key)?,
+            into_json(voting_address)?,
+            into_json(operator_reward)?,
+            into_json(payout_address)?,
+            into_json(platform_node_id)?,
+            into_json(platform_p2p_port)?,
+            into_json(platform_http_port)?,
+            opt_into_json(fund_address)?,
+            opt_into_json(submit)?,
+        ];
+        self.call("protx", handle_defaults(&mut args, &["".into(), null()]))
+    }
+
+    /// Creates and sends a ProUpServTx updating the service and Platform fields of an
+    /// Evo masternode. Available since Dash Core v19.
+    fn get_protx_update_service_evo(
+        &self,
+        pro_tx_hash: &json::ProTxHash,
+        ip_and_port: &str,
+        operator_key: &str,
+        platform_node_id: &str,
+        platform_p2p_port: u16,
+        platform_http_port: u16,
+        operator_payout_address: Option<&str>,
+        fee_source_address: Option<&str>,
+    ) -> Result<dashcore::Txid> {
+        let mut args = [
+            "update_service_evo".into(),
+            into_json(pro_tx_hash)?,
+            into_json(ip_and_port)?,
+            into_json(operator_key)?,
+            into_json(platform_node_id)?,
+            into_json(platform_p2p_port)?,
+            into_json(platform_http_port)?,
+            opt_into_json(operator_payout_address)?,
+            opt_into_json(fee_source_address)?,
+        ];
+        self.call("protx", handle_defaults(&mut args, &["".into(), null()]))
+    }
+
+    // --------------------------- Governance -------------------------------
+
+    fn get_governance_info(&self) -> Result<json::GetGovernanceInfoResult> {
+        self.call("getgovernanceinfo", &[])
+    }
+
+    /// The heights and estimated times of the next `n` superblocks, computed
+    /// from the superblock cycle of `getgovernanceinfo`.
+    fn next_superblocks(&self, n: usize) -> Result<Vec<Superblock>> {
+        governance::next_superblocks(self, n)
+    }
+
+    /// Validate the hex encoded data of a governance object.
+    fn gobject_check(&self, data_hex: &str) -> Result<json::GObjectCheckResult> {
+        self.call("gobject", &["check".into(), into_json(data_hex)?])
+    }
+
+    /// Pay the collateral of a governance object, returns the txid of the
+    /// collateral transaction to pass to [RpcApi::gobject_submit].
+    ///
+    /// A proposal has no parent, which is `None`.
+    fn gobject_prepare(
+        &self,
+        parent_hash: Option<&json::GovernanceObjectHash>,
+        revision: u32,
+        time: u64,
+        data_hex: &str,
+    ) -> Result<dashcore::Txid> {
+        let parent = match parent_hash {
+            Some(hash) => into_json(hash)?,
+            None => "0".into(),
+        };
+        let args = ["prepare".into(), parent, revision.into(), time.into(), into_json(data_hex)?];
+        self.call("gobject", &args)
+    }
+
+    /// Submit a governance object once its collateral transaction has enough
+    /// confirmations. The arguments must match the ones given to
+    /// [RpcApi::gobject_prepare].
+    fn gobject_submit(
+        &self,
+        parent_hash: Option<&json::GovernanceObjectHash>,
+        revision: u32,
+        time: u64,
+        data_hex: &str,
+        collateral_txid: &dashcore::Txid,
+    ) -> Result<json::GovernanceObjectHash> {
+        let parent = match parent_hash {
+            Some(hash) => into_json(hash)?,
+            None => "0".into(),
+        };
+        let args = [
+            "submit".into(),
+            parent,
+            revision.into(),
+            time.into(),
+            into_json(data_hex)?,
+            into_json(collateral_txid)?,
+        ];
+        self.call("gobject", &args)
+    }
+
+    /// The current votes on a governance object by their vote hash, one per
+    /// masternode and signal.
+    fn gobject_votes(
+        &self,
+        hash: &json::GovernanceObjectHash,
+    ) -> Result<HashMap<dashcore::hashes::sha256d::Hash, json::GovernanceVote>> {
+        self.call("gobject", &["getcurrentvotes".into(), into_json(hash)?])
+    }
+
+    /// Count the current votes on a governance object, see [VoteTally].
+    fn gobject_vote_tally(&self, hash: &json::GovernanceObjectHash) -> Result<VoteTally> {
+        Ok(VoteTally::from_votes(self.gobject_votes(hash)?.values()))
+    }
 }
 
 /// Client implements a JSON-RPC client for the Dash Core daemon or compatible APIs.
+///
+/// Every request gets a fresh id from a counter that is shared between all
+/// threads using the client, so concurrent and batched requests never share
+/// an id.
+///
+/// The version, the [Capabilities] and the [Network] of the node are detected
+/// on the first call that depends on them and cached for the lifetime of the
+/// client.
 pub struct Client {
     client: jsonrpc::client::Client,
+    next_id: AtomicUsize,
+    strict_jsonrpc: bool,
+    /// The detected node version, zero until it is detected.
+    node_version: AtomicUsize,
+    capabilities: Mutex<Option<Capabilities>>,
+    network: Mutex<Option<Network>>,
+    validate_addresses: bool,
+    verify_blocks: bool,
+    max_fee: Option<Amount>,
+    slow_call_threshold: Option<Duration>,
+    rate_limiter: Option<RateLimiter>,
+    in_flight_limit: Option<InFlightLimit>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<RpcMetrics>,
 }
 
 impl fmt::Debug for Client {
@@ -1332,20 +2966,176 @@ impl Client {
     /// Can only return [Err] when using cookie authentication.
     pub fn new(url: &str, auth: Auth) -> Result<Self> {
         let (user, pass) = auth.get_user_pass()?;
-        jsonrpc::client::Client::simple_http(url, user, pass)
-            .map(|client| Client {
-                client,
-            })
+        HttpTransport::new(url, user, pass)
+            .map(Client::from_transport)
+            .map_err(|e| super::error::Error::JsonRpc(e.into()))
+    }
+
+    /// Creates a client to a dashd JSON-RPC server that is reached through an
+    /// HTTP or a SOCKS5 [Proxy], such as Tor.
+    ///
+    /// Can only return [Err] when using cookie authentication.
+    pub fn new_with_proxy(url: &str, auth: Auth, proxy: Proxy) -> Result<Self> {
+        let (user, pass) = auth.get_user_pass()?;
+        HttpTransport::new(url, user, pass)
+            .map(|transport| Client::from_transport(transport.with_proxy(proxy)))
             .map_err(|e| super::error::Error::JsonRpc(e.into()))
     }
 
+    /// Create a new Client using the given transport, such as a configured
+    /// [HttpTransport].
+    pub fn from_transport<T: jsonrpc::Transport>(transport: T) -> Client {
+        Client::from_jsonrpc(jsonrpc::client::Client::with_transport(transport))
+    }
+
     /// Create a new Client using the given [jsonrpc::Client].
     pub fn from_jsonrpc(client: jsonrpc::client::Client) -> Client {
         Client {
             client,
+            next_id: AtomicUsize::new(1),
+            strict_jsonrpc: false,
+            node_version: AtomicUsize::new(0),
+            capabilities: Mutex::new(None),
+            network: Mutex::new(None),
+            validate_addresses: false,
+            verify_blocks: false,
+            max_fee: None,
+            slow_call_threshold: None,
+            rate_limiter: None,
+            in_flight_limit: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
         }
     }
 
+    /// Enable strict JSON-RPC 2.0 mode.
+    ///
+    /// In strict mode, requests are always framed as JSON-RPC 2.0 and every
+    /// response must carry `"jsonrpc": "2.0"` and the id of its request.
+    /// Other responses are rejected with a [jsonrpc::Error::VersionMismatch]
+    /// or [jsonrpc::Error::NonceMismatch] error.
+    pub fn with_strict_jsonrpc(mut self, strict: bool) -> Self {
+        self.strict_jsonrpc = strict;
+        self
+    }
+
+    /// Check that the addresses passed to the node belong to its chain.
+    ///
+    /// When enabled, calls that take an [Address] of another network fail
+    /// with [Error::NetworkMismatch] before they are sent. This costs one
+    /// `getblockchaininfo` call to detect the network of the node.
+    pub fn with_address_validation(mut self, validate: bool) -> Self {
+        self.validate_addresses = validate;
+        self
+    }
+
+    /// Check that the blocks and headers returned by the node are the ones
+    /// requested.
+    ///
+    /// When enabled, the hash of every block or header fetched by its hash is
+    /// recomputed and compared to the requested hash, and the transactions of
+    /// raw blocks are checked against their merkle root. A mismatch, as caused
+    /// by a corrupting proxy or a misbehaving endpoint, fails with
+    /// [Error::BlockHashMismatch] or [Error::InvalidMerkleRoot].
+    pub fn with_block_verification(mut self, verify: bool) -> Self {
+        self.verify_blocks = verify;
+        self
+    }
+
+    /// Refuse to broadcast raw transactions paying a fee above `max_fee`.
+    ///
+    /// When set, [RpcApi::send_raw_transaction] computes the fee of the
+    /// transaction from the outputs it spends and fails with
+    /// [Error::FeeTooHigh] if it is above `max_fee`, which protects against
+    /// transactions built with a wrong amount or change output, or with
+    /// [Error::UnknownFee] if it can't be computed. This costs a batch of
+    /// `getrawtransaction` or `gettxout` calls per transaction.
+    pub fn with_max_fee(mut self, max_fee: Amount) -> Self {
+        self.max_fee = Some(max_fee);
+        self
+    }
+
+    /// Log the calls that take longer than `threshold`.
+    ///
+    /// Every call or batch whose response takes longer than `threshold` to
+    /// arrive is logged at the warn level with the `dashcore_rpc::slow`
+    /// target, as `method=<method> duration_ms=<duration>`. A batch is
+    /// logged once, with the distinct methods it calls joined by commas.
+    pub fn with_slow_call_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_call_threshold = Some(threshold);
+        self
+    }
+
+    /// Make at most `calls_per_second` calls per second, on average.
+    ///
+    /// Calls are delayed to follow a token bucket that holds up to `burst`
+    /// calls, so that up to `burst` calls can be made at once after the
+    /// client was idle. Each call of a batch counts. Protects the RPC work
+    /// queue of the node, sized with `-rpcworkqueue`, from aggressive callers
+    /// such as indexers.
+    ///
+    /// # Panics
+    ///
+    /// If `calls_per_second` is not positive.
+    pub fn with_rate_limit(mut self, calls_per_second: f64, burst: u32) -> Self {
+        self.rate_limiter = Some(RateLimiter::new(calls_per_second, burst));
+        self
+    }
+
+    /// Send at most `max` requests at the same time when the client is shared
+    /// between threads, a batch being one request.
+    ///
+    /// Further requests wait until one of those in flight completes.
+    pub fn with_max_in_flight(mut self, max: usize) -> Self {
+        self.in_flight_limit = Some(InFlightLimit::new(max));
+        self
+    }
+
+    /// Record the calls in `metrics`, see [RpcMetrics].
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: RpcMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Use `network` as the network of the node instead of detecting it.
+    ///
+    /// Needed for nodes on a chain whose name is not known, like a custom
+    /// chain, which [RpcApi::network] fails to detect.
+    pub fn with_network(self, network: Network) -> Self {
+        *self.network.lock().unwrap() = Some(network);
+        self
+    }
+
+    /// Build a request for `cmd` with a fresh request id.
+    fn build_request<'a>(
+        &self,
+        cmd: &'a str,
+        params: &'a [Box<serde_json::value::RawValue>],
+    ) -> jsonrpc::Request<'a> {
+        let mut req = self.client.build_request(cmd, params);
+        req.id = self.next_id.fetch_add(1, Ordering::Relaxed).into();
+        if self.strict_jsonrpc {
+            req.jsonrpc = Some("2.0");
+        }
+        req
+    }
+
+    /// In strict mode, check that `resp` is a JSON-RPC 2.0 response to the
+    /// request with the given id.
+    fn check_response(&self, id: &serde_json::Value, resp: &jsonrpc::Response) -> Result<()> {
+        if !self.strict_jsonrpc {
+            return Ok(());
+        }
+        if resp.jsonrpc.as_ref().map(|v| v.as_str()) != Some("2.0") {
+            return Err(Error::JsonRpc(jsonrpc::Error::VersionMismatch));
+        }
+        if resp.id != *id {
+            return Err(Error::JsonRpc(jsonrpc::Error::NonceMismatch));
+        }
+        Ok(())
+    }
+
     /// Get the underlying JSONRPC client.
     pub fn get_jsonrpc_client(&self) -> &jsonrpc::client::Client {
         &self.client
@@ -1359,23 +3149,198 @@ impl RpcApi for Client {
         cmd: &str,
         args: &[serde_json::Value],
     ) -> Result<T> {
-        let raw_args: Vec<_> = args
-            .iter()
-            .map(|a| {
-                let json_string = serde_json::to_string(a)?;
-                serde_json::value::RawValue::from_string(json_string) // we can't use to_raw_value here due to compat with Rust 1.29
-            })
-            .map(|a| a.map_err(|e| Error::Json(e)))
-            .collect::<Result<Vec<_>>>()?;
-        let req = self.client.build_request(&cmd, &raw_args);
+        let raw_args = raw_args(args)?;
+        let req = self.build_request(cmd, &raw_args);
+        let id = req.id.clone();
         if log_enabled!(Debug) {
             debug!(target: "dashcore_rpc", "JSON-RPC request: {} {}", cmd, serde_json::Value::from(args));
         }
 
+        if let Some(ref limiter) = self.rate_limiter {
+            limiter.acquire(1);
+        }
+        let guard = self.in_flight_limit.as_ref().map(InFlightLimit::acquire);
+        let start = Instant::now();
         let resp = self.client.send_request(req).map_err(Error::from);
+        drop(guard);
+        let elapsed = start.elapsed();
+        log_slow_call(self.slow_call_threshold, iter::once(cmd), elapsed);
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(ref metrics) = self.metrics {
+                metrics.observe_latency(cmd, elapsed);
+                metrics.observe_response(cmd, resp.as_ref().ok());
+            }
+        }
         log_response(cmd, &resp);
-        Ok(resp?.result()?)
+        let resp = resp?;
+        self.check_response(&id, &resp)?;
+        Ok(resp.result()?)
     }
+
+    /// Call several rpcs in a single JSON-RPC batch request
+    fn call_batch<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        calls: &[(&str, Vec<serde_json::Value>)],
+    ) -> Result<Vec<Result<T>>> {
+        if calls.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let params =
+            calls.iter().map(|&(_, ref args)| raw_args(args)).collect::<Result<Vec<_>>>()?;
+        let reqs: Vec<_> = calls
+            .iter()
+            .zip(params.iter())
+            .map(|(&(cmd, _), args)| self.build_request(cmd, args))
+            .collect();
+        if log_enabled!(Debug) {
+            debug!(target: "dashcore_rpc", "JSON-RPC batch request of {} calls", calls.len());
+        }
+
+        if let Some(ref limiter) = self.rate_limiter {
+            limiter.acquire(calls.len());
+        }
+        let guard = self.in_flight_limit.as_ref().map(InFlightLimit::acquire);
+        let start = Instant::now();
+        let resps = self.client.send_batch(&reqs);
+        drop(guard);
+        let elapsed = start.elapsed();
+        log_slow_call(self.slow_call_threshold, calls.iter().map(|&(cmd, _)| cmd), elapsed);
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(ref metrics) = self.metrics {
+                metrics.observe_latency(BATCH_METHOD_LABEL, elapsed);
+                for (i, &(method, _)) in calls.iter().enumerate() {
+                    let resp = resps.as_ref().ok().and_then(|r| r.get(i)).and_then(|r| r.as_ref());
+                    metrics.observe_response(method, resp);
+                }
+            }
+        }
+        let resps = resps?;
+        Ok(resps
+            .into_iter()
+            .zip(calls.iter().zip(reqs.iter()))
+            .map(|(resp, (&(cmd, _), req))| {
+                let resp = resp.ok_or(Error::UnexpectedStructure);
+                log_response(cmd, &resp);
+                let resp = resp?;
+                self.check_response(&req.id, &resp)?;
+                Ok(resp.result()?)
+            })
+            .collect())
+    }
+
+    fn node_version(&self) -> Result<usize> {
+        match self.node_version.load(Ordering::Relaxed) {
+            0 => {
+                let version = self.version()?;
+                self.node_version.store(version, Ordering::Relaxed);
+                Ok(version)
+            }
+            version => Ok(version),
+        }
+    }
+
+    fn capabilities(&self) -> Result<Capabilities> {
+        let mut cached = self.capabilities.lock().unwrap();
+        if let Some(ref capabilities) = *cached {
+            return Ok(capabilities.clone());
+        }
+        let help: String = self.call("help", &[])?;
+        let capabilities = Capabilities::from_help(&help);
+        *cached = Some(capabilities.clone());
+        Ok(capabilities)
+    }
+
+    fn network(&self) -> Result<Network> {
+        let mut cached = self.network.lock().unwrap();
+        if let Some(network) = *cached {
+            return Ok(network);
+        }
+        let network = detect_network(self)?;
+        *cached = Some(network);
+        Ok(network)
+    }
+
+    fn check_address_network(&self, address: &Address) -> Result<()> {
+        if !self.validate_addresses {
+            return Ok(());
+        }
+        check_network(address.network, self.network()?)
+    }
+
+    fn check_block_header(
+        &self,
+        requested: &dashcore::BlockHash,
+        header: &BlockHeader,
+    ) -> Result<()> {
+        if !self.verify_blocks {
+            return Ok(());
+        }
+        verify_block_header(requested, header)
+    }
+
+    fn check_block(&self, requested: &dashcore::BlockHash, block: &Block) -> Result<()> {
+        if !self.verify_blocks {
+            return Ok(());
+        }
+        verify_block_header(requested, &block.header)?;
+        if !block.check_merkle_root() {
+            return Err(Error::InvalidMerkleRoot(*requested));
+        }
+        Ok(())
+    }
+
+    fn verifies_blocks(&self) -> bool {
+        self.verify_blocks
+    }
+
+    fn check_transaction_fee(&self, hex: &str) -> Result<()> {
+        let max_fee = match self.max_fee {
+            Some(max_fee) => max_fee,
+            None => return Ok(()),
+        };
+        let tx = TxWithPrevouts::resolve(self, decode_hex(hex)?)?;
+        match tx.fee() {
+            Some(fee) if fee > max_fee => Err(Error::FeeTooHigh {
+                txid: tx.tx.txid(),
+                fee,
+                max_fee,
+            }),
+            Some(_) => Ok(()),
+            None => Err(Error::UnknownFee(tx.tx.txid())),
+        }
+    }
+}
+
+/// Sign a transaction with the `signrawtransaction` call of older nodes.
+fn sign_raw_transaction_legacy<C: RpcApi, R: RawTx>(
+    rpc: &C,
+    tx: R,
+    utxos: Option<&[json::SignRawTransactionInput]>,
+    private_keys: Option<&[PrivateKey]>,
+    sighash_type: Option<json::SigHashType>,
+) -> Result<json::SignRawTransactionResult> {
+    let mut args = [
+        tx.raw_hex().into(),
+        opt_into_json(utxos)?,
+        opt_into_json(private_keys)?,
+        opt_into_json(sighash_type)?,
+    ];
+    let defaults = [empty_arr(), empty_arr(), null()];
+    rpc.call("signrawtransaction", handle_defaults(&mut args, &defaults))
+}
+
+/// Convert the arguments of a call into the raw JSON values jsonrpc expects.
+fn raw_args(args: &[serde_json::Value]) -> Result<Vec<Box<serde_json::value::RawValue>>> {
+    args.iter()
+        .map(|a| {
+            let json_string = serde_json::to_string(a)?;
+            serde_json::value::RawValue::from_string(json_string) // we can't use to_raw_value here due to compat with Rust 1.29
+        })
+        .map(|a| a.map_err(|e| Error::Json(e)))
+        .collect()
 }
 
 fn log_response(cmd: &str, resp: &Result<jsonrpc::Response>) {
@@ -1405,11 +3370,49 @@ fn log_response(cmd: &str, resp: &Result<jsonrpc::Response>) {
     }
 }
 
+/// Log a call or batch of calls to `methods` that took `elapsed`, if that is
+/// above the slow call threshold.
+fn log_slow_call<'a, I>(threshold: Option<Duration>, methods: I, elapsed: Duration)
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    if let Some(message) = slow_call_message(threshold, methods, elapsed) {
+        warn!(target: "dashcore_rpc::slow", "{}", message);
+    }
+}
+
+/// The message logged by [log_slow_call], if any. The methods are only
+/// looked at for a slow call.
+fn slow_call_message<'a, I>(
+    threshold: Option<Duration>,
+    methods: I,
+    elapsed: Duration,
+) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    match threshold {
+        Some(threshold) if elapsed > threshold => {
+            let mut distinct: Vec<&str> = Vec::new();
+            for method in methods {
+                if !distinct.contains(&method) {
+                    distinct.push(method);
+                }
+            }
+            let millis = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+            Some(format!("method={} duration_ms={}", distinct.join(","), millis))
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use dashcore;
     use serde_json;
+    use std::cell::RefCell;
+    use testutil::MockRpc;
 
     #[test]
     fn test_raw_tx() {
@@ -1417,10 +3420,10 @@ mod tests {
         let client = Client::new("http://localhost/".into(), Auth::None).unwrap();
         let tx: dashcore::Transaction = encode::deserialize(&Vec::<u8>::from_hex("0200000001586bd02815cf5faabfec986a4e50d25dbee089bd2758621e61c5fab06c334af0000000006b483045022100e85425f6d7c589972ee061413bcf08dc8c8e589ce37b217535a42af924f0e4d602205c9ba9cb14ef15513c9d946fa1c4b797883e748e8c32171bdf6166583946e35c012103dae30a4d7870cd87b45dd53e6012f71318fdd059c1c2623b8cc73f8af287bb2dfeffffff021dc4260c010000001976a914f602e88b2b5901d8aab15ebe4a97cf92ec6e03b388ac00e1f505000000001976a914687ffeffe8cf4e4c038da46a9b1d37db385a472d88acfd211500").unwrap()).unwrap();
 
-        assert!(client.send_raw_transaction(&tx).is_err());
-        assert!(client.send_raw_transaction(&encode::serialize(&tx)).is_err());
-        assert!(client.send_raw_transaction("deadbeef").is_err());
-        assert!(client.send_raw_transaction("deadbeef".to_owned()).is_err());
+        assert!(client.send_raw_transaction(&tx, None).is_err());
+        assert!(client.send_raw_transaction(&encode::serialize(&tx), None).is_err());
+        assert!(client.send_raw_transaction("deadbeef", None).is_err());
+        assert!(client.send_raw_transaction("deadbeef".to_owned(), None).is_err());
     }
 
     fn test_handle_defaults_inner() -> Result<()> {
@@ -1479,4 +3482,463 @@ mod tests {
     fn test_handle_defaults() {
         test_handle_defaults_inner().unwrap();
     }
+
+    /// A transport answering every request with its own id.
+    struct EchoTransport {
+        jsonrpc: Option<&'static str>,
+        id_offset: u64,
+    }
+
+    impl EchoTransport {
+        fn respond(&self, req: &jsonrpc::Request) -> jsonrpc::Response {
+            let id = req.id.as_u64().unwrap();
+            jsonrpc::Response {
+                result: Some(serde_json::value::RawValue::from_string(id.to_string()).unwrap()),
+                error: None,
+                id: (id + self.id_offset).into(),
+                jsonrpc: self.jsonrpc.map(|v| v.to_owned()),
+            }
+        }
+    }
+
+    impl jsonrpc::Transport for EchoTransport {
+        fn send_request(
+            &self,
+            req: jsonrpc::Request,
+        ) -> result::Result<jsonrpc::Response, jsonrpc::Error> {
+            Ok(self.respond(&req))
+        }
+
+        fn send_batch(
+            &self,
+            reqs: &[jsonrpc::Request],
+        ) -> result::Result<Vec<jsonrpc::Response>, jsonrpc::Error> {
+            Ok(reqs.iter().map(|r| self.respond(r)).collect())
+        }
+
+        fn fmt_target(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "echo")
+        }
+    }
+
+    fn echo_client(jsonrpc: Option<&'static str>, id_offset: u64) -> Client {
+        Client::from_jsonrpc(jsonrpc::Client::with_transport(EchoTransport {
+            jsonrpc,
+            id_offset,
+        }))
+    }
+
+    #[test]
+    fn test_request_ids() {
+        use std::collections::HashSet;
+        use std::sync::Arc;
+        use std::thread;
+
+        let client = Arc::new(echo_client(Some("2.0"), 0).with_strict_jsonrpc(true));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let client = client.clone();
+                thread::spawn(move || {
+                    (0..50).map(|_| client.call::<u64>("echo", &[]).unwrap()).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut ids = HashSet::new();
+        for thread in threads {
+            for id in thread.join().unwrap() {
+                assert!(ids.insert(id));
+            }
+        }
+        assert_eq!(ids.len(), 200);
+
+        let calls = vec![("echo", vec![]), ("echo", vec![])];
+        let batch: Vec<u64> =
+            client.call_batch(&calls).unwrap().into_iter().map(|r| r.unwrap()).collect();
+        assert_ne!(batch[0], batch[1]);
+    }
+
+    #[test]
+    fn test_slow_call_threshold() {
+        let client = echo_client(None, 0).with_slow_call_threshold(Duration::from_secs(0));
+        assert!(client.call::<u64>("echo", &[]).is_ok());
+        let calls = vec![("echo", vec![]), ("echo", vec![])];
+        assert_eq!(client.call_batch::<u64>(&calls).unwrap().len(), 2);
+
+        let methods = ["getblock", "getblockhash", "getblock"];
+        let second = Duration::from_secs(1);
+        let message = |threshold, millis| {
+            slow_call_message(threshold, methods.iter().cloned(), Duration::from_millis(millis))
+        };
+        assert_eq!(message(None, 5000), None);
+        assert_eq!(message(Some(second), 1000), None);
+        assert_eq!(
+            message(Some(second), 1500).unwrap(),
+            "method=getblock,getblockhash duration_ms=1500"
+        );
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let client = echo_client(None, 0).with_rate_limit(100.0, 1).with_max_in_flight(1);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert!(client.call::<u64>("echo", &[]).is_ok());
+        }
+        let calls = vec![("echo", vec![]), ("echo", vec![])];
+        assert_eq!(client.call_batch::<u64>(&calls).unwrap().len(), 2);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_strict_jsonrpc() {
+        let legacy = echo_client(None, 0);
+        assert!(legacy.call::<u64>("echo", &[]).is_ok());
+        match legacy.with_strict_jsonrpc(true).call::<u64>("echo", &[]) {
+            Err(Error::JsonRpc(jsonrpc::Error::VersionMismatch)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let mismatched = echo_client(Some("2.0"), 1);
+        assert!(mismatched.call::<u64>("echo", &[]).is_ok());
+        match mismatched.with_strict_jsonrpc(true).call::<u64>("echo", &[]) {
+            Err(Error::JsonRpc(jsonrpc::Error::NonceMismatch)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    /// A node with `txindex` synced and the block filter index syncing.
+    struct IndexedNode;
+
+    impl MockRpc for IndexedNode {
+        fn respond(&self, cmd: &str, _: &[serde_json::Value]) -> Result<String> {
+            let value = match cmd {
+                "help" => "getindexinfo ( \"index_name\" )".into(),
+                "getindexinfo" => serde_json::from_str(
+                    r#"{"txindex": {"synced": true, "best_block_height": 100},
+                        "basic block filter index": {"synced": false, "best_block_height": 50}}"#,
+                )?,
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_require_indexes() {
+        let node = IndexedNode;
+        assert_eq!(node.get_index_info(None).unwrap()["txindex"].best_block_height, 100);
+        assert!(node.require_indexes(&["txindex"]).is_ok());
+        match node.require_indexes(&["txindex", "basic block filter index"]) {
+            Err(Error::IndexUnavailable {
+                name,
+                best_block_height: Some(50),
+            }) => assert_eq!(name, "basic block filter index"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match node.require_indexes(&["coinstatsindex"]) {
+            Err(Error::IndexUnavailable {
+                best_block_height: None,
+                ..
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_block_verification() {
+        let genesis = dashcore::blockdata::constants::genesis_block(Network::Dash);
+        let hash = genesis.block_hash();
+        let other = dashcore::BlockHash::default();
+
+        // Verification is opt-in.
+        let client = echo_client(None, 0);
+        assert!(!client.verifies_blocks());
+        assert!(client.check_block_header(&other, &genesis.header).is_ok());
+
+        let client = client.with_block_verification(true);
+        assert!(client.verifies_blocks());
+        assert!(client.check_block_header(&hash, &genesis.header).is_ok());
+        assert!(client.check_block(&hash, &genesis).is_ok());
+        match client.check_block(&other, &genesis) {
+            Err(Error::BlockHashMismatch {
+                requested,
+                found,
+            }) => {
+                assert_eq!(requested, other);
+                assert_eq!(found, hash);
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let mut tampered = genesis.clone();
+        tampered.txdata[0].lock_time += 1;
+        match client.check_block(&hash, &tampered) {
+            Err(Error::InvalidMerkleRoot(h)) => assert_eq!(h, hash),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_max_fee() {
+        let genesis = dashcore::blockdata::constants::genesis_block(Network::Dash);
+        let coinbase = dashcore::consensus::encode::serialize(&genesis.txdata[0]).to_hex();
+
+        // The transaction is not even decoded without a maximum fee.
+        let client = echo_client(None, 0);
+        assert!(client.check_transaction_fee("deadbeef").is_ok());
+
+        // A coinbase transaction spends nothing to look up, but its fee is
+        // unknown.
+        let client = client.with_max_fee(Amount::from_sat(1000));
+        match client.check_transaction_fee(&coinbase) {
+            Err(Error::UnknownFee(txid)) => assert_eq!(txid, genesis.txdata[0].txid()),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(client.check_transaction_fee("deadbeef").is_err());
+    }
+
+    #[test]
+    fn test_network() {
+        assert_eq!(network_from_chain("main"), Some(Network::Dash));
+        assert_eq!(network_from_chain("test"), Some(Network::Testnet));
+        assert_eq!(network_from_chain("regtest"), Some(Network::Regtest));
+        assert_eq!(network_from_chain("devnet-mobile"), Some(Network::Devnet));
+        assert_eq!(network_from_chain("signet"), None);
+
+        assert!(check_network(Network::Dash, Network::Dash).is_ok());
+        assert!(check_network(Network::Testnet, Network::Regtest).is_ok());
+        assert!(check_network(Network::Testnet, Network::Devnet).is_ok());
+        match check_network(Network::Dash, Network::Testnet) {
+            Err(Error::NetworkMismatch {
+                address: Network::Dash,
+                node: Network::Testnet,
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(check_network(Network::Regtest, Network::Dash).is_err());
+
+        // Validation is opt-in, so no call is made without it.
+        let client = echo_client(None, 0);
+        let address = Address {
+            network: Network::Dash,
+            payload: dashcore::util::address::Payload::ScriptHash(Default::default()),
+        };
+        assert!(client.check_address_network(&address).is_ok());
+
+        // A network given up front is not detected.
+        let client = client.with_network(Network::Devnet).with_address_validation(true);
+        assert_eq!(client.network().unwrap(), Network::Devnet);
+        match client.check_address_network(&address) {
+            Err(Error::NetworkMismatch {
+                address: Network::Dash,
+                node: Network::Devnet,
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        let address = Address {
+            network: Network::Testnet,
+            ..address
+        };
+        assert!(client.check_address_network(&address).is_ok());
+    }
+
+    /// A wallet on a chain with address prefixes unknown to [Address].
+    struct CustomChainWallet {
+        calls: RefCell<Vec<(String, Vec<serde_json::Value>)>>,
+    }
+
+    impl MockRpc for CustomChainWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            self.calls.borrow_mut().push((cmd.to_owned(), args.to_vec()));
+            let value = match cmd {
+                "getnewaddress" => "Xc1mt2tXmHgjeKtq7NwGkdmJ4jsbM6Bwr3".into(),
+                "sendtoaddress" => serde_json::to_value(dashcore::Txid::default())?,
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_address_passthrough() {
+        let wallet = CustomChainWallet {
+            calls: RefCell::new(vec![]),
+        };
+        let address = wallet.get_new_address_str(None, None).unwrap();
+        assert_eq!(address, "Xc1mt2tXmHgjeKtq7NwGkdmJ4jsbM6Bwr3");
+        let amount = Amount::from_sat(1000);
+        let txid = wallet.send_to_address_str(&address, amount, None, None, None, None, None, None);
+        assert_eq!(txid.unwrap(), dashcore::Txid::default());
+
+        let calls = wallet.calls.borrow();
+        assert_eq!(calls[1].0, "sendtoaddress");
+        assert_eq!(calls[1].1[0], "Xc1mt2tXmHgjeKtq7NwGkdmJ4jsbM6Bwr3");
+        assert_eq!(calls[1].1[1], 0.00001);
+    }
+
+    /// A masternode wallet that records the arguments of its calls.
+    struct MasternodeWallet {
+        args: RefCell<Vec<Vec<serde_json::Value>>>,
+    }
+
+    impl MockRpc for MasternodeWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            assert_eq!(cmd, "masternode");
+            self.args.borrow_mut().push(args.to_vec());
+            let json = match args[0].as_str() {
+                Some("outputs") => format!(r#"["{}-1"]"#, dashcore::Txid::default()),
+                Some("payments") => "[]".to_owned(),
+                Some("list") => "{}".to_owned(),
+                _ => panic!("unexpected call: {:?}", args),
+            };
+            Ok(json)
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_masternode_calls() {
+        let wallet = MasternodeWallet {
+            args: RefCell::new(vec![]),
+        };
+        let outputs = wallet.get_masternode_outputs().unwrap();
+        assert_eq!(outputs[&dashcore::Txid::default().to_string()], "1");
+
+        let hash = dashcore::BlockHash::default();
+        assert!(wallet.get_masternode_payments(Some(&hash.to_string()), Some("-2")).is_ok());
+        assert!(wallet.get_masternode_payments(Some("xyz"), None).is_err());
+        assert!(wallet.get_masternode_list(None, Some("192.0.2.1")).unwrap().is_empty());
+        let args = wallet.args.borrow();
+        assert_eq!(args.len(), 3);
+        assert_eq!(args[1][1], hash.to_string());
+        assert_eq!(args[1][2], -2);
+        assert_eq!(args[2], vec!["list", "json", "192.0.2.1"]);
+    }
+
+    /// A node of the given version that records the calls it gets.
+    struct VersionedNode {
+        version: usize,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl VersionedNode {
+        fn new(version: usize) -> VersionedNode {
+            VersionedNode {
+                version,
+                calls: RefCell::new(vec![]),
+            }
+        }
+    }
+
+    impl MockRpc for VersionedNode {
+        fn respond(&self, cmd: &str, _: &[serde_json::Value]) -> Result<String> {
+            if cmd == "getnetworkinfo" {
+                return Ok(format!("{{\"version\":{}}}", self.version));
+            }
+            self.calls.borrow_mut().push(cmd.to_owned());
+            Err(Error::UnexpectedStructure)
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_version_dispatch() {
+        let tx = "00";
+        let hash = dashcore::BlockHash::default();
+
+        let old = VersionedNode::new(160000);
+        assert!(old.sign_raw_transaction(tx, None, None, None).is_err());
+        assert!(old.sign_raw_transaction_with_wallet(tx, None, None).is_err());
+        assert!(old.sign_raw_transaction_with_key(tx, &[], None, None).is_err());
+        assert!(old.generate(1, None).is_err());
+        match old.get_block_filter(&hash, None) {
+            Err(Error::UnsupportedByNode {
+                method: "getblockfilter",
+                version: 160000,
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(
+            *old.calls.borrow(),
+            ["signrawtransaction", "signrawtransaction", "signrawtransaction", "generate"]
+        );
+
+        let new = VersionedNode::new(190000);
+        assert!(new.sign_raw_transaction(tx, None, None, None).is_err());
+        assert!(new.sign_raw_transaction(tx, None, Some(&[][..]), None).is_err());
+        assert!(new.sign_raw_transaction_with_wallet(tx, None, None).is_err());
+        assert!(new.generate(1, None).is_err());
+        assert!(new.get_block_filter(&hash, None).is_err());
+        assert_eq!(
+            *new.calls.borrow(),
+            [
+                "signrawtransactionwithwallet",
+                "signrawtransactionwithkey",
+                "signrawtransactionwithwallet",
+                "getnewaddress",
+                "getblockfilter",
+            ]
+        );
+    }
+
+    /// A node on the `chain` that records the calls it gets, whose tip is
+    /// the regtest genesis block.
+    struct MockTimeNode {
+        chain: &'static str,
+        calls: RefCell<Vec<(String, Vec<serde_json::Value>)>>,
+    }
+
+    impl MockRpc for MockTimeNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            self.calls.borrow_mut().push((cmd.to_owned(), args.to_vec()));
+            let genesis = dashcore::blockdata::constants::genesis_block(Network::Regtest);
+            let value = match cmd {
+                "getblockchaininfo" => {
+                    serde_json::from_str(&format!(r#"{{"chain": "{}"}}"#, self.chain))?
+                }
+                "getbestblockhash" => serde_json::to_value(genesis.block_hash())?,
+                "getblockheader" => {
+                    dashcore::consensus::encode::serialize_hex(&genesis.header).into()
+                }
+                "setmocktime" => serde_json::Value::Null,
+                "generatetoaddress" => serde_json::to_value(vec![genesis.block_hash()])?,
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_advance_time_and_generate() {
+        let address = Address {
+            network: Network::Regtest,
+            payload: dashcore::util::address::Payload::PubkeyHash(Default::default()),
+        };
+        let node = MockTimeNode {
+            chain: "regtest",
+            calls: RefCell::new(vec![]),
+        };
+        assert_eq!(node.advance_time_and_generate(600, 2, &address).unwrap().len(), 1);
+        let calls = node.calls.borrow();
+        let genesis = dashcore::blockdata::constants::genesis_block(Network::Regtest);
+        let mock_time = u64::from(genesis.header.time) + 600;
+        assert_eq!(calls[3], ("setmocktime".to_owned(), vec![mock_time.into()]));
+        assert_eq!(calls[4].0, "generatetoaddress");
+        assert_eq!(calls[4].1[0], 2);
+
+        let node = MockTimeNode {
+            chain: "test",
+            calls: RefCell::new(vec![]),
+        };
+        match node.advance_time_and_generate(600, 2, &address) {
+            Err(Error::RegtestOnly(Network::Testnet)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(node.calls.borrow().len(), 1);
+        match node.mine_block_with_transactions(&address, &[]) {
+            Err(Error::RegtestOnly(Network::Testnet)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(node.calls.borrow().len(), 2);
+    }
 }
@@ -0,0 +1,334 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Coin selection over the unspent outputs listed by `listunspent`.
+
+use dashcore::{Amount, OutPoint};
+
+use client::Result;
+use error::Error;
+use json::ListUnspentResultEntry;
+
+/// The size of a P2PKH input with a compressed key.
+pub const P2PKH_INPUT_SIZE: usize = 148;
+
+/// The size of a P2PKH output.
+pub const P2PKH_OUTPUT_SIZE: usize = 34;
+
+/// The size of the version, lock time and the input and output counts.
+pub const TX_OVERHEAD_SIZE: usize = 10;
+
+/// The default fee rate of a [CoinSelector] in duffs per kB.
+pub const DEFAULT_FEE_RATE: u64 = 1000;
+
+/// The default smallest change a [CoinSelector] creates an output for, in
+/// duffs. Smaller change is left to the fee.
+pub const DEFAULT_MIN_CHANGE: u64 = 1000;
+
+/// The number of branches the branch and bound search tries at most.
+const BNB_TOTAL_TRIES: usize = 100_000;
+
+/// Estimate the size of a transaction with P2PKH inputs and outputs.
+pub fn estimate_tx_size(inputs: usize, outputs: usize) -> usize {
+    TX_OVERHEAD_SIZE + inputs * P2PKH_INPUT_SIZE + outputs * P2PKH_OUTPUT_SIZE
+}
+
+/// Estimate the fee of a transaction with P2PKH inputs and outputs at
+/// `fee_rate` per kB.
+pub fn estimate_fee(inputs: usize, outputs: usize, fee_rate: Amount) -> Amount {
+    Amount::from_sat(fee_for_size(estimate_tx_size(inputs, outputs), fee_rate))
+}
+
+fn fee_for_size(size: usize, fee_rate: Amount) -> u64 {
+    fee_rate.as_sat() * size as u64 / 1000
+}
+
+/// The unspent outputs selected to fund a transaction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CoinSelection {
+    pub inputs: Vec<ListUnspentResultEntry>,
+    /// The fee the transaction pays.
+    pub fee: Amount,
+    /// The change to pay back to the wallet, zero if the transaction needs
+    /// no change output.
+    pub change: Amount,
+}
+
+impl CoinSelection {
+    /// The total amount of the selected inputs.
+    pub fn input_amount(&self) -> Amount {
+        self.inputs.iter().fold(Amount::ZERO, |sum, i| sum + i.amount)
+    }
+
+    /// The outpoints of the selected inputs.
+    pub fn outpoints(&self) -> Vec<OutPoint> {
+        self.inputs.iter().map(|i| OutPoint::new(i.txid, i.vout)).collect()
+    }
+}
+
+/// Selects the unspent outputs that fund a payment.
+///
+/// Fees are estimated for P2PKH inputs and outputs, see [estimate_fee].
+#[derive(Clone, Debug)]
+pub struct CoinSelector {
+    utxos: Vec<ListUnspentResultEntry>,
+    fee_rate: Amount,
+    outputs: usize,
+    min_change: Amount,
+}
+
+impl CoinSelector {
+    /// Select from `utxos`, skipping the ones that are not spendable or not
+    /// safe to spend.
+    pub fn new(utxos: Vec<ListUnspentResultEntry>) -> CoinSelector {
+        CoinSelector {
+            utxos: utxos.into_iter().filter(|u| u.spendable && u.safe).collect(),
+            fee_rate: Amount::from_sat(DEFAULT_FEE_RATE),
+            outputs: 1,
+            min_change: Amount::from_sat(DEFAULT_MIN_CHANGE),
+        }
+    }
+
+    /// Set the fee rate per kB.
+    pub fn with_fee_rate(mut self, fee_rate: Amount) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// Set the number of outputs the payment has, not counting the change.
+    pub fn with_outputs(mut self, outputs: usize) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
+    /// Set the smallest change to create a change output for.
+    pub fn with_min_change(mut self, min_change: Amount) -> Self {
+        self.min_change = min_change;
+        self
+    }
+
+    /// Never select the given outputs, like the ones locked with
+    /// `lockunspent`, see `RpcApi::list_lock_unspent`.
+    pub fn without(mut self, outpoints: &[OutPoint]) -> Self {
+        self.utxos.retain(|u| !outpoints.contains(&OutPoint::new(u.txid, u.vout)));
+        self
+    }
+
+    /// Select the outputs with the largest amounts until the target and the
+    /// fee are covered.
+    pub fn largest_first(&self, target: Amount) -> Result<CoinSelection> {
+        let mut utxos: Vec<&ListUnspentResultEntry> = self.utxos.iter().collect();
+        utxos.sort_by(|a, b| b.amount.cmp(&a.amount));
+
+        let mut inputs = Vec::new();
+        let mut total = Amount::ZERO;
+        for utxo in utxos {
+            inputs.push(utxo.clone());
+            total += utxo.amount;
+            let fee = estimate_fee(inputs.len(), self.outputs, self.fee_rate);
+            if total < target + fee {
+                continue;
+            }
+            let fee_with_change = estimate_fee(inputs.len(), self.outputs + 1, self.fee_rate);
+            if total >= target + fee_with_change + self.min_change {
+                return Ok(CoinSelection {
+                    inputs,
+                    fee: fee_with_change,
+                    change: total - target - fee_with_change,
+                });
+            }
+            return Ok(CoinSelection {
+                inputs,
+                fee: total - target,
+                change: Amount::ZERO,
+            });
+        }
+        Err(Error::InsufficientFunds {
+            needed: target + estimate_fee(inputs.len(), self.outputs, self.fee_rate),
+            available: total,
+        })
+    }
+
+    /// Search for outputs that cover the target and the fee without leaving
+    /// change, wasting at most what a change output would cost.
+    ///
+    /// Returns `None` if no such selection was found.
+    pub fn branch_and_bound(&self, target: Amount) -> Option<CoinSelection> {
+        // The value of an output is its amount minus the fee of spending it.
+        let input_fee = fee_for_size(P2PKH_INPUT_SIZE, self.fee_rate);
+        let mut pool: Vec<(u64, &ListUnspentResultEntry)> = self
+            .utxos
+            .iter()
+            .filter(|u| u.amount.as_sat() > input_fee)
+            .map(|u| (u.amount.as_sat() - input_fee, u))
+            .collect();
+        pool.sort_by(|a, b| b.0.cmp(&a.0));
+
+        let base_size = TX_OVERHEAD_SIZE + self.outputs * P2PKH_OUTPUT_SIZE;
+        let base_fee = fee_for_size(base_size, self.fee_rate);
+        let low = target.as_sat() + base_fee;
+        let cost_of_change = fee_for_size(P2PKH_OUTPUT_SIZE + P2PKH_INPUT_SIZE, self.fee_rate);
+        let high = low + cost_of_change;
+
+        // A depth first search over including or excluding each output.
+        let mut remaining: u64 = pool.iter().map(|p| p.0).sum();
+        let mut value = 0;
+        let mut included: Vec<bool> = Vec::new();
+        let mut best: Option<(u64, Vec<bool>)> = None;
+        for _ in 0..BNB_TOTAL_TRIES {
+            let backtrack = if value + remaining < low || value > high {
+                true
+            } else if value >= low {
+                let excess = value - low;
+                if best.as_ref().map_or(true, |b| excess < b.0) {
+                    best = Some((excess, included.clone()));
+                }
+                if excess == 0 {
+                    break;
+                }
+                true
+            } else {
+                false
+            };
+
+            if backtrack {
+                while let Some(&false) = included.last() {
+                    included.pop();
+                    remaining += pool[included.len()].0;
+                }
+                match included.last_mut() {
+                    Some(last) => *last = false,
+                    None => break,
+                }
+                value -= pool[included.len() - 1].0;
+            } else {
+                let next = pool[included.len()].0;
+                remaining -= next;
+                value += next;
+                included.push(true);
+            }
+        }
+
+        let (_, included) = best?;
+        let inputs: Vec<ListUnspentResultEntry> = pool
+            .iter()
+            .zip(included)
+            .filter(|&(_, included)| included)
+            .map(|(p, _)| p.1.clone())
+            .collect();
+        let total = inputs.iter().fold(Amount::ZERO, |sum, i| sum + i.amount);
+        Some(CoinSelection {
+            inputs,
+            fee: total - target,
+            change: Amount::ZERO,
+        })
+    }
+
+    /// Select without change with [CoinSelector::branch_and_bound] if
+    /// possible, falling back to [CoinSelector::largest_first].
+    pub fn select(&self, target: Amount) -> Result<CoinSelection> {
+        match self.branch_and_bound(target) {
+            Some(selection) => Ok(selection),
+            None => self.largest_first(target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use dashcore::{Script, Txid};
+
+    fn utxo(i: u8, duffs: u64) -> ListUnspentResultEntry {
+        ListUnspentResultEntry {
+            txid: Txid::hash(&[i]),
+            vout: 0,
+            address: None,
+            label: None,
+            redeem_script: None,
+            witness_script: None,
+            script_pub_key: Script::new(),
+            amount: Amount::from_sat(duffs),
+            confirmations: 6,
+            spendable: true,
+            solvable: true,
+            descriptor: None,
+            safe: true,
+        }
+    }
+
+    fn amounts(selection: &CoinSelection) -> Vec<u64> {
+        let mut amounts: Vec<u64> = selection.inputs.iter().map(|i| i.amount.as_sat()).collect();
+        amounts.sort();
+        amounts
+    }
+
+    #[test]
+    fn test_estimate_fee() {
+        assert_eq!(estimate_tx_size(1, 2), 226);
+        assert_eq!(estimate_fee(1, 2, Amount::from_sat(1000)), Amount::from_sat(226));
+        assert_eq!(estimate_fee(2, 2, Amount::from_sat(10_000)), Amount::from_sat(3740));
+    }
+
+    #[test]
+    fn test_largest_first() {
+        let utxos = vec![utxo(0, 50_000), utxo(1, 200_000), utxo(2, 100_000)];
+        let selector = CoinSelector::new(utxos);
+
+        let selection = selector.largest_first(Amount::from_sat(250_000)).unwrap();
+        assert_eq!(amounts(&selection), [100_000, 200_000]);
+        assert_eq!(selection.fee, estimate_fee(2, 2, Amount::from_sat(DEFAULT_FEE_RATE)));
+        let spent = Amount::from_sat(250_000) + selection.fee + selection.change;
+        assert_eq!(selection.input_amount(), spent);
+
+        // Change below the minimum is left to the fee.
+        let selection = selector.largest_first(Amount::from_sat(199_500)).unwrap();
+        assert_eq!(amounts(&selection), [200_000]);
+        assert_eq!((selection.fee, selection.change), (Amount::from_sat(500), Amount::ZERO));
+
+        let selector = selector.without(&[OutPoint::new(Txid::hash(&[1]), 0)]);
+        match selector.largest_first(Amount::from_sat(150_000)) {
+            Err(Error::InsufficientFunds {
+                available,
+                ..
+            }) => assert_eq!(available, Amount::from_sat(150_000)),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_branch_and_bound() {
+        let utxos = vec![utxo(0, 1_000), utxo(1, 2_000), utxo(2, 3_000), utxo(3, 4_000)];
+        let mut unsafe_utxo = utxo(4, 5_000);
+        unsafe_utxo.safe = false;
+        let mut all = utxos.clone();
+        all.push(unsafe_utxo);
+        let selector = CoinSelector::new(all).with_fee_rate(Amount::ZERO);
+
+        let selection = selector.branch_and_bound(Amount::from_sat(5_000)).unwrap();
+        assert_eq!(selection.input_amount(), Amount::from_sat(5_000));
+        assert_eq!((selection.fee, selection.change), (Amount::ZERO, Amount::ZERO));
+        let selection = selector.branch_and_bound(Amount::from_sat(10_000)).unwrap();
+        assert_eq!(amounts(&selection), [1_000, 2_000, 3_000, 4_000]);
+        assert!(selector.branch_and_bound(Amount::from_sat(10_001)).is_none());
+
+        // With fees, the selection must cover the fee of each input without
+        // wasting more than a change output would cost.
+        let fee_rate = Amount::from_sat(10_000);
+        let selector = CoinSelector::new(vec![utxo(0, 101_480), utxo(1, 51_480), utxo(2, 70_000)])
+            .with_fee_rate(fee_rate);
+        let target = Amount::from_sat(150_000) - estimate_fee(0, 1, fee_rate);
+        let selection = selector.select(target).unwrap();
+        assert_eq!(amounts(&selection), [51_480, 101_480]);
+        assert_eq!(selection.change, Amount::ZERO);
+        assert_eq!(selection.fee, estimate_fee(2, 1, fee_rate));
+    }
+}
@@ -0,0 +1,205 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Proving the ownership of masternode collaterals.
+
+use std::fmt;
+
+use dashcore::{Address, Amount, OutPoint};
+
+use client::{Result, RpcApi};
+use error::Error;
+
+/// The collateral of a regular masternode, 1000 DASH.
+const MASTERNODE_COLLATERAL: u64 = 100_000_000_000;
+/// The collateral of an evonode, 4000 DASH.
+const EVONODE_COLLATERAL: u64 = 400_000_000_000;
+
+/// Why a collateral ownership proof is invalid, see
+/// [Error::InvalidCollateralProof].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CollateralFailure {
+    /// The output is not listed by `masternode outputs` of the wallet.
+    NotInWallet,
+    /// The output is spent or does not exist.
+    Spent,
+    /// The output does not hold the collateral of a masternode or evonode.
+    WrongAmount(Amount),
+    /// The output does not pay to the address of the proof.
+    WrongAddress,
+    /// The signature is not one of the message by the key of the address.
+    InvalidSignature,
+}
+
+impl fmt::Display for CollateralFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CollateralFailure::NotInWallet => write!(f, "not a collateral of the wallet"),
+            CollateralFailure::Spent => write!(f, "spent or unknown output"),
+            CollateralFailure::WrongAmount(amount) => write!(f, "wrong amount {}", amount),
+            CollateralFailure::WrongAddress => write!(f, "output does not pay to the address"),
+            CollateralFailure::InvalidSignature => write!(f, "invalid signature"),
+        }
+    }
+}
+
+/// A signed message proving that its signer controls the key of the address
+/// holding a masternode collateral, see [RpcApi::prove_collateral_ownership].
+///
+/// The message is typically a challenge given by the party asking for the
+/// proof, so that a proof can't be reused.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct CollateralProof {
+    pub outpoint: OutPoint,
+    pub address: Address,
+    pub message: String,
+    /// The signature of the message by the key of the address, base64
+    /// encoded as returned by `signmessage`.
+    pub signature: String,
+}
+
+/// The address the unspent output pays to.
+fn collateral_address<C: RpcApi>(rpc: &C, outpoint: &OutPoint) -> Result<(Address, Amount)> {
+    let fail = |reason| Error::InvalidCollateralProof {
+        outpoint: *outpoint,
+        reason,
+    };
+    let txout = match rpc.get_tx_out(&outpoint.txid, outpoint.vout, Some(false))? {
+        Some(txout) => txout,
+        None => return Err(fail(CollateralFailure::Spent)),
+    };
+    let address = match txout.script_pub_key.addresses {
+        Some(ref addresses) if addresses.len() == 1 => Some(addresses[0].clone()),
+        Some(_) => None,
+        None => Address::from_script(&txout.script_pub_key.script()?, rpc.network()?),
+    };
+    match address {
+        Some(address) => Ok((address, txout.value)),
+        None => Err(fail(CollateralFailure::WrongAddress)),
+    }
+}
+
+/// See [RpcApi::prove_collateral_ownership].
+pub(crate) fn prove<C: RpcApi>(
+    rpc: &C,
+    outpoint: &OutPoint,
+    message: &str,
+) -> Result<CollateralProof> {
+    if !rpc.masternode_outputs()?.contains(outpoint) {
+        return Err(Error::InvalidCollateralProof {
+            outpoint: *outpoint,
+            reason: CollateralFailure::NotInWallet,
+        });
+    }
+    let (address, _) = collateral_address(rpc, outpoint)?;
+    let signature = rpc.sign_message(&address, message)?;
+    Ok(CollateralProof {
+        outpoint: *outpoint,
+        address,
+        message: message.to_owned(),
+        signature,
+    })
+}
+
+/// See [RpcApi::verify_collateral_proof].
+pub(crate) fn verify<C: RpcApi>(rpc: &C, proof: &CollateralProof) -> Result<Amount> {
+    let fail = |reason| Error::InvalidCollateralProof {
+        outpoint: proof.outpoint,
+        reason,
+    };
+    let (address, amount) = collateral_address(rpc, &proof.outpoint)?;
+    if amount.as_sat() != MASTERNODE_COLLATERAL && amount.as_sat() != EVONODE_COLLATERAL {
+        return Err(fail(CollateralFailure::WrongAmount(amount)));
+    }
+    if address != proof.address {
+        return Err(fail(CollateralFailure::WrongAddress));
+    }
+    if !rpc.verify_message_base64(&address, &proof.signature, &proof.message)? {
+        return Err(fail(CollateralFailure::InvalidSignature));
+    }
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use dashcore::Txid;
+    use serde_json;
+    use testutil::{address, MockRpc};
+
+    /// A wallet holding a collateral at output 0 and 10 DASH at output 1 of
+    /// a transaction, whose signatures are "sig".
+    struct MockNode;
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "masternode" => format!(r#"["{}-0"]"#, Txid::hash(&[1])),
+                "gettxout" => format!(
+                    r#"{{"bestblock": "{}", "confirmations": 10, "value": {},
+                         "scriptPubKey": {{"asm": "", "hex": "{}", "type": "pubkeyhash",
+                                           "addresses": ["{}"]}},
+                         "coinbase": false}}"#,
+                    Txid::hash(&[]),
+                    if args[1] == 0 {
+                        "1000.0"
+                    } else {
+                        "10.0"
+                    },
+                    format!("{:x}", address().script_pubkey()),
+                    address()
+                ),
+                "signmessage" => r#""sig""#.to_owned(),
+                "verifymessage" => (args[1] == "sig").to_string(),
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    fn failure(result: Result<Amount>) -> CollateralFailure {
+        match result {
+            Err(Error::InvalidCollateralProof {
+                reason,
+                ..
+            }) => reason,
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_collateral_proof() {
+        let outpoint = OutPoint::new(Txid::hash(&[1]), 0);
+        let proof = MockNode.prove_collateral_ownership(&outpoint, "challenge").unwrap();
+        assert_eq!(proof.address, address());
+        assert_eq!(proof.signature, "sig");
+        let amount = MockNode.verify_collateral_proof(&proof).unwrap();
+        assert_eq!(amount, Amount::from_sat(MASTERNODE_COLLATERAL));
+
+        let forged = CollateralProof {
+            signature: "forged".to_owned(),
+            ..proof.clone()
+        };
+        let result = MockNode.verify_collateral_proof(&forged);
+        assert_eq!(failure(result), CollateralFailure::InvalidSignature);
+
+        let not_collateral = CollateralProof {
+            outpoint: OutPoint::new(Txid::hash(&[1]), 1),
+            ..proof
+        };
+        let result = MockNode.verify_collateral_proof(&not_collateral);
+        let amount = Amount::from_sat(1_000_000_000);
+        assert_eq!(failure(result), CollateralFailure::WrongAmount(amount));
+
+        let result = MockNode.prove_collateral_ownership(&not_collateral.outpoint, "challenge");
+        assert_eq!(failure(result.map(|_| Amount::ZERO)), CollateralFailure::NotInWallet);
+    }
+}
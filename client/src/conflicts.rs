@@ -0,0 +1,214 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Detecting double spends of wallet transactions.
+
+use dashcore::Txid;
+
+use client::{Result, RpcApi};
+use error::Error;
+
+/// The reject reason of `testmempoolaccept` for a transaction that is
+/// already in the mempool.
+const ALREADY_IN_MEMPOOL: &str = "txn-already-in-mempool";
+
+/// A wallet transaction spending an input of the transaction of a
+/// [ConflictReport].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WalletConflict {
+    pub txid: Txid,
+    /// The confirmations of the conflicting transaction, negative if it is
+    /// itself conflicted.
+    pub confirmations: i32,
+}
+
+/// Whether the mempool of the node takes the transaction of a
+/// [ConflictReport].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MempoolStatus {
+    /// The transaction is mined, so the mempool is not checked.
+    Mined,
+    /// The transaction is in the mempool.
+    InMempool,
+    /// The transaction is not in the mempool, but would be accepted.
+    Accepted,
+    /// The transaction conflicts with a mempool transaction, with the reject
+    /// reason.
+    Conflicted(String),
+    /// The transaction is rejected for another reason, like inputs that are
+    /// spent by a mined transaction.
+    Rejected(String),
+}
+
+/// The conflicts of a wallet transaction, see [RpcApi::get_conflicts].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConflictReport {
+    pub txid: Txid,
+    /// The confirmations of the transaction, negative if a conflicting
+    /// transaction has that many.
+    pub confirmations: i32,
+    /// The wallet transactions spending an input of the transaction, from
+    /// `walletconflicts` of `gettransaction`.
+    pub wallet_conflicts: Vec<WalletConflict>,
+    pub mempool: MempoolStatus,
+}
+
+impl ConflictReport {
+    /// Whether a conflicting transaction was mined or is in the mempool, so
+    /// the transaction can't be mined unless the chain is reorganized or
+    /// the mempool transaction is evicted.
+    pub fn is_double_spent(&self) -> bool {
+        let conflict_mined = self.wallet_conflicts.iter().any(|c| c.confirmations > 0);
+        let mempool_conflict = match self.mempool {
+            MempoolStatus::Conflicted(_) => true,
+            _ => false,
+        };
+        self.confirmations < 0 || conflict_mined || mempool_conflict
+    }
+}
+
+/// See [RpcApi::get_conflicts].
+pub(crate) fn get_conflicts<C: RpcApi>(rpc: &C, txid: &Txid) -> Result<ConflictReport> {
+    let tx = rpc.get_transaction(txid, Some(true))?;
+    let mut wallet_conflicts = Vec::with_capacity(tx.info.wallet_conflicts.len());
+    for conflict in &tx.info.wallet_conflicts {
+        wallet_conflicts.push(WalletConflict {
+            txid: *conflict,
+            confirmations: rpc.get_transaction(conflict, Some(true))?.info.confirmations,
+        });
+    }
+
+    let mempool = if tx.info.confirmations > 0 {
+        MempoolStatus::Mined
+    } else {
+        let results = rpc.test_mempool_accept(&[&tx.hex])?;
+        let result = results.into_iter().next().ok_or(Error::UnexpectedStructure)?;
+        let reason = result.reject_reason.unwrap_or_default();
+        if result.allowed {
+            MempoolStatus::Accepted
+        } else if reason.contains(ALREADY_IN_MEMPOOL) {
+            MempoolStatus::InMempool
+        } else if reason.contains("conflict") {
+            MempoolStatus::Conflicted(reason)
+        } else {
+            MempoolStatus::Rejected(reason)
+        }
+    };
+    Ok(ConflictReport {
+        txid: *txid,
+        confirmations: tx.info.confirmations,
+        wallet_conflicts,
+        mempool,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use serde_json;
+    use std::cell::RefCell;
+    use testutil::MockRpc;
+
+    /// A wallet in which transaction 2 spends the same input as transaction
+    /// 1, with the given confirmations, and whose mempool rejects
+    /// transaction 1 with `reject_reason`.
+    struct MockWallet {
+        confirmations: (i32, i32),
+        reject_reason: &'static str,
+        tested: RefCell<Vec<serde_json::Value>>,
+    }
+
+    impl MockRpc for MockWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "gettransaction" => {
+                    let txid: Txid = serde_json::from_value(args[0].clone())?;
+                    let (conflict, confirmations) = if txid == Txid::hash(&[1]) {
+                        (Txid::hash(&[2]), self.confirmations.0)
+                    } else {
+                        (Txid::hash(&[1]), self.confirmations.1)
+                    };
+                    format!(
+                        r#"{{"amount": -1.0, "confirmations": {}, "txid": "{}", "time": 0,
+                             "timereceived": 0, "bip125-replaceable": "no",
+                             "walletconflicts": ["{}"], "details": [], "hex": "0{}"}}"#,
+                        confirmations,
+                        txid,
+                        conflict,
+                        confirmations.abs()
+                    )
+                }
+                "testmempoolaccept" => {
+                    self.tested.borrow_mut().push(args[0][0].clone());
+                    format!(
+                        r#"[{{"txid": "{}", "allowed": {}, "reject-reason": "{}"}}]"#,
+                        Txid::hash(&[1]),
+                        self.reject_reason.is_empty(),
+                        self.reject_reason
+                    )
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    fn report(confirmations: (i32, i32), reject_reason: &'static str) -> ConflictReport {
+        let wallet = MockWallet {
+            confirmations,
+            reject_reason,
+            tested: RefCell::new(vec![]),
+        };
+        let report = wallet.get_conflicts(&Txid::hash(&[1])).unwrap();
+        assert_eq!(report.txid, Txid::hash(&[1]));
+        assert_eq!(
+            report.wallet_conflicts,
+            [WalletConflict {
+                txid: Txid::hash(&[2]),
+                confirmations: confirmations.1,
+            }]
+        );
+        let tested = wallet.tested.borrow();
+        if confirmations.0 > 0 {
+            assert!(tested.is_empty());
+        } else {
+            assert_eq!(*tested, [format!("0{}", confirmations.0.abs())]);
+        }
+        report
+    }
+
+    #[test]
+    fn test_get_conflicts() {
+        let mined = report((2, -2), "");
+        assert_eq!(mined.mempool, MempoolStatus::Mined);
+        assert!(!mined.is_double_spent());
+
+        let pending = report((0, 0), "txn-already-in-mempool");
+        assert_eq!(pending.mempool, MempoolStatus::InMempool);
+        assert!(!pending.is_double_spent());
+
+        let dropped = report((0, 0), "");
+        assert_eq!(dropped.mempool, MempoolStatus::Accepted);
+        assert!(!dropped.is_double_spent());
+
+        let replaced = report((0, 0), "txn-mempool-conflict");
+        assert_eq!(replaced.mempool, MempoolStatus::Conflicted("txn-mempool-conflict".into()));
+        assert!(replaced.is_double_spent());
+
+        let double_spent = report((-1, 1), "bad-txns-inputs-missingorspent");
+        assert_eq!(double_spent.confirmations, -1);
+        assert_eq!(
+            double_spent.mempool,
+            MempoolStatus::Rejected("bad-txns-inputs-missingorspent".into())
+        );
+        assert!(double_spent.is_double_spent());
+    }
+}
@@ -0,0 +1,125 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! The checksums of output descriptors.
+//!
+//! `importdescriptors` and `deriveaddresses` require descriptors with their
+//! checksum, the eight characters after the `#`, which
+//! [with_descriptor_checksum] adds without a call to the node.
+
+use client::Result;
+use error::Error;
+
+/// The characters of descriptors, in three classes of 32.
+const INPUT_CHARSET: &'static str = concat!(
+    "0123456789()[],'/*abcdefgh@:$%{}",
+    "IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~",
+    "ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ",
+);
+const CHECKSUM_CHARSET: &'static [u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn poly_mod(c: u64, val: u64) -> u64 {
+    let c0 = c >> 35;
+    let mut c = ((c & 0x7ffffffff) << 5) ^ val;
+    if c0 & 1 != 0 {
+        c ^= 0xf5dee51989;
+    }
+    if c0 & 2 != 0 {
+        c ^= 0xa9fdca3312;
+    }
+    if c0 & 4 != 0 {
+        c ^= 0x1bab10e32d;
+    }
+    if c0 & 8 != 0 {
+        c ^= 0x3706b1677a;
+    }
+    if c0 & 16 != 0 {
+        c ^= 0x644d626ffd;
+    }
+    c
+}
+
+/// The checksum of a descriptor, any checksum it already has is ignored.
+///
+/// Returns `None` if the descriptor has a character that can't be part of a
+/// descriptor.
+pub fn descriptor_checksum(descriptor: &str) -> Option<String> {
+    let descriptor = descriptor.split('#').next().unwrap_or("");
+    let mut c = 1;
+    let mut class = 0;
+    let mut class_count = 0;
+    for ch in descriptor.chars() {
+        let pos = INPUT_CHARSET.find(ch)? as u64;
+        c = poly_mod(c, pos & 31);
+        class = class * 3 + (pos >> 5);
+        class_count += 1;
+        if class_count == 3 {
+            c = poly_mod(c, class);
+            class = 0;
+            class_count = 0;
+        }
+    }
+    if class_count > 0 {
+        c = poly_mod(c, class);
+    }
+    for _ in 0..8 {
+        c = poly_mod(c, 0);
+    }
+    c ^= 1;
+
+    let checksum = (0..8).map(|i| CHECKSUM_CHARSET[((c >> (5 * (7 - i))) & 31) as usize] as char);
+    Some(checksum.collect())
+}
+
+/// The descriptor with its checksum appended.
+///
+/// A descriptor that already has a checksum is returned as is if the
+/// checksum is valid, so the result can be passed to the node either way.
+pub fn with_descriptor_checksum(descriptor: &str) -> Result<String> {
+    let checksum = descriptor_checksum(descriptor)
+        .ok_or_else(|| Error::InvalidDescriptor("invalid character".to_owned()))?;
+    let mut parts = descriptor.splitn(2, '#');
+    let body = parts.next().unwrap_or("");
+    match parts.next() {
+        None => Ok(format!("{}#{}", body, checksum)),
+        Some(found) if found == checksum => Ok(descriptor.to_owned()),
+        Some(found) => Err(Error::InvalidDescriptor(format!(
+            "checksum {} does not match the expected {}",
+            found, checksum
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PKH: &'static str =
+        "pkh(02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5)";
+
+    #[test]
+    fn test_descriptor_checksum() {
+        assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+        assert_eq!(descriptor_checksum("raw(deadbeef)#89f8spxm").unwrap(), "89f8spxm");
+        assert_eq!(descriptor_checksum(PKH).unwrap(), "8fhd9pwu");
+        assert_eq!(descriptor_checksum("raw(deadbeef)\n"), None);
+    }
+
+    #[test]
+    fn test_with_descriptor_checksum() {
+        let expected = format!("{}#8fhd9pwu", PKH);
+        assert_eq!(with_descriptor_checksum(PKH).unwrap(), expected);
+        assert_eq!(with_descriptor_checksum(&expected).unwrap(), expected);
+        match with_descriptor_checksum(&format!("{}#8fhd9pwv", PKH)) {
+            Err(Error::InvalidDescriptor(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
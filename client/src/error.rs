@@ -8,6 +8,7 @@
 // If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
 //
 
+use std::time::Duration;
 use std::{error, fmt, io};
 
 use dashcore;
@@ -16,6 +17,26 @@ use dashcore::secp256k1;
 use jsonrpc;
 use serde_json;
 
+use collateral::CollateralFailure;
+use json;
+use payment::PaymentFailure;
+use transport::{fmt_auth_failed, is_transient_io, TransportError};
+
+/// The RPC error code for an internal error of the node, also returned by
+/// `getbestchainlock` when it knows no ChainLock.
+pub(crate) const RPC_INTERNAL_ERROR: i32 = -32603;
+/// The RPC error code for an unknown transaction, block or address.
+pub(crate) const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+/// The RPC error code of a node that is still starting up, like while it
+/// loads the block index.
+pub(crate) const RPC_IN_WARMUP: i32 = -28;
+/// The RPC error code for a transaction whose inputs are missing or spent.
+pub(crate) const RPC_VERIFY_ERROR: i32 = -25;
+/// The RPC error code for a transaction rejected by the mempool.
+pub(crate) const RPC_VERIFY_REJECTED: i32 = -26;
+/// The RPC error code for a transaction that is already mined.
+pub(crate) const RPC_VERIFY_ALREADY_IN_CHAIN: i32 = -27;
+
 /// The error type for errors produced in this library.
 #[derive(Debug)]
 pub enum Error {
@@ -29,11 +50,275 @@ pub enum Error {
     InvalidCookieFile,
     /// The JSON result had an unexpected structure.
     UnexpectedStructure,
+    /// A long-running operation was cancelled through its `Operations` registry.
+    Cancelled,
+    /// A chain reorganization was detected at the given height.
+    Reorg(u64),
+    /// An error of a request to the REST interface.
+    Rest(TransportError),
+    /// A wallet dump file could not be parsed at the given line.
+    InvalidWalletDump {
+        line: usize,
+        reason: String,
+    },
+    /// The connected node does not support the call, not even under another
+    /// name.
+    UnsupportedByNode {
+        method: &'static str,
+        version: usize,
+    },
+    /// The output spent by an input could not be found.
+    MissingPrevout(dashcore::OutPoint),
+    /// The unspent outputs do not cover the amount needed.
+    InsufficientFunds {
+        needed: dashcore::Amount,
+        available: dashcore::Amount,
+    },
+    /// The wallet could not sign every input of a transaction.
+    IncompleteSignature(Vec<json::SignRawTransactionResultError>),
+    /// An address of another network than the one of the node.
+    NetworkMismatch {
+        address: dashcore::Network,
+        node: dashcore::Network,
+    },
+    /// A payment could not be verified, see `RpcApi::verify_payment`.
+    InvalidPayment {
+        txid: dashcore::Txid,
+        reason: PaymentFailure,
+    },
+    /// A masternode list diff does not start at the block of the list.
+    MasternodeListMismatch {
+        expected: dashcore::BlockHash,
+        found: dashcore::BlockHash,
+    },
+    /// Waiting for something took longer than the given timeout.
+    Timeout(Duration),
+    /// A block or header of the node does not hash to the requested block
+    /// hash, see `Client::with_block_verification`.
+    BlockHashMismatch {
+        requested: dashcore::BlockHash,
+        found: dashcore::BlockHash,
+    },
+    /// The transactions of a block of the node do not match the merkle root
+    /// of its header.
+    InvalidMerkleRoot(dashcore::BlockHash),
+    /// A hex encoded result could not be consensus decoded.
+    Decode {
+        /// The call that returned the result.
+        method: String,
+        /// The hash or txid of the requested item, if any.
+        id: Option<String>,
+        /// The length of the hex string.
+        len: usize,
+        error: Box<Error>,
+    },
+    /// An index of the node is disabled, or still syncing at the given
+    /// height, see `RpcApi::require_indexes`.
+    IndexUnavailable {
+        name: String,
+        best_block_height: Option<u64>,
+    },
+    /// A transaction pays a higher fee than allowed, see
+    /// `Client::with_max_fee`.
+    FeeTooHigh {
+        txid: dashcore::Txid,
+        fee: dashcore::Amount,
+        max_fee: dashcore::Amount,
+    },
+    /// The fee of a transaction could not be computed to check it against
+    /// the maximum, like for a coinbase transaction or one that creates more
+    /// than it spends, see `Client::with_max_fee`.
+    UnknownFee(dashcore::Txid),
+    /// The node is still starting up, like while it loads the block index,
+    /// with the progress message of the node, see
+    /// `RpcApi::wait_until_warmed_up`.
+    NodeWarmingUp {
+        message: String,
+    },
+    /// The chain tip changed while reading, on each of the given number of
+    /// attempts, see `RpcApi::get_tx_outs_at_tip`.
+    TipChanged {
+        attempts: usize,
+    },
+    /// A masternode collateral ownership proof is invalid, see
+    /// `RpcApi::verify_collateral_proof`.
+    InvalidCollateralProof {
+        outpoint: dashcore::OutPoint,
+        reason: CollateralFailure,
+    },
+    /// The data of an `OP_RETURN` output is larger than what nodes relay.
+    DataTooLarge {
+        size: usize,
+        max: usize,
+    },
+    /// A call that only works on a regtest node was made on another network.
+    RegtestOnly(dashcore::Network),
+    /// No nonce solves the proof of work of an assembled block.
+    NoValidNonce,
+    /// The node rejected a submitted block, with the reason.
+    BlockRejected(String),
+    /// A descriptor is invalid, like one with a wrong checksum.
+    InvalidDescriptor(String),
+    /// An external signer failed, with its message, see `Signer`.
+    Signer(String),
+    /// A masternode list does not match the `merkleRootMNList` the coinbase
+    /// of its block commits to, see `MasternodeList::verify_merkle_root`.
+    MasternodeListMerkleRootMismatch {
+        block_hash: dashcore::BlockHash,
+        expected: dashcore::hashes::sha256d::Hash,
+        computed: dashcore::hashes::sha256d::Hash,
+    },
+    /// A sent transaction was not locked by InstantSend in time, see
+    /// `RpcApi::send_and_wait_for_islock`. It may still be locked later.
+    InstantLockTimeout {
+        txid: dashcore::Txid,
+        timeout: Duration,
+    },
+    /// The node refused to sign a request with a quorum, like when it is not
+    /// a member of the quorum, see `RpcApi::quorum_sign_and_wait`.
+    QuorumSignRefused,
+    /// None of the nodes of a `MultiClient` can serve the call, given their
+    /// roles, like a wallet call without a wallet node.
+    NoNodeForCall(String),
+    /// Two nodes returned different results for the same call, see
+    /// `ParanoidClient`.
+    ResponseMismatch {
+        method: String,
+        primary: serde_json::Value,
+        secondary: serde_json::Value,
+    },
+    /// The node rejected the credentials, with HTTP status 401, or the client,
+    /// with 403, like when its IP is not in `rpcallowip` or the call is not
+    /// in its `rpcwhitelist`. `body` is the start of the body of the
+    /// response, if any.
+    AuthFailed {
+        status: u16,
+        body: String,
+    },
+    /// More outpoints than the REST interface takes at once were given, see
+    /// `RestClient::get_utxos`.
+    TooManyOutpoints {
+        count: usize,
+        max: usize,
+    },
+    /// A sink of a `PushService` failed to deliver an event, like a webhook
+    /// whose server is down.
+    Push(TransportError),
+}
+
+impl Error {
+    /// Whether the call may succeed when retried as is, so retry loops can
+    /// tell a node that is briefly unavailable from a failed call.
+    ///
+    /// Transient errors are dropped or refused connections, timeouts, HTTP
+    /// 5xx statuses without a JSON-RPC response, like the 503 of a node whose
+    /// work queue is full, and [Error::NodeWarmingUp]. Errors of the node
+    /// about the call itself, like invalid parameters or unknown items, are
+    /// not transient.
+    pub fn is_transient(&self) -> bool {
+        match *self {
+            Error::JsonRpc(ref e) => is_transient_jsonrpc(e),
+            Error::Io(ref e) => is_transient_io(e),
+            Error::Rest(ref e) => e.is_transient(),
+            Error::NodeWarmingUp {
+                ..
+            } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the call surely was not run by the node, so that it can be
+    /// sent again even if it has side effects, like a payment.
+    ///
+    /// This is the case when the connection to the node could not be opened,
+    /// when the node rejected the call because it is warming up or its work
+    /// queue is full, but not when the connection broke or timed out after
+    /// the call was sent.
+    pub fn is_unsent(&self) -> bool {
+        match *self {
+            Error::JsonRpc(jsonrpc::Error::Rpc(ref e)) => e.code == RPC_IN_WARMUP,
+            Error::JsonRpc(jsonrpc::Error::Transport(ref e)) => {
+                if let Some(e) = e.downcast_ref::<TransportError>() {
+                    return e.is_unsent();
+                }
+                match e.downcast_ref::<jsonrpc::simple_http::Error>() {
+                    Some(&jsonrpc::simple_http::Error::SocketError(ref e)) => {
+                        e.kind() == io::ErrorKind::ConnectionRefused
+                    }
+                    Some(&jsonrpc::simple_http::Error::HttpErrorCode(503)) => true,
+                    _ => false,
+                }
+            }
+            Error::Io(ref e) => e.kind() == io::ErrorKind::ConnectionRefused,
+            Error::Rest(ref e) => e.is_unsent(),
+            Error::NodeWarmingUp {
+                ..
+            } => true,
+            _ => false,
+        }
+    }
+}
+
+fn is_transient_jsonrpc(e: &jsonrpc::error::Error) -> bool {
+    match *e {
+        jsonrpc::Error::Rpc(ref e) => e.code == RPC_IN_WARMUP,
+        jsonrpc::Error::Transport(ref e) => {
+            if let Some(e) = e.downcast_ref::<TransportError>() {
+                return e.is_transient();
+            }
+            // The transport of the `jsonrpc` crate, used with a proxy.
+            match e.downcast_ref::<jsonrpc::simple_http::Error>() {
+                Some(&jsonrpc::simple_http::Error::SocketError(ref e)) => is_transient_io(e),
+                Some(&jsonrpc::simple_http::Error::HttpErrorCode(status)) => status >= 500,
+                Some(&jsonrpc::simple_http::Error::IncompleteResponse {
+                    ..
+                }) => true,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
 }
 
 impl From<jsonrpc::error::Error> for Error {
     fn from(e: jsonrpc::error::Error) -> Error {
-        Error::JsonRpc(e)
+        match e {
+            jsonrpc::Error::Rpc(ref e) if e.code == RPC_IN_WARMUP => Error::NodeWarmingUp {
+                message: e.message.clone(),
+            },
+            e => match auth_failure(&e) {
+                Some((status, body)) => Error::AuthFailed {
+                    status,
+                    body,
+                },
+                None => Error::JsonRpc(e),
+            },
+        }
+    }
+}
+
+/// The status and body of a transport error that is an authentication
+/// failure.
+fn auth_failure(e: &jsonrpc::error::Error) -> Option<(u16, String)> {
+    let e = match *e {
+        jsonrpc::Error::Transport(ref e) => e,
+        _ => return None,
+    };
+    if let Some(&TransportError::AuthFailed {
+        status,
+        ref body,
+    }) = e.downcast_ref::<TransportError>()
+    {
+        return Some((status, body.clone()));
+    }
+    // The transport of the `jsonrpc` crate, used with a proxy, drops the body.
+    match e.downcast_ref::<jsonrpc::simple_http::Error>() {
+        Some(&jsonrpc::simple_http::Error::HttpErrorCode(status))
+            if status == 401 || status == 403 =>
+        {
+            Some((status, String::new()))
+        }
+        _ => None,
     }
 }
 
@@ -85,6 +370,121 @@ impl fmt::Display for Error {
             Error::InvalidAmount(ref e) => write!(f, "invalid amount: {}", e),
             Error::InvalidCookieFile => write!(f, "invalid cookie file"),
             Error::UnexpectedStructure => write!(f, "the JSON result had an unexpected structure"),
+            Error::Cancelled => write!(f, "the operation was cancelled"),
+            Error::Reorg(h) => write!(f, "chain reorganization detected at height {}", h),
+            Error::Rest(ref e) => write!(f, "REST error: {}", e),
+            Error::InvalidWalletDump {
+                line,
+                ref reason,
+            } => write!(f, "invalid wallet dump at line {}: {}", line, reason),
+            Error::UnsupportedByNode {
+                method,
+                version,
+            } => write!(f, "{} is not supported by node version {}", method, version),
+            Error::MissingPrevout(ref o) => write!(f, "previous output {} not found", o),
+            Error::InsufficientFunds {
+                needed,
+                available,
+            } => write!(f, "insufficient funds: needed {}, available {}", needed, available),
+            Error::IncompleteSignature(ref errors) => {
+                write!(f, "could not sign {} inputs of the transaction", errors.len())
+            }
+            Error::NetworkMismatch {
+                address,
+                node,
+            } => write!(f, "address of network {} used on a {} node", address, node),
+            Error::InvalidPayment {
+                ref txid,
+                ref reason,
+            } => write!(f, "payment {} not verified: {}", txid, reason),
+            Error::MasternodeListMismatch {
+                ref expected,
+                ref found,
+            } => write!(f, "masternode list diff from {} applied to list at {}", found, expected),
+            Error::Timeout(ref timeout) => write!(f, "timed out after {:?}", timeout),
+            Error::BlockHashMismatch {
+                ref requested,
+                ref found,
+            } => write!(f, "requested block {} but got block {}", requested, found),
+            Error::InvalidMerkleRoot(ref hash) => {
+                write!(f, "transactions of block {} do not match its merkle root", hash)
+            }
+            Error::Decode {
+                ref method,
+                ref id,
+                len,
+                ref error,
+            } => {
+                write!(f, "invalid result of {}", method)?;
+                if let Some(ref id) = *id {
+                    write!(f, " for {}", id)?;
+                }
+                write!(f, " ({} hex chars): {}", len, error)
+            }
+            Error::IndexUnavailable {
+                ref name,
+                best_block_height: None,
+            } => write!(f, "index {} is disabled", name),
+            Error::IndexUnavailable {
+                ref name,
+                best_block_height: Some(height),
+            } => write!(f, "index {} is still syncing, at height {}", name, height),
+            Error::FeeTooHigh {
+                ref txid,
+                fee,
+                max_fee,
+            } => write!(f, "transaction {} pays a fee of {}, more than {}", txid, fee, max_fee),
+            Error::UnknownFee(ref txid) => {
+                write!(f, "fee of transaction {} could not be computed", txid)
+            }
+            Error::NodeWarmingUp {
+                ref message,
+            } => write!(f, "node is warming up: {}", message),
+            Error::TipChanged {
+                attempts,
+            } => write!(f, "chain tip changed during {} attempts to read", attempts),
+            Error::InvalidCollateralProof {
+                ref outpoint,
+                ref reason,
+            } => write!(f, "collateral proof of {} is invalid: {}", outpoint, reason),
+            Error::DataTooLarge {
+                size,
+                max,
+            } => write!(f, "{} bytes of data exceed the maximum of {}", size, max),
+            Error::RegtestOnly(network) => write!(f, "only available on regtest, not {}", network),
+            Error::NoValidNonce => write!(f, "no nonce solves the proof of work of the block"),
+            Error::BlockRejected(ref reason) => write!(f, "block rejected: {}", reason),
+            Error::InvalidDescriptor(ref reason) => write!(f, "invalid descriptor: {}", reason),
+            Error::Signer(ref message) => write!(f, "signer error: {}", message),
+            Error::MasternodeListMerkleRootMismatch {
+                ref block_hash,
+                ref expected,
+                ref computed,
+            } => write!(
+                f,
+                "masternode list at {} has merkle root {}, its coinbase commits to {}",
+                block_hash, computed, expected
+            ),
+            Error::InstantLockTimeout {
+                ref txid,
+                ref timeout,
+            } => write!(f, "transaction {} not InstantSend locked after {:?}", txid, timeout),
+            Error::QuorumSignRefused => write!(f, "node refused to sign with the quorum"),
+            Error::NoNodeForCall(ref cmd) => write!(f, "no node can serve the call {}", cmd),
+            Error::ResponseMismatch {
+                ref method,
+                ref primary,
+                ref secondary,
+            } => write!(f, "nodes disagree on {}: {} vs {}", method, primary, secondary),
+            Error::AuthFailed {
+                status,
+                ref body,
+            } => fmt_auth_failed(f, status, body),
+            Error::TooManyOutpoints {
+                count,
+                max,
+            } => write!(f, "{} outpoints exceed the maximum of {} per request", count, max),
+            Error::Push(ref e) => write!(f, "push error: {}", e),
         }
     }
 }
@@ -102,7 +502,111 @@ impl error::Error for Error {
             Error::BitcoinSerialization(ref e) => Some(e),
             Error::Secp256k1(ref e) => Some(e),
             Error::Io(ref e) => Some(e),
+            Error::Rest(ref e) | Error::Push(ref e) => Some(e),
+            Error::Decode {
+                ref error,
+                ..
+            } => Some(&**error),
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpc_error(code: i32) -> Error {
+        Error::JsonRpc(jsonrpc::Error::Rpc(jsonrpc::error::RpcError {
+            code,
+            message: "".into(),
+            data: None,
+        }))
+    }
+
+    fn transport_error(e: TransportError) -> Error {
+        Error::JsonRpc(jsonrpc::Error::Transport(Box::new(e)))
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(rpc_error(RPC_IN_WARMUP).is_transient());
+        let warming_up = jsonrpc::error::RpcError {
+            code: RPC_IN_WARMUP,
+            message: "Loading block index...".into(),
+            data: None,
+        };
+        match Error::from(jsonrpc::Error::Rpc(warming_up)) {
+            e @ Error::NodeWarmingUp {
+                ..
+            } => {
+                assert!(e.is_transient());
+                assert_eq!(e.to_string(), "node is warming up: Loading block index...");
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        assert!(!rpc_error(RPC_INVALID_ADDRESS_OR_KEY).is_transient());
+
+        let reset = || io::Error::new(io::ErrorKind::ConnectionReset, "reset");
+        assert!(Error::Io(reset()).is_transient());
+        assert!(transport_error(TransportError::Io(reset())).is_transient());
+        assert!(!Error::Io(io::Error::new(io::ErrorKind::NotFound, "")).is_transient());
+
+        assert!(transport_error(TransportError::HttpStatus(503)).is_transient());
+        assert!(transport_error(TransportError::HttpStatus(500)).is_transient());
+        assert!(!transport_error(TransportError::HttpStatus(401)).is_transient());
+        assert!(Error::Rest(TransportError::HttpStatus(503)).is_transient());
+
+        let simple_http = jsonrpc::simple_http::Error::HttpErrorCode(503);
+        assert!(Error::JsonRpc(jsonrpc::Error::Transport(Box::new(simple_http))).is_transient());
+
+        assert!(!Error::UnexpectedStructure.is_transient());
+        assert!(!Error::Timeout(Duration::from_secs(1)).is_transient());
+    }
+
+    #[test]
+    fn test_is_unsent() {
+        let refused = || io::Error::new(io::ErrorKind::ConnectionRefused, "refused");
+        let timed_out = || io::Error::new(io::ErrorKind::TimedOut, "timed out");
+        assert!(Error::Io(refused()).is_unsent());
+        assert!(transport_error(TransportError::Connect(timed_out())).is_unsent());
+        assert!(transport_error(TransportError::HttpStatus(503)).is_unsent());
+        assert!(rpc_error(RPC_IN_WARMUP).is_unsent());
+
+        // The node may have run the call before the connection failed.
+        assert!(!transport_error(TransportError::Io(timed_out())).is_unsent());
+        assert!(transport_error(TransportError::Io(timed_out())).is_transient());
+        assert!(!transport_error(TransportError::HttpStatus(500)).is_unsent());
+        assert!(!rpc_error(RPC_INVALID_ADDRESS_OR_KEY).is_unsent());
+    }
+
+    #[test]
+    fn test_auth_failed() {
+        let forbidden = TransportError::AuthFailed {
+            status: 403,
+            body: "Forbidden".to_owned(),
+        };
+        match Error::from(jsonrpc::Error::Transport(Box::new(forbidden))) {
+            e @ Error::AuthFailed {
+                ..
+            } => {
+                assert!(!e.is_transient());
+                assert_eq!(e.to_string(), "the client is not allowed (HTTP status 403): Forbidden");
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let simple_http = jsonrpc::simple_http::Error::HttpErrorCode(401);
+        match Error::from(jsonrpc::Error::Transport(Box::new(simple_http))) {
+            Error::AuthFailed {
+                status: 401,
+                ref body,
+            } => assert!(body.is_empty()),
+            e => panic!("unexpected error: {}", e),
+        }
+        match Error::from(jsonrpc::Error::Transport(Box::new(TransportError::HttpStatus(404)))) {
+            Error::JsonRpc(_) => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+}
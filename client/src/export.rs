@@ -0,0 +1,253 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Exporting the transactions of a wallet for bookkeeping.
+
+use std::io;
+
+use dashcore::{Address, SignedAmount, Txid};
+
+use client::{Result, RpcApi};
+use json::{GetTransactionResultDetailCategory, ListTransactionResult};
+
+/// The number of transactions fetched per `listtransactions` call by
+/// [RpcApi::export_transactions].
+pub const EXPORT_PAGE_SIZE: usize = 100;
+
+/// The direction of the funds of an [ExportRecord].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Funds received by the wallet.
+    Incoming,
+    /// Funds sent by the wallet.
+    Outgoing,
+    /// Coins mined by the wallet, including immature ones.
+    Mined,
+}
+
+/// A normalized wallet transaction entry, one per output received or sent.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ExportRecord {
+    /// The time of the transaction, in seconds since the epoch.
+    pub timestamp: u64,
+    pub txid: Txid,
+    pub vout: u32,
+    pub direction: Direction,
+    /// The amount in DASH, negative when sent.
+    #[serde(with = "::json::serde_amount::as_btc")]
+    pub amount: SignedAmount,
+    /// The fee in DASH, negative, only for sent outputs.
+    #[serde(default, with = "::json::serde_amount::as_btc::opt")]
+    pub fee: Option<SignedAmount>,
+    pub address: Option<Address>,
+    pub label: Option<String>,
+    /// The confirmations, negative for a transaction that conflicts with
+    /// the main chain.
+    pub confirmations: i32,
+    pub chainlocked: bool,
+}
+
+impl ExportRecord {
+    /// The columns written by [ExportRecord::write_csv].
+    pub const CSV_HEADER: &'static str =
+        "timestamp,txid,vout,direction,amount,fee,address,label,confirmations,chainlocked";
+
+    /// Normalize an entry of `listtransactions`, `None` for an orphaned
+    /// coinbase, which never paid anything.
+    pub fn from_list_transaction(tx: &ListTransactionResult) -> Option<ExportRecord> {
+        let direction = match tx.detail.category {
+            GetTransactionResultDetailCategory::Send => Direction::Outgoing,
+            GetTransactionResultDetailCategory::Receive => Direction::Incoming,
+            GetTransactionResultDetailCategory::Generate
+            | GetTransactionResultDetailCategory::Immature => Direction::Mined,
+            GetTransactionResultDetailCategory::Orphan => return None,
+        };
+        Some(ExportRecord {
+            timestamp: tx.info.time,
+            txid: tx.info.txid,
+            vout: tx.detail.vout,
+            direction,
+            amount: tx.detail.amount,
+            fee: tx.detail.fee,
+            address: tx.detail.address.clone(),
+            label: tx.detail.label.clone(),
+            confirmations: tx.info.confirmations,
+            chainlocked: tx.info.chainlock == Some(true),
+        })
+    }
+
+    /// Write the records as CSV, with a header line.
+    pub fn write_csv<W: io::Write>(records: &[ExportRecord], mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", ExportRecord::CSV_HEADER)?;
+        for r in records {
+            let direction = match r.direction {
+                Direction::Incoming => "incoming",
+                Direction::Outgoing => "outgoing",
+                Direction::Mined => "mined",
+            };
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{}",
+                r.timestamp,
+                r.txid,
+                r.vout,
+                direction,
+                format_amount(r.amount),
+                r.fee.map(format_amount).unwrap_or_default(),
+                r.address.as_ref().map(ToString::to_string).unwrap_or_default(),
+                csv_field(r.label.as_ref().map_or("", String::as_str)),
+                r.confirmations,
+                r.chainlocked
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Format an amount in DASH with exactly 8 decimals, from its satoshis.
+fn format_amount(amount: SignedAmount) -> String {
+    let sat = amount.as_sat();
+    let abs = if sat < 0 {
+        (sat as u64).wrapping_neg()
+    } else {
+        sat as u64
+    };
+    let sign = if sat < 0 {
+        "-"
+    } else {
+        ""
+    };
+    format!("{}{}.{:08}", sign, abs / 100_000_000, abs % 100_000_000)
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(field: &str) -> String {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// See [RpcApi::export_transactions].
+pub(crate) fn export_transactions<C: RpcApi>(
+    rpc: &C,
+    label: Option<&str>,
+    include_watchonly: Option<bool>,
+) -> Result<Vec<ExportRecord>> {
+    let mut records = Vec::new();
+    let mut skip = 0;
+    loop {
+        let page =
+            rpc.list_transactions(label, Some(EXPORT_PAGE_SIZE), Some(skip), include_watchonly)?;
+        records.extend(page.iter().filter_map(ExportRecord::from_list_transaction));
+        if page.len() < EXPORT_PAGE_SIZE {
+            break;
+        }
+        skip += page.len();
+    }
+    // Transactions arriving while paging shift the pages, which repeats
+    // entries.
+    records.sort_by_key(|r| (r.timestamp, r.txid, r.vout, r.direction as u8));
+    records.dedup_by(|a, b| (a.txid, a.vout, a.direction) == (b.txid, b.vout, b.direction));
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use serde_json;
+    use testutil::{address, MockRpc};
+
+    /// A wallet with `count` transactions, one per second, the last one
+    /// being a send with a label that needs quoting.
+    struct MockWallet {
+        count: usize,
+    }
+
+    fn entry(i: usize, last: bool) -> String {
+        let (category, amount, fee, label) = if last {
+            ("send", "-1.5", r#", "fee": -0.0001"#, r#""rent, \"march\"""#)
+        } else if i == 0 {
+            ("orphan", "500.0", "", "null")
+        } else {
+            ("receive", "1.0", "", "null")
+        };
+        format!(
+            r#"{{"confirmations": {}, "txid": "{}", "time": {}, "timereceived": 0,
+                 "bip125-replaceable": "no", "walletconflicts": [], "chainlock": {},
+                 "address": "{}", "category": "{}",
+                 "amount": {}, "label": {}, "vout": 0{}}}"#,
+            i + 1,
+            Txid::hash(&[i as u8]),
+            1000 + i,
+            i > 0,
+            address(),
+            category,
+            amount,
+            label,
+            fee
+        )
+    }
+
+    impl MockRpc for MockWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            assert_eq!(cmd, "listtransactions");
+            let (count, skip) = (args[1].as_u64().unwrap(), args[2].as_u64().unwrap());
+            // Pages go back from the most recent transaction, oldest first.
+            let end = self.count.saturating_sub(skip as usize);
+            let start = end.saturating_sub(count as usize);
+            let entries: Vec<_> = (start..end).map(|i| entry(i, i == self.count - 1)).collect();
+            Ok(format!("[{}]", entries.join(",")))
+        }
+    }
+
+    #[test]
+    fn test_export_transactions() {
+        let records = MockWallet {
+            count: 150,
+        }
+        .export_transactions(None, None)
+        .unwrap();
+        // The orphaned coinbase is left out.
+        assert_eq!(records.len(), 149);
+        assert!(records.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        assert_eq!(records[0].direction, Direction::Incoming);
+        assert!(records[0].chainlocked);
+
+        let last = records.last().unwrap();
+        assert_eq!(last.direction, Direction::Outgoing);
+        assert_eq!(last.amount, SignedAmount::from_sat(-150_000_000));
+        assert_eq!(last.fee, Some(SignedAmount::from_sat(-10_000)));
+
+        let mut csv = Vec::new();
+        ExportRecord::write_csv(&records[148..], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], ExportRecord::CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            format!(
+                "1149,{},0,outgoing,-1.50000000,-0.00010000,{},\"rent, \"\"march\"\"\",150,true",
+                Txid::hash(&[149]),
+                address()
+            )
+        );
+
+        assert_eq!(format_amount(SignedAmount::from_sat(1)), "0.00000001");
+        assert_eq!(format_amount(SignedAmount::from_sat(-2_100_000_000)), "-21.00000000");
+
+        let json = serde_json::to_value(last).unwrap();
+        assert_eq!(json["direction"], "outgoing");
+        assert_eq!(json["amount"], -1.5);
+    }
+}
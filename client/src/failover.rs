@@ -0,0 +1,632 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Failover across several nodes.
+
+use std::sync::Mutex;
+
+use serde;
+use serde_json;
+
+use capabilities::Capabilities;
+use client::{Auth, Client, Result, RpcApi};
+use error::Error;
+
+/// The calls that need the wallet of the node, routed to a node with the
+/// [NodeRole::Wallet] or [NodeRole::Full] role by a [MultiClient], see also
+/// [WALLET_SUBCOMMANDS].
+pub const WALLET_COMMANDS: &[&str] = &[
+    "abandontransaction",
+    "abortrescan",
+    "addmultisigaddress",
+    "backupwallet",
+    "coinjoin",
+    "createwallet",
+    "dumphdinfo",
+    "dumpprivkey",
+    "dumpwallet",
+    "encryptwallet",
+    "fundrawtransaction",
+    "getaddressesbylabel",
+    "getaddressinfo",
+    "getbalance",
+    "getbalances",
+    "getnewaddress",
+    "getrawchangeaddress",
+    "getreceivedbyaddress",
+    "getreceivedbylabel",
+    "gettransaction",
+    "getunconfirmedbalance",
+    "getwalletinfo",
+    "importaddress",
+    "importdescriptors",
+    "importelectrumwallet",
+    "importmulti",
+    "importprivkey",
+    "importprunedfunds",
+    "importpubkey",
+    "importwallet",
+    "instantsendtoaddress",
+    "keypoolrefill",
+    "listaddressbalances",
+    "listaddressgroupings",
+    "listdescriptors",
+    "listlabels",
+    "listlockunspent",
+    "listreceivedbyaddress",
+    "listreceivedbylabel",
+    "listsinceblock",
+    "listtransactions",
+    "listunspent",
+    "listwalletdir",
+    "listwallets",
+    "loadwallet",
+    "lockunspent",
+    "removeprunedfunds",
+    "rescanblockchain",
+    "send",
+    "sendmany",
+    "sendtoaddress",
+    "setcoinjoinamount",
+    "setcoinjoinrounds",
+    "sethdseed",
+    "setlabel",
+    "settxfee",
+    "setwalletflag",
+    "signmessage",
+    "signrawtransaction",
+    "signrawtransactionwithwallet",
+    "unloadwallet",
+    "upgradetohd",
+    "upgradewallet",
+    "walletcreatefundedpsbt",
+    "walletlock",
+    "walletpassphrase",
+    "walletpassphrasechange",
+    "walletprocesspsbt",
+];
+
+/// The subcommands that need the wallet of the node, as `(method,
+/// subcommand)`, of the calls that otherwise don't, see [WALLET_COMMANDS].
+pub const WALLET_SUBCOMMANDS: &[(&str, &str)] = &[
+    ("gobject", "list-prepared"),
+    ("gobject", "prepare"),
+    ("gobject", "vote-alias"),
+    ("gobject", "vote-many"),
+    ("masternode", "outputs"),
+    ("protx", "register"),
+    ("protx", "register_evo"),
+    ("protx", "register_fund"),
+    ("protx", "register_fund_evo"),
+    ("protx", "register_prepare"),
+    ("protx", "register_prepare_evo"),
+    ("protx", "revoke"),
+    ("protx", "update_registrar"),
+    ("protx", "update_service"),
+    ("protx", "update_service_evo"),
+];
+
+/// Whether a call needs the wallet of the node, see [WALLET_COMMANDS] and
+/// [WALLET_SUBCOMMANDS].
+fn is_wallet_call(cmd: &str, args: &[serde_json::Value]) -> bool {
+    if WALLET_COMMANDS.contains(&cmd) {
+        return true;
+    }
+    match args.first().and_then(|arg| arg.as_str()) {
+        Some(sub) => WALLET_SUBCOMMANDS.contains(&(cmd, sub)),
+        None => false,
+    }
+}
+
+/// The name of a call for [Error::NoNodeForCall], with its subcommand if it
+/// has one.
+fn call_name(cmd: &str, args: &[serde_json::Value]) -> String {
+    match args.first().and_then(|arg| arg.as_str()) {
+        Some(sub) if WALLET_SUBCOMMANDS.contains(&(cmd, sub)) => format!("{} {}", cmd, sub),
+        _ => cmd.to_owned(),
+    }
+}
+
+/// The calls besides the [WALLET_COMMANDS] that change the state of the node
+/// or of the network, which a [MultiClient] doesn't send again to another
+/// node unless they surely were not run, see [Error::is_unsent].
+pub const SIDE_EFFECT_COMMANDS: &[&str] = &[
+    "addnode",
+    "clearbanned",
+    "disconnectnode",
+    "generate",
+    "generatetoaddress",
+    "generatetodescriptor",
+    "gobject",
+    "invalidateblock",
+    "mnsync",
+    "preciousblock",
+    "protx",
+    "pruneblockchain",
+    "reconsiderblock",
+    "sendrawtransaction",
+    "setban",
+    "setmocktime",
+    "setnetworkactive",
+    "spork",
+    "stop",
+    "submitblock",
+    "voteraw",
+];
+
+/// What the node of a [MultiClient] is used for, see
+/// [MultiClient::with_role].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum NodeRole {
+    /// Serves all calls, the default.
+    Full,
+    /// A public node without a wallet, like one exposed to the internet,
+    /// that serves the calls about the chain and broadcasts the
+    /// transactions. When a [MultiClient] has such nodes, `sendrawtransaction`
+    /// only goes to them, so that the wallet nodes are not linked to the
+    /// transactions.
+    Broadcast,
+    /// A node with the wallet, that serves the [WALLET_COMMANDS] and the
+    /// [WALLET_SUBCOMMANDS] besides the calls about the chain.
+    Wallet,
+}
+
+/// The health of a node of a [MultiClient], see [MultiClient::check_health].
+#[derive(Debug)]
+pub enum NodeHealth {
+    /// The node answers, at the given chain tip height.
+    Healthy {
+        height: u64,
+    },
+    /// The node answers, but its chain tip is further behind the highest tip
+    /// of the other nodes than [MultiClient::with_max_height_lag] allows.
+    Lagging {
+        height: u64,
+    },
+    /// The node failed to answer.
+    Unavailable(Error),
+}
+
+impl NodeHealth {
+    pub fn is_healthy(&self) -> bool {
+        match *self {
+            NodeHealth::Healthy {
+                ..
+            } => true,
+            _ => false,
+        }
+    }
+}
+
+/// A client that spreads over several nodes, for high availability.
+///
+/// Calls go to the first healthy node, in the order the nodes were given.
+/// When a node fails with a transient error, see [Error::is_transient], it
+/// is marked unhealthy and the call is retried with the next node, then with
+/// the unhealthy ones as a last resort. A node that answers is marked
+/// healthy again. Errors of a node about the call itself are returned as is.
+///
+/// Calls with side effects, the [SIDE_EFFECT_COMMANDS], are only retried
+/// with another node if the failed node surely did not run them, see
+/// [Error::is_unsent]. Otherwise, like when the connection timed out after a
+/// block was submitted, the error is returned.
+///
+/// The nodes have different wallets, so wallet calls always go to the first
+/// node with the [NodeRole::Wallet] or [NodeRole::Full] role and are never
+/// retried with another node. Balances then always come from the same wallet
+/// and a payment is never sent twice.
+///
+/// The nodes should be interchangeable, like replicas following the same
+/// chain, except for their roles, see [MultiClient::with_role]: only the
+/// nodes that can serve a call are tried. [MultiClient::check_health] can be
+/// called periodically to find the nodes that are unavailable or lag behind
+/// the others before calls fail.
+pub struct MultiClient<C> {
+    nodes: Vec<C>,
+    roles: Vec<NodeRole>,
+    healthy: Mutex<Vec<bool>>,
+    max_height_lag: Option<u64>,
+}
+
+impl MultiClient<Client> {
+    /// Creates a client to the dashd JSON-RPC servers at `urls`, which share
+    /// the same authentication, see [Client::new].
+    pub fn new(urls: &[&str], auth: Auth) -> Result<MultiClient<Client>> {
+        let nodes = urls.iter().map(|url| Client::new(url, auth.clone()));
+        Ok(MultiClient::from_clients(nodes.collect::<Result<_>>()?))
+    }
+}
+
+impl<C: RpcApi> MultiClient<C> {
+    /// Spread the calls over the given clients, preferring the first ones.
+    ///
+    /// Panics if there are no clients.
+    pub fn from_clients(nodes: Vec<C>) -> MultiClient<C> {
+        assert!(!nodes.is_empty(), "no nodes");
+        MultiClient {
+            healthy: Mutex::new(vec![true; nodes.len()]),
+            roles: vec![NodeRole::Full; nodes.len()],
+            nodes,
+            max_height_lag: None,
+        }
+    }
+
+    /// Have [MultiClient::check_health] mark the nodes whose chain tip is
+    /// more than `lag` blocks behind the highest tip of the nodes unhealthy,
+    /// so that calls go to the nodes that agree on the chain tip.
+    pub fn with_max_height_lag(mut self, lag: u64) -> Self {
+        self.max_height_lag = Some(lag);
+        self
+    }
+
+    /// Set the role of the node at `index`, see [NodeRole].
+    ///
+    /// Wallet calls, the [WALLET_COMMANDS] and the [WALLET_SUBCOMMANDS], only
+    /// go to the first [NodeRole::Wallet] or [NodeRole::Full] node, and fail
+    /// with [Error::NoNodeForCall] if there is none. Panics if there is no
+    /// node at `index`.
+    pub fn with_role(mut self, index: usize, role: NodeRole) -> Self {
+        self.roles[index] = role;
+        self
+    }
+
+    pub fn nodes(&self) -> &[C] {
+        &self.nodes
+    }
+
+    pub fn role(&self, index: usize) -> NodeRole {
+        self.roles[index]
+    }
+
+    /// Whether the node at `index` is currently considered healthy.
+    pub fn is_healthy(&self, index: usize) -> bool {
+        self.healthy.lock().unwrap()[index]
+    }
+
+    /// Ask every node for its chain tip and mark the nodes that fail, or lag
+    /// behind, unhealthy and the others healthy.
+    ///
+    /// Returns the health of the nodes, in their order.
+    pub fn check_health(&self) -> Vec<NodeHealth> {
+        let heights: Vec<_> = self.nodes.iter().map(|node| node.get_block_count()).collect();
+        let best = heights.iter().filter_map(|h| h.as_ref().ok()).cloned().max().unwrap_or(0);
+        let health: Vec<_> = heights
+            .into_iter()
+            .map(|height| match height {
+                Ok(height) if self.max_height_lag.map_or(false, |lag| height + lag < best) => {
+                    NodeHealth::Lagging {
+                        height,
+                    }
+                }
+                Ok(height) => NodeHealth::Healthy {
+                    height,
+                },
+                Err(e) => NodeHealth::Unavailable(e),
+            })
+            .collect();
+        *self.healthy.lock().unwrap() = health.iter().map(NodeHealth::is_healthy).collect();
+        health
+    }
+
+    /// The nodes that can serve all the given calls, which are not wallet
+    /// calls.
+    fn nodes_for(&self, cmds: &[&str]) -> Vec<usize> {
+        let all = 0..self.nodes.len();
+        if !cmds.is_empty() && cmds.iter().all(|&cmd| cmd == "sendrawtransaction") {
+            let broadcast: Vec<_> =
+                all.clone().filter(|&i| self.roles[i] == NodeRole::Broadcast).collect();
+            if broadcast.is_empty() {
+                all.collect()
+            } else {
+                broadcast
+            }
+        } else {
+            all.collect()
+        }
+    }
+
+    /// Make the given calls with the first of the nodes that can serve them
+    /// that answers, see [MultiClient].
+    fn route<T, F>(&self, calls: &[(&str, &[serde_json::Value])], f: F) -> Result<T>
+    where
+        F: Fn(&C) -> Result<T>,
+    {
+        let wallet_calls: Vec<_> =
+            calls.iter().filter(|&&(cmd, args)| is_wallet_call(cmd, args)).collect();
+        if !wallet_calls.is_empty() {
+            let i = match self.roles.iter().position(|&role| role != NodeRole::Broadcast) {
+                Some(i) => i,
+                None => {
+                    let names: Vec<_> =
+                        wallet_calls.iter().map(|&&(cmd, args)| call_name(cmd, args)).collect();
+                    return Err(Error::NoNodeForCall(names.join(", ")));
+                }
+            };
+            let result = f(&self.nodes[i]);
+            let healthy = result.as_ref().err().map_or(true, |e| !e.is_transient());
+            self.healthy.lock().unwrap()[i] = healthy;
+            return result;
+        }
+
+        let cmds: Vec<_> = calls.iter().map(|&(cmd, _)| cmd).collect();
+        let nodes = self.nodes_for(&cmds);
+        let repeatable = !cmds.iter().any(|cmd| SIDE_EFFECT_COMMANDS.contains(cmd));
+        let order = {
+            let healthy = self.healthy.lock().unwrap();
+            let mut order: Vec<_> = nodes.iter().cloned().filter(|&i| healthy[i]).collect();
+            order.extend(nodes.iter().cloned().filter(|&i| !healthy[i]));
+            order
+        };
+        let mut last_error = None;
+        for i in order {
+            match f(&self.nodes[i]) {
+                Err(e) => {
+                    if !e.is_transient() {
+                        self.healthy.lock().unwrap()[i] = true;
+                        return Err(e);
+                    }
+                    self.healthy.lock().unwrap()[i] = false;
+                    if !repeatable && !e.is_unsent() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                }
+                Ok(result) => {
+                    self.healthy.lock().unwrap()[i] = true;
+                    return Ok(result);
+                }
+            }
+        }
+        Err(last_error.expect("there is at least one node for the call"))
+    }
+}
+
+impl<C: RpcApi> RpcApi for MultiClient<C> {
+    fn call<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        cmd: &str,
+        args: &[serde_json::Value],
+    ) -> Result<T> {
+        self.route(&[(cmd, args)], |node| node.call(cmd, args))
+    }
+
+    fn call_batch<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        calls: &[(&str, Vec<serde_json::Value>)],
+    ) -> Result<Vec<Result<T>>> {
+        let routed: Vec<_> = calls.iter().map(|&(cmd, ref args)| (cmd, &args[..])).collect();
+        self.route(&routed, |node| node.call_batch(calls))
+    }
+
+    fn node_version(&self) -> Result<usize> {
+        self.route(&[], |node| node.node_version())
+    }
+
+    fn capabilities(&self) -> Result<Capabilities> {
+        self.route(&[], |node| node.capabilities())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc;
+    use std::cell::Cell;
+    use std::io;
+    use testutil::MockRpc;
+
+    /// A node at `height` that refuses connections while `down`, and times
+    /// out after running the calls while `timing_out`, counting the calls it
+    /// ran.
+    struct MockNode {
+        height: u64,
+        down: Cell<bool>,
+        timing_out: Cell<bool>,
+        calls: Cell<usize>,
+    }
+
+    fn node(height: u64, down: bool) -> MockNode {
+        MockNode {
+            height,
+            down: Cell::new(down),
+            timing_out: Cell::new(false),
+            calls: Cell::new(0),
+        }
+    }
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, _: &[serde_json::Value]) -> Result<String> {
+            if self.down.get() {
+                return Err(Error::Io(io::ErrorKind::ConnectionRefused.into()));
+            }
+            self.calls.set(self.calls.get() + 1);
+            if self.timing_out.get() {
+                return Err(Error::Io(io::ErrorKind::TimedOut.into()));
+            }
+            match cmd {
+                "getblockcount" => Ok(self.height.to_string()),
+                "getblockhash" => {
+                    let e = jsonrpc::error::RpcError {
+                        code: -8,
+                        message: "Block height out of range".to_owned(),
+                        data: None,
+                    };
+                    Err(Error::JsonRpc(jsonrpc::Error::Rpc(e)))
+                }
+                "getbalance" | "protx" | "sendrawtransaction" | "sethdseed" => {
+                    Ok("null".to_owned())
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            }
+        }
+    }
+
+    #[test]
+    fn test_failover() {
+        let client = MultiClient::from_clients(vec![node(10, true), node(10, false)]);
+        assert_eq!(client.get_block_count().unwrap(), 10);
+        assert!(!client.is_healthy(0));
+        assert!(client.is_healthy(1));
+
+        // The unhealthy node is tried last, and again once it is back.
+        client.nodes()[0].down.set(false);
+        client.get_block_count().unwrap();
+        assert_eq!(client.nodes()[0].calls.get(), 0);
+        client.nodes()[1].down.set(true);
+        client.get_block_count().unwrap();
+        assert_eq!(client.nodes()[0].calls.get(), 1);
+        assert!(client.is_healthy(0));
+        assert!(!client.is_healthy(1));
+
+        // Errors about the call are not retried with another node.
+        client.nodes()[1].down.set(false);
+        assert!(client.get_block_hash(100).is_err());
+        assert_eq!(client.nodes()[0].calls.get(), 2);
+        assert_eq!(client.nodes()[1].calls.get(), 2);
+
+        client.nodes()[0].down.set(true);
+        client.nodes()[1].down.set(true);
+        match client.get_block_count() {
+            Err(ref e) if e.is_transient() => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_side_effects() {
+        let client = MultiClient::from_clients(vec![node(10, false), node(10, false)]);
+        let call = |cmd: &str| -> Result<serde_json::Value> { client.call(cmd, &[]) };
+
+        // A transaction that may have been broadcast is not sent again.
+        client.nodes()[0].timing_out.set(true);
+        match call("sendrawtransaction") {
+            Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::TimedOut => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(client.nodes()[1].calls.get(), 0);
+        assert!(!client.is_healthy(0));
+
+        // Calls that only read are.
+        client.nodes()[1].timing_out.set(true);
+        client.nodes()[0].timing_out.set(false);
+        call("getblockcount").unwrap();
+        assert_eq!(client.nodes()[1].calls.get(), 1);
+
+        // A transaction that surely was not sent is sent to another node.
+        client.nodes()[1].timing_out.set(false);
+        client.nodes()[0].down.set(true);
+        call("sendrawtransaction").unwrap();
+        assert_eq!(client.nodes()[1].calls.get(), 2);
+    }
+
+    #[test]
+    fn test_wallet_calls() {
+        let nodes = vec![node(10, false), node(10, false), node(10, false)];
+        let client = MultiClient::from_clients(nodes)
+            .with_role(0, NodeRole::Broadcast)
+            .with_role(1, NodeRole::Wallet)
+            .with_role(2, NodeRole::Wallet);
+        let calls = || -> Vec<usize> { client.nodes().iter().map(|n| n.calls.get()).collect() };
+        let call =
+            |cmd: &str, sub: &str| -> Result<serde_json::Value> { client.call(cmd, &[sub.into()]) };
+
+        // Only the wallet subcommands go to the wallet node.
+        call("protx", "register_prepare").unwrap();
+        assert_eq!(calls(), vec![0, 1, 0]);
+        call("protx", "list").unwrap();
+        assert_eq!(calls(), vec![1, 1, 0]);
+        call("sethdseed", "").unwrap();
+        assert_eq!(calls(), vec![1, 2, 0]);
+
+        // The wallet of another node is never used instead.
+        client.nodes()[1].down.set(true);
+        assert!(call("getbalance", "").is_err());
+        assert_eq!(calls(), vec![1, 2, 0]);
+        assert!(!client.is_healthy(1));
+
+        let public =
+            MultiClient::from_clients(vec![node(10, false)]).with_role(0, NodeRole::Broadcast);
+        match public.call::<serde_json::Value>("protx", &["register_fund".into()]) {
+            Err(Error::NoNodeForCall(ref cmd)) if cmd == "protx register_fund" => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_check_health() {
+        let client =
+            MultiClient::from_clients(vec![node(8, false), node(10, false), node(9, true)])
+                .with_max_height_lag(1);
+        let health = client.check_health();
+        match health[0] {
+            NodeHealth::Lagging {
+                height: 8,
+            } => {}
+            ref h => panic!("unexpected health: {:?}", h),
+        }
+        assert!(health[1].is_healthy());
+        match health[2] {
+            NodeHealth::Unavailable(_) => {}
+            ref h => panic!("unexpected health: {:?}", h),
+        }
+        assert!(!client.is_healthy(0));
+        assert!(client.is_healthy(1));
+        assert!(!client.is_healthy(2));
+
+        // Calls go to the node at the highest tip first.
+        client.get_block_count().unwrap();
+        assert_eq!(client.nodes()[1].calls.get(), 2);
+        assert_eq!(client.nodes()[0].calls.get(), 1);
+    }
+
+    #[test]
+    fn test_roles() {
+        let nodes = vec![node(10, false), node(10, false), node(10, false)];
+        let client = MultiClient::from_clients(nodes)
+            .with_role(0, NodeRole::Broadcast)
+            .with_role(1, NodeRole::Wallet)
+            .with_role(2, NodeRole::Broadcast);
+        let calls = |client: &MultiClient<MockNode>| -> Vec<usize> {
+            client.nodes().iter().map(|n| n.calls.get()).collect()
+        };
+        let call = |client: &MultiClient<MockNode>, cmd: &str| -> Result<serde_json::Value> {
+            client.call(cmd, &[])
+        };
+
+        call(&client, "getbalance").unwrap();
+        assert_eq!(calls(&client), vec![0, 1, 0]);
+        call(&client, "sendrawtransaction").unwrap();
+        assert_eq!(calls(&client), vec![1, 1, 0]);
+        client.nodes()[0].down.set(true);
+        call(&client, "sendrawtransaction").unwrap();
+        assert_eq!(calls(&client), vec![1, 1, 1]);
+
+        // Transactions are not broadcast by the wallet node.
+        client.nodes()[2].down.set(true);
+        assert!(call(&client, "sendrawtransaction").is_err());
+        assert_eq!(calls(&client), vec![1, 1, 1]);
+        call(&client, "getblockcount").unwrap();
+        assert_eq!(calls(&client), vec![1, 2, 1]);
+
+        // A batch with a wallet call goes to a wallet node.
+        client.nodes()[0].down.set(false);
+        let batch = [("getblockcount", vec![]), ("getbalance", vec![])];
+        client.call_batch::<serde_json::Value>(&batch).unwrap();
+        assert_eq!(calls(&client), vec![1, 4, 1]);
+
+        let public =
+            MultiClient::from_clients(vec![node(10, false)]).with_role(0, NodeRole::Broadcast);
+        match call(&public, "getbalance") {
+            Err(Error::NoNodeForCall(ref cmd)) if cmd == "getbalance" => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        call(&public, "sendrawtransaction").unwrap();
+    }
+}
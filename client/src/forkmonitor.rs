@@ -0,0 +1,188 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Monitoring competing chains for deep forks.
+
+use std::cmp;
+use std::collections::HashSet;
+
+use dashcore::BlockHash;
+
+use client::{Result, RpcApi};
+use json::GetChainTipsResultStatus;
+
+/// A deep fork reported by a [ForkMonitor].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct ForkAlert {
+    /// The tip of the fork.
+    pub tip: BlockHash,
+    /// The height of the tip of the fork.
+    pub height: u64,
+    /// The number of blocks of the fork since it left the active chain.
+    pub branch_length: usize,
+    /// The height of the last block the fork shares with the active chain.
+    pub fork_height: u64,
+    /// The height of the best ChainLock, `None` if the node knows none.
+    pub chain_lock_height: Option<u32>,
+}
+
+/// Watches the chain tips of the node for forks that could replace a deep
+/// part of the active chain, as in a 51% attack.
+///
+/// Every [ForkMonitor::poll] looks at the `valid-fork` tips reported by
+/// `getchaintips` and raises a [ForkAlert] for each that is at least
+/// `depth` blocks long, unless the active chain is ChainLocked past the
+/// fork point. A ChainLocked block can't be reorganized, so such a fork can
+/// never become active. Each fork tip is reported once, a growing fork is
+/// reported again with its new tip.
+#[derive(Clone, Debug)]
+pub struct ForkMonitor {
+    depth: usize,
+    reported: HashSet<BlockHash>,
+}
+
+impl ForkMonitor {
+    /// Create a monitor alerting on forks of at least `depth` blocks.
+    pub fn new(depth: usize) -> ForkMonitor {
+        ForkMonitor {
+            depth: cmp::max(depth, 1),
+            reported: HashSet::new(),
+        }
+    }
+
+    /// Fetch the chain tips and return the alerts for the deep forks that
+    /// were not reported yet.
+    pub fn poll<C: RpcApi>(&mut self, rpc: &C) -> Result<Vec<ForkAlert>> {
+        let tips = rpc.get_chain_tips()?;
+        let forks: Vec<_> = tips
+            .iter()
+            .filter(|t| t.status == GetChainTipsResultStatus::ValidFork)
+            .filter(|t| t.branch_length >= self.depth && !self.reported.contains(&t.hash))
+            .collect();
+
+        let mut alerts = Vec::new();
+        if !forks.is_empty() {
+            let chain_lock_height = rpc.get_best_chain_lock()?.map(|c| c.height);
+            for fork in forks {
+                let fork_height = fork.height.saturating_sub(fork.branch_length as u64);
+                if chain_lock_height.map_or(false, |h| u64::from(h) > fork_height) {
+                    continue;
+                }
+                self.reported.insert(fork.hash);
+                alerts.push(ForkAlert {
+                    tip: fork.hash,
+                    height: fork.height,
+                    branch_length: fork.branch_length,
+                    fork_height,
+                    chain_lock_height,
+                });
+            }
+        }
+        self.reported.retain(|hash| tips.iter().any(|t| t.hash == *hash));
+        Ok(alerts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use serde_json;
+    use std::cell::RefCell;
+    use testutil::MockRpc;
+
+    /// A node answering with the queued chain tips, as `(tip, height,
+    /// branch length)` of valid forks, and best ChainLock heights.
+    struct MockNode {
+        tips: RefCell<Vec<Vec<(u8, u64, usize)>>>,
+        chain_locks: RefCell<Vec<Option<u32>>>,
+    }
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, _args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "getchaintips" => {
+                    let mut tips = vec![format!(
+                        r#"{{"height": 100, "hash": "{}", "branchlen": 0, "status": "active"}}"#,
+                        BlockHash::hash(&[0])
+                    )];
+                    for (tip, height, branch_length) in self.tips.borrow_mut().remove(0) {
+                        tips.push(format!(
+                            r#"{{"height": {}, "hash": "{}", "branchlen": {},
+                                 "status": "valid-fork"}}"#,
+                            height,
+                            BlockHash::hash(&[tip]),
+                            branch_length
+                        ));
+                    }
+                    format!("[{}]", tips.join(","))
+                }
+                "getbestchainlock" => match self.chain_locks.borrow_mut().remove(0) {
+                    Some(height) => format!(
+                        r#"{{"blockhash": "{}", "height": {}, "signature": "00",
+                             "known_block": true}}"#,
+                        BlockHash::hash(&[0]),
+                        height
+                    ),
+                    None => {
+                        let error = ::jsonrpc::error::RpcError {
+                            code: -32603,
+                            message: "Unable to find any ChainLock".to_owned(),
+                            data: None,
+                        };
+                        return Err(::jsonrpc::Error::Rpc(error).into());
+                    }
+                },
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    #[test]
+    fn test_poll() {
+        let node = MockNode {
+            tips: RefCell::new(vec![
+                vec![(1, 99, 2)],
+                vec![(1, 99, 2), (2, 98, 6)],
+                vec![(2, 98, 6)],
+                vec![(3, 99, 7)],
+                vec![(4, 100, 8)],
+            ]),
+            chain_locks: RefCell::new(vec![None, Some(90), Some(95)]),
+        };
+        let mut monitor = ForkMonitor::new(6);
+        assert!(monitor.poll(&node).unwrap().is_empty());
+
+        let alerts = monitor.poll(&node).unwrap();
+        assert_eq!(
+            alerts,
+            vec![ForkAlert {
+                tip: BlockHash::hash(&[2]),
+                height: 98,
+                branch_length: 6,
+                fork_height: 92,
+                chain_lock_height: None,
+            }]
+        );
+        // Already reported.
+        assert!(monitor.poll(&node).unwrap().is_empty());
+
+        // The fork grew, still above the best ChainLock.
+        let alerts = monitor.poll(&node).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].tip, BlockHash::hash(&[3]));
+        assert_eq!(alerts[0].chain_lock_height, Some(90));
+
+        // The active chain is ChainLocked past the fork point.
+        assert!(monitor.poll(&node).unwrap().is_empty());
+        assert!(node.chain_locks.borrow().is_empty());
+    }
+}
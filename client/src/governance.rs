@@ -0,0 +1,459 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Creating governance proposals and following the superblocks.
+
+use std::cmp;
+use std::collections::hash_map::{HashMap, Iter};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json;
+
+use dashcore::hashes::hex::ToHex;
+use dashcore::{Address, Amount, Txid};
+
+use client::{Result, RpcApi};
+use error::Error;
+use json;
+
+/// The number of confirmations the collateral of a governance object needs
+/// before the object can be submitted, see `GOVERNANCE_FEE_CONFIRMATIONS` in
+/// Dash Core.
+pub const GOVERNANCE_FEE_CONFIRMATIONS: u32 = 6;
+
+/// The number of seconds between two blocks Dash targets.
+pub const TARGET_BLOCK_SPACING: u64 = 150;
+
+/// The governance object type of proposals.
+const GOVERNANCE_OBJECT_PROPOSAL: u8 = 1;
+
+/// The data of a proposal as serialized by Dash Core.
+#[derive(Serialize)]
+struct ProposalData<'a> {
+    #[serde(rename = "type")]
+    type_: u8,
+    name: &'a str,
+    start_epoch: u64,
+    end_epoch: u64,
+    payment_address: String,
+    payment_amount: f64,
+    url: &'a str,
+}
+
+/// Builds and submits a governance proposal.
+///
+/// Submitting a proposal takes several steps: its data is checked with
+/// `gobject check`, the collateral is paid with `gobject prepare`, and once
+/// the collateral transaction has enough confirmations the proposal is sent
+/// with `gobject submit`. [ProposalBuilder::submit] does all of them,
+/// [ProposalBuilder::prepare] only the first two so the proposal can be
+/// submitted later with [PreparedProposal::wait_and_submit].
+#[derive(Clone, PartialEq, Debug)]
+pub struct ProposalBuilder {
+    name: String,
+    url: String,
+    payment_address: Address,
+    payment_amount: Amount,
+    start_epoch: u64,
+    end_epoch: u64,
+    time: Option<u64>,
+    confirmations: u32,
+}
+
+impl ProposalBuilder {
+    /// A proposal paying `payment_amount` to `payment_address` in every
+    /// superblock between the UNIX timestamps `start_epoch` and `end_epoch`.
+    pub fn new(
+        name: &str,
+        payment_address: Address,
+        payment_amount: Amount,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> ProposalBuilder {
+        ProposalBuilder {
+            name: name.to_owned(),
+            url: String::new(),
+            payment_address,
+            payment_amount,
+            start_epoch,
+            end_epoch,
+            time: None,
+            confirmations: GOVERNANCE_FEE_CONFIRMATIONS,
+        }
+    }
+
+    /// Set the URL describing the proposal.
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = url.to_owned();
+        self
+    }
+
+    /// Set the creation time of the proposal, the current time by default.
+    pub fn with_time(mut self, time: u64) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Set the number of confirmations of the collateral to wait for before
+    /// submitting, [GOVERNANCE_FEE_CONFIRMATIONS] by default.
+    pub fn with_confirmations(mut self, confirmations: u32) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// The hex encoded data of the proposal.
+    pub fn data_hex(&self) -> Result<String> {
+        let data = ProposalData {
+            type_: GOVERNANCE_OBJECT_PROPOSAL,
+            name: &self.name,
+            start_epoch: self.start_epoch,
+            end_epoch: self.end_epoch,
+            payment_address: self.payment_address.to_string(),
+            payment_amount: self.payment_amount.as_btc(),
+            url: &self.url,
+        };
+        Ok(serde_json::to_string(&data)?.as_bytes().to_hex())
+    }
+
+    /// Check the proposal and pay its collateral.
+    pub fn prepare<C: RpcApi>(&self, rpc: &C) -> Result<PreparedProposal> {
+        let data_hex = self.data_hex()?;
+        rpc.gobject_check(&data_hex)?;
+        let time = match self.time {
+            Some(time) => time,
+            None => SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        };
+        let collateral_txid = rpc.gobject_prepare(None, 1, time, &data_hex)?;
+        Ok(PreparedProposal {
+            data_hex,
+            time,
+            collateral_txid,
+            confirmations: self.confirmations,
+        })
+    }
+
+    /// Check the proposal, pay its collateral and submit it once the
+    /// collateral is confirmed.
+    ///
+    /// Fails with [Error::Timeout] if the collateral is not confirmed within
+    /// `timeout`, see [PreparedProposal] to submit it later.
+    pub fn submit<C: RpcApi>(
+        &self,
+        rpc: &C,
+        timeout: Duration,
+    ) -> Result<json::GovernanceObjectHash> {
+        self.prepare(rpc)?.wait_and_submit(rpc, timeout)
+    }
+}
+
+/// A proposal whose collateral was paid, see [ProposalBuilder::prepare].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PreparedProposal {
+    pub data_hex: String,
+    pub time: u64,
+    pub collateral_txid: Txid,
+    confirmations: u32,
+}
+
+impl PreparedProposal {
+    /// Wait for the collateral to be confirmed and submit the proposal.
+    ///
+    /// New blocks are awaited with `waitfornewblock`. Fails with
+    /// [Error::Timeout] if the collateral is not confirmed within `timeout`.
+    pub fn wait_and_submit<C: RpcApi>(
+        &self,
+        rpc: &C,
+        timeout: Duration,
+    ) -> Result<json::GovernanceObjectHash> {
+        #[derive(Deserialize)]
+        struct Response {
+            confirmations: i32,
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let txid = serde_json::to_value(self.collateral_txid)?;
+            let tx: Response = rpc.call("gettransaction", &[txid])?;
+            if tx.confirmations >= self.confirmations as i32 {
+                break;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Timeout(timeout));
+            }
+            // Wait at least a millisecond, as zero waits forever.
+            let left = deadline - now;
+            let wait = cmp::max(left.as_secs() * 1000 + left.subsec_millis() as u64, 1);
+            rpc.wait_for_new_block(wait)?;
+        }
+        rpc.gobject_submit(None, 1, self.time, &self.data_hex, &self.collateral_txid)
+    }
+}
+
+/// A superblock to come, see [RpcApi::next_superblocks].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Superblock {
+    pub height: u32,
+    /// The estimated UNIX timestamp of the superblock, assuming a block every
+    /// [TARGET_BLOCK_SPACING] seconds from now.
+    pub estimated_time: u64,
+    /// The height from which the budget paid by the superblock is finalized,
+    /// votes that come later may not count. `None` if the node doesn't tell
+    /// the maturity window.
+    pub voting_deadline: Option<u32>,
+}
+
+/// See [RpcApi::next_superblocks].
+pub(crate) fn next_superblocks<C: RpcApi>(rpc: &C, n: usize) -> Result<Vec<Superblock>> {
+    let info = rpc.get_governance_info()?;
+    let count = rpc.get_block_count()? as u32;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let cycle = cmp::max(info.superblock_cycle, 1);
+    // A superblock may have been mined between the two calls.
+    let mut height = info.next_superblock;
+    while height <= count {
+        height += cycle;
+    }
+    let superblocks = (0..n as u32).map(|i| {
+        let height = height + i * cycle;
+        Superblock {
+            height,
+            estimated_time: now + u64::from(height - count) * TARGET_BLOCK_SPACING,
+            voting_deadline: info.superblock_maturity_window.map(|w| height.saturating_sub(w)),
+        }
+    });
+    Ok(superblocks.collect())
+}
+
+/// The number of votes of each outcome for a signal.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct VoteCount {
+    pub yes: u32,
+    pub no: u32,
+    pub abstain: u32,
+}
+
+impl VoteCount {
+    /// The yes votes minus the no votes, which decides whether a proposal
+    /// is funded.
+    pub fn absolute_yes(&self) -> i64 {
+        self.yes as i64 - self.no as i64
+    }
+
+    /// The number of votes of any outcome.
+    pub fn total(&self) -> u32 {
+        self.yes + self.no + self.abstain
+    }
+}
+
+/// The votes on a governance object counted per signal, see
+/// [RpcApi::gobject_vote_tally].
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct VoteTally {
+    counts: HashMap<json::VoteSignal, VoteCount>,
+}
+
+impl VoteTally {
+    pub fn from_votes<'a, I>(votes: I) -> VoteTally
+    where
+        I: IntoIterator<Item = &'a json::GovernanceVote>,
+    {
+        let mut tally = VoteTally::default();
+        for vote in votes {
+            tally.add(vote);
+        }
+        tally
+    }
+
+    /// Count a vote, votes without an outcome are ignored.
+    pub fn add(&mut self, vote: &json::GovernanceVote) {
+        let count = self.counts.entry(vote.signal).or_insert_with(VoteCount::default);
+        match vote.outcome {
+            json::VoteOutcome::Yes => count.yes += 1,
+            json::VoteOutcome::No => count.no += 1,
+            json::VoteOutcome::Abstain => count.abstain += 1,
+            json::VoteOutcome::None => {}
+        }
+    }
+
+    /// The votes for `signal`, all zero if there are none.
+    pub fn get(&self, signal: json::VoteSignal) -> VoteCount {
+        self.counts.get(&signal).cloned().unwrap_or_default()
+    }
+
+    /// The votes on whether a proposal should be paid.
+    pub fn funding(&self) -> VoteCount {
+        self.get(json::VoteSignal::Funding)
+    }
+
+    /// The signals that were voted on with their votes, in no particular
+    /// order.
+    pub fn iter(&self) -> Iter<json::VoteSignal, VoteCount> {
+        self.counts.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::hex::FromHex;
+    use dashcore::util::address::Payload;
+    use dashcore::Network;
+    use std::cell::{Cell, RefCell};
+    use testutil::MockRpc;
+
+    /// A node on which every `waitfornewblock` mines a block.
+    #[derive(Default)]
+    struct GovernanceNode {
+        height: Cell<i32>,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl MockRpc for GovernanceNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let value = match cmd {
+                "gobject" => {
+                    self.calls.borrow_mut().push(args[0].as_str().unwrap().to_owned());
+                    match args[0].as_str().unwrap() {
+                        "check" => serde_json::from_str(r#"{"Object status": "OK"}"#)?,
+                        "prepare" => {
+                            self.height.set(0);
+                            serde_json::to_value(Txid::default())?
+                        }
+                        "submit" => "00".repeat(32).into(),
+                        c => panic!("unexpected call: gobject {}", c),
+                    }
+                }
+                "gettransaction" => {
+                    serde_json::from_str(&format!(r#"{{"confirmations": {}}}"#, self.height.get()))?
+                }
+                "waitfornewblock" => {
+                    self.height.set(self.height.get() + 1);
+                    serde_json::from_str(&format!(
+                        r#"{{"hash": "{}", "height": 0}}"#,
+                        "00".repeat(32)
+                    ))?
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    fn proposal() -> ProposalBuilder {
+        let address = Address {
+            network: Network::Dash,
+            payload: Payload::PubkeyHash(Default::default()),
+        };
+        ProposalBuilder::new("test-proposal", address, Amount::from_sat(150_000_000), 10, 20)
+            .with_url("https://dash.org")
+            .with_time(5)
+    }
+
+    #[test]
+    fn test_data_hex() {
+        let data = Vec::<u8>::from_hex(&proposal().data_hex().unwrap()).unwrap();
+        let data: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(data["type"], 1);
+        assert_eq!(data["name"], "test-proposal");
+        assert_eq!(data["start_epoch"], 10);
+        assert_eq!(data["end_epoch"], 20);
+        assert_eq!(data["payment_amount"], 1.5);
+        assert_eq!(data["url"], "https://dash.org");
+        assert!(data["payment_address"].as_str().unwrap().starts_with('X'));
+    }
+
+    #[test]
+    fn test_submit() {
+        let node = GovernanceNode::default();
+        let prepared = proposal().prepare(&node).unwrap();
+        assert_eq!(prepared.time, 5);
+        match prepared.wait_and_submit(&node, Duration::from_millis(0)) {
+            Err(Error::Timeout(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let hash = proposal().submit(&node, Duration::from_secs(10)).unwrap();
+        assert_eq!(hash, Default::default());
+        assert_eq!(node.height.get(), GOVERNANCE_FEE_CONFIRMATIONS as i32);
+        assert_eq!(*node.calls.borrow(), ["check", "prepare", "check", "prepare", "submit"]);
+    }
+
+    #[test]
+    fn test_vote_tally() {
+        let votes: Vec<json::GovernanceVote> = [
+            "yes:funding",
+            "yes:funding",
+            "no:funding",
+            "abstain:funding",
+            "none:funding",
+            "yes:valid",
+            "no:delete",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, v)| format!("{}-{}:1:{}", Txid::default(), i, v).parse().unwrap())
+        .collect();
+        let tally = VoteTally::from_votes(&votes);
+        assert_eq!(
+            tally.funding(),
+            VoteCount {
+                yes: 2,
+                no: 1,
+                abstain: 1,
+            }
+        );
+        assert_eq!(tally.funding().absolute_yes(), 1);
+        assert_eq!(tally.funding().total(), 4);
+        assert_eq!(tally.get(json::VoteSignal::Valid).yes, 1);
+        assert_eq!(tally.get(json::VoteSignal::Delete).absolute_yes(), -1);
+        assert_eq!(tally.get(json::VoteSignal::Endorsed), VoteCount::default());
+        assert_eq!(tally.iter().count(), 3);
+    }
+
+    /// A node at `height` whose last superblock was mined at height 96.
+    struct SuperblockNode {
+        height: u32,
+    }
+
+    impl MockRpc for SuperblockNode {
+        fn respond(&self, cmd: &str, _args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "getgovernanceinfo" => r#"{"governanceminquorum": 1, "proposalfee": 1,
+                    "superblockcycle": 24, "superblockmaturitywindow": 8,
+                    "lastsuperblock": 96, "nextsuperblock": 120}"#
+                    .to_owned(),
+                "getblockcount" => self.height.to_string(),
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    #[test]
+    fn test_next_superblocks() {
+        let node = SuperblockNode {
+            height: 100,
+        };
+        let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let superblocks = node.next_superblocks(3).unwrap();
+        let heights: Vec<u32> = superblocks.iter().map(|s| s.height).collect();
+        assert_eq!(heights, [120, 144, 168]);
+        assert_eq!(superblocks[0].voting_deadline, Some(112));
+        let wait = superblocks[1].estimated_time - before;
+        assert!(wait >= 44 * TARGET_BLOCK_SPACING && wait <= 44 * TARGET_BLOCK_SPACING + 1);
+
+        // The next superblock was mined after `getgovernanceinfo`.
+        let node = SuperblockNode {
+            height: 120,
+        };
+        assert_eq!(node.next_superblocks(1).unwrap()[0].height, 144);
+        assert!(node.next_superblocks(0).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Health checks of nodes.
+
+use std::cmp;
+use std::time::{Duration, Instant};
+
+use client::{Result, RpcApi};
+
+/// The round-trip times of calls to a node, measured by
+/// [RpcApi::measure_latency].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LatencyStats {
+    /// The samples, sorted from the fastest to the slowest.
+    samples: Vec<Duration>,
+}
+
+impl LatencyStats {
+    /// The statistics of the given round-trip times, of which there must be
+    /// at least one.
+    pub fn new(mut samples: Vec<Duration>) -> LatencyStats {
+        assert!(!samples.is_empty(), "no latency samples");
+        samples.sort();
+        LatencyStats {
+            samples,
+        }
+    }
+
+    /// The samples, sorted from the fastest to the slowest.
+    pub fn samples(&self) -> &[Duration] {
+        &self.samples
+    }
+
+    /// The round-trip time that `percent` percent of the calls were at most
+    /// as slow as, by the nearest-rank method.
+    pub fn percentile(&self, percent: f64) -> Duration {
+        let percent = percent.max(0.0).min(100.0);
+        let rank = (percent / 100.0 * self.samples.len() as f64).ceil() as usize;
+        self.samples[cmp::max(rank, 1) - 1]
+    }
+
+    pub fn min(&self) -> Duration {
+        self.samples[0]
+    }
+
+    pub fn median(&self) -> Duration {
+        self.percentile(50.0)
+    }
+
+    pub fn max(&self) -> Duration {
+        self.samples[self.samples.len() - 1]
+    }
+}
+
+/// See [RpcApi::measure_latency].
+pub(crate) fn measure_latency<C: RpcApi>(rpc: &C, samples: usize) -> Result<LatencyStats> {
+    let samples = cmp::max(samples, 1);
+    let mut durations = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let start = Instant::now();
+        rpc.ping()?;
+        durations.push(start.elapsed());
+    }
+    Ok(LatencyStats::new(durations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error::Error;
+    use serde_json;
+    use std::cell::Cell;
+    use testutil::MockRpc;
+
+    fn millis(ms: &[u64]) -> Vec<Duration> {
+        ms.iter().map(|&ms| Duration::from_millis(ms)).collect()
+    }
+
+    #[test]
+    fn test_latency_stats() {
+        let stats = LatencyStats::new(millis(&[5, 1, 4, 2, 3, 10, 6, 8, 7, 9]));
+        assert_eq!(stats.samples(), &millis(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10])[..]);
+        assert_eq!(stats.min(), Duration::from_millis(1));
+        assert_eq!(stats.median(), Duration::from_millis(5));
+        assert_eq!(stats.percentile(90.0), Duration::from_millis(9));
+        assert_eq!(stats.percentile(99.0), Duration::from_millis(10));
+        assert_eq!(stats.percentile(0.0), Duration::from_millis(1));
+        assert_eq!(stats.max(), Duration::from_millis(10));
+
+        let single = LatencyStats::new(millis(&[3]));
+        assert_eq!(single.percentile(0.0), single.percentile(100.0));
+    }
+
+    /// A node that fails the call after `pings` pings.
+    struct PingNode {
+        pings: Cell<usize>,
+    }
+
+    impl MockRpc for PingNode {
+        fn respond(&self, cmd: &str, _: &[serde_json::Value]) -> Result<String> {
+            assert_eq!(cmd, "ping");
+            if self.pings.get() == 0 {
+                return Err(Error::UnexpectedStructure);
+            }
+            self.pings.set(self.pings.get() - 1);
+            Ok("null".to_owned())
+        }
+    }
+
+    #[test]
+    fn test_measure_latency() {
+        let node = PingNode {
+            pings: Cell::new(5),
+        };
+        assert_eq!(node.measure_latency(3).unwrap().samples().len(), 3);
+        assert_eq!(node.measure_latency(0).unwrap().samples().len(), 1);
+        assert!(node.measure_latency(2).is_err());
+    }
+}
@@ -23,16 +23,105 @@ extern crate log;
 extern crate serde;
 extern crate serde_json;
 
+#[cfg(feature = "gzip")]
+extern crate flate2;
 pub extern crate jsonrpc;
+#[cfg(feature = "metrics")]
+pub extern crate prometheus;
 
 pub extern crate dashcore_rpc_json;
 pub use dashcore_rpc_json as json;
 pub use json::dashcore;
 
+mod addressutxos;
+mod bloom;
+mod cache;
+mod capabilities;
+mod chain;
 mod client;
+mod coinselect;
+mod collateral;
+mod conflicts;
+mod descriptors;
 mod error;
+mod export;
+mod failover;
+mod forkmonitor;
+mod governance;
+mod health;
+mod masternodes;
+mod mempool;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod mining;
+mod operations;
+mod ownership;
+mod paranoid;
+mod payment;
+mod prevouts;
+mod push;
 mod queryable;
+mod quorum;
+mod ratelimit;
+mod raw;
+mod rebroadcast;
+#[cfg(feature = "rest")]
+mod rest;
+mod signing;
+#[cfg(feature = "testkit")]
+mod testkit;
+#[cfg(test)]
+mod testutil;
+mod transport;
+mod txout;
+mod utxosnapshot;
+mod walletdump;
+mod walletsync;
+mod warmup;
+mod watcher;
+mod workflow;
 
+pub use addressutxos::*;
+pub use bloom::*;
+pub use cache::*;
+pub use capabilities::*;
+pub use chain::*;
 pub use client::*;
+pub use coinselect::*;
+pub use collateral::*;
+pub use conflicts::*;
+pub use descriptors::*;
 pub use error::Error;
+pub use export::*;
+pub use failover::*;
+pub use forkmonitor::*;
+pub use governance::*;
+pub use health::*;
+pub use masternodes::*;
+pub use mempool::*;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
+pub use mining::*;
+pub use operations::*;
+pub use ownership::*;
+pub use paranoid::*;
+pub use payment::*;
+pub use prevouts::*;
+pub use push::*;
 pub use queryable::*;
+pub use quorum::*;
+pub use raw::*;
+pub use rebroadcast::*;
+#[cfg(feature = "rest")]
+pub use rest::*;
+pub use signing::*;
+#[cfg(feature = "testkit")]
+pub use testkit::*;
+pub use transport::*;
+pub use txout::*;
+pub use utxosnapshot::*;
+pub use walletdump::*;
+pub use walletsync::*;
+pub use warmup::*;
+pub use watcher::*;
+pub use workflow::*;
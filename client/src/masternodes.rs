@@ -0,0 +1,576 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Maintaining the simplified masternode list.
+
+use std::cmp;
+use std::collections::hash_map::{HashMap, Values};
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::{Bound, RangeBounds};
+
+use dashcore::hashes::{sha256d, Hash};
+use dashcore::util::address::Payload;
+use dashcore::BlockHash;
+
+use client::{Result, RpcApi};
+use error::Error;
+use json::{CbTx, MasternodeListDiff, ProTxHash, QuorumMasternodeListItem};
+use mining::merkle_root;
+
+/// The version of the entries of the simplified masternode list with a type,
+/// and the basic BLS scheme for the operator key.
+const BASIC_BLS_VERSION: u16 = 2;
+/// The type of Evo masternodes, the ones that run Platform.
+const EVO_TYPE: u16 = 1;
+
+/// The default number of diffs a [MasternodeListDiffs] fetches per batch.
+pub const DEFAULT_PROTX_DIFF_BATCH_SIZE: usize = 16;
+
+/// The simplified masternode list (SML) at a block, see
+/// [RpcApi::masternode_list].
+///
+/// The list is built from a `protx diff` against the genesis block and kept
+/// up to date by applying the diffs to later blocks.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MasternodeList {
+    block_hash: BlockHash,
+    height: u32,
+    entries: HashMap<ProTxHash, QuorumMasternodeListItem>,
+}
+
+impl MasternodeList {
+    /// An empty list at the genesis block.
+    pub fn new(genesis_hash: BlockHash) -> MasternodeList {
+        MasternodeList {
+            block_hash: genesis_hash,
+            height: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Fetch the list at the chain tip.
+    pub fn bootstrap<C: RpcApi>(rpc: &C) -> Result<MasternodeList> {
+        let mut list = MasternodeList::new(rpc.get_block_hash(0)?);
+        list.sync(rpc)?;
+        Ok(list)
+    }
+
+    /// Bring the list up to the chain tip.
+    ///
+    /// When the block of the list was reorganized out of the main chain, or
+    /// the tip is below the list, the list is fetched again from the genesis
+    /// block, the only block it is known to share with the chain of the node.
+    /// Returns whether the list changed.
+    pub fn sync<C: RpcApi>(&mut self, rpc: &C) -> Result<bool> {
+        let tip = rpc.get_block_count()? as u32;
+        // A diff can't go back from the block of the list.
+        if tip < self.height {
+            self.rebase(rpc, tip)?;
+            return Ok(true);
+        }
+        if tip == self.height && rpc.get_block_hash(tip as u64)? == self.block_hash {
+            return Ok(false);
+        }
+        let diff = rpc.get_protx_diff(self.height, tip)?;
+        match self.apply_diff(&diff, tip) {
+            Ok(()) => {}
+            Err(Error::MasternodeListMismatch {
+                ..
+            }) => self.rebase(rpc, tip)?,
+            Err(e) => return Err(e),
+        }
+        Ok(true)
+    }
+
+    /// Fetch the list at the height `tip` again from the genesis block.
+    fn rebase<C: RpcApi>(&mut self, rpc: &C, tip: u32) -> Result<()> {
+        let mut list = MasternodeList::new(rpc.get_block_hash(0)?);
+        list.apply_diff(&rpc.get_protx_diff(0, tip)?, tip)?;
+        *self = list;
+        Ok(())
+    }
+
+    /// Apply a diff from the block of the list to the block at `height`.
+    ///
+    /// Fails with [Error::MasternodeListMismatch] if the diff starts at
+    /// another block.
+    pub fn apply_diff(&mut self, diff: &MasternodeListDiff, height: u32) -> Result<()> {
+        if diff.base_block_hash != self.block_hash {
+            return Err(Error::MasternodeListMismatch {
+                expected: self.block_hash,
+                found: diff.base_block_hash,
+            });
+        }
+        for pro_tx_hash in &diff.deleted_mns {
+            self.entries.remove(pro_tx_hash);
+        }
+        for entry in &diff.mn_list {
+            self.entries.insert(entry.pro_reg_tx_hash, entry.clone());
+        }
+        self.block_hash = diff.block_hash;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Like [MasternodeList::apply_diff], but also check the resulting list
+    /// against the coinbase of the diff, see
+    /// [MasternodeList::verify_merkle_root]. The list is left unchanged if
+    /// the check fails.
+    pub fn apply_verified_diff(&mut self, diff: &MasternodeListDiff, height: u32) -> Result<()> {
+        let cb_tx = diff.cb_tx_payload()?;
+        let mut list = self.clone();
+        list.apply_diff(diff, height)?;
+        list.verify_merkle_root(&cb_tx)?;
+        *self = list;
+        Ok(())
+    }
+
+    /// The block the list is at.
+    pub fn block_hash(&self) -> BlockHash {
+        self.block_hash
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All the masternodes, including the invalid ones, in no particular
+    /// order.
+    pub fn iter(&self) -> Values<ProTxHash, QuorumMasternodeListItem> {
+        self.entries.values()
+    }
+
+    /// The masternodes that are currently valid.
+    pub fn valid(&self) -> impl Iterator<Item = &QuorumMasternodeListItem> {
+        self.entries.values().filter(|e| e.is_valid)
+    }
+
+    pub fn get(&self, pro_tx_hash: &ProTxHash) -> Option<&QuorumMasternodeListItem> {
+        self.entries.get(pro_tx_hash)
+    }
+
+    /// The masternode with the given service address.
+    pub fn by_service(&self, service: &SocketAddr) -> Option<&QuorumMasternodeListItem> {
+        self.entries.values().find(|e| e.service == *service)
+    }
+
+    /// The masternode with the given BLS operator public key.
+    pub fn by_operator_key(&self, key: &[u8]) -> Option<&QuorumMasternodeListItem> {
+        self.entries.values().find(|e| e.pub_key_operator == key)
+    }
+
+    /// The merkle root of the list, computed like the `merkleRootMNList` of
+    /// the coinbase of a block: over the hashes of the entries, ordered by
+    /// the hash of their ProRegTx.
+    pub fn merkle_root(&self) -> Result<sha256d::Hash> {
+        let mut entries: Vec<_> = self.entries.values().collect();
+        entries.sort_by_key(|e| e.pro_reg_tx_hash);
+        let hashes = entries.into_iter().map(entry_hash).collect::<Result<Vec<_>>>()?;
+        Ok(merkle_root(hashes))
+    }
+
+    /// Check the list against the `merkleRootMNList` the coinbase of its
+    /// block commits to, `cb_tx`, which makes sure no node tampered with the
+    /// diffs the list was built from.
+    ///
+    /// Fails with [Error::MasternodeListMerkleRootMismatch] if the list
+    /// doesn't match.
+    pub fn verify_merkle_root(&self, cb_tx: &CbTx) -> Result<()> {
+        let computed = self.merkle_root()?;
+        if computed != cb_tx.merkle_root_mn_list {
+            return Err(Error::MasternodeListMerkleRootMismatch {
+                block_hash: self.block_hash,
+                expected: cb_tx.merkle_root_mn_list,
+                computed,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The hash of an entry of the list, a leaf of its merkle tree.
+fn entry_hash(entry: &QuorumMasternodeListItem) -> Result<sha256d::Hash> {
+    let mut engine = sha256d::Hash::engine();
+    engine.input(&entry.pro_reg_tx_hash.0[..]);
+    engine.input(&entry.confirmed_hash[..]);
+    let ip = match entry.service.ip() {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    };
+    engine.input(&ip.octets());
+    engine.input(&[(entry.service.port() >> 8) as u8, entry.service.port() as u8]);
+    engine.input(&entry.pub_key_operator);
+    match entry.voting_address.payload {
+        Payload::PubkeyHash(ref hash) => engine.input(&hash[..]),
+        _ => return Err(Error::UnexpectedStructure),
+    }
+    engine.input(&[entry.is_valid as u8]);
+    if entry.version == Some(BASIC_BLS_VERSION) {
+        let mn_type = entry.mn_type.unwrap_or(0);
+        engine.input(&[mn_type as u8, (mn_type >> 8) as u8]);
+        if mn_type == EVO_TYPE {
+            let port = entry.platform_http_port.ok_or(Error::UnexpectedStructure)?;
+            engine.input(&[port as u8, (port >> 8) as u8]);
+            let node_id = entry.platform_node_id.as_ref().ok_or(Error::UnexpectedStructure)?;
+            // The Platform node id is shown in reverse byte order, like a
+            // block hash.
+            engine.input(&reversed(node_id));
+        }
+    }
+    Ok(sha256d::Hash::from_engine(engine))
+}
+
+fn reversed(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().rev().cloned().collect()
+}
+
+/// An iterator over the masternode list diffs of the blocks of the main
+/// chain, see [RpcApi::iter_protx_diffs].
+///
+/// Each item is the diff of a block against its parent, with the height of
+/// the block, ready for [MasternodeList::apply_diff]. The genesis block has
+/// no diff, so the iteration starts at height 1 at the earliest.
+///
+/// The diffs are fetched lazily in batches of `protx diff` calls. Like
+/// `BlockIter`, every diff is checked to start at the block of the previous
+/// one, so a chain reorganization during the iteration results in an
+/// [Error::Reorg] for the first height that no longer connects, after which
+/// the iterator stops. Without an end height, the iterator returns
+/// `None` once it caught up with the chain tip and can be polled again later.
+pub struct MasternodeListDiffs<'a, C: 'a> {
+    rpc: &'a C,
+    next_height: u32,
+    /// The last height to return, inclusive.
+    end_height: Option<u32>,
+    batch_size: usize,
+    buffer: VecDeque<(u32, MasternodeListDiff)>,
+    last_hash: Option<BlockHash>,
+    done: bool,
+}
+
+impl<'a, C: RpcApi> MasternodeListDiffs<'a, C> {
+    pub fn new<R: RangeBounds<u32>>(rpc: &'a C, heights: R) -> MasternodeListDiffs<'a, C> {
+        let next_height = match heights.start_bound() {
+            Bound::Included(&h) => cmp::max(h, 1),
+            Bound::Excluded(&h) => h + 1,
+            Bound::Unbounded => 1,
+        };
+        let (end_height, empty) = match heights.end_bound() {
+            Bound::Included(&h) => (Some(h), h < next_height),
+            Bound::Excluded(&h) => (h.checked_sub(1), h <= next_height),
+            Bound::Unbounded => (None, false),
+        };
+        MasternodeListDiffs {
+            rpc,
+            next_height,
+            end_height,
+            batch_size: DEFAULT_PROTX_DIFF_BATCH_SIZE,
+            buffer: VecDeque::new(),
+            last_hash: None,
+            done: empty,
+        }
+    }
+
+    /// Set the number of diffs fetched per batch.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = cmp::max(batch_size, 1);
+        self
+    }
+
+    /// Check that the first diff starts at the given block, like the block
+    /// of a [MasternodeList] the diffs are applied to.
+    pub fn after(mut self, prev_hash: BlockHash) -> Self {
+        self.last_hash = Some(prev_hash);
+        self
+    }
+
+    /// The block of the last diff returned by the iterator.
+    pub fn last_hash(&self) -> Option<BlockHash> {
+        self.last_hash
+    }
+
+    /// Fetch the next batch of diffs, up to the chain tip.
+    fn fill(&mut self) -> Result<()> {
+        let tip = self.rpc.get_block_count()? as u32;
+        let batch_size = cmp::min(self.batch_size, u32::max_value() as usize) as u32;
+        let mut last = cmp::min(tip, self.next_height.saturating_add(batch_size - 1));
+        if let Some(end) = self.end_height {
+            last = cmp::min(last, end);
+        }
+        if self.next_height > last {
+            return Ok(());
+        }
+
+        let calls: Vec<_> = (self.next_height..=last)
+            .map(|h| ("protx", vec!["diff".into(), (h - 1).into(), h.into()]))
+            .collect();
+        let diffs = self.rpc.call_batch::<MasternodeListDiff>(&calls)?;
+        for (height, diff) in (self.next_height..).zip(diffs) {
+            self.buffer.push_back((height, diff?));
+        }
+        self.next_height = last + 1;
+        Ok(())
+    }
+}
+
+impl<'a, C: RpcApi> Iterator for MasternodeListDiffs<'a, C> {
+    type Item = Result<(u32, MasternodeListDiff)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if self.buffer.is_empty() {
+            if let Err(e) = self.fill() {
+                self.done = true;
+                return Some(Err(e));
+            }
+            if self.buffer.is_empty() {
+                self.done = self.end_height.map_or(false, |end| self.next_height > end);
+                return None;
+            }
+        }
+
+        let (height, diff) = self.buffer.pop_front().unwrap();
+        if let Some(prev) = self.last_hash {
+            if diff.base_block_hash != prev {
+                self.done = true;
+                self.buffer.clear();
+                return Some(Err(Error::Reorg(height as u64)));
+            }
+        }
+        self.last_hash = Some(diff.block_hash);
+        Some(Ok((height, diff)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::{sha256d, Hash};
+    use dashcore::util::address::Payload;
+    use dashcore::{Address, Network};
+    use serde_json;
+    use testutil::MockRpc;
+
+    fn entry(n: u8, is_valid: bool) -> QuorumMasternodeListItem {
+        QuorumMasternodeListItem {
+            version: Some(1),
+            mn_type: Some(0),
+            pro_reg_tx_hash: ProTxHash(sha256d::Hash::hash(&[n])),
+            confirmed_hash: Default::default(),
+            service: format!("1.2.3.{}:9999", n).parse().unwrap(),
+            pub_key_operator: vec![n; 48],
+            voting_address: Address {
+                network: Network::Dash,
+                payload: Payload::PubkeyHash(Default::default()),
+            },
+            is_valid,
+            platform_http_port: None,
+            platform_node_id: None,
+        }
+    }
+
+    fn diff(
+        base: u8,
+        block: u8,
+        deleted: &[u8],
+        mns: Vec<QuorumMasternodeListItem>,
+    ) -> MasternodeListDiff {
+        MasternodeListDiff {
+            base_block_hash: BlockHash::hash(&[base]),
+            block_hash: BlockHash::hash(&[block]),
+            cb_tx_merkle_tree_hex: vec![],
+            cb_tx_hex: vec![],
+            deleted_mns: deleted.iter().map(|n| ProTxHash(sha256d::Hash::hash(&[*n]))).collect(),
+            mn_list: mns,
+            deleted_quorums: vec![],
+            new_quorums: vec![],
+            merkle_root_mn_list: Default::default(),
+            merkle_root_quorums: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_apply_diff() {
+        let mut list = MasternodeList::new(BlockHash::hash(&[0]));
+        list.apply_diff(&diff(0, 1, &[], vec![entry(1, true), entry(2, true)]), 10).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.block_hash(), BlockHash::hash(&[1]));
+        assert_eq!(list.height(), 10);
+
+        list.apply_diff(&diff(1, 2, &[1], vec![entry(2, false), entry(3, true)]), 11).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list.get(&entry(1, true).pro_reg_tx_hash).is_none());
+        assert!(!list.get(&entry(2, true).pro_reg_tx_hash).unwrap().is_valid);
+        assert_eq!(list.valid().count(), 1);
+        assert_eq!(list.by_service(&"1.2.3.3:9999".parse().unwrap()), Some(&entry(3, true)));
+        assert_eq!(list.by_operator_key(&[2; 48]), Some(&entry(2, false)));
+        assert_eq!(list.by_operator_key(&[1; 48]), None);
+
+        match list.apply_diff(&diff(1, 3, &[], vec![]), 12) {
+            Err(Error::MasternodeListMismatch {
+                expected,
+                found,
+            }) => {
+                assert_eq!(expected, BlockHash::hash(&[2]));
+                assert_eq!(found, BlockHash::hash(&[1]));
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(list.height(), 11);
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let mut list = MasternodeList::new(BlockHash::hash(&[0]));
+        assert_eq!(list.merkle_root().unwrap(), Default::default());
+
+        list.apply_diff(&diff(0, 1, &[], vec![entry(1, true), entry(2, true)]), 10).unwrap();
+        let root = list.merkle_root().unwrap();
+        assert_ne!(root, Default::default());
+        assert_ne!(entry_hash(&entry(1, true)).unwrap(), entry_hash(&entry(1, false)).unwrap());
+
+        // The leaves are ordered by ProRegTx hash, whatever the order of the diffs.
+        let mut other = MasternodeList::new(BlockHash::hash(&[0]));
+        other.apply_diff(&diff(0, 1, &[], vec![entry(2, true), entry(1, true)]), 10).unwrap();
+        assert_eq!(other.merkle_root().unwrap(), root);
+
+        let mut cb_tx = CbTx {
+            version: 2,
+            height: 10,
+            merkle_root_mn_list: root,
+            merkle_root_quorums: Some(Default::default()),
+            best_cl_height_diff: None,
+            best_cl_signature: None,
+            credit_pool_balance: None,
+        };
+        list.verify_merkle_root(&cb_tx).unwrap();
+
+        list.apply_diff(&diff(1, 2, &[], vec![entry(2, false)]), 11).unwrap();
+        match list.verify_merkle_root(&cb_tx) {
+            Err(Error::MasternodeListMerkleRootMismatch {
+                block_hash,
+                expected,
+                computed,
+            }) => {
+                assert_eq!(block_hash, BlockHash::hash(&[2]));
+                assert_eq!(expected, root);
+                assert_eq!(computed, list.merkle_root().unwrap());
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+        cb_tx.merkle_root_mn_list = list.merkle_root().unwrap();
+        list.verify_merkle_root(&cb_tx).unwrap();
+    }
+
+    /// A chain up to `tip` whose block at `forked` is replaced after the first
+    /// `getblockcount`, as seen by the diff of the next block.
+    struct DiffChain {
+        tip: u32,
+        forked: Option<u32>,
+        calls: ::std::cell::Cell<usize>,
+    }
+
+    impl MockRpc for DiffChain {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let value = match cmd {
+                "getblockcount" => {
+                    self.calls.set(self.calls.get() + 1);
+                    serde_json::to_value(self.tip)?
+                }
+                "getblockhash" => {
+                    serde_json::to_value(BlockHash::hash(&[args[0].as_u64().unwrap() as u8]))?
+                }
+                "protx" => {
+                    let base = args[1].as_u64().unwrap() as u8;
+                    let block = args[2].as_u64().unwrap() as u8;
+                    let mut diff = diff(base, block, &[], vec![entry(block, true)]);
+                    if self.calls.get() > 1 && self.forked == Some(base as u32) {
+                        diff.base_block_hash = BlockHash::hash(&[base, 1]);
+                    }
+                    serde_json::to_value(diff)?
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_iter_protx_diffs() {
+        let chain = DiffChain {
+            tip: 5,
+            forked: None,
+            calls: Default::default(),
+        };
+        let mut list = MasternodeList::new(BlockHash::hash(&[0]));
+        for item in chain.iter_protx_diffs(..).batch_size(2).after(list.block_hash()) {
+            let (height, diff) = item.unwrap();
+            list.apply_diff(&diff, height).unwrap();
+        }
+        assert_eq!(list.height(), 5);
+        assert_eq!(list.len(), 5);
+        assert_eq!(chain.calls.get(), 4);
+
+        let heights: Vec<_> = chain.iter_protx_diffs(0..3).map(|r| r.unwrap().0).collect();
+        assert_eq!(heights, vec![1, 2]);
+        assert_eq!(chain.iter_protx_diffs(4..=4).count(), 1);
+        assert_eq!(chain.iter_protx_diffs(3..3).count(), 0);
+        // A huge batch stops at the tip.
+        assert_eq!(chain.iter_protx_diffs(1..).batch_size(usize::max_value()).count(), 5);
+
+        let chain = DiffChain {
+            tip: 5,
+            forked: Some(2),
+            calls: Default::default(),
+        };
+        let mut diffs = chain.iter_protx_diffs(1..).batch_size(2);
+        assert_eq!(diffs.next().unwrap().unwrap().0, 1);
+        assert_eq!(diffs.next().unwrap().unwrap().0, 2);
+        match diffs.next() {
+            Some(Err(Error::Reorg(3))) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(diffs.next().is_none());
+        assert_eq!(diffs.last_hash(), Some(BlockHash::hash(&[2])));
+    }
+
+    #[test]
+    fn test_sync() {
+        let chain = DiffChain {
+            tip: 5,
+            forked: None,
+            calls: Default::default(),
+        };
+        let mut list = MasternodeList::bootstrap(&chain).unwrap();
+        assert_eq!((list.height(), list.block_hash()), (5, BlockHash::hash(&[5])));
+        assert!(!list.sync(&chain).unwrap());
+
+        // The chain of the node is shorter than the list.
+        let chain = DiffChain {
+            tip: 3,
+            ..chain
+        };
+        assert!(list.sync(&chain).unwrap());
+        assert_eq!((list.height(), list.block_hash()), (3, BlockHash::hash(&[3])));
+        assert_eq!(list.len(), 1);
+    }
+}
@@ -0,0 +1,250 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Mempool helpers.
+
+use std::cmp;
+use std::collections::{BTreeMap, VecDeque};
+
+use jsonrpc;
+use serde_json;
+
+use dashcore::{Amount, Txid};
+
+use client::{Result, RpcApi};
+use error::{Error, RPC_INVALID_ADDRESS_OR_KEY};
+use json;
+
+/// The default number of entries a [MempoolEntries] fetches per batch.
+pub const DEFAULT_MEMPOOL_BATCH_SIZE: usize = 100;
+
+/// An iterator over the entries of the mempool, see
+/// [RpcApi::iter_mempool_entries].
+///
+/// The txids are fetched up front and the entries are fetched lazily in
+/// batches of `getmempoolentry` calls. Transactions that left the mempool in
+/// the meantime are skipped.
+pub struct MempoolEntries<'a, C: 'a> {
+    rpc: &'a C,
+    txids: VecDeque<Txid>,
+    batch_size: usize,
+    buffer: VecDeque<(Txid, json::GetMempoolEntryResult)>,
+}
+
+impl<'a, C: RpcApi> MempoolEntries<'a, C> {
+    pub fn new(rpc: &'a C) -> Result<MempoolEntries<'a, C>> {
+        Ok(MempoolEntries {
+            rpc,
+            txids: rpc.get_raw_mempool()?.into(),
+            batch_size: DEFAULT_MEMPOOL_BATCH_SIZE,
+            buffer: VecDeque::new(),
+        })
+    }
+
+    /// Set the number of entries fetched per batch.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = cmp::max(batch_size, 1);
+        self
+    }
+
+    /// The number of transactions whose entries were not fetched yet.
+    pub fn remaining(&self) -> usize {
+        self.txids.len()
+    }
+
+    fn fill(&mut self) -> Result<()> {
+        let n = cmp::min(self.batch_size, self.txids.len());
+        let txids: Vec<Txid> = self.txids.drain(..n).collect();
+        let mut calls = Vec::with_capacity(txids.len());
+        for txid in &txids {
+            calls.push(("getmempoolentry", vec![serde_json::to_value(txid)?]));
+        }
+        for (txid, entry) in txids.into_iter().zip(self.rpc.call_batch(&calls)?) {
+            match entry {
+                Ok(entry) => self.buffer.push_back((txid, entry)),
+                Err(Error::JsonRpc(jsonrpc::Error::Rpc(ref e)))
+                    if e.code == RPC_INVALID_ADDRESS_OR_KEY => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, C: RpcApi> Iterator for MempoolEntries<'a, C> {
+    type Item = Result<(Txid, json::GetMempoolEntryResult)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.buffer.is_empty() && !self.txids.is_empty() {
+            if let Err(e) = self.fill() {
+                return Some(Err(e));
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.buffer.len(), Some(self.buffer.len() + self.txids.len()))
+    }
+}
+
+/// A fee rate bucket of a mempool fee histogram, see
+/// [RpcApi::mempool_fee_histogram].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct FeeHistogramBucket {
+    /// The lowest fee rate in the bucket in duffs per kB, inclusive.
+    pub min_fee_rate: u64,
+    /// The highest fee rate in the bucket in duffs per kB, exclusive.
+    pub max_fee_rate: u64,
+    /// The number of transactions in the bucket.
+    pub count: u64,
+    /// The total size of the transactions in the bucket.
+    pub total_vsize: u64,
+    /// The total fees paid by the transactions in the bucket.
+    pub total_fees: Amount,
+}
+
+/// Accumulates mempool entries into fee rate buckets.
+#[derive(Clone, Debug)]
+pub struct FeeHistogram {
+    bucket_size: u64,
+    buckets: BTreeMap<u64, FeeHistogramBucket>,
+}
+
+impl FeeHistogram {
+    /// Create an empty histogram with buckets `bucket_size` duffs per kB wide.
+    pub fn new(bucket_size: u64) -> FeeHistogram {
+        FeeHistogram {
+            bucket_size: cmp::max(bucket_size, 1),
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Add a mempool entry, using the base fee it pays.
+    pub fn add(&mut self, entry: &json::GetMempoolEntryResult) {
+        let fee = entry.fees.base;
+        let fee_rate = fee.as_sat() * 1000 / cmp::max(entry.vsize, 1);
+        let idx = fee_rate / self.bucket_size;
+        let bucket_size = self.bucket_size;
+        let bucket = self.buckets.entry(idx).or_insert_with(|| FeeHistogramBucket {
+            min_fee_rate: idx * bucket_size,
+            max_fee_rate: (idx + 1) * bucket_size,
+            count: 0,
+            total_vsize: 0,
+            total_fees: Amount::ZERO,
+        });
+        bucket.count += 1;
+        bucket.total_vsize += entry.vsize;
+        bucket.total_fees += fee;
+    }
+
+    /// The non-empty buckets, ordered by fee rate.
+    pub fn buckets(&self) -> Vec<FeeHistogramBucket> {
+        self.buckets.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use testutil::MockRpc;
+
+    /// A mempool where every other transaction disappears before its entry
+    /// is fetched.
+    struct MockMempool(Vec<Txid>);
+
+    impl MockRpc for MockMempool {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let value = match cmd {
+                "getrawmempool" => serde_json::to_value(&self.0)?,
+                "getmempoolentry" => {
+                    let txid: Txid = serde_json::from_value(args[0].clone())?;
+                    let idx = self.0.iter().position(|t| *t == txid).unwrap();
+                    if idx % 2 == 1 {
+                        let e = jsonrpc::error::RpcError {
+                            code: RPC_INVALID_ADDRESS_OR_KEY,
+                            message: "Transaction not in mempool".into(),
+                            data: None,
+                        };
+                        return Err(Error::JsonRpc(jsonrpc::Error::Rpc(e)));
+                    }
+                    serde_json::to_value(entry(200 + idx as u64, Amount::from_sat(1000)))?
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    fn entry(vsize: u64, fee: Amount) -> json::GetMempoolEntryResult {
+        json::GetMempoolEntryResult {
+            vsize,
+            weight: None,
+            time: 0,
+            height: 100,
+            descendant_count: 1,
+            descendant_size: vsize,
+            ancestor_count: 1,
+            ancestor_size: vsize,
+            wtxid: None,
+            fees: json::GetMempoolEntryResultFees {
+                base: fee,
+                modified: fee,
+                ancestor: fee,
+                descendant: fee,
+            },
+            depends: vec![],
+            spent_by: vec![],
+            bip125_replaceable: false,
+            unbroadcast: None,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_mempool_entries() {
+        let txids: Vec<Txid> = (0..7u8).map(|i| Txid::hash(&[i])).collect();
+        let rpc = MockMempool(txids.clone());
+        let mut entries = rpc.iter_mempool_entries().unwrap().batch_size(3);
+        assert_eq!(entries.remaining(), 7);
+        let first = entries.next().unwrap().unwrap();
+        assert_eq!(first.0, txids[0]);
+        assert_eq!(entries.remaining(), 4);
+
+        let rest: Vec<_> = entries.map(|r| r.unwrap()).collect();
+        assert_eq!(rest.iter().map(|e| e.0).collect::<Vec<_>>(), [txids[2], txids[4], txids[6]]);
+        assert_eq!(rest[2].1.vsize, 206);
+    }
+
+    #[test]
+    fn test_fee_histogram() {
+        let mut histogram = FeeHistogram::new(1000);
+        // 1000, 1500, 1000 and 5000 duffs per kB.
+        histogram.add(&entry(200, Amount::from_sat(200)));
+        histogram.add(&entry(200, Amount::from_sat(300)));
+        histogram.add(&entry(1000, Amount::from_sat(1000)));
+        histogram.add(&entry(100, Amount::from_sat(500)));
+
+        let buckets = histogram.buckets();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(
+            buckets[0],
+            FeeHistogramBucket {
+                min_fee_rate: 1000,
+                max_fee_rate: 2000,
+                count: 3,
+                total_vsize: 1400,
+                total_fees: Amount::from_sat(1500),
+            }
+        );
+        assert_eq!((buckets[1].min_fee_rate, buckets[1].count), (5000, 1));
+    }
+}
@@ -0,0 +1,139 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Prometheus metrics of the calls made by a [Client](::Client).
+
+use std::time::Duration;
+
+use jsonrpc;
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::MetricFamily;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts};
+
+/// The `method` label of the latency of batch requests.
+pub const BATCH_METHOD_LABEL: &str = "batch";
+
+/// The `code` label of the errors that are not RPC errors of the node, such
+/// as connection failures or unparsable responses.
+pub const TRANSPORT_ERROR_CODE_LABEL: &str = "transport";
+
+/// Metrics of the calls made by a [Client](::Client), enabled with
+/// [Client::with_metrics](::Client::with_metrics).
+///
+/// Registering the metrics with a [prometheus::Registry] exports:
+/// - `<namespace>_calls_total{method}`: the calls made, also those of batches.
+/// - `<namespace>_errors_total{method, code}`: the calls that failed, by RPC
+///   error code, or [TRANSPORT_ERROR_CODE_LABEL] if the node didn't answer.
+/// - `<namespace>_call_duration_seconds{method}`: the latency of the
+///   requests, batches are observed once with the [BATCH_METHOD_LABEL]
+///   method.
+///
+/// Cloned metrics share their values, so the same metrics can be registered
+/// and given to several clients.
+#[derive(Clone)]
+pub struct RpcMetrics {
+    calls: IntCounterVec,
+    errors: IntCounterVec,
+    latency: HistogramVec,
+}
+
+impl RpcMetrics {
+    /// Create the metrics, with names prefixed by `namespace`, for example
+    /// "dashcore_rpc".
+    pub fn new(namespace: &str) -> prometheus::Result<RpcMetrics> {
+        let calls = Opts::new("calls_total", "Number of RPC calls by method.");
+        let errors = Opts::new("errors_total", "Number of failed RPC calls by method and code.");
+        let latency = HistogramOpts::new("call_duration_seconds", "Latency of RPC requests.");
+        Ok(RpcMetrics {
+            calls: IntCounterVec::new(calls.namespace(namespace), &["method"])?,
+            errors: IntCounterVec::new(errors.namespace(namespace), &["method", "code"])?,
+            latency: HistogramVec::new(latency.namespace(namespace), &["method"])?,
+        })
+    }
+
+    /// Record the latency of a request.
+    pub(crate) fn observe_latency(&self, method: &str, elapsed: Duration) {
+        let seconds = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+        self.latency.with_label_values(&[method]).observe(seconds);
+    }
+
+    /// Record a call and its response, `None` if the node didn't answer it.
+    pub(crate) fn observe_response(&self, method: &str, resp: Option<&jsonrpc::Response>) {
+        self.calls.with_label_values(&[method]).inc();
+        let code = match resp {
+            None => TRANSPORT_ERROR_CODE_LABEL.to_owned(),
+            Some(&jsonrpc::Response {
+                error: Some(ref e),
+                ..
+            }) => e.code.to_string(),
+            Some(_) => return,
+        };
+        self.errors.with_label_values(&[method, &code]).inc();
+    }
+}
+
+impl Collector for RpcMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        let mut descs = self.calls.desc();
+        descs.extend(self.errors.desc());
+        descs.extend(self.latency.desc());
+        descs
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let mut families = self.calls.collect();
+        families.extend(self.errors.collect());
+        families.extend(self.latency.collect());
+        families
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::Registry;
+    use serde_json;
+
+    fn response(error: Option<i32>) -> jsonrpc::Response {
+        jsonrpc::Response {
+            result: None,
+            error: error.map(|code| jsonrpc::error::RpcError {
+                code,
+                message: String::new(),
+                data: None,
+            }),
+            id: serde_json::Value::from(1),
+            jsonrpc: None,
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        let metrics = RpcMetrics::new("dashcore_rpc").unwrap();
+        let registry = Registry::new();
+        registry.register(Box::new(metrics.clone())).unwrap();
+
+        metrics.observe_latency("getblockcount", Duration::from_millis(10));
+        metrics.observe_response("getblockcount", Some(&response(None)));
+        metrics.observe_response("getblock", Some(&response(Some(-5))));
+        metrics.observe_response("getblock", None);
+
+        let families = registry.gather();
+        let family = |name: &str| families.iter().find(|f| f.get_name() == name).unwrap();
+        let calls = family("dashcore_rpc_calls_total").get_metric();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls.iter().map(|m| m.get_counter().get_value()).sum::<f64>(), 3.0);
+        let errors = family("dashcore_rpc_errors_total").get_metric();
+        let codes: Vec<&str> = errors.iter().map(|m| m.get_label()[0].get_value()).collect();
+        assert_eq!(codes, vec!["-5", TRANSPORT_ERROR_CODE_LABEL]);
+        let latency = family("dashcore_rpc_call_duration_seconds").get_metric();
+        assert_eq!(latency[0].get_histogram().get_sample_count(), 1);
+    }
+}
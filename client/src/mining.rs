@@ -0,0 +1,368 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Assembling blocks from block templates, to mine on regtest.
+
+use std::cmp;
+
+use dashcore::blockdata::script::Builder;
+use dashcore::consensus::encode::{self, VarInt};
+use dashcore::hashes::{sha256d, Hash, HashEngine};
+use dashcore::{
+    BlockHash, BlockHeader, Network, OutPoint, Script, Transaction, TxIn, TxMerkleNode, TxOut,
+    Txid, Witness,
+};
+
+use client::{Result, RpcApi};
+use error::Error;
+use json;
+
+/// The version of special transactions, see DIP2.
+const SPECIAL_TRANSACTION_VERSION: i32 = 3;
+
+/// The special transaction type of coinbase transactions, see DIP4.
+const TRANSACTION_COINBASE: i32 = 5;
+
+/// A block assembled and solved by a [BlockAssembler].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AssembledBlock {
+    pub header: BlockHeader,
+    /// The serialized block, to submit with [RpcApi::submit_block_bytes].
+    pub raw: Vec<u8>,
+}
+
+impl AssembledBlock {
+    pub fn block_hash(&self) -> BlockHash {
+        self.header.block_hash()
+    }
+}
+
+/// Builds a block from the result of `getblocktemplate` and solves its proof
+/// of work, which is only practical with the trivial difficulty of regtest.
+///
+/// The coinbase pays the masternode and superblock payments of the template
+/// and the rest of the reward to the payout script. With a DIP3 template,
+/// the coinbase is a special transaction carrying the payload of the
+/// template. Transactions can be added to the ones of the template, to mine
+/// a block with transactions that are not in the mempool.
+#[derive(Clone, Debug)]
+pub struct BlockAssembler<'a> {
+    template: &'a json::GetBlockTemplateResult,
+    payout_script: Script,
+    template_transactions: bool,
+    transactions: Vec<Vec<u8>>,
+    time: Option<u32>,
+}
+
+impl<'a> BlockAssembler<'a> {
+    pub fn new(template: &'a json::GetBlockTemplateResult, payout_script: Script) -> Self {
+        BlockAssembler {
+            template,
+            payout_script,
+            template_transactions: true,
+            transactions: Vec::new(),
+            time: None,
+        }
+    }
+
+    /// Include the transactions of the template, the default. Without them
+    /// the coinbase doesn't claim their fees.
+    pub fn with_template_transactions(mut self, include: bool) -> Self {
+        self.template_transactions = include;
+        self
+    }
+
+    /// Add a serialized transaction after those of the template. Its fee is
+    /// not claimed by the coinbase. A transaction of the template with the
+    /// same txid is left out, so it is not included twice.
+    pub fn with_transaction(mut self, raw_tx: Vec<u8>) -> Self {
+        self.transactions.push(raw_tx);
+        self
+    }
+
+    /// Set the time of the block, the current time of the template by
+    /// default.
+    pub fn with_time(mut self, time: u32) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// The transactions of the template included in the block.
+    fn included_template_transactions(&self) -> Vec<&'a json::GetBlockTemplateResultTransaction> {
+        if !self.template_transactions {
+            return vec![];
+        }
+        let added: Vec<Txid> =
+            self.transactions.iter().map(|raw| Txid::from_hash(sha256d::Hash::hash(raw))).collect();
+        self.template.transactions.iter().filter(|tx| !added.contains(&tx.txid)).collect()
+    }
+
+    /// The coinbase transaction, without the special transaction payload.
+    pub fn coinbase(&self) -> Transaction {
+        let template = self.template;
+        let script_sig = Builder::new().push_int(template.height as i64).push_int(0).into_script();
+        let mut reward = template.coinbase_value;
+        let included = self.included_template_transactions();
+        for tx in &template.transactions {
+            if !included.iter().any(|i| i.txid == tx.txid) {
+                reward -= tx.fee;
+            }
+        }
+        let mut output = vec![];
+        for payment in template.masternode.iter().chain(&template.superblock) {
+            reward -= payment.amount;
+            output.push(TxOut {
+                value: payment.amount.as_sat(),
+                script_pubkey: payment.script.clone(),
+            });
+        }
+        output.insert(
+            0,
+            TxOut {
+                value: reward.as_sat(),
+                script_pubkey: self.payout_script.clone(),
+            },
+        );
+        let version = if template.coinbase_payload.is_empty() {
+            1
+        } else {
+            SPECIAL_TRANSACTION_VERSION | (TRANSACTION_COINBASE << 16)
+        };
+        Transaction {
+            version,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig,
+                sequence: 0xFFFFFFFF,
+                witness: Witness::default(),
+            }],
+            output,
+        }
+    }
+
+    /// The serialized coinbase transaction, with its special transaction
+    /// payload.
+    fn coinbase_bytes(&self) -> Vec<u8> {
+        let mut raw = encode::serialize(&self.coinbase());
+        if !self.template.coinbase_payload.is_empty() {
+            raw.extend(encode::serialize(&self.template.coinbase_payload));
+        }
+        raw
+    }
+
+    /// Assemble the block and find a nonce that solves its proof of work.
+    ///
+    /// Fails with [Error::NoValidNonce] if no nonce does.
+    pub fn assemble(&self) -> Result<AssembledBlock> {
+        let template = self.template;
+        if template.bits.len() != 4 {
+            return Err(Error::UnexpectedStructure);
+        }
+        let bits = template.bits.iter().fold(0u32, |bits, b| (bits << 8) | u32::from(*b));
+
+        let coinbase = self.coinbase_bytes();
+        let mut txids = vec![sha256d::Hash::hash(&coinbase)];
+        let mut raw_txs = vec![coinbase];
+        for tx in self.included_template_transactions() {
+            txids.push(tx.txid.as_hash());
+            raw_txs.push(tx.raw_tx.clone());
+        }
+        for raw_tx in &self.transactions {
+            txids.push(sha256d::Hash::hash(raw_tx));
+            raw_txs.push(raw_tx.clone());
+        }
+
+        let time = cmp::max(template.current_time, template.min_time) as u32;
+        let mut header = BlockHeader {
+            version: template.version as i32,
+            prev_blockhash: template.previous_block_hash,
+            merkle_root: TxMerkleNode::from_hash(merkle_root(txids)),
+            time: self.time.unwrap_or(time),
+            bits,
+            nonce: 0,
+        };
+        let target = header.target();
+        while header.validate_pow(&target).is_err() {
+            if header.nonce == u32::max_value() {
+                return Err(Error::NoValidNonce);
+            }
+            header.nonce += 1;
+        }
+
+        let mut raw = encode::serialize(&header);
+        raw.extend(encode::serialize(&VarInt(raw_txs.len() as u64)));
+        for raw_tx in raw_txs {
+            raw.extend(raw_tx);
+        }
+        Ok(AssembledBlock {
+            header,
+            raw,
+        })
+    }
+}
+
+/// The merkle root of the given hashes, like the txids of the transactions
+/// of a block, zero for none.
+pub(crate) fn merkle_root(mut hashes: Vec<sha256d::Hash>) -> sha256d::Hash {
+    if hashes.is_empty() {
+        return Default::default();
+    }
+    while hashes.len() > 1 {
+        if hashes.len() % 2 == 1 {
+            let last = hashes[hashes.len() - 1];
+            hashes.push(last);
+        }
+        hashes = hashes
+            .chunks(2)
+            .map(|pair| {
+                let mut engine = sha256d::Hash::engine();
+                engine.input(&pair[0][..]);
+                engine.input(&pair[1][..]);
+                sha256d::Hash::from_engine(engine)
+            })
+            .collect();
+    }
+    hashes[0]
+}
+
+/// See [RpcApi::mine_block_with_transactions].
+pub(crate) fn mine_block_with_transactions<C: RpcApi>(
+    rpc: &C,
+    payout_script: Script,
+    transactions: &[Transaction],
+) -> Result<BlockHash> {
+    let network = rpc.network()?;
+    if network != Network::Regtest {
+        return Err(Error::RegtestOnly(network));
+    }
+    let template = rpc.get_block_template(json::GetBlockTemplateModes::Template, &[], &[])?;
+    let mut assembler = BlockAssembler::new(&template, payout_script);
+    for tx in transactions {
+        assembler = assembler.with_transaction(encode::serialize(tx));
+    }
+    let block = assembler.assemble()?;
+    rpc.submit_block_bytes(&block.raw)?;
+    Ok(block.block_hash())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::hex::ToHex;
+    use dashcore::Txid;
+    use serde_json;
+
+    /// A regtest template after DIP3 with a masternode payment and a
+    /// transaction paying a fee of 1000 duffs.
+    fn template() -> json::GetBlockTemplateResult {
+        let tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![TxOut {
+                value: 5000,
+                script_pubkey: Script::new(),
+            }],
+        };
+        let json = format!(
+            r#"{{"capabilities": ["proposal"], "version": 536870912,
+                 "rules": ["csv", "dip0001", "bip147", "dip0003", "dip0008"],
+                 "vbavailable": {{}}, "vbrequired": 0,
+                 "previousblockhash": "{}",
+                 "transactions": [{{"data": "{}", "hash": "{}", "txid": "{}", "depends": [],
+                                    "fee": 1000, "sigops": 1}}],
+                 "coinbaseaux": {{}}, "coinbasevalue": 50001000,
+                 "longpollid": "00", "target": "{}",
+                 "mintime": 1600000000, "mutable": ["time", "transactions", "prevblock"],
+                 "noncerange": "00000000ffffffff", "sigoplimit": 80000, "sizelimit": 2000000,
+                 "curtime": 1600000100, "bits": "207fffff", "previousbits": "207fffff",
+                 "height": 600,
+                 "masternode": [{{"payee": "", "script": "51", "amount": 30000000}}],
+                 "superblock": [],
+                 "coinbase_payload": "0200580200000000"}}"#,
+            BlockHash::hash(&[1]),
+            encode::serialize(&tx).to_hex(),
+            tx.txid(),
+            tx.txid(),
+            "7fffff".to_owned() + &"00".repeat(29),
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_assemble() {
+        let template = template();
+        assert!(template.rules.contains(&json::GetBlockTemplateResultRules::Dip0003));
+        assert!(template.rules.contains(&json::GetBlockTemplateResultRules::Other));
+        let payout = Builder::new().push_int(1).into_script();
+        let custom: Vec<u8> = encode::serialize(&Transaction {
+            version: 1,
+            lock_time: 7,
+            input: vec![],
+            output: vec![],
+        });
+        let assembler =
+            BlockAssembler::new(&template, payout.clone()).with_transaction(custom.clone());
+
+        let coinbase = assembler.coinbase();
+        assert_eq!(coinbase.version, 0x0005_0003);
+        assert!(coinbase.is_coin_base());
+        assert_eq!(coinbase.output[0].value, 20_001_000);
+        assert_eq!(coinbase.output[0].script_pubkey, payout);
+        assert_eq!(coinbase.output[1].value, 30_000_000);
+        let coinbase_bytes = assembler.coinbase_bytes();
+        assert!(coinbase_bytes.ends_with(&[8, 2, 0, 0x58, 2, 0, 0, 0, 0]));
+
+        let block = assembler.assemble().unwrap();
+        assert_eq!(block.header.prev_blockhash, BlockHash::hash(&[1]));
+        assert_eq!(block.header.time, 1600000100);
+        assert_eq!(block.header.bits, 0x207fffff);
+        assert!(block.header.validate_pow(&block.header.target()).is_ok());
+        assert_eq!(&block.raw[..80], &encode::serialize(&block.header)[..]);
+        assert_eq!(block.raw[80], 3);
+        assert_eq!(&block.raw[81..81 + coinbase_bytes.len()], &coinbase_bytes[..]);
+
+        let coinbase_txid = Txid::hash(&coinbase_bytes);
+        let txids = [coinbase_txid, template.transactions[0].txid];
+        let mut hashes: Vec<sha256d::Hash> = txids.iter().map(|t| t.as_hash()).collect();
+        hashes.push(sha256d::Hash::hash(&custom));
+        assert!(block.raw.ends_with(&custom));
+        assert_eq!(block.header.merkle_root.as_hash(), merkle_root(hashes));
+
+        // Without the template transactions, their fees are left out.
+        let coinbase =
+            BlockAssembler::new(&template, payout).with_template_transactions(false).coinbase();
+        assert_eq!(coinbase.output[0].value, 20_000_000);
+        let total: u64 = coinbase.output.iter().map(|o| o.value).sum();
+        assert_eq!(total, 50_000_000);
+
+        // A template transaction that is also added is only included once.
+        let raw_tx = template.transactions[0].raw_tx.clone();
+        let assembler = BlockAssembler::new(&template, Script::new()).with_transaction(raw_tx);
+        assert_eq!(assembler.coinbase().output[0].value, 20_000_000);
+        let block = assembler.assemble().unwrap();
+        assert_eq!(block.raw[80], 2);
+        assert!(block.raw.ends_with(&template.transactions[0].raw_tx));
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let hashes: Vec<sha256d::Hash> = (0..3u8).map(|i| sha256d::Hash::hash(&[i])).collect();
+        assert_eq!(merkle_root(vec![hashes[0]]), hashes[0]);
+        let pair = |a: sha256d::Hash, b: sha256d::Hash| {
+            let mut data = a[..].to_vec();
+            data.extend(&b[..]);
+            sha256d::Hash::hash(&data)
+        };
+        let root = pair(pair(hashes[0], hashes[1]), pair(hashes[2], hashes[2]));
+        assert_eq!(merkle_root(hashes), root);
+    }
+}
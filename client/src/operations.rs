@@ -0,0 +1,256 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! A registry of long-running operations.
+//!
+//! Helpers that take a long time register themselves in an [Operations]
+//! registry and report their progress through the returned
+//! [OperationHandle]. Applications can then list the running operations,
+//! show their progress and cancel them by id.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use client::Result;
+use error::Error;
+
+/// The id of an operation within its registry.
+pub type OperationId = u64;
+
+/// The state an operation is in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum OperationState {
+    Running,
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+/// A snapshot of an operation's progress.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OperationInfo {
+    pub id: OperationId,
+    /// A short description of the kind of work, like "rescan".
+    pub kind: String,
+    pub state: OperationState,
+    /// The number of work items done so far.
+    pub done: u64,
+    /// The total number of work items, if known.
+    pub total: Option<u64>,
+}
+
+impl OperationInfo {
+    /// The progress as a fraction between 0 and 1, if the total is known.
+    pub fn progress(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.done as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    info: Mutex<OperationInfo>,
+    cancelled: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    next_id: OperationId,
+    entries: BTreeMap<OperationId, Arc<Entry>>,
+}
+
+/// A registry of long-running operations.
+///
+/// Clones share the same registry, so it can be handed to several helpers
+/// and to the code displaying progress.
+#[derive(Clone, Debug, Default)]
+pub struct Operations {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl Operations {
+    pub fn new() -> Operations {
+        Operations::default()
+    }
+
+    /// Register a new running operation of the given kind.
+    pub fn register(&self, kind: &str) -> OperationHandle {
+        let mut registry = self.registry.lock().unwrap();
+        let id = registry.next_id;
+        registry.next_id += 1;
+        let entry = Arc::new(Entry {
+            info: Mutex::new(OperationInfo {
+                id,
+                kind: kind.to_owned(),
+                state: OperationState::Running,
+                done: 0,
+                total: None,
+            }),
+            cancelled: AtomicBool::new(false),
+        });
+        registry.entries.insert(id, entry.clone());
+        OperationHandle {
+            entry,
+        }
+    }
+
+    /// Get the current progress of an operation.
+    pub fn get(&self, id: OperationId) -> Option<OperationInfo> {
+        let registry = self.registry.lock().unwrap();
+        registry.entries.get(&id).map(|e| e.info.lock().unwrap().clone())
+    }
+
+    /// List all operations, ordered by id.
+    pub fn list(&self) -> Vec<OperationInfo> {
+        let registry = self.registry.lock().unwrap();
+        registry.entries.values().map(|e| e.info.lock().unwrap().clone()).collect()
+    }
+
+    /// Request an operation to stop. Returns false if there is no running
+    /// operation with the given id.
+    ///
+    /// The operation stops the next time it checks for cancellation.
+    pub fn cancel(&self, id: OperationId) -> bool {
+        let registry = self.registry.lock().unwrap();
+        match registry.entries.get(&id) {
+            Some(e) if e.info.lock().unwrap().state == OperationState::Running => {
+                e.cancelled.store(true, Ordering::SeqCst);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forget about all operations that are no longer running.
+    pub fn clear_completed(&self) {
+        let mut registry = self.registry.lock().unwrap();
+        registry.entries.retain(|_, e| e.info.lock().unwrap().state == OperationState::Running);
+    }
+}
+
+/// The handle through which a running operation reports its progress.
+///
+/// Dropping the handle without calling [OperationHandle::finish] marks the
+/// operation as failed, or as cancelled if cancellation was requested.
+#[derive(Debug)]
+pub struct OperationHandle {
+    entry: Arc<Entry>,
+}
+
+impl OperationHandle {
+    pub fn id(&self) -> OperationId {
+        self.entry.info.lock().unwrap().id
+    }
+
+    /// Set the total number of work items.
+    pub fn set_total(&self, total: u64) {
+        self.entry.info.lock().unwrap().total = Some(total);
+    }
+
+    /// Set the number of work items done so far.
+    pub fn set_done(&self, done: u64) {
+        self.entry.info.lock().unwrap().done = done;
+    }
+
+    /// Add to the number of work items done so far.
+    pub fn add_done(&self, n: u64) {
+        self.entry.info.lock().unwrap().done += n;
+    }
+
+    /// Whether cancellation of the operation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.entry.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Return [Error::Cancelled] if cancellation of the operation was requested.
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Mark the operation as successfully finished.
+    pub fn finish(self) {
+        self.complete(OperationState::Finished);
+    }
+
+    fn complete(&self, state: OperationState) {
+        let mut info = self.entry.info.lock().unwrap();
+        if info.state == OperationState::Running {
+            info.state = state;
+        }
+    }
+}
+
+/// Return [Error::Cancelled] if cancellation of `operation` was requested,
+/// for the helpers that can run as an operation.
+pub(crate) fn check_cancelled(operation: Option<&OperationHandle>) -> Result<()> {
+    match operation {
+        Some(op) => op.check_cancelled(),
+        None => Ok(()),
+    }
+}
+
+impl Drop for OperationHandle {
+    fn drop(&mut self) {
+        if self.is_cancelled() {
+            self.complete(OperationState::Cancelled);
+        } else {
+            self.complete(OperationState::Failed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations() {
+        let ops = Operations::new();
+        let rescan = ops.register("rescan");
+        let sweep = ops.register("sweep");
+        assert_ne!(rescan.id(), sweep.id());
+
+        rescan.set_total(200);
+        rescan.add_done(50);
+        let info = ops.get(rescan.id()).unwrap();
+        assert_eq!(info.kind, "rescan");
+        assert_eq!(info.state, OperationState::Running);
+        assert_eq!(info.progress(), Some(0.25));
+        assert_eq!(ops.get(sweep.id()).unwrap().progress(), None);
+
+        let sweep_id = sweep.id();
+        assert!(ops.cancel(sweep_id));
+        assert!(sweep.check_cancelled().is_err());
+        drop(sweep);
+        assert_eq!(ops.get(sweep_id).unwrap().state, OperationState::Cancelled);
+        assert!(!ops.cancel(sweep_id));
+
+        let rescan_id = rescan.id();
+        rescan.finish();
+        assert_eq!(ops.get(rescan_id).unwrap().state, OperationState::Finished);
+
+        let failed = ops.register("backfill");
+        let failed_id = failed.id();
+        drop(failed);
+        assert_eq!(ops.get(failed_id).unwrap().state, OperationState::Failed);
+
+        assert_eq!(ops.list().len(), 3);
+        ops.clear_completed();
+        assert!(ops.list().is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Classifying the outputs of transactions by who owns them.
+
+use dashcore::{Address, Amount, Transaction};
+
+use client::{Result, RpcApi};
+use json::{self, GetAddressInfoResultLabel, ScriptKind};
+
+/// Who owns an output, see [RpcApi::classify_outputs].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum OutputOwnership {
+    /// Pays to a change address of the wallet.
+    Change,
+    /// Pays to a receiving address of the wallet.
+    Wallet,
+    /// Pays to an address watched by the wallet without its key.
+    WatchOnly,
+    /// Pays to an address that is not in the wallet.
+    External,
+    /// Doesn't pay to an address, like an `OP_RETURN` output.
+    NoAddress,
+}
+
+/// An output of a transaction classified by [RpcApi::classify_outputs].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ClassifiedOutput {
+    pub vout: u32,
+    pub amount: Amount,
+    pub address: Option<Address>,
+    pub ownership: OutputOwnership,
+    /// The first label of the address in the wallet.
+    pub label: Option<String>,
+}
+
+/// See [RpcApi::classify_outputs].
+pub(crate) fn classify_outputs<C: RpcApi>(
+    rpc: &C,
+    tx: &Transaction,
+) -> Result<Vec<ClassifiedOutput>> {
+    let network = rpc.network()?;
+    let addresses: Vec<Option<Address>> = tx
+        .output
+        .iter()
+        .map(|o| {
+            ScriptKind::from_script(&o.script_pubkey, network)
+                .and_then(|kind| kind.address().cloned())
+        })
+        .collect();
+
+    let mut distinct: Vec<&Address> = Vec::new();
+    for address in addresses.iter().filter_map(Option::as_ref) {
+        if !distinct.contains(&address) {
+            distinct.push(address);
+        }
+    }
+    let calls: Vec<_> =
+        distinct.iter().map(|a| ("getaddressinfo", vec![a.to_string().into()])).collect();
+    let infos = rpc
+        .call_batch::<json::GetAddressInfoResult>(&calls)?
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut outputs = Vec::with_capacity(tx.output.len());
+    for (vout, (output, address)) in tx.output.iter().zip(addresses).enumerate() {
+        let info = address.as_ref().and_then(|a| distinct.iter().position(|d| *d == a));
+        let info = info.map(|i| &infos[i]);
+        let ownership = match info {
+            None => OutputOwnership::NoAddress,
+            Some(info) if info.is_mine == Some(true) && info.is_change == Some(true) => {
+                OutputOwnership::Change
+            }
+            Some(info) if info.is_mine == Some(true) => OutputOwnership::Wallet,
+            Some(info) if info.is_watchonly == Some(true) => OutputOwnership::WatchOnly,
+            Some(_) => OutputOwnership::External,
+        };
+        let label = info.and_then(|info| info.labels.first()).map(|label| match *label {
+            GetAddressInfoResultLabel::Simple(ref name) => name.clone(),
+            GetAddressInfoResultLabel::WithPurpose {
+                ref name,
+                ..
+            } => name.clone(),
+        });
+        outputs.push(ClassifiedOutput {
+            vout: vout as u32,
+            amount: Amount::from_sat(output.value),
+            address,
+            ownership,
+            label,
+        });
+    }
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::blockdata::opcodes::all::OP_RETURN;
+    use dashcore::blockdata::script::Builder;
+    use dashcore::hashes::Hash;
+    use dashcore::util::address::Payload;
+    use dashcore::{Network, PubkeyHash, TxOut};
+    use serde_json;
+    use testutil::MockRpc;
+
+    fn address(seed: u8) -> Address {
+        Address {
+            network: Network::Dash,
+            payload: Payload::PubkeyHash(PubkeyHash::hash(&[seed])),
+        }
+    }
+
+    /// A wallet owning the address of seed 1 as change and the one of seed 2
+    /// labeled "savings".
+    struct MockWallet;
+
+    impl MockRpc for MockWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "getblockchaininfo" => r#"{"chain": "main"}"#.to_owned(),
+                "getaddressinfo" => {
+                    let (mine, change, labels) = match args[0].as_str().unwrap() {
+                        a if a == address(1).to_string() => (true, true, "[]"),
+                        a if a == address(2).to_string() => (true, false, r#"["savings"]"#),
+                        _ => (false, false, "[]"),
+                    };
+                    format!(
+                        r#"{{"address": "{}", "scriptPubKey": "", "ismine": {},
+                             "ischange": {}, "labels": {}}}"#,
+                        args[0].as_str().unwrap(),
+                        mine,
+                        change,
+                        labels
+                    )
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    #[test]
+    fn test_classify_outputs() {
+        let output = |script_pubkey, value| TxOut {
+            value,
+            script_pubkey,
+        };
+        let tx = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![],
+            output: vec![
+                output(address(3).script_pubkey(), 300),
+                output(address(1).script_pubkey(), 100),
+                output(Builder::new().push_opcode(OP_RETURN).into_script(), 0),
+                output(address(2).script_pubkey(), 200),
+                output(address(1).script_pubkey(), 100),
+            ],
+        };
+        let outputs = MockWallet.classify_outputs(&tx).unwrap();
+        let ownership: Vec<_> = outputs.iter().map(|o| o.ownership).collect();
+        assert_eq!(
+            ownership,
+            vec![
+                OutputOwnership::External,
+                OutputOwnership::Change,
+                OutputOwnership::NoAddress,
+                OutputOwnership::Wallet,
+                OutputOwnership::Change,
+            ]
+        );
+        assert_eq!(outputs[3].label, Some("savings".to_owned()));
+        assert_eq!(outputs[3].amount, Amount::from_sat(200));
+        assert_eq!(outputs[0].address, Some(address(3)));
+        assert_eq!(outputs[2].address, None);
+    }
+}
@@ -0,0 +1,254 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Comparing the results of two nodes.
+
+use serde;
+use serde_json;
+
+use capabilities::Capabilities;
+use client::{Result, RpcApi};
+use error::Error;
+
+/// The calls a [ParanoidClient] makes with both nodes, those whose results
+/// only depend on the chain: block hashes, blocks, headers, transactions and
+/// their proofs.
+pub const COMPARED_COMMANDS: &[&str] = &[
+    "getblock",
+    "getblockhash",
+    "getblockheader",
+    "getrawtransaction",
+    "gettxout",
+    "gettxoutproof",
+];
+
+/// The fields of the results of [COMPARED_COMMANDS] that depend on the chain
+/// tip or on the timing of locks, so they are not compared.
+const VOLATILE_FIELDS: &[&str] = &[
+    "bestblock",
+    "chainlock",
+    "confirmations",
+    "instantlock",
+    "instantlock_internal",
+    "nextblockhash",
+];
+
+/// A client that makes the [COMPARED_COMMANDS] with two independent nodes
+/// and fails with [Error::ResponseMismatch] if their results differ, for
+/// when a compromised or buggy node would be costly, like before paying out
+/// a withdrawal.
+///
+/// The fields that depend on the chain tip, like `confirmations`, are
+/// ignored. A failure of either node fails the call. All other calls only go
+/// to the primary node.
+pub struct ParanoidClient<P, S> {
+    primary: P,
+    secondary: S,
+}
+
+impl<P: RpcApi, S: RpcApi> ParanoidClient<P, S> {
+    pub fn new(primary: P, secondary: S) -> ParanoidClient<P, S> {
+        ParanoidClient {
+            primary,
+            secondary,
+        }
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &S {
+        &self.secondary
+    }
+
+    pub fn into_inner(self) -> (P, S) {
+        (self.primary, self.secondary)
+    }
+}
+
+/// The result of the primary node if it matches the one of the secondary.
+fn compare(
+    method: &str,
+    primary: Result<serde_json::Value>,
+    secondary: Result<serde_json::Value>,
+) -> Result<serde_json::Value> {
+    let (primary, secondary) = (primary?, secondary?);
+    if strip_volatile(primary.clone()) != strip_volatile(secondary.clone()) {
+        return Err(Error::ResponseMismatch {
+            method: method.to_owned(),
+            primary,
+            secondary,
+        });
+    }
+    Ok(primary)
+}
+
+/// Remove the [VOLATILE_FIELDS] from `value` and the values it contains,
+/// like the transactions of a block fetched with verbosity 2.
+fn strip_volatile(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => serde_json::Value::Object(
+            object
+                .into_iter()
+                .filter(|&(ref field, _)| !VOLATILE_FIELDS.contains(&field.as_str()))
+                .map(|(field, value)| (field, strip_volatile(value)))
+                .collect(),
+        ),
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(strip_volatile).collect())
+        }
+        value => value,
+    }
+}
+
+impl<P: RpcApi, S: RpcApi> RpcApi for ParanoidClient<P, S> {
+    fn call<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        cmd: &str,
+        args: &[serde_json::Value],
+    ) -> Result<T> {
+        if !COMPARED_COMMANDS.contains(&cmd) {
+            return self.primary.call(cmd, args);
+        }
+        let result = compare(cmd, self.primary.call(cmd, args), self.secondary.call(cmd, args))?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    fn call_batch<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        calls: &[(&str, Vec<serde_json::Value>)],
+    ) -> Result<Vec<Result<T>>> {
+        if !calls.iter().any(|&(cmd, _)| COMPARED_COMMANDS.contains(&cmd)) {
+            return self.primary.call_batch(calls);
+        }
+        let primary = self.primary.call_batch(calls)?;
+        let secondary = self.secondary.call_batch(calls)?;
+        let results = calls.iter().zip(primary.into_iter().zip(secondary)).map(
+            |(&(cmd, _), (primary, secondary))| -> Result<T> {
+                let result = if COMPARED_COMMANDS.contains(&cmd) {
+                    compare(cmd, primary, secondary)?
+                } else {
+                    primary?
+                };
+                Ok(serde_json::from_value(result)?)
+            },
+        );
+        Ok(results.collect())
+    }
+
+    fn node_version(&self) -> Result<usize> {
+        self.primary.node_version()
+    }
+
+    fn capabilities(&self) -> Result<Capabilities> {
+        self.primary.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+    use testutil::MockRpc;
+
+    /// A node at the given height whose blocks hash to `hash`.
+    struct MockNode {
+        height: u64,
+        hash: &'static str,
+    }
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, _: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "getblockcount" => self.height.to_string(),
+                "getblockhash" => format!(r#""{}""#, self.hash),
+                "getblockheader" => format!(
+                    r#"{{"hash": "{}", "confirmations": {}, "height": 1}}"#,
+                    self.hash, self.height
+                ),
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    const HASH: &str = "000000000000000000000000000000000000000000000000000000000000000a";
+    const OTHER_HASH: &str = "000000000000000000000000000000000000000000000000000000000000000b";
+
+    #[test]
+    fn test_strip_volatile() {
+        let block = |confirmations: u64, locked: bool| -> serde_json::Value {
+            let json = format!(
+                r#"{{"hash": "{}", "confirmations": {}, "tx": [{{"txid": "{}",
+                     "instantlock": {}, "instantlock_internal": {}}}]}}"#,
+                HASH, confirmations, HASH, locked, locked
+            );
+            serde_json::from_str(&json).unwrap()
+        };
+        assert_eq!(strip_volatile(block(1, false)), strip_volatile(block(2, true)));
+        let stripped = format!(r#"{{"hash": "{}", "tx": [{{"txid": "{}"}}]}}"#, HASH, HASH);
+        let stripped: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(strip_volatile(block(1, false)), stripped);
+    }
+
+    #[test]
+    fn test_paranoid_client() {
+        let agreeing = ParanoidClient::new(
+            MockNode {
+                height: 10,
+                hash: HASH,
+            },
+            MockNode {
+                height: 11,
+                hash: HASH,
+            },
+        );
+        assert_eq!(agreeing.get_block_hash(1).unwrap().to_string(), HASH);
+        // Only the primary node is asked for the tip, and confirmations are
+        // not compared.
+        assert_eq!(agreeing.get_block_count().unwrap(), 10);
+        let header: serde_json::Value = agreeing.call("getblockheader", &[]).unwrap();
+        assert_eq!(header["confirmations"], 10);
+
+        let disagreeing = ParanoidClient::new(
+            MockNode {
+                height: 10,
+                hash: HASH,
+            },
+            MockNode {
+                height: 10,
+                hash: OTHER_HASH,
+            },
+        );
+        match disagreeing.get_block_hash(1) {
+            Err(Error::ResponseMismatch {
+                ref method,
+                ref primary,
+                ref secondary,
+            }) => {
+                assert_eq!(method, "getblockhash");
+                assert_eq!(primary, HASH);
+                assert_eq!(secondary, OTHER_HASH);
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let batch = [("getblockcount", vec![]), ("getblockhash", vec![1.into()])];
+        let results = disagreeing.call_batch::<serde_json::Value>(&batch).unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), 10);
+        match results[1] {
+            Err(Error::ResponseMismatch {
+                ..
+            }) => {}
+            ref r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Verifying incoming payments.
+
+use std::fmt;
+
+use dashcore::consensus::encode;
+use dashcore::util::merkleblock::MerkleBlock;
+use dashcore::{Address, Amount, BlockHash, Txid};
+
+use client::{Result, RpcApi};
+use error::Error;
+
+/// Why a payment could not be verified, see [Error::InvalidPayment].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PaymentFailure {
+    /// The proof of the node does not include the transaction under the
+    /// merkle root of its block.
+    InvalidProof,
+    /// The block of the transaction is not in the main chain.
+    NotInMainChain(BlockHash),
+    /// The transaction has fewer confirmations than required.
+    NotConfirmed {
+        confirmations: u32,
+    },
+    /// The transaction pays less than required to the address.
+    InsufficientAmount {
+        paid: Amount,
+    },
+}
+
+impl fmt::Display for PaymentFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PaymentFailure::InvalidProof => write!(f, "invalid merkle proof"),
+            PaymentFailure::NotInMainChain(ref hash) => {
+                write!(f, "block {} is not in the main chain", hash)
+            }
+            PaymentFailure::NotConfirmed {
+                confirmations,
+            } => write!(f, "only {} confirmations", confirmations),
+            PaymentFailure::InsufficientAmount {
+                paid,
+            } => write!(f, "only {} paid", paid),
+        }
+    }
+}
+
+/// A payment verified by [RpcApi::verify_payment].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VerifiedPayment {
+    pub txid: Txid,
+    pub block_hash: BlockHash,
+    pub height: usize,
+    pub confirmations: u32,
+    /// The total amount paid to the address.
+    pub amount: Amount,
+    /// The indexes of the outputs paying to the address.
+    pub vouts: Vec<u32>,
+}
+
+/// See [RpcApi::verify_payment].
+pub(crate) fn verify_payment<C: RpcApi>(
+    rpc: &C,
+    txid: &Txid,
+    address: &Address,
+    min_amount: Amount,
+    min_confirmations: u32,
+) -> Result<VerifiedPayment> {
+    let fail = |reason| Error::InvalidPayment {
+        txid: *txid,
+        reason,
+    };
+
+    let proof: MerkleBlock = encode::deserialize(&rpc.get_tx_out_proof(&[*txid], None)?)?;
+    let (mut matches, mut indexes) = (vec![], vec![]);
+    if proof.extract_matches(&mut matches, &mut indexes).is_err() || matches != [*txid] {
+        return Err(fail(PaymentFailure::InvalidProof));
+    }
+
+    // Check the proof against the header the node has for the block, rather
+    // than against the one sent along with the proof.
+    let block_hash = proof.header.block_hash();
+    let header = rpc.get_block_header_info(&block_hash)?;
+    if header.merkle_root != proof.header.merkle_root {
+        return Err(fail(PaymentFailure::InvalidProof));
+    }
+    if header.confirmations < 0 {
+        return Err(fail(PaymentFailure::NotInMainChain(block_hash)));
+    }
+    let confirmations = header.confirmations as u32;
+    if confirmations < min_confirmations {
+        return Err(fail(PaymentFailure::NotConfirmed {
+            confirmations,
+        }));
+    }
+
+    let tx = rpc.get_raw_transaction(txid, Some(&block_hash))?;
+    if tx.txid() != *txid {
+        return Err(fail(PaymentFailure::InvalidProof));
+    }
+    let script_pubkey = address.script_pubkey();
+    let mut amount = Amount::ZERO;
+    let mut vouts = vec![];
+    for (vout, output) in tx.output.iter().enumerate() {
+        if output.script_pubkey == script_pubkey {
+            amount += Amount::from_sat(output.value);
+            vouts.push(vout as u32);
+        }
+    }
+    if amount < min_amount || vouts.is_empty() {
+        return Err(fail(PaymentFailure::InsufficientAmount {
+            paid: amount,
+        }));
+    }
+
+    Ok(VerifiedPayment {
+        txid: *txid,
+        block_hash,
+        height: header.height,
+        confirmations,
+        amount,
+        vouts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::hex::ToHex;
+    use dashcore::{Block, BlockHeader, Network, Transaction, TxMerkleNode};
+    use json;
+    use raw::decode_hex;
+    use serde_json;
+    use testutil::MockRpc;
+
+    const TX: &str = "0200000001586bd02815cf5faabfec986a4e50d25dbee089bd2758621e61c5fab06c334af0000000006b483045022100e85425f6d7c589972ee061413bcf08dc8c8e589ce37b217535a42af924f0e4d602205c9ba9cb14ef15513c9d946fa1c4b797883e748e8c32171bdf6166583946e35c012103dae30a4d7870cd87b45dd53e6012f71318fdd059c1c2623b8cc73f8af287bb2dfeffffff021dc4260c010000001976a914f602e88b2b5901d8aab15ebe4a97cf92ec6e03b388ac00e1f505000000001976a914687ffeffe8cf4e4c038da46a9b1d37db385a472d88acfd211500";
+
+    /// A node with a block holding only the transaction `TX`.
+    struct MockNode {
+        block: Block,
+        confirmations: i32,
+        /// The merkle root of the header the node reports.
+        merkle_root: TxMerkleNode,
+    }
+
+    impl MockNode {
+        fn new(confirmations: i32) -> MockNode {
+            let tx: Transaction = decode_hex(TX).unwrap();
+            let mut block = Block {
+                header: BlockHeader {
+                    version: 1,
+                    prev_blockhash: Default::default(),
+                    merkle_root: Default::default(),
+                    time: 0,
+                    bits: 0,
+                    nonce: 0,
+                },
+                txdata: vec![tx],
+            };
+            block.header.merkle_root = block.compute_merkle_root().unwrap();
+            MockNode {
+                merkle_root: block.header.merkle_root,
+                block,
+                confirmations,
+            }
+        }
+    }
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, _: &[serde_json::Value]) -> Result<String> {
+            let value = match cmd {
+                "gettxoutproof" => {
+                    let proof = MerkleBlock::from_block_with_predicate(&self.block, |_| true);
+                    encode::serialize(&proof).to_hex().into()
+                }
+                "getblockheader" => serde_json::to_value(json::GetBlockHeaderResult {
+                    hash: self.block.block_hash(),
+                    confirmations: self.confirmations,
+                    height: 100,
+                    version: 1,
+                    version_hex: None,
+                    merkle_root: self.merkle_root,
+                    time: 0,
+                    median_time: None,
+                    nonce: 0,
+                    bits: "00000000".into(),
+                    difficulty: 1.0,
+                    chainwork: vec![0],
+                    n_tx: 1,
+                    previous_block_hash: None,
+                    next_block_hash: None,
+                    extra: Default::default(),
+                })?,
+                "getrawtransaction" => TX.into(),
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    fn failure(node: &MockNode, address: &Address, amount: u64, confs: u32) -> PaymentFailure {
+        let txid = node.block.txdata[0].txid();
+        match verify_payment(node, &txid, address, Amount::from_sat(amount), confs) {
+            Err(Error::InvalidPayment {
+                reason,
+                ..
+            }) => reason,
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_verify_payment() {
+        let node = MockNode::new(6);
+        let tx = &node.block.txdata[0];
+        let address = Address::from_script(&tx.output[1].script_pubkey, Network::Dash).unwrap();
+
+        let payment =
+            verify_payment(&node, &tx.txid(), &address, Amount::from_sat(100_000_000), 6).unwrap();
+        assert_eq!(payment.block_hash, node.block.block_hash());
+        assert_eq!(payment.height, 100);
+        assert_eq!(payment.amount, Amount::from_sat(100_000_000));
+        assert_eq!(payment.vouts, vec![1]);
+
+        assert_eq!(
+            failure(&node, &address, 100_000_001, 6),
+            PaymentFailure::InsufficientAmount {
+                paid: Amount::from_sat(100_000_000),
+            }
+        );
+        assert_eq!(
+            failure(&node, &address, 1, 7),
+            PaymentFailure::NotConfirmed {
+                confirmations: 6,
+            }
+        );
+
+        let stale = MockNode::new(-1);
+        assert_eq!(
+            failure(&stale, &address, 1, 0),
+            PaymentFailure::NotInMainChain(stale.block.block_hash())
+        );
+
+        let mut forged = MockNode::new(6);
+        forged.merkle_root = Default::default();
+        assert_eq!(failure(&forged, &address, 1, 0), PaymentFailure::InvalidProof);
+    }
+}
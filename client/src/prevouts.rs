@@ -0,0 +1,346 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Resolving the outputs spent by a transaction.
+
+use std::collections::{HashMap, HashSet};
+
+use jsonrpc;
+use serde_json;
+
+use dashcore::{Amount, Transaction, TxOut};
+
+use client::{Result, RpcApi};
+use error::{Error, RPC_INVALID_ADDRESS_OR_KEY};
+use json;
+use raw::RawResult;
+
+/// A transaction together with the outputs its inputs spend, see
+/// [RpcApi::get_tx_with_prevouts].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TxWithPrevouts {
+    pub tx: Transaction,
+    /// The outputs spent by the inputs, in input order, `None` for the input
+    /// of a coinbase transaction.
+    pub prevouts: Vec<Option<TxOut>>,
+}
+
+impl TxWithPrevouts {
+    /// Resolve the outputs spent by `tx`.
+    ///
+    /// The parent transactions are fetched in one batch of `getrawtransaction`
+    /// calls. The outputs of the parents the node can't find, like when it has
+    /// no transaction index, are looked up in the UTXO set in one batch of
+    /// `gettxout` calls. That only finds unspent outputs, so the parents of
+    /// the outputs still missing are then fetched from the wallet in one
+    /// batch of `gettransaction` calls. An output spent by a transaction that
+    /// is not in the wallet can only be resolved by a node with `-txindex`,
+    /// otherwise this fails with [Error::MissingPrevout].
+    pub fn resolve<C: RpcApi>(rpc: &C, tx: Transaction) -> Result<TxWithPrevouts> {
+        let mut parents = Vec::new();
+        let mut seen = HashSet::new();
+        for input in &tx.input {
+            let txid = input.previous_output.txid;
+            if !input.previous_output.is_null() && seen.insert(txid) {
+                parents.push(txid);
+            }
+        }
+
+        let mut calls = Vec::with_capacity(parents.len());
+        for txid in &parents {
+            let verbosity = serde_json::to_value(json::TxVerbosity::Raw)?;
+            calls.push(("getrawtransaction", vec![serde_json::to_value(txid)?, verbosity]));
+        }
+        let mut txs = HashMap::new();
+        let results = rpc.call_batch::<RawResult<Transaction>>(&calls)?;
+        for (txid, result) in parents.into_iter().zip(results) {
+            match result {
+                Ok(parent) => {
+                    txs.insert(txid, parent.decode("getrawtransaction", Some(&txid))?);
+                }
+                Err(Error::JsonRpc(jsonrpc::Error::Rpc(ref e)))
+                    if e.code == RPC_INVALID_ADDRESS_OR_KEY => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut prevouts = Vec::with_capacity(tx.input.len());
+        // The inputs whose parent was not found, by index.
+        let mut unresolved = Vec::new();
+        for (i, input) in tx.input.iter().enumerate() {
+            let outpoint = input.previous_output;
+            if outpoint.is_null() {
+                prevouts.push(None);
+                continue;
+            }
+            match txs.get(&outpoint.txid) {
+                Some(parent) => match parent.output.get(outpoint.vout as usize) {
+                    Some(output) => prevouts.push(Some(output.clone())),
+                    None => return Err(Error::MissingPrevout(outpoint)),
+                },
+                None => {
+                    prevouts.push(None);
+                    unresolved.push((i, outpoint));
+                }
+            }
+        }
+
+        let mut calls = Vec::with_capacity(unresolved.len());
+        for &(_, ref outpoint) in &unresolved {
+            let args = vec![serde_json::to_value(outpoint.txid)?, outpoint.vout.into()];
+            calls.push(("gettxout", args));
+        }
+        let results = rpc.call_batch::<Option<json::GetTxOutResult>>(&calls)?;
+        let mut spent = Vec::new();
+        for ((i, outpoint), result) in unresolved.into_iter().zip(results) {
+            match result? {
+                Some(output) => {
+                    prevouts[i] = Some(TxOut {
+                        value: output.value.as_sat(),
+                        script_pubkey: output.script_pub_key.hex,
+                    })
+                }
+                None => spent.push((i, outpoint)),
+            }
+        }
+
+        let mut wallet_parents = Vec::new();
+        let mut seen = HashSet::new();
+        for &(_, ref outpoint) in &spent {
+            if seen.insert(outpoint.txid) {
+                wallet_parents.push(outpoint.txid);
+            }
+        }
+        let mut calls = Vec::with_capacity(wallet_parents.len());
+        for txid in &wallet_parents {
+            calls.push(("gettransaction", vec![serde_json::to_value(txid)?]));
+        }
+        let mut txs = HashMap::new();
+        let results = rpc.call_batch::<json::GetTransactionResult>(&calls)?;
+        for (txid, result) in wallet_parents.into_iter().zip(results) {
+            match result {
+                Ok(parent) => {
+                    txs.insert(txid, parent.transaction()?);
+                }
+                // Not a wallet transaction, or the node has no wallet.
+                Err(Error::JsonRpc(jsonrpc::Error::Rpc(_))) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        for (i, outpoint) in spent {
+            let output = txs.get(&outpoint.txid).and_then(|p| p.output.get(outpoint.vout as usize));
+            match output {
+                Some(output) => prevouts[i] = Some(output.clone()),
+                None => return Err(Error::MissingPrevout(outpoint)),
+            }
+        }
+
+        Ok(TxWithPrevouts {
+            tx,
+            prevouts,
+        })
+    }
+
+    /// Whether the transaction is an asset unlock transaction, which
+    /// withdraws Platform credits without spending any output.
+    pub fn is_asset_unlock(&self) -> bool {
+        let version = self.tx.version as u32;
+        (version & 0xffff) >= 3 && (version >> 16) as u16 == json::TRANSACTION_TYPE_ASSET_UNLOCK
+    }
+
+    /// The fee paid by the transaction.
+    ///
+    /// `None` for a coinbase transaction, which pays no fee, for an asset
+    /// unlock transaction, whose fee is in its payload, see
+    /// [RpcApi::get_tx_fee], and for a transaction that creates more than it
+    /// spends.
+    pub fn fee(&self) -> Option<Amount> {
+        if self.is_asset_unlock() {
+            return None;
+        }
+        let mut spent = 0u64;
+        for prevout in &self.prevouts {
+            spent = spent.checked_add(prevout.as_ref()?.value)?;
+        }
+        let mut created = 0u64;
+        for output in &self.tx.output {
+            created = created.checked_add(output.value)?;
+        }
+        spent.checked_sub(created).map(Amount::from_sat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::consensus::encode::serialize;
+    use dashcore::hashes::hex::ToHex;
+    use dashcore::hashes::Hash;
+    use dashcore::{BlockHash, OutPoint, Script, TxIn, Txid, Witness};
+    use raw::decode_hex;
+    use testutil::MockRpc;
+
+    const TX: &str = "0200000001586bd02815cf5faabfec986a4e50d25dbee089bd2758621e61c5fab06c334af0000000006b483045022100e85425f6d7c589972ee061413bcf08dc8c8e589ce37b217535a42af924f0e4d602205c9ba9cb14ef15513c9d946fa1c4b797883e748e8c32171bdf6166583946e35c012103dae30a4d7870cd87b45dd53e6012f71318fdd059c1c2623b8cc73f8af287bb2dfeffffff021dc4260c010000001976a914f602e88b2b5901d8aab15ebe4a97cf92ec6e03b388ac00e1f505000000001976a914687ffeffe8cf4e4c038da46a9b1d37db385a472d88acfd211500";
+
+    /// A transaction of the wallet of [MockNode] with two outputs.
+    fn wallet_tx() -> Transaction {
+        let mut tx = spending(&[OutPoint::null()]);
+        tx.output = vec![
+            TxOut {
+                value: 3000,
+                script_pubkey: Script::new(),
+            },
+            TxOut {
+                value: 4000,
+                script_pubkey: Script::from(vec![0x51]),
+            },
+        ];
+        tx
+    }
+
+    /// A node without a transaction index, that only knows the transaction
+    /// `TX` and has the first output of every other transaction unspent. Its
+    /// wallet has the transaction [wallet_tx].
+    struct MockNode;
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let txid: Txid = serde_json::from_value(args[0].clone())?;
+            let known = txid == decode_hex::<Transaction>(TX)?.txid();
+            let value = match cmd {
+                "getrawtransaction" if known => TX.into(),
+                "getrawtransaction" => {
+                    let e = jsonrpc::error::RpcError {
+                        code: RPC_INVALID_ADDRESS_OR_KEY,
+                        message: "No such mempool or blockchain transaction".into(),
+                        data: None,
+                    };
+                    return Err(Error::JsonRpc(jsonrpc::Error::Rpc(e)));
+                }
+                "gettxout" if args[1] == 0 => serde_json::to_value(json::GetTxOutResult {
+                    bestblock: BlockHash::default(),
+                    confirmations: 1,
+                    value: Amount::from_sat(5000),
+                    script_pub_key: json::GetRawTransactionResultVoutScriptPubKey {
+                        asm: "OP_TRUE".into(),
+                        hex: Script::from(vec![0x51]),
+                        req_sigs: None,
+                        type_: None,
+                        addresses: None,
+                        kind: json::ScriptKind::Nonstandard,
+                    },
+                    coinbase: false,
+                })?,
+                "gettxout" => serde_json::Value::Null,
+                "gettransaction" if txid == wallet_tx().txid() => serde_json::from_str(&format!(
+                    r#"{{"confirmations": 1, "txid": "{}", "time": 0, "timereceived": 0,
+                         "bip125-replaceable": "no", "walletconflicts": [], "amount": 0.0,
+                         "details": [], "hex": "{}"}}"#,
+                    txid,
+                    serialize(&wallet_tx()).to_hex(),
+                ))?,
+                "gettransaction" => {
+                    let e = jsonrpc::error::RpcError {
+                        code: RPC_INVALID_ADDRESS_OR_KEY,
+                        message: "Invalid or non-wallet transaction id".into(),
+                        data: None,
+                    };
+                    return Err(Error::JsonRpc(jsonrpc::Error::Rpc(e)));
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    fn spending(outpoints: &[OutPoint]) -> Transaction {
+        Transaction {
+            version: 1,
+            lock_time: 0,
+            input: outpoints
+                .iter()
+                .map(|o| TxIn {
+                    previous_output: *o,
+                    sequence: 0xFFFFFFFF,
+                    script_sig: Script::new(),
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: vec![],
+        }
+    }
+
+    #[test]
+    fn test_resolve_prevouts() {
+        let parent: Transaction = decode_hex(TX).unwrap();
+        let unknown = Txid::hash(&[1]);
+        let tx = spending(&[
+            OutPoint::new(parent.txid(), 1),
+            OutPoint::new(unknown, 0),
+            OutPoint::new(parent.txid(), 0),
+        ]);
+
+        let resolved = TxWithPrevouts::resolve(&MockNode, tx.clone()).unwrap();
+        assert_eq!(resolved.tx, tx);
+        assert_eq!(resolved.prevouts[0].as_ref(), Some(&parent.output[1]));
+        assert_eq!(resolved.prevouts[1].as_ref().unwrap().value, 5000);
+        assert_eq!(resolved.prevouts[2].as_ref(), Some(&parent.output[0]));
+
+        let coinbase = spending(&[OutPoint::null()]);
+        assert_eq!(TxWithPrevouts::resolve(&MockNode, coinbase).unwrap().prevouts, vec![None]);
+
+        // The spent output of a wallet transaction is found in the wallet.
+        let wallet_tx = wallet_tx();
+        let tx = spending(&[OutPoint::new(wallet_tx.txid(), 1)]);
+        let resolved = TxWithPrevouts::resolve(&MockNode, tx).unwrap();
+        assert_eq!(resolved.prevouts, vec![Some(wallet_tx.output[1].clone())]);
+
+        for outpoint in &[OutPoint::new(unknown, 1), OutPoint::new(parent.txid(), 2)] {
+            match TxWithPrevouts::resolve(&MockNode, spending(&[*outpoint])) {
+                Err(Error::MissingPrevout(o)) => assert_eq!(o, *outpoint),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fee() {
+        let output = |value| TxOut {
+            value,
+            script_pubkey: Script::new(),
+        };
+        let mut tx = TxWithPrevouts {
+            tx: spending(&[OutPoint::new(Txid::hash(&[1]), 0), OutPoint::new(Txid::hash(&[2]), 0)]),
+            prevouts: vec![Some(output(6000)), Some(output(4000))],
+        };
+        tx.tx.output = vec![output(7000), output(2000)];
+        assert_eq!(tx.fee(), Some(Amount::from_sat(1000)));
+
+        tx.tx.output.push(output(2000));
+        assert_eq!(tx.fee(), None);
+
+        tx.tx.output = vec![output(u64::max_value()), output(2)];
+        assert_eq!(tx.fee(), None);
+
+        let coinbase = TxWithPrevouts {
+            tx: spending(&[OutPoint::null()]),
+            prevouts: vec![None],
+        };
+        assert_eq!(coinbase.fee(), None);
+
+        let mut asset_unlock = TxWithPrevouts {
+            tx: spending(&[]),
+            prevouts: vec![],
+        };
+        asset_unlock.tx.version = 3 | (9 << 16);
+        assert!(asset_unlock.is_asset_unlock());
+        assert_eq!(asset_unlock.fee(), None);
+    }
+}
@@ -0,0 +1,568 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Pushing chain events and deposits to external sinks.
+
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use serde_json;
+
+use dashcore::hashes::{sha1, Hash};
+use dashcore::Address;
+
+use chain::{ChainEvent, ChainTracker};
+use client::{Result, RpcApi};
+use error::Error;
+use json::ConfirmationStatus;
+use transport::{self, TransportError, DEFAULT_TIMEOUT};
+use watcher::{Deposit, Watcher};
+
+/// The GUID a WebSocket server appends to the key of the client to accept
+/// the handshake, from RFC 6455.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// An event pushed by a [PushService] to its sinks.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PushEvent {
+    /// A block was connected to or disconnected from the main chain.
+    Chain(ChainEvent),
+    /// The status of a deposit to a watched address changed.
+    Deposit(Deposit),
+}
+
+impl PushEvent {
+    /// The event as the JSON object sent by the webhook and WebSocket sinks.
+    ///
+    /// Its `type` is `blockConnected`, `blockDisconnected` or `deposit`.
+    /// Block events have the `height` and `hash` of the block. Deposits have
+    /// the `address`, `txid`, `vout`, `amount` in duffs, `status`, one of
+    /// `unconfirmed`, `instantLocked`, `confirmed` and `chainLocked`, the
+    /// number of `confirmations` and whether the deposit is `final`.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        match *self {
+            PushEvent::Chain(event) => {
+                let (kind, height, hash) = match event {
+                    ChainEvent::BlockConnected {
+                        height,
+                        hash,
+                    } => ("blockConnected", height, hash),
+                    ChainEvent::BlockDisconnected {
+                        height,
+                        hash,
+                    } => ("blockDisconnected", height, hash),
+                };
+                object.insert("type".into(), kind.into());
+                object.insert("height".into(), height.into());
+                object.insert("hash".into(), hash.to_string().into());
+            }
+            PushEvent::Deposit(ref deposit) => {
+                let (status, confirmations) = match deposit.status {
+                    ConfirmationStatus::Unconfirmed => ("unconfirmed", 0),
+                    ConfirmationStatus::InstantLocked => ("instantLocked", 0),
+                    ConfirmationStatus::Confirmed {
+                        confirmations,
+                        ..
+                    } => ("confirmed", confirmations),
+                    ConfirmationStatus::ChainLocked {
+                        confirmations,
+                    } => ("chainLocked", confirmations),
+                };
+                object.insert("type".into(), "deposit".into());
+                object.insert("address".into(), deposit.address.to_string().into());
+                object.insert("txid".into(), deposit.outpoint.txid.to_string().into());
+                object.insert("vout".into(), deposit.outpoint.vout.into());
+                object.insert("amount".into(), deposit.amount.as_sat().into());
+                object.insert("status".into(), status.into());
+                object.insert("confirmations".into(), confirmations.into());
+                object.insert("final".into(), deposit.status.is_final().into());
+            }
+        }
+        serde_json::Value::Object(object)
+    }
+}
+
+/// Where a [PushService] delivers its events.
+pub trait EventSink: Send {
+    /// Deliver one event.
+    fn push(&mut self, event: &PushEvent) -> Result<()>;
+}
+
+/// Sends the events to the receiver of the channel.
+impl EventSink for mpsc::Sender<PushEvent> {
+    fn push(&mut self, event: &PushEvent) -> Result<()> {
+        self.send(event.clone()).map_err(|_| {
+            let e = io::Error::new(io::ErrorKind::BrokenPipe, "the receiver was dropped");
+            Error::Push(TransportError::Io(e))
+        })
+    }
+}
+
+/// POSTs every event as JSON, see [PushEvent::to_json], to a URL like
+/// "http://127.0.0.1:8080/notify", expecting a 2xx status.
+#[derive(Clone, Debug)]
+pub struct WebhookSink {
+    /// The `host:port` to connect to.
+    addr: String,
+    path: String,
+    timeout: Duration,
+}
+
+impl WebhookSink {
+    /// Create a sink posting to `url`, which must be a plain http URL.
+    pub fn new(url: &str) -> Result<WebhookSink> {
+        let (addr, path) = transport::parse_url(url).map_err(Error::Push)?;
+        Ok(WebhookSink {
+            addr,
+            path,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Set the timeout for connecting, sending and receiving.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn post(&self, body: &[u8]) -> ::std::result::Result<(), TransportError> {
+        let stream = transport::connect(&self.addr, self.timeout)?;
+        {
+            let mut w = BufWriter::new(&stream);
+            write!(w, "POST {} HTTP/1.1\r\n", self.path)?;
+            write!(w, "Host: {}\r\n", self.addr)?;
+            w.write_all(b"Content-Type: application/json\r\n")?;
+            write!(w, "Content-Length: {}\r\n", body.len())?;
+            w.write_all(b"Connection: close\r\n\r\n")?;
+            w.write_all(body)?;
+            w.flush()?;
+        }
+        match transport::read_response_head(&mut BufReader::new(&stream))?.status {
+            200..=299 => Ok(()),
+            status => Err(TransportError::HttpStatus(status)),
+        }
+    }
+}
+
+impl EventSink for WebhookSink {
+    fn push(&mut self, event: &PushEvent) -> Result<()> {
+        let body = serde_json::to_vec(&event.to_json())?;
+        self.post(&body).map_err(Error::Push)
+    }
+}
+
+/// A WebSocket server sending every event as a JSON text message, see
+/// [PushEvent::to_json], to all its connected clients.
+///
+/// Clients are accepted when an event is pushed, and dropped once a message
+/// can't be written to them. Messages from the clients are never read.
+#[derive(Debug)]
+pub struct WebSocketSink {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+    timeout: Duration,
+}
+
+impl WebSocketSink {
+    /// Listen for WebSocket clients on `addr`, like "127.0.0.1:8081".
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<WebSocketSink> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(WebSocketSink {
+            listener,
+            clients: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Set the timeout for the handshake with a client and for sending it a
+    /// message.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The address the server listens on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// The number of connected clients.
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Accept the clients waiting to connect.
+    fn accept(&mut self) -> Result<()> {
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+            match self.handshake(&stream) {
+                Ok(()) => self.clients.push(stream),
+                Err(e) => debug!(target: "dashcore_rpc::push", "WebSocket handshake failed: {}", e),
+            }
+        }
+    }
+
+    /// Read the opening handshake of a client and accept it.
+    fn handshake(&self, stream: &TcpStream) -> io::Result<()> {
+        // The accepted stream may inherit the mode of the listener.
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let mut reader = BufReader::new(stream);
+        let mut key = None;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated handshake"));
+            }
+            if line == "\r\n" || line == "\n" {
+                break;
+            }
+            let mut parts = line.splitn(2, ':');
+            let name = parts.next().unwrap_or("").trim();
+            if name.eq_ignore_ascii_case("sec-websocket-key") {
+                key = parts.next().map(|k| k.trim().to_owned());
+            }
+        }
+        let mut stream = stream;
+        match key {
+            Some(key) => {
+                let hash = sha1::Hash::hash(format!("{}{}", key, WEBSOCKET_GUID).as_bytes());
+                write!(
+                    stream,
+                    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                     Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                    transport::base64_encode(&hash[..])
+                )
+            }
+            None => {
+                stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+                Err(io::Error::new(io::ErrorKind::InvalidData, "not a WebSocket handshake"))
+            }
+        }
+    }
+}
+
+impl EventSink for WebSocketSink {
+    fn push(&mut self, event: &PushEvent) -> Result<()> {
+        self.accept()?;
+        let frame = text_frame(&event.to_json().to_string());
+        self.clients.retain(|client| {
+            let mut client = client;
+            client.write_all(&frame).is_ok()
+        });
+        Ok(())
+    }
+}
+
+/// An unmasked WebSocket frame with a single text message.
+fn text_frame(text: &str) -> Vec<u8> {
+    let len = text.len();
+    let mut frame = vec![0x81];
+    if len < 126 {
+        frame.push(len as u8);
+    } else if len <= 0xffff {
+        frame.push(126);
+        frame.extend_from_slice(&[(len >> 8) as u8, len as u8]);
+    } else {
+        frame.push(127);
+        frame.extend((0..8).rev().map(|i| ((len as u64) >> (8 * i)) as u8));
+    }
+    frame.extend_from_slice(text.as_bytes());
+    frame
+}
+
+/// Follows the chain and a set of watched addresses, and pushes what
+/// changed to its sinks, the backbone of a merchant notification service.
+///
+/// Every [PushService::poll] pushes the blocks connected and disconnected
+/// since the last poll, as reported by a [ChainTracker], followed by the
+/// deposits whose status changed, as reported by a [Watcher]. Addresses can
+/// be watched and unwatched between polls. A sink that fails to deliver an
+/// event is logged and kept, the other sinks still get the event.
+pub struct PushService {
+    tracker: ChainTracker,
+    watcher: Watcher,
+    sinks: Vec<Box<dyn EventSink>>,
+}
+
+impl PushService {
+    /// Create a service watching addresses with `watcher`, and following
+    /// chain reorganizations up to `depth` blocks deep.
+    pub fn new(watcher: Watcher, depth: usize) -> PushService {
+        PushService {
+            tracker: ChainTracker::new(depth),
+            watcher,
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Register a sink to push the events to.
+    pub fn add_sink<S: EventSink + 'static>(&mut self, sink: S) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    /// Start watching an address, see [Watcher::watch].
+    pub fn watch<C: RpcApi>(&mut self, rpc: &C, address: Address) -> Result<()> {
+        self.watcher.watch(rpc, address)
+    }
+
+    /// Stop watching an address, see [Watcher::unwatch].
+    pub fn unwatch(&mut self, address: &Address) -> bool {
+        self.watcher.unwatch(address)
+    }
+
+    /// The watched addresses.
+    pub fn addresses(&self) -> &[Address] {
+        self.watcher.addresses()
+    }
+
+    /// Fetch what changed since the last poll and push it to every sink.
+    /// Returns the pushed events.
+    pub fn poll<C: RpcApi>(&mut self, rpc: &C) -> Result<Vec<PushEvent>> {
+        let mut events: Vec<PushEvent> =
+            self.tracker.poll(rpc)?.into_iter().map(PushEvent::Chain).collect();
+        events.extend(self.watcher.poll(rpc)?.into_iter().map(PushEvent::Deposit));
+        for event in &events {
+            for sink in &mut self.sinks {
+                if let Err(e) = sink.push(event) {
+                    warn!(target: "dashcore_rpc::push", "failed to push {:?}: {}", event, e);
+                }
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::{Amount, BlockHash, OutPoint, Txid};
+    use std::cell::Cell;
+    use std::io::Read;
+    use std::thread;
+    use testutil::{address, MockRpc};
+
+    /// A node with the address index, whose tip is at `tip` and which has a
+    /// single deposit to [address], mined in block 10 and ChainLocked.
+    struct MockNode {
+        tip: Cell<u64>,
+    }
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "getblockcount" => self.tip.get().to_string(),
+                "getblockhash" => {
+                    let height = args[0].as_u64().unwrap() as u8;
+                    format!("\"{}\"", BlockHash::hash(&[height]))
+                }
+                "getaddressdeltas" => format!(
+                    r#"[{{"satoshis": 1000, "txid": "{}", "index": 1, "blockindex": 1,
+                          "height": 10, "address": "{}"}}]"#,
+                    Txid::hash(&[1]),
+                    address()
+                ),
+                "getaddressmempool" => "[]".into(),
+                "getrawtransaction" => format!(
+                    r#"{{"hex": "00", "txid": "{0}", "hash": "{0}", "size": 1, "vsize": 1,
+                         "version": 3, "locktime": 0, "vin": [], "vout": [],
+                         "confirmations": 1, "chainlock": true}}"#,
+                    Txid::hash(&[1])
+                ),
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    /// A sink that always fails.
+    struct BrokenSink;
+
+    impl EventSink for BrokenSink {
+        fn push(&mut self, _: &PushEvent) -> Result<()> {
+            Err(Error::Push(TransportError::HttpStatus(500)))
+        }
+    }
+
+    fn deposit() -> Deposit {
+        Deposit {
+            address: address(),
+            outpoint: OutPoint::new(Txid::hash(&[1]), 1),
+            amount: Amount::from_sat(1000),
+            status: ConfirmationStatus::ChainLocked {
+                confirmations: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_poll() {
+        let node = MockNode {
+            tip: Cell::new(10),
+        };
+        let mut service = PushService::new(Watcher::with_address_index(6, 8), 6);
+        let (sender, receiver) = mpsc::channel();
+        service.add_sink(BrokenSink);
+        service.add_sink(sender);
+
+        // The first poll starts following the chain at the tip.
+        assert!(service.poll(&node).unwrap().is_empty());
+        service.watch(&node, address()).unwrap();
+        assert_eq!(service.addresses(), &[address()]);
+
+        node.tip.set(11);
+        let events = service.poll(&node).unwrap();
+        let connected = ChainEvent::BlockConnected {
+            height: 11,
+            hash: BlockHash::hash(&[11]),
+        };
+        assert_eq!(events, vec![PushEvent::Chain(connected), PushEvent::Deposit(deposit())]);
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), events);
+
+        assert!(service.unwatch(&address()));
+        assert!(service.addresses().is_empty());
+        assert!(service.poll(&node).unwrap().is_empty());
+
+        drop(receiver);
+        node.tip.set(12);
+        assert_eq!(service.poll(&node).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json = PushEvent::Deposit(deposit()).to_json();
+        assert_eq!(json["type"], "deposit");
+        assert_eq!(json["address"], address().to_string());
+        assert_eq!(json["txid"], Txid::hash(&[1]).to_string());
+        assert_eq!(json["vout"], 1);
+        assert_eq!(json["amount"], 1000);
+        assert_eq!(json["status"], "chainLocked");
+        assert_eq!(json["confirmations"], 1);
+        assert_eq!(json["final"], true);
+
+        let json = PushEvent::Chain(ChainEvent::BlockDisconnected {
+            height: 5,
+            hash: BlockHash::hash(&[5]),
+        })
+        .to_json();
+        assert_eq!(json["type"], "blockDisconnected");
+        assert_eq!(json["height"], 5);
+        assert_eq!(json["hash"], BlockHash::hash(&[5]).to_string());
+    }
+
+    #[test]
+    fn test_webhook() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/notify", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = vec![];
+            for status in &["200 OK", "503 Service Unavailable"] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.to_ascii_lowercase().starts_with("content-length:") {
+                        length = line[15..].trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8(body).unwrap());
+                write!(&stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+
+        let mut sink = WebhookSink::new(&url).unwrap();
+        let event = PushEvent::Deposit(deposit());
+        sink.push(&event).unwrap();
+        match sink.push(&event) {
+            Err(Error::Push(TransportError::HttpStatus(503))) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("POST /notify HTTP/1.1\r\n"));
+        let body = requests[0].splitn(2, "\r\n\r\n").nth(1).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(body).unwrap(), event.to_json());
+
+        match WebhookSink::new("https://example.com/notify") {
+            Err(Error::Push(TransportError::InvalidUrl {
+                ..
+            })) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_websocket() {
+        let mut sink = WebSocketSink::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(sink.local_addr().unwrap()).unwrap();
+        // The example handshake of RFC 6455.
+        client
+            .write_all(
+                b"GET /events HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                  Sec-WebSocket-Version: 13\r\n\r\n",
+            )
+            .unwrap();
+        let mut other = TcpStream::connect(sink.local_addr().unwrap()).unwrap();
+        other.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        let event = PushEvent::Deposit(deposit());
+        sink.push(&event).unwrap();
+        assert_eq!(sink.clients(), 1);
+
+        let mut reader = BufReader::new(client);
+        let mut head = String::new();
+        while !head.ends_with("\r\n\r\n") {
+            reader.read_line(&mut head).unwrap();
+        }
+        assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+        let text = event.to_json().to_string();
+        let mut frame = vec![0; 4 + text.len()];
+        reader.read_exact(&mut frame).unwrap();
+        assert_eq!(frame[..4], [0x81, 126, (text.len() >> 8) as u8, text.len() as u8]);
+        assert_eq!(&frame[4..], text.as_bytes());
+
+        let mut response = String::new();
+        other.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[test]
+    fn test_text_frame() {
+        assert_eq!(text_frame("hi"), b"\x81\x02hi".to_vec());
+        let long = "a".repeat(70000);
+        let frame = text_frame(&long);
+        assert_eq!(frame[..10], [0x81, 127, 0, 0, 0, 0, 0, 1, 0x11, 0x70]);
+        assert_eq!(frame.len(), 10 + 70000);
+    }
+}
@@ -13,6 +13,7 @@ use serde_json;
 
 use client::Result;
 use client::RpcApi;
+use raw::RawResult;
 
 /// A type that can be queried from Bitcoin Core.
 pub trait Queryable<C: RpcApi>: Sized {
@@ -27,9 +28,9 @@ impl<C: RpcApi> Queryable<C> for dashcore::blockdata::block::Block {
 
     fn query(rpc: &C, id: &Self::Id) -> Result<Self> {
         let rpc_name = "getblock";
-        let hex: String = rpc.call(rpc_name, &[serde_json::to_value(id)?, 0.into()])?;
-        let bytes: Vec<u8> = dashcore::hashes::hex::FromHex::from_hex(&hex)?;
-        Ok(dashcore::consensus::encode::deserialize(&bytes)?)
+        let verbosity = serde_json::to_value(::json::BlockVerbosity::Raw)?;
+        let raw: RawResult<Self> = rpc.call(rpc_name, &[serde_json::to_value(id)?, verbosity])?;
+        raw.decode(rpc_name, Some(id))
     }
 }
 
@@ -38,9 +39,8 @@ impl<C: RpcApi> Queryable<C> for dashcore::blockdata::transaction::Transaction {
 
     fn query(rpc: &C, id: &Self::Id) -> Result<Self> {
         let rpc_name = "getrawtransaction";
-        let hex: String = rpc.call(rpc_name, &[serde_json::to_value(id)?])?;
-        let bytes: Vec<u8> = dashcore::hashes::hex::FromHex::from_hex(&hex)?;
-        Ok(dashcore::consensus::encode::deserialize(&bytes)?)
+        let raw: RawResult<Self> = rpc.call(rpc_name, &[serde_json::to_value(id)?])?;
+        raw.decode(rpc_name, Some(id))
     }
 }
 
@@ -0,0 +1,189 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Helpers for LLMQ signing sessions.
+
+use std::cmp;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use client::{Result, RpcApi};
+use error::Error;
+use json;
+use operations::{self, OperationHandle};
+
+/// How often [RpcApi::quorum_sign_and_wait] asks the node whether the
+/// signature was recovered.
+pub const RECOVERED_SIG_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// See [RpcApi::quorum_sign_and_wait].
+///
+/// With an `operation`, the wait stops with [Error::Cancelled] once
+/// cancellation is requested.
+pub fn quorum_sign_and_wait<C: RpcApi>(
+    rpc: &C,
+    llmq_type: json::LLMQType,
+    id: &str,
+    msg_hash: &str,
+    timeout: Duration,
+    operation: Option<&OperationHandle>,
+) -> Result<json::QuorumSignature> {
+    let deadline = Instant::now() + timeout;
+    if let json::QuorumSignResult::QuorumSignStatus(false) =
+        rpc.get_quorum_sign(llmq_type, id, msg_hash, None, None)?
+    {
+        return Err(Error::QuorumSignRefused);
+    }
+    loop {
+        operations::check_cancelled(operation)?;
+        if rpc.get_quorum_hasrecsig(llmq_type, id, msg_hash)? {
+            return rpc.get_quorum_getrecsig(llmq_type, id, msg_hash);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(Error::Timeout(timeout));
+        }
+        thread::sleep(cmp::min(RECOVERED_SIG_POLL_INTERVAL, deadline - now));
+    }
+}
+
+/// The result of [RpcApi::quorum_select_and_verify].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct QuorumVerification {
+    /// Whether the signature is valid.
+    pub valid: bool,
+    /// The quorum the signature was verified against.
+    pub quorum_hash: json::QuorumHash,
+}
+
+/// See [RpcApi::quorum_select_and_verify].
+pub(crate) fn quorum_select_and_verify<C: RpcApi>(
+    rpc: &C,
+    llmq_type: json::LLMQType,
+    id: &str,
+    msg_hash: &str,
+    signature: &str,
+) -> Result<QuorumVerification> {
+    let quorum_hash = rpc.get_quorum_selectquorum(llmq_type, id)?.quorum_hash;
+    let valid =
+        rpc.get_quorum_verify(llmq_type, id, msg_hash, signature, Some(&quorum_hash), None)?;
+    Ok(QuorumVerification {
+        valid,
+        quorum_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use operations::Operations;
+    use serde_json;
+    use std::cell::Cell;
+    use testutil::MockRpc;
+
+    /// A node that recovers the signature after it was asked `polls` times,
+    /// if it is a `member` of the quorum.
+    struct SigningNode {
+        polls: Cell<usize>,
+        signed: Cell<bool>,
+        member: bool,
+    }
+
+    impl SigningNode {
+        fn new(polls: usize) -> SigningNode {
+            SigningNode {
+                polls: Cell::new(polls),
+                signed: Cell::new(false),
+                member: true,
+            }
+        }
+    }
+
+    impl MockRpc for SigningNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            assert_eq!(cmd, "quorum");
+            let value = match args[0].as_str().unwrap() {
+                "sign" => {
+                    self.signed.set(self.member);
+                    self.member.into()
+                }
+                "hasrecsig" => {
+                    assert!(self.signed.get());
+                    let polls = self.polls.get();
+                    self.polls.set(polls.saturating_sub(1));
+                    (polls == 0).into()
+                }
+                "getrecsig" => serde_json::from_str(&format!(
+                    r#"{{"llmqType": "llmq_test", "quorumHash": "{}", "id": "01", "msgHash": "02",
+                        "signHash": "03", "signature": "{}"}}"#,
+                    "00".repeat(32),
+                    "04".repeat(96),
+                ))?,
+                "selectquorum" => serde_json::from_str(&format!(
+                    r#"{{"quorumHash": "{}", "recoveryMembers": []}}"#,
+                    "05".repeat(32),
+                ))?,
+                "verify" => {
+                    assert_eq!(args[5], "05".repeat(32));
+                    (args[4] == "04".repeat(96)).into()
+                }
+                c => panic!("unexpected call: quorum {}", c),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_quorum_sign_and_wait() {
+        let node = SigningNode::new(2);
+        let llmq_type = json::LLMQType::LlmqTest;
+        let timeout = Duration::from_secs(10);
+        let sig = quorum_sign_and_wait(&node, llmq_type, "01", "02", timeout, None).unwrap();
+        assert_eq!(sig.signature, vec![4; 96]);
+        assert_eq!(node.polls.get(), 0);
+
+        let node = SigningNode::new(usize::max_value());
+        let short = Duration::from_millis(10);
+        match quorum_sign_and_wait(&node, llmq_type, "01", "02", short, None) {
+            Err(Error::Timeout(timeout)) => assert_eq!(timeout, short),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let outsider = SigningNode {
+            member: false,
+            ..SigningNode::new(0)
+        };
+        match quorum_sign_and_wait(&outsider, llmq_type, "01", "02", timeout, None) {
+            Err(Error::QuorumSignRefused) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let ops = Operations::new();
+        let op = ops.register("sign");
+        ops.cancel(op.id());
+        match quorum_sign_and_wait(&node, llmq_type, "01", "02", timeout, Some(&op)) {
+            Err(Error::Cancelled) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_quorum_select_and_verify() {
+        let node = SigningNode::new(0);
+        let llmq_type = json::LLMQType::LlmqTest;
+        let verification =
+            quorum_select_and_verify(&node, llmq_type, "01", "02", &"04".repeat(96)).unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.quorum_hash.to_string(), "05".repeat(32));
+        let verification =
+            quorum_select_and_verify(&node, llmq_type, "01", "02", &"06".repeat(96)).unwrap();
+        assert!(!verification.valid);
+    }
+}
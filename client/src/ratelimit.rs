@@ -0,0 +1,160 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Bounding the load a [Client](::Client) puts on the node.
+
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A token bucket refilled with `rate` tokens per second, up to `burst`.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(calls_per_second: f64, burst: u32) -> RateLimiter {
+        assert!(calls_per_second > 0.0, "the rate limit must be positive");
+        let burst = f64::from(burst.max(1));
+        RateLimiter {
+            rate: calls_per_second,
+            burst,
+            bucket: Mutex::new((burst, Instant::now())),
+        }
+    }
+
+    /// Block until `calls` calls can be made.
+    ///
+    /// A batch larger than the burst waits for a full bucket and leaves it in
+    /// debt, which delays the next calls instead.
+    pub(crate) fn acquire(&self, calls: usize) {
+        let calls = calls as f64;
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+                let refill = seconds(now.duration_since(bucket.1)) * self.rate;
+                bucket.0 = (bucket.0 + refill).min(self.burst);
+                bucket.1 = now;
+                let needed = calls.min(self.burst);
+                if bucket.0 >= needed {
+                    bucket.0 -= calls;
+                    return;
+                }
+                (needed - bucket.0) / self.rate
+            };
+            thread::sleep(from_seconds(wait));
+        }
+    }
+}
+
+/// A limit on the number of requests sent at the same time.
+#[derive(Debug)]
+pub(crate) struct InFlightLimit {
+    max: usize,
+    in_flight: Mutex<usize>,
+    released: Condvar,
+}
+
+/// A request counted by an [InFlightLimit] until it is dropped.
+pub(crate) struct InFlightGuard<'a> {
+    limit: &'a InFlightLimit,
+}
+
+impl InFlightLimit {
+    pub(crate) fn new(max: usize) -> InFlightLimit {
+        InFlightLimit {
+            max: max.max(1),
+            in_flight: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Block until fewer than the maximum of requests are in flight.
+    pub(crate) fn acquire(&self) -> InFlightGuard {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        while *in_flight >= self.max {
+            in_flight = self.released.wait(in_flight).unwrap();
+        }
+        *in_flight += 1;
+        InFlightGuard {
+            limit: self,
+        }
+    }
+}
+
+impl<'a> Drop for InFlightGuard<'a> {
+    fn drop(&mut self) {
+        *self.limit.in_flight.lock().unwrap() -= 1;
+        self.limit.released.notify_one();
+    }
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9
+}
+
+fn from_seconds(seconds: f64) -> Duration {
+    Duration::new(seconds.trunc() as u64, (seconds.fract() * 1e9) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(100.0, 2);
+        let start = Instant::now();
+        limiter.acquire(1);
+        limiter.acquire(1);
+        limiter.acquire(1);
+        assert!(start.elapsed() >= Duration::from_millis(5));
+
+        // A batch above the burst only waits for a full bucket.
+        let start = Instant::now();
+        limiter.acquire(10);
+        assert!(start.elapsed() < Duration::from_millis(500));
+        let start = Instant::now();
+        limiter.acquire(1);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_in_flight_limit() {
+        let limit = Arc::new(InFlightLimit::new(2));
+        let current = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(Mutex::new(0));
+        let threads: Vec<_> = (0..6)
+            .map(|_| {
+                let (limit, current, peak) = (limit.clone(), current.clone(), peak.clone());
+                thread::spawn(move || {
+                    let _guard = limit.acquire();
+                    let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                    {
+                        let mut peak = peak.lock().unwrap();
+                        *peak = (*peak).max(now);
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                    current.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert!(*peak.lock().unwrap() <= 2);
+    }
+}
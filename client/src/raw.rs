@@ -0,0 +1,247 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Decoding of raw results, the hex encoded consensus serializations the
+//! node returns for blocks, headers and transactions.
+
+use std::marker::PhantomData;
+use std::{fmt, io};
+
+use dashcore::consensus::encode::{self, Decodable};
+use dashcore::hashes::hex;
+use log::Level::Debug;
+use serde;
+use serde::de;
+
+use client::Result;
+use error::Error;
+
+/// The log target that the hex of results failing to decode is logged to, at
+/// the debug level, so the failure can be reproduced.
+pub const DECODE_LOG_TARGET: &str = "dashcore_rpc::decode";
+
+/// A consensus encoded value that the node returns as a hex string.
+///
+/// When deserialized, the hex is decoded on the fly while the value is being
+/// consensus decoded, so neither the hex string nor the raw bytes are
+/// allocated. Use it as the result type of a call:
+///
+/// ```no_run
+/// # use dashcore_rpc::{Auth, Client, RawHex, RpcApi};
+/// # use dashcore_rpc::dashcore::{Block, BlockHash};
+/// # let client = Client::new("http://localhost:9998", Auth::None).unwrap();
+/// # let hash = BlockHash::default();
+/// let block: RawHex<Block> = client.call("getblock", &[hash.to_string().into(), 0.into()])?;
+/// # Ok::<(), dashcore_rpc::Error>(())
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RawHex<T>(pub T);
+
+impl<T> RawHex<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<'de, T: Decodable> serde::Deserialize<'de> for RawHex<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> ::std::result::Result<Self, D::Error> {
+        match d.deserialize_str(HexVisitor(PhantomData))? {
+            Ok(value) => Ok(RawHex(value)),
+            Err((_, e)) => Err(de::Error::custom(e)),
+        }
+    }
+}
+
+/// Like [RawHex], but a hex string that fails to decode is kept instead of
+/// failing the deserialization, so the error can be given the context of the
+/// call with [RawResult::decode].
+pub(crate) struct RawResult<T>(::std::result::Result<T, (usize, Error)>);
+
+impl<T> RawResult<T> {
+    /// The decoded value, or an [Error::Decode] of the result of `method`
+    /// for the item `id`.
+    pub(crate) fn decode(self, method: &str, id: Option<&dyn fmt::Display>) -> Result<T> {
+        self.0.map_err(|(len, error)| Error::Decode {
+            method: method.to_owned(),
+            id: id.map(|id| id.to_string()),
+            len,
+            error: Box::new(error),
+        })
+    }
+}
+
+impl<'de, T: Decodable> serde::Deserialize<'de> for RawResult<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> ::std::result::Result<Self, D::Error> {
+        d.deserialize_str(HexVisitor(PhantomData)).map(RawResult)
+    }
+}
+
+/// Decodes a hex string, keeping a decoding error with the length of the hex.
+struct HexVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Decodable> de::Visitor<'de> for HexVisitor<T> {
+    type Value = ::std::result::Result<T, (usize, Error)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a hex encoded consensus serialization")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> ::std::result::Result<Self::Value, E> {
+        Ok(decode_hex(s).map_err(|e| (s.len(), e)))
+    }
+}
+
+/// Like [decode_hex], but fails with an [Error::Decode] of the result of
+/// `method` for the item `id`.
+pub(crate) fn decode_result<T: Decodable>(
+    method: &str,
+    id: Option<&dyn fmt::Display>,
+    hex: &str,
+) -> Result<T> {
+    RawResult(decode_hex(hex).map_err(|e| (hex.len(), e))).decode(method, id)
+}
+
+/// Consensus decode a value from its hex encoding, without allocating the
+/// intermediate bytes.
+///
+/// The hex of a value that fails to decode is logged to [DECODE_LOG_TARGET].
+pub fn decode_hex<T: Decodable>(hex: &str) -> Result<T> {
+    let result = decode_hex_inner(hex);
+    if result.is_err() && log_enabled!(target: DECODE_LOG_TARGET, Debug) {
+        debug!(target: DECODE_LOG_TARGET, "failed to decode {} hex chars: {}", hex.len(), hex);
+    }
+    result
+}
+
+fn decode_hex_inner<T: Decodable>(hex: &str) -> Result<T> {
+    if hex.len() % 2 != 0 {
+        return Err(hex::Error::OddLengthString(hex.len()).into());
+    }
+    let mut reader = HexReader {
+        hex: hex.as_bytes(),
+        error: None,
+    };
+    let value = match Decodable::consensus_decode(&mut reader) {
+        Ok(value) => value,
+        Err(e) => {
+            return Err(match reader.error {
+                Some(e) => e.into(),
+                None => e.into(),
+            })
+        }
+    };
+    if !reader.hex.is_empty() {
+        return Err(encode::Error::ParseFailed("data not consumed entirely").into());
+    }
+    Ok(value)
+}
+
+/// Reads the bytes encoded by a hex string.
+struct HexReader<'a> {
+    hex: &'a [u8],
+    /// The error that made the last read fail, if it was invalid hex.
+    error: Option<hex::Error>,
+}
+
+pub(crate) fn hex_val(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+impl<'a> io::Read for HexReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = ::std::cmp::min(buf.len(), self.hex.len() / 2);
+        for (i, pair) in self.hex[..n * 2].chunks(2).enumerate() {
+            match (hex_val(pair[0]), hex_val(pair[1])) {
+                (Some(hi), Some(lo)) => buf[i] = hi << 4 | lo,
+                (hi, _) => {
+                    let c = if hi.is_none() {
+                        pair[0]
+                    } else {
+                        pair[1]
+                    };
+                    self.error = Some(hex::Error::InvalidChar(c));
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid hex"));
+                }
+            }
+        }
+        self.hex = &self.hex[n * 2..];
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::consensus::encode::serialize;
+    use dashcore::hashes::hex::{FromHex, ToHex};
+    use dashcore::{BlockHeader, Transaction};
+    use error::Error;
+    use serde_json;
+
+    const TX: &str = "0200000001586bd02815cf5faabfec986a4e50d25dbee089bd2758621e61c5fab06c334af0000000006b483045022100e85425f6d7c589972ee061413bcf08dc8c8e589ce37b217535a42af924f0e4d602205c9ba9cb14ef15513c9d946fa1c4b797883e748e8c32171bdf6166583946e35c012103dae30a4d7870cd87b45dd53e6012f71318fdd059c1c2623b8cc73f8af287bb2dfeffffff021dc4260c010000001976a914f602e88b2b5901d8aab15ebe4a97cf92ec6e03b388ac00e1f505000000001976a914687ffeffe8cf4e4c038da46a9b1d37db385a472d88acfd211500";
+
+    #[test]
+    fn test_decode_hex() {
+        let bytes = Vec::<u8>::from_hex(TX).unwrap();
+        let tx: Transaction = decode_hex(TX).unwrap();
+        assert_eq!(serialize(&tx), bytes);
+        let upper: Transaction = decode_hex(&TX.to_uppercase()).unwrap();
+        assert_eq!(upper, tx);
+
+        let raw: RawHex<Transaction> = serde_json::from_str(&format!("\"{}\"", TX)).unwrap();
+        assert_eq!(raw.into_inner(), tx);
+
+        match decode_hex::<Transaction>(&TX[1..]) {
+            Err(Error::Hex(hex::Error::OddLengthString(_))) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match decode_hex::<Transaction>(&TX.replace("586b", "58xb")) {
+            Err(Error::Hex(hex::Error::InvalidChar(b'x'))) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match decode_hex::<Transaction>(&format!("{}00", TX)) {
+            Err(Error::BitcoinSerialization(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(decode_hex::<Transaction>(&TX[..100]).is_err());
+        assert!(serde_json::from_str::<RawHex<Transaction>>("\"zz\"").is_err());
+
+        let bad = format!("\"{}\"", &TX[..100]);
+        let result: RawResult<Transaction> = serde_json::from_str(&bad).unwrap();
+        match result.decode("getrawtransaction", Some(&"ab")) {
+            Err(Error::Decode {
+                method,
+                id,
+                len,
+                error,
+            }) => {
+                assert_eq!(method, "getrawtransaction");
+                assert_eq!(id, Some("ab".to_owned()));
+                assert_eq!(len, 100);
+                match *error {
+                    Error::BitcoinSerialization(_) => {}
+                    e => panic!("unexpected error: {:?}", e),
+                }
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+        let result: RawResult<Transaction> = serde_json::from_str(&format!("\"{}\"", TX)).unwrap();
+        assert_eq!(result.decode("getrawtransaction", None).unwrap(), tx);
+        assert!(serde_json::from_str::<RawResult<Transaction>>("1").is_err());
+
+        let header: BlockHeader = decode_hex(&[0u8; 80].to_hex()).unwrap();
+        assert_eq!(header.nonce, 0);
+    }
+}
@@ -0,0 +1,431 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Broadcasting transactions again until they are mined.
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use jsonrpc;
+
+use dashcore::{Transaction, Txid};
+
+use client::{Result, RpcApi};
+use error::{
+    Error, RPC_INVALID_ADDRESS_OR_KEY, RPC_VERIFY_ALREADY_IN_CHAIN, RPC_VERIFY_ERROR,
+    RPC_VERIFY_REJECTED,
+};
+use json::ConfirmationStatus;
+
+/// When a [Rebroadcaster] submits a transaction again.
+///
+/// Implemented by closures taking the number of broadcasts so far.
+pub trait RebroadcastSchedule {
+    /// The delay until the next broadcast of a transaction that was
+    /// broadcast `attempts` times.
+    fn delay(&self, attempts: u32) -> Duration;
+}
+
+impl<F: Fn(u32) -> Duration> RebroadcastSchedule for F {
+    fn delay(&self, attempts: u32) -> Duration {
+        self(attempts)
+    }
+}
+
+/// Broadcast again after the same delay every time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FixedInterval(pub Duration);
+
+impl RebroadcastSchedule for FixedInterval {
+    fn delay(&self, _attempts: u32) -> Duration {
+        self.0
+    }
+}
+
+/// Broadcast again after a delay that doubles with every broadcast, starting
+/// at `initial` and capped at `max`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ExponentialBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl RebroadcastSchedule for ExponentialBackoff {
+    fn delay(&self, attempts: u32) -> Duration {
+        let doublings = cmp::min(attempts.saturating_sub(1), 31);
+        match self.initial.checked_mul(1 << doublings) {
+            Some(delay) => cmp::min(delay, self.max),
+            None => self.max,
+        }
+    }
+}
+
+/// What happened to a transaction in a [Rebroadcaster::poll].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RebroadcastEvent {
+    /// The transaction was submitted again.
+    Rebroadcast(Txid),
+    /// The node rejected the transaction for now, like when its mempool is
+    /// full, with the given reason. It is submitted again later.
+    Rejected(Txid, String),
+    /// The transaction was mined, it is no longer tracked. The status is
+    /// `None` if the node can't look up mined transactions because it has no
+    /// transaction index.
+    Confirmed(Txid, Option<ConfirmationStatus>),
+    /// The transaction conflicts with a mined or mempool transaction, so it
+    /// can never be mined and is no longer tracked.
+    ///
+    /// A transaction rejected for missing inputs only counts as conflicted
+    /// when the node knows the transaction of a missing input, so the input
+    /// was spent by another transaction. Otherwise the parent may just not
+    /// have reached the node yet, and the transaction is [Rejected] for now.
+    ///
+    /// [Rejected]: RebroadcastEvent::Rejected
+    Conflicted(Txid, String),
+}
+
+/// A transaction tracked by a [Rebroadcaster].
+#[derive(Clone, Debug)]
+struct Tracked {
+    tx: Transaction,
+    attempts: u32,
+    due: Instant,
+}
+
+/// Tracks transactions that were broadcast but are not mined yet, and
+/// submits them again with `sendrawtransaction` until they are mined or
+/// turn out to be conflicted.
+///
+/// Nodes forget transactions when they restart, evict them from a full
+/// mempool or never relayed them, so a service broadcasting payments should
+/// not rely on a single broadcast. Call [Rebroadcaster::poll] regularly, at
+/// the latest at [Rebroadcaster::next_due]; the delays between the
+/// broadcasts of a transaction are set by the [RebroadcastSchedule].
+#[derive(Debug)]
+pub struct Rebroadcaster<S> {
+    schedule: S,
+    txs: BTreeMap<Txid, Tracked>,
+}
+
+impl<S: RebroadcastSchedule> Rebroadcaster<S> {
+    pub fn new(schedule: S) -> Rebroadcaster<S> {
+        Rebroadcaster {
+            schedule,
+            txs: BTreeMap::new(),
+        }
+    }
+
+    /// Track a transaction that was just broadcast.
+    pub fn track(&mut self, tx: Transaction) {
+        let due = Instant::now() + self.schedule.delay(1);
+        self.txs.insert(
+            tx.txid(),
+            Tracked {
+                tx,
+                attempts: 1,
+                due,
+            },
+        );
+    }
+
+    /// Stop tracking a transaction, returning it if it was tracked.
+    pub fn forget(&mut self, txid: &Txid) -> Option<Transaction> {
+        self.txs.remove(txid).map(|t| t.tx)
+    }
+
+    /// The txids of the tracked transactions.
+    pub fn tracked(&self) -> Vec<Txid> {
+        self.txs.keys().cloned().collect()
+    }
+
+    /// When the next transaction is due, `None` if no transaction is
+    /// tracked.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.txs.values().map(|t| t.due).min()
+    }
+
+    /// Check the transactions that are due and submit again those that are
+    /// not mined yet.
+    pub fn poll<C: RpcApi>(&mut self, rpc: &C) -> Result<Vec<RebroadcastEvent>> {
+        let now = Instant::now();
+        let mut events = Vec::new();
+        let mut done = Vec::new();
+        for (txid, tracked) in self.txs.iter_mut() {
+            if tracked.due > now {
+                continue;
+            }
+            match rpc.get_tx_confirmation_status(txid) {
+                Ok(status) => match status {
+                    ConfirmationStatus::Confirmed {
+                        ..
+                    }
+                    | ConfirmationStatus::ChainLocked {
+                        ..
+                    } => {
+                        events.push(RebroadcastEvent::Confirmed(*txid, Some(status)));
+                        done.push(*txid);
+                        continue;
+                    }
+                    ConfirmationStatus::Unconfirmed | ConfirmationStatus::InstantLocked => {}
+                },
+                // Not in the mempool, or mined and there is no transaction
+                // index.
+                Err(Error::JsonRpc(jsonrpc::Error::Rpc(ref e)))
+                    if e.code == RPC_INVALID_ADDRESS_OR_KEY => {}
+                Err(e) => return Err(e),
+            }
+
+            let event = match rpc.send_raw_transaction(&tracked.tx, None) {
+                Ok(_) => RebroadcastEvent::Rebroadcast(*txid),
+                Err(Error::JsonRpc(jsonrpc::Error::Rpc(e))) => match e.code {
+                    RPC_VERIFY_ALREADY_IN_CHAIN => RebroadcastEvent::Confirmed(*txid, None),
+                    RPC_VERIFY_ERROR => {
+                        if spends_spent_output(rpc, &tracked.tx)? {
+                            RebroadcastEvent::Conflicted(*txid, e.message)
+                        } else {
+                            RebroadcastEvent::Rejected(*txid, e.message)
+                        }
+                    }
+                    RPC_VERIFY_REJECTED if e.message.contains("conflict") => {
+                        RebroadcastEvent::Conflicted(*txid, e.message)
+                    }
+                    _ => RebroadcastEvent::Rejected(*txid, e.message),
+                },
+                Err(e) => return Err(e),
+            };
+            match event {
+                RebroadcastEvent::Confirmed(..) | RebroadcastEvent::Conflicted(..) => {
+                    done.push(*txid)
+                }
+                _ => {
+                    tracked.attempts += 1;
+                    tracked.due = now + self.schedule.delay(tracked.attempts);
+                }
+            }
+            events.push(event);
+        }
+        for txid in done {
+            self.txs.remove(&txid);
+        }
+        Ok(events)
+    }
+}
+
+/// Whether an input of `tx` spends an output that was spent by another
+/// transaction, rather than an output of a transaction the node doesn't know.
+///
+/// Without a transaction index, a mined parent is only found while it has
+/// unspent outputs, so a conflict with a mined transaction may go unnoticed.
+fn spends_spent_output<C: RpcApi>(rpc: &C, tx: &Transaction) -> Result<bool> {
+    for input in &tx.input {
+        let outpoint = input.previous_output;
+        if rpc.get_tx_out(&outpoint.txid, outpoint.vout, Some(true))?.is_some() {
+            continue;
+        }
+        match rpc.get_raw_transaction(&outpoint.txid, None) {
+            Ok(_) => return Ok(true),
+            Err(Error::JsonRpc(jsonrpc::Error::Rpc(ref e)))
+                if e.code == RPC_INVALID_ADDRESS_OR_KEY => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::blockdata::constants::genesis_block;
+    use dashcore::consensus::encode::serialize;
+    use dashcore::hashes::hex::ToHex;
+    use dashcore::hashes::Hash;
+    use dashcore::{Network, OutPoint};
+    use raw::decode_hex;
+    use serde_json;
+    use std::cell::RefCell;
+    use std::result;
+    use testutil::MockRpc;
+
+    /// A node answering `getrawtransaction` and `sendrawtransaction` for
+    /// each transaction with the queued results, an RPC error code for
+    /// errors. All outputs are spent, and the node knows the parent of [tx]
+    /// if `parent_known`.
+    struct MockNode {
+        statuses: RefCell<Vec<(Txid, result::Result<u32, i32>)>>,
+        sends: RefCell<Vec<(Txid, result::Result<(), (i32, &'static str)>)>>,
+        parent_known: bool,
+    }
+
+    fn rpc_error(code: i32, message: &str) -> Error {
+        Error::JsonRpc(jsonrpc::Error::Rpc(jsonrpc::error::RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }))
+    }
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            match cmd {
+                "getrawtransaction" if args[0] == Txid::hash(&[]).to_string() => {
+                    if !self.parent_known {
+                        return Err(rpc_error(RPC_INVALID_ADDRESS_OR_KEY, "No such transaction"));
+                    }
+                    let parent = genesis_block(Network::Dash).txdata[0].clone();
+                    Ok(format!(r#""{}""#, serialize(&parent).to_hex()))
+                }
+                "gettxout" => Ok("null".to_owned()),
+                "getrawtransaction" => {
+                    let txid: Txid = serde_json::from_value(args[0].clone())?;
+                    let mut statuses = self.statuses.borrow_mut();
+                    let idx = statuses.iter().position(|s| s.0 == txid).unwrap();
+                    let confirmations = match statuses.remove(idx).1 {
+                        Ok(confirmations) => confirmations,
+                        Err(code) => return Err(rpc_error(code, "No such transaction")),
+                    };
+                    let json = format!(
+                        r#"{{"hex": "00", "txid": "{}", "hash": "{}", "size": 1, "vsize": 1,
+                             "version": 3, "locktime": 0, "vin": [], "vout": [],
+                             "confirmations": {}}}"#,
+                        txid, txid, confirmations
+                    );
+                    Ok(json)
+                }
+                "sendrawtransaction" => {
+                    let hex: String = serde_json::from_value(args[0].clone())?;
+                    let tx: Transaction = decode_hex(&hex)?;
+                    let mut sends = self.sends.borrow_mut();
+                    let idx = sends.iter().position(|s| s.0 == tx.txid()).unwrap();
+                    match sends.remove(idx).1 {
+                        Ok(()) => Ok(format!(r#""{}""#, tx.txid())),
+                        Err((code, message)) => Err(rpc_error(code, message)),
+                    }
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            }
+        }
+    }
+
+    /// Distinct transactions, spending the coinbase of the genesis block.
+    fn tx(vout: u32) -> Transaction {
+        let mut tx = genesis_block(Network::Dash).txdata[0].clone();
+        tx.input[0].previous_output = OutPoint::new(Txid::hash(&[]), vout);
+        tx
+    }
+
+    #[test]
+    fn test_schedules() {
+        let fixed = FixedInterval(Duration::from_secs(5));
+        assert_eq!(fixed.delay(1), Duration::from_secs(5));
+        assert_eq!(fixed.delay(10), Duration::from_secs(5));
+
+        let backoff = ExponentialBackoff {
+            initial: Duration::from_secs(10),
+            max: Duration::from_secs(60),
+        };
+        assert_eq!(backoff.delay(1), Duration::from_secs(10));
+        assert_eq!(backoff.delay(2), Duration::from_secs(20));
+        assert_eq!(backoff.delay(3), Duration::from_secs(40));
+        assert_eq!(backoff.delay(4), Duration::from_secs(60));
+        assert_eq!(backoff.delay(100), Duration::from_secs(60));
+
+        let closure = |attempts| Duration::from_secs(attempts as u64);
+        assert_eq!(closure.delay(3), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_poll() {
+        let (a, b, c, d) = (tx(0), tx(1), tx(2), tx(3));
+        let node = MockNode {
+            statuses: RefCell::new(vec![
+                (a.txid(), Ok(0)),
+                (b.txid(), Err(RPC_INVALID_ADDRESS_OR_KEY)),
+                (c.txid(), Ok(2)),
+                (d.txid(), Err(RPC_INVALID_ADDRESS_OR_KEY)),
+                (a.txid(), Err(RPC_INVALID_ADDRESS_OR_KEY)),
+            ]),
+            sends: RefCell::new(vec![
+                (a.txid(), Ok(())),
+                (b.txid(), Err((RPC_VERIFY_REJECTED, "mempool full"))),
+                (d.txid(), Err((RPC_VERIFY_REJECTED, "txn-mempool-conflict"))),
+                (a.txid(), Err((RPC_VERIFY_ALREADY_IN_CHAIN, "already in block chain"))),
+            ]),
+            parent_known: false,
+        };
+        let mut rebroadcaster = Rebroadcaster::new(FixedInterval(Duration::from_secs(0)));
+        assert_eq!(rebroadcaster.next_due(), None);
+        for tx in &[&a, &b, &c, &d] {
+            rebroadcaster.track((*tx).clone());
+        }
+        assert!(rebroadcaster.next_due().is_some());
+
+        let mut events = rebroadcaster.poll(&node).unwrap();
+        events.sort_by_key(|e| format!("{:?}", e));
+        let mut expected = vec![
+            RebroadcastEvent::Rebroadcast(a.txid()),
+            RebroadcastEvent::Rejected(b.txid(), "mempool full".into()),
+            RebroadcastEvent::Confirmed(
+                c.txid(),
+                Some(ConfirmationStatus::Confirmed {
+                    confirmations: 2,
+                    instant_locked: false,
+                }),
+            ),
+            RebroadcastEvent::Conflicted(d.txid(), "txn-mempool-conflict".into()),
+        ];
+        expected.sort_by_key(|e| format!("{:?}", e));
+        assert_eq!(events, expected);
+
+        let mut tracked = vec![a.txid(), b.txid()];
+        tracked.sort();
+        assert_eq!(rebroadcaster.tracked(), tracked);
+        assert_eq!(rebroadcaster.forget(&b.txid()), Some(b.clone()));
+
+        // Mined while the node has no transaction index.
+        let events = rebroadcaster.poll(&node).unwrap();
+        assert_eq!(events, vec![RebroadcastEvent::Confirmed(a.txid(), None)]);
+        assert!(rebroadcaster.tracked().is_empty());
+
+        // Transactions are not checked before they are due.
+        let mut rebroadcaster = Rebroadcaster::new(FixedInterval(Duration::from_secs(3600)));
+        rebroadcaster.track(a.clone());
+        assert!(rebroadcaster.poll(&node).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_poll_missing_inputs() {
+        let a = tx(0);
+        let mut node = MockNode {
+            statuses: RefCell::new(vec![
+                (a.txid(), Err(RPC_INVALID_ADDRESS_OR_KEY)),
+                (a.txid(), Err(RPC_INVALID_ADDRESS_OR_KEY)),
+            ]),
+            sends: RefCell::new(vec![
+                (a.txid(), Err((RPC_VERIFY_ERROR, "Missing inputs"))),
+                (a.txid(), Err((RPC_VERIFY_ERROR, "Missing inputs"))),
+            ]),
+            parent_known: false,
+        };
+        let mut rebroadcaster = Rebroadcaster::new(FixedInterval(Duration::from_secs(0)));
+        rebroadcaster.track(a.clone());
+
+        // The parent may not have reached the node yet.
+        let events = rebroadcaster.poll(&node).unwrap();
+        assert_eq!(events, vec![RebroadcastEvent::Rejected(a.txid(), "Missing inputs".into())]);
+        assert_eq!(rebroadcaster.tracked(), vec![a.txid()]);
+
+        // The parent is known, so its output was spent by another transaction.
+        node.parent_known = true;
+        let events = rebroadcaster.poll(&node).unwrap();
+        assert_eq!(events, vec![RebroadcastEvent::Conflicted(a.txid(), "Missing inputs".into())]);
+        assert!(rebroadcaster.tracked().is_empty());
+    }
+}
@@ -0,0 +1,320 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! A client for the REST interface that dashd serves with `-rest`.
+//!
+//! The REST interface needs no authentication and serves blocks, headers and
+//! UTXOs in their binary encoding, which is much faster than JSON-RPC for
+//! bulk downloads.
+
+use std::io::{self, BufReader, Read, Write};
+use std::time::Duration;
+
+use dashcore::consensus::encode::{self, Decodable, VarInt};
+use dashcore::{Block, BlockHash, BlockHeader, OutPoint, TxOut};
+use serde;
+use serde_json;
+
+use client::Result;
+use error::Error;
+use json;
+use transport::{self, TransportError, DEFAULT_MAX_RESPONSE_SIZE, DEFAULT_TIMEOUT};
+
+/// The maximum number of outpoints in a single `getutxos` request.
+pub const MAX_GETUTXOS_OUTPOINTS: usize = 15;
+
+/// An unspent output returned by [RestClient::get_utxos].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RestUtxo {
+    /// The height of the block containing the output, or 0x7FFFFFFF for
+    /// mempool outputs.
+    pub height: u32,
+    pub tx_out: TxOut,
+}
+
+/// The result of [RestClient::get_utxos].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RestUtxos {
+    /// The height of the chain tip the query was answered at.
+    pub chain_height: u32,
+    /// The hash of the chain tip the query was answered at.
+    pub chain_tip: BlockHash,
+    /// For every requested outpoint, the output if it is unspent.
+    pub utxos: Vec<Option<RestUtxo>>,
+}
+
+/// A client for dashd's REST interface.
+#[derive(Clone, Debug)]
+pub struct RestClient {
+    /// The `host:port` to connect to.
+    addr: String,
+    /// The path prefix of all requests, without a trailing slash.
+    base_path: String,
+    timeout: Duration,
+    max_response_size: u64,
+}
+
+impl RestClient {
+    /// Create a client for the REST interface of the node at `url`, like
+    /// "http://127.0.0.1:9998".
+    pub fn new(url: &str) -> Result<RestClient> {
+        let (addr, path) = transport::parse_url(url).map_err(Error::Rest)?;
+        Ok(RestClient {
+            addr,
+            base_path: path.trim_end_matches('/').to_owned(),
+            timeout: DEFAULT_TIMEOUT,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Set the timeout for connecting, sending and receiving.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum size of a response body in bytes.
+    pub fn with_max_response_size(mut self, max: u64) -> Self {
+        self.max_response_size = max;
+        self
+    }
+
+    /// Get a block.
+    pub fn block(&self, hash: &BlockHash) -> Result<Block> {
+        self.get_decoded(&format!("/rest/block/{}.bin", hash))
+    }
+
+    /// Get up to `count` headers, starting at the block with the given hash.
+    pub fn headers(&self, count: u32, hash: &BlockHash) -> Result<Vec<BlockHeader>> {
+        let bytes = self.get(&format!("/rest/headers/{}/{}.bin", count, hash))?;
+        if bytes.len() % 80 != 0 {
+            return Err(encode::Error::ParseFailed("headers not a multiple of 80 bytes").into());
+        }
+        let mut headers = Vec::with_capacity(bytes.len() / 80);
+        for chunk in bytes.chunks(80) {
+            headers.push(encode::deserialize(chunk)?);
+        }
+        Ok(headers)
+    }
+
+    /// Get information about the chain, like `getblockchaininfo`.
+    pub fn chain_info(&self) -> Result<json::GetBlockchainInfoResult> {
+        self.get_json("/rest/chaininfo.json")
+    }
+
+    /// Look up whether the given outpoints are unspent, optionally taking
+    /// the mempool into account. At most [MAX_GETUTXOS_OUTPOINTS] can be
+    /// queried at once, more fail with [Error::TooManyOutpoints].
+    pub fn get_utxos(&self, outpoints: &[OutPoint], check_mempool: bool) -> Result<RestUtxos> {
+        if outpoints.len() > MAX_GETUTXOS_OUTPOINTS {
+            return Err(Error::TooManyOutpoints {
+                count: outpoints.len(),
+                max: MAX_GETUTXOS_OUTPOINTS,
+            });
+        }
+        let mut path = "/rest/getutxos".to_owned();
+        if check_mempool {
+            path.push_str("/checkmempool");
+        }
+        for outpoint in outpoints {
+            path.push_str(&format!("/{}-{}", outpoint.txid, outpoint.vout));
+        }
+        path.push_str(".bin");
+
+        let bytes = self.get(&path)?;
+        let mut cursor = io::Cursor::new(&bytes);
+        let chain_height: u32 = Decodable::consensus_decode(&mut cursor)?;
+        let chain_tip: BlockHash = Decodable::consensus_decode(&mut cursor)?;
+        let bitmap: Vec<u8> = Decodable::consensus_decode(&mut cursor)?;
+        let count: VarInt = Decodable::consensus_decode(&mut cursor)?;
+
+        let mut unspent = Vec::with_capacity(count.0 as usize);
+        for _ in 0..count.0 {
+            // The transaction version is unused and always 0.
+            let _version: u32 = Decodable::consensus_decode(&mut cursor)?;
+            unspent.push(RestUtxo {
+                height: Decodable::consensus_decode(&mut cursor)?,
+                tx_out: Decodable::consensus_decode(&mut cursor)?,
+            });
+        }
+        if cursor.position() != bytes.len() as u64 {
+            return Err(encode::Error::ParseFailed("data not consumed entirely").into());
+        }
+
+        let mut unspent = unspent.into_iter();
+        let utxos = (0..outpoints.len())
+            .map(|i| match bitmap.get(i / 8) {
+                Some(byte) if byte & (1 << (i % 8)) != 0 => unspent.next(),
+                _ => None,
+            })
+            .collect();
+        Ok(RestUtxos {
+            chain_height,
+            chain_tip,
+            utxos,
+        })
+    }
+
+    fn get_decoded<T: Decodable>(&self, path: &str) -> Result<T> {
+        Ok(encode::deserialize(&self.get(path)?)?)
+    }
+
+    fn get_json<T: for<'a> serde::de::Deserialize<'a>>(&self, path: &str) -> Result<T> {
+        Ok(serde_json::from_slice(&self.get(path)?)?)
+    }
+
+    /// Make a GET request and return the response body.
+    fn get(&self, path: &str) -> Result<Vec<u8>> {
+        self.try_get(path).map_err(Error::Rest)
+    }
+
+    fn try_get(&self, path: &str) -> ::std::result::Result<Vec<u8>, TransportError> {
+        let stream = transport::connect(&self.addr, self.timeout)?;
+        write!(
+            &stream,
+            "GET {}{} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            self.base_path, path, self.addr
+        )?;
+
+        let mut reader = BufReader::new(&stream);
+        let head = transport::read_response_head(&mut reader)?;
+        let mut body = transport::body_reader(&mut reader, &head, self.max_response_size)?;
+        let mut bytes = vec![];
+        if let Err(e) = body.read_to_end(&mut bytes) {
+            return Err(if body.exceeded() {
+                TransportError::ResponseTooLarge {
+                    length: None,
+                    max: self.max_response_size,
+                }
+            } else {
+                e.into()
+            });
+        }
+        if head.status != 200 {
+            return Err(TransportError::HttpStatus(head.status));
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::consensus::encode::serialize;
+    use dashcore::hashes::Hash;
+    use dashcore::{Script, Txid};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Answer a single request with the given body and return the request.
+    fn serve(body: Vec<u8>) -> (RestClient, thread::JoinHandle<String>) {
+        let mut response =
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes();
+        response.extend(body);
+        serve_response(response)
+    }
+
+    /// Answer a single request with the given raw response and return the
+    /// request.
+    fn serve_response(response: Vec<u8>) -> (RestClient, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = RestClient::new(&listener.local_addr().unwrap().to_string()).unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).unwrap();
+            stream.write_all(&response).unwrap();
+            String::from_utf8(buf[..n].to_vec()).unwrap()
+        });
+        (client, handle)
+    }
+
+    #[test]
+    fn test_get_utxos() {
+        let tip = BlockHash::hash(&[1]);
+        let outs = [
+            TxOut {
+                value: 1000,
+                script_pubkey: Script::new(),
+            },
+            TxOut {
+                value: 2000,
+                script_pubkey: Script::new(),
+            },
+        ];
+        let mut body = serialize(&100u32);
+        body.extend(serialize(&tip));
+        body.extend(serialize(&vec![0b101u8]));
+        body.extend(serialize(&VarInt(2)));
+        for (height, out) in [90u32, 0x7FFFFFFF].iter().zip(outs.iter()) {
+            body.extend(serialize(&0u32));
+            body.extend(serialize(height));
+            body.extend(serialize(out));
+        }
+
+        let (client, server) = serve(body);
+        let txid = Txid::hash(&[2]);
+        let outpoints: Vec<_> = (0..3).map(|vout| OutPoint::new(txid, vout)).collect();
+        let utxos = client.get_utxos(&outpoints, true).unwrap();
+        assert_eq!(utxos.chain_height, 100);
+        assert_eq!(utxos.chain_tip, tip);
+        assert_eq!(
+            utxos.utxos,
+            vec![
+                Some(RestUtxo {
+                    height: 90,
+                    tx_out: outs[0].clone(),
+                }),
+                None,
+                Some(RestUtxo {
+                    height: 0x7FFFFFFF,
+                    tx_out: outs[1].clone(),
+                }),
+            ]
+        );
+
+        let request = server.join().unwrap();
+        let path = format!("/rest/getutxos/checkmempool/{}-0/{}-1/{}-2.bin", txid, txid, txid);
+        assert!(request.starts_with(&format!("GET {} HTTP/1.1\r\n", path)));
+
+        // Too many outpoints are rejected before any request.
+        let outpoints = vec![OutPoint::new(txid, 0); MAX_GETUTXOS_OUTPOINTS + 1];
+        match client.get_utxos(&outpoints, false) {
+            Err(Error::TooManyOutpoints {
+                count: 16,
+                max: 15,
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_headers() {
+        let (client, server) = serve(vec![0; 160]);
+        let headers = client.headers(2, &BlockHash::hash(&[1])).unwrap();
+        assert_eq!(headers.len(), 2);
+        assert!(server.join().unwrap().starts_with("GET /rest/headers/2/"));
+
+        let (client, server) = serve(vec![0; 100]);
+        assert!(client.headers(2, &BlockHash::hash(&[1])).is_err());
+        server.join().unwrap();
+
+        // Proxies may send the body in chunks.
+        let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        response.extend(&b"50\r\n"[..]);
+        response.extend(vec![0; 80]);
+        response.extend(&b"\r\n50\r\n"[..]);
+        response.extend(vec![0; 80]);
+        response.extend(&b"\r\n0\r\n\r\n"[..]);
+        let (client, server) = serve_response(response);
+        assert_eq!(client.headers(2, &BlockHash::hash(&[1])).unwrap().len(), 2);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,186 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Signing with external signers, like hardware wallets.
+
+use dashcore::util::bip32::{DerivationPath, Fingerprint};
+use dashcore::{Address, Transaction, TxOut};
+
+use client::{Result, RpcApi};
+use error::Error;
+use prevouts::TxWithPrevouts;
+
+/// An input for a [Signer] to sign, with what it needs to find the key.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SigningInput {
+    /// The index of the input in the transaction.
+    pub index: usize,
+    /// The output spent by the input.
+    pub prevout: TxOut,
+    /// The derivation path of the key of the output, `None` if the wallet of
+    /// the node doesn't know it.
+    pub path: Option<DerivationPath>,
+    /// The fingerprint of the master key of `path`, if the node knows it.
+    pub master_fingerprint: Option<Fingerprint>,
+}
+
+/// A signer holding the keys of a wallet, like a hardware wallet, so that
+/// transactions are signed without the wallet RPCs.
+///
+/// The wallet of the node only needs to watch the addresses of the signer,
+/// imported with their derivation paths, like with
+/// [RpcApi::import_descriptors], to fund the transactions of
+/// [RpcApi::create_and_sign_transaction_with_signer].
+pub trait Signer {
+    /// Sign the `inputs` of `tx` and return the signed transaction.
+    fn sign_transaction(&self, tx: &Transaction, inputs: &[SigningInput]) -> Result<Transaction>;
+
+    /// Sign the `inputs` of a base64 encoded PSBT, like one of
+    /// [RpcApi::wallet_create_funded_psbt], and return the PSBT with the
+    /// signatures, to be finalized with [RpcApi::finalize_psbt].
+    fn sign_psbt(&self, psbt: &str, inputs: &[SigningInput]) -> Result<String>;
+}
+
+/// See [RpcApi::get_signing_inputs].
+pub(crate) fn get_signing_inputs<C: RpcApi>(
+    rpc: &C,
+    tx: &Transaction,
+) -> Result<Vec<SigningInput>> {
+    let resolved = TxWithPrevouts::resolve(rpc, tx.clone())?;
+    let network = rpc.network()?;
+    let mut inputs = Vec::with_capacity(tx.input.len());
+    for (index, prevout) in resolved.prevouts.into_iter().enumerate() {
+        let prevout = prevout.ok_or(Error::MissingPrevout(tx.input[index].previous_output))?;
+        let (path, master_fingerprint) = match Address::from_script(&prevout.script_pubkey, network)
+        {
+            Some(address) => {
+                let info = rpc.get_address_info(&address)?;
+                (info.hd_key_path, info.hd_master_fingerprint)
+            }
+            None => (None, None),
+        };
+        inputs.push(SigningInput {
+            index,
+            prevout,
+            path,
+            master_fingerprint,
+        });
+    }
+    Ok(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::consensus::encode::serialize_hex;
+    use dashcore::{OutPoint, Script, TxIn, Witness};
+    use raw::decode_hex;
+    use serde_json;
+    use std::cell::RefCell;
+    use std::str::FromStr;
+    use testutil::MockRpc;
+    use workflow::CreateTransactionOptions;
+
+    const TX: &str = "0200000001586bd02815cf5faabfec986a4e50d25dbee089bd2758621e61c5fab06c334af0000000006b483045022100e85425f6d7c589972ee061413bcf08dc8c8e589ce37b217535a42af924f0e4d602205c9ba9cb14ef15513c9d946fa1c4b797883e748e8c32171bdf6166583946e35c012103dae30a4d7870cd87b45dd53e6012f71318fdd059c1c2623b8cc73f8af287bb2dfeffffff021dc4260c010000001976a914f602e88b2b5901d8aab15ebe4a97cf92ec6e03b388ac00e1f505000000001976a914687ffeffe8cf4e4c038da46a9b1d37db385a472d88acfd211500";
+
+    /// A watch-only wallet that funds transactions with the second output of
+    /// `TX`, whose key it knows the derivation path of.
+    struct WatchOnlyWallet {
+        funding: RefCell<Vec<serde_json::Value>>,
+    }
+
+    fn funded() -> Transaction {
+        let parent: Transaction = decode_hex(TX).unwrap();
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(parent.txid(), 1),
+                sequence: 0xFFFFFFFF,
+                script_sig: Script::new(),
+                witness: Witness::new(),
+            }],
+            output: vec![],
+        }
+    }
+
+    impl MockRpc for WatchOnlyWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "createrawtransaction" => r#""02000000000000000000""#.to_owned(),
+                "fundrawtransaction" => {
+                    self.funding.borrow_mut().push(args[1].clone());
+                    format!(
+                        r#"{{"hex": "{}", "fee": 0.0001, "changepos": -1}}"#,
+                        serialize_hex(&funded())
+                    )
+                }
+                "getrawtransaction" => format!(r#""{}""#, TX),
+                "getblockchaininfo" => r#"{"chain": "main"}"#.to_owned(),
+                "getaddressinfo" => format!(
+                    r#"{{"address": {}, "scriptPubKey": "{:x}", "iswatchonly": true,
+                         "hdkeypath": "m/44'/5'/0'/0/5", "hdmasterfingerprint": "d34db33f",
+                         "labels": []}}"#,
+                    args[0],
+                    decode_hex::<Transaction>(TX)?.output[1].script_pubkey
+                ),
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    /// A signer that records the inputs it is asked to sign.
+    struct MockSigner {
+        signed: RefCell<Vec<SigningInput>>,
+    }
+
+    impl Signer for MockSigner {
+        fn sign_transaction(
+            &self,
+            tx: &Transaction,
+            inputs: &[SigningInput],
+        ) -> Result<Transaction> {
+            self.signed.borrow_mut().extend_from_slice(inputs);
+            let mut signed = tx.clone();
+            for input in &mut signed.input {
+                input.script_sig = Script::from(vec![0x51]);
+            }
+            Ok(signed)
+        }
+
+        fn sign_psbt(&self, _psbt: &str, _inputs: &[SigningInput]) -> Result<String> {
+            Err(Error::Signer("PSBTs are not supported".to_owned()))
+        }
+    }
+
+    #[test]
+    fn test_create_and_sign_transaction_with_signer() {
+        let wallet = WatchOnlyWallet {
+            funding: RefCell::new(vec![]),
+        };
+        let signer = MockSigner {
+            signed: RefCell::new(vec![]),
+        };
+        let options = CreateTransactionOptions::new();
+        let tx = wallet.create_and_sign_transaction_with_signer(&[], &options, &signer).unwrap();
+        assert_eq!(tx.input[0].previous_output, funded().input[0].previous_output);
+        assert_eq!(tx.input[0].script_sig, Script::from(vec![0x51]));
+        assert_eq!(wallet.funding.borrow()[0]["includeWatching"], true);
+
+        let signed = signer.signed.borrow();
+        assert_eq!(signed.len(), 1);
+        assert_eq!(signed[0].index, 0);
+        assert_eq!(signed[0].prevout, decode_hex::<Transaction>(TX).unwrap().output[1]);
+        assert_eq!(signed[0].path, Some(DerivationPath::from_str("m/44'/5'/0'/0/5").unwrap()));
+        let fingerprint = Fingerprint::from_str("d34db33f").unwrap();
+        assert_eq!(signed[0].master_fingerprint, Some(fingerprint));
+    }
+}
@@ -0,0 +1,478 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Running regtest nodes and clusters of them for tests, enabled with the
+//! `testkit` feature.
+
+use std::cmp;
+use std::env;
+use std::fs;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use dashcore::{Address, BlockHash};
+
+use client::{Auth, Client, Result, RpcApi};
+use error::Error;
+
+/// The environment variable with the path of the `dashd` executable to run,
+/// `dashd` of the `PATH` if it is not set.
+pub const DASHD_EXE_ENV: &str = "DASHD_EXE";
+
+/// How long [DashD] waits for a node to answer calls by default.
+pub const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long [DashD] waits for a node to exit after `stop` before killing it.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long [Cluster] waits for nodes to connect, disconnect or agree on
+/// the chain by default.
+pub const DEFAULT_SYNC_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often [DashD] and [Cluster] check whether nodes are ready.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Numbers the data directories of the nodes of this process.
+static NEXT_DATADIR: AtomicUsize = AtomicUsize::new(0);
+
+/// The options of the nodes started by [DashD].
+#[derive(Clone, Debug)]
+pub struct DashDConf {
+    exe: PathBuf,
+    args: Vec<String>,
+    wallet: Option<String>,
+    startup_timeout: Duration,
+    keep_datadir: bool,
+}
+
+impl Default for DashDConf {
+    fn default() -> DashDConf {
+        let exe = env::var_os(DASHD_EXE_ENV).unwrap_or_else(|| "dashd".into());
+        DashDConf {
+            exe: exe.into(),
+            args: Vec::new(),
+            wallet: None,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
+            keep_datadir: false,
+        }
+    }
+}
+
+impl DashDConf {
+    pub fn new() -> DashDConf {
+        DashDConf::default()
+    }
+
+    /// Run the `dashd` executable at `exe`, the one given by [DASHD_EXE_ENV]
+    /// by default.
+    pub fn with_exe<P: Into<PathBuf>>(mut self, exe: P) -> Self {
+        self.exe = exe.into();
+        self
+    }
+
+    /// Pass `arg`, like `-txindex=1`, to the node.
+    pub fn with_arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_owned());
+        self
+    }
+
+    /// Create a wallet named `wallet` once the node is started, and send
+    /// the calls of [DashD::client] to it.
+    pub fn with_wallet(mut self, wallet: &str) -> Self {
+        self.wallet = Some(wallet.to_owned());
+        self
+    }
+
+    /// Fail with [Error::Timeout] if the node doesn't answer calls within
+    /// `timeout`, [DEFAULT_STARTUP_TIMEOUT] by default.
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// Keep the data directory of the node when it is dropped, to look into
+    /// its logs.
+    pub fn with_keep_datadir(mut self, keep: bool) -> Self {
+        self.keep_datadir = keep;
+        self
+    }
+
+    /// The arguments of a node with the given data directory and ports.
+    fn node_args(&self, datadir: &Path, rpc_port: u16, p2p_port: u16) -> Vec<String> {
+        let mut args = vec![
+            "-regtest".to_owned(),
+            format!("-datadir={}", datadir.display()),
+            format!("-port={}", p2p_port),
+            format!("-rpcport={}", rpc_port),
+            "-rpcbind=127.0.0.1".to_owned(),
+            "-rpcallowip=127.0.0.1".to_owned(),
+            "-server=1".to_owned(),
+            "-listen=1".to_owned(),
+            "-printtoconsole=0".to_owned(),
+        ];
+        args.extend(self.args.iter().cloned());
+        args
+    }
+}
+
+/// A regtest `dashd` with a temporary data directory, stopped and removed
+/// when dropped.
+///
+/// The node listens on free ports of localhost, [DashD::client] is
+/// connected to it with the cookie file.
+#[derive(Debug)]
+pub struct DashD {
+    process: Child,
+    client: Client,
+    datadir: PathBuf,
+    rpc_port: u16,
+    p2p_port: u16,
+    keep_datadir: bool,
+}
+
+impl DashD {
+    /// Start a node with the default options.
+    pub fn new() -> Result<DashD> {
+        DashD::with_conf(&DashDConf::default())
+    }
+
+    /// Start a node and wait until it answers calls.
+    pub fn with_conf(conf: &DashDConf) -> Result<DashD> {
+        let id = NEXT_DATADIR.fetch_add(1, Ordering::SeqCst);
+        let datadir = env::temp_dir().join(format!("dashcore-rpc-{}-{}", process::id(), id));
+        if datadir.exists() {
+            fs::remove_dir_all(&datadir)?;
+        }
+        fs::create_dir_all(&datadir)?;
+        let rpc_port = free_port()?;
+        let p2p_port = free_port()?;
+
+        let rpc_url = format!("http://127.0.0.1:{}", rpc_port);
+        let cookie_file = datadir.join("regtest").join(".cookie");
+
+        let started = Command::new(&conf.exe)
+            .args(conf.node_args(&datadir, rpc_port, p2p_port))
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(Error::from)
+            .and_then(|mut process| {
+                match wait_for_rpc(&mut process, &rpc_url, &cookie_file, conf.startup_timeout) {
+                    Ok(client) => Ok((process, client)),
+                    Err(e) => {
+                        let _ = process.kill();
+                        let _ = process.wait();
+                        Err(e)
+                    }
+                }
+            });
+        let (process, client) = match started {
+            Ok(started) => started,
+            Err(e) => {
+                if !conf.keep_datadir {
+                    let _ = fs::remove_dir_all(&datadir);
+                }
+                return Err(e);
+            }
+        };
+        let mut node = DashD {
+            process,
+            client,
+            datadir,
+            rpc_port,
+            p2p_port,
+            keep_datadir: conf.keep_datadir,
+        };
+        if let Some(ref wallet) = conf.wallet {
+            node.client.create_wallet(wallet, None, None, None, None)?;
+            let url = format!("{}/wallet/{}", rpc_url, wallet);
+            node.client = Client::new(&url, Auth::CookieFile(cookie_file))?;
+        }
+        Ok(node)
+    }
+
+    /// A client of the node, of its wallet if one was created with
+    /// [DashDConf::with_wallet].
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The URL of the RPC server of the node.
+    pub fn rpc_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.rpc_port)
+    }
+
+    /// The P2P address of the node, to connect other nodes to it.
+    pub fn p2p_address(&self) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], self.p2p_port))
+    }
+
+    pub fn datadir(&self) -> &Path {
+        &self.datadir
+    }
+
+    /// The cookie file with the RPC credentials of the node.
+    pub fn cookie_file(&self) -> PathBuf {
+        self.datadir.join("regtest").join(".cookie")
+    }
+
+    /// Stop the node with `stop`, or kill it if it doesn't exit in time.
+    fn shutdown(&mut self) -> Result<()> {
+        if self.client.stop().is_ok() {
+            let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+            while Instant::now() < deadline {
+                if self.process.try_wait()?.is_some() {
+                    return Ok(());
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+        self.process.kill()?;
+        self.process.wait()?;
+        Ok(())
+    }
+}
+
+impl Drop for DashD {
+    fn drop(&mut self) {
+        if let Err(e) = self.shutdown() {
+            warn!("could not stop dashd at {}: {}", self.datadir.display(), e);
+        }
+        if !self.keep_datadir {
+            let _ = fs::remove_dir_all(&self.datadir);
+        }
+    }
+}
+
+/// Wait until a starting node wrote its cookie file and answers calls.
+fn wait_for_rpc(
+    process: &mut Child,
+    rpc_url: &str,
+    cookie_file: &Path,
+    timeout: Duration,
+) -> Result<Client> {
+    let deadline = Instant::now() + timeout;
+    while !cookie_file.exists() {
+        if let Some(status) = process.try_wait()? {
+            let msg = format!("dashd exited during startup with {}", status);
+            return Err(io::Error::new(io::ErrorKind::Other, msg).into());
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::Timeout(timeout));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let client = Client::new(rpc_url, Auth::CookieFile(cookie_file.to_owned()))?;
+    let now = Instant::now();
+    let left = if deadline > now {
+        deadline - now
+    } else {
+        Duration::from_secs(0)
+    };
+    match client.wait_until_warmed_up(left) {
+        Ok(()) => Ok(client),
+        Err(Error::Timeout(_)) => Err(Error::Timeout(timeout)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Regtest nodes connected to each other, to test how they agree on the
+/// chain, stopped when dropped.
+///
+/// Every node connects to all the nodes started before it. The nodes can be
+/// [split](Cluster::split) into two groups that don't relay to each other,
+/// like to mine competing chains, and [joined](Cluster::join) again to let
+/// the shorter chain reorganize.
+#[derive(Debug)]
+pub struct Cluster {
+    nodes: Vec<DashD>,
+    split_at: Option<usize>,
+    sync_timeout: Duration,
+}
+
+impl Cluster {
+    /// Start `n` nodes with the options of `conf` and connect them.
+    pub fn new(n: usize, conf: &DashDConf) -> Result<Cluster> {
+        let mut nodes = Vec::with_capacity(n);
+        for _ in 0..n {
+            nodes.push(DashD::with_conf(conf)?);
+        }
+        let cluster = Cluster {
+            nodes,
+            split_at: None,
+            sync_timeout: DEFAULT_SYNC_TIMEOUT,
+        };
+        for i in 0..n {
+            for j in 0..i {
+                cluster.connect(i, j)?;
+            }
+        }
+        Ok(cluster)
+    }
+
+    /// Fail with [Error::Timeout] if the nodes don't connect, disconnect or
+    /// sync within `timeout`, [DEFAULT_SYNC_TIMEOUT] by default.
+    pub fn with_sync_timeout(mut self, timeout: Duration) -> Self {
+        self.sync_timeout = timeout;
+        self
+    }
+
+    pub fn nodes(&self) -> &[DashD] {
+        &self.nodes
+    }
+
+    pub fn node(&self, i: usize) -> &DashD {
+        &self.nodes[i]
+    }
+
+    /// Split the nodes into `nodes()[..at]` and `nodes()[at..]`, which no
+    /// longer relay blocks and transactions to each other. A cluster that is
+    /// already split is joined first.
+    pub fn split(&mut self, at: usize) -> Result<()> {
+        self.join()?;
+        for i in at..self.nodes.len() {
+            for j in 0..cmp::min(at, i) {
+                self.disconnect(i, j)?;
+            }
+        }
+        self.split_at = Some(at);
+        Ok(())
+    }
+
+    /// Connect the two groups of a split again.
+    pub fn join(&mut self) -> Result<()> {
+        if let Some(at) = self.split_at.take() {
+            for i in at..self.nodes.len() {
+                for j in 0..cmp::min(at, i) {
+                    self.connect(i, j)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Mine `block_num` blocks to `address` on node `i`, and wait until the
+    /// nodes connected to it have them.
+    pub fn generate(&self, i: usize, block_num: u64, address: &Address) -> Result<Vec<BlockHash>> {
+        let blocks = self.nodes[i].client.generate_to_address(block_num, address)?;
+        self.sync_blocks()?;
+        Ok(blocks)
+    }
+
+    /// Wait until the nodes connected to each other have the same tip.
+    pub fn sync_blocks(&self) -> Result<()> {
+        let groups = match self.split_at {
+            Some(at) => vec![0..at, at..self.nodes.len()],
+            None => vec![0..self.nodes.len()],
+        };
+        wait_until(self.sync_timeout, || {
+            for group in groups.iter().cloned() {
+                if !self.same_tip(group)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })
+    }
+
+    /// Whether the nodes of `group` have the same tip.
+    fn same_tip(&self, group: Range<usize>) -> Result<bool> {
+        let mut tips = Vec::new();
+        for node in &self.nodes[group] {
+            tips.push(node.client.get_best_block_hash()?);
+        }
+        Ok(tips.windows(2).all(|w| w[0] == w[1]))
+    }
+
+    /// Connect node `from` to node `to` and wait for the connection.
+    fn connect(&self, from: usize, to: usize) -> Result<()> {
+        let addr = self.nodes[to].p2p_address().to_string();
+        self.nodes[from].client.onetry_node(&addr)?;
+        wait_until(self.sync_timeout, || Ok(self.peer_id(from, to)?.is_some()))
+    }
+
+    /// Close the connection of node `from` to node `to`.
+    fn disconnect(&self, from: usize, to: usize) -> Result<()> {
+        if let Some(id) = self.peer_id(from, to)? {
+            self.nodes[from].client.disconnect_node_by_id(id as u32)?;
+        }
+        wait_until(self.sync_timeout, || Ok(self.peer_id(from, to)?.is_none()))
+    }
+
+    /// The id of the peer of node `from` that is its connection to node
+    /// `to`, if connected.
+    fn peer_id(&self, from: usize, to: usize) -> Result<Option<u64>> {
+        let addr = self.nodes[to].p2p_address().to_string();
+        let peers = self.nodes[from].client.get_peer_info()?;
+        Ok(peers.into_iter().find(|p| p.addr == addr).map(|p| p.id))
+    }
+}
+
+/// Poll `done` until it returns `true`, or fail with [Error::Timeout].
+fn wait_until<F: FnMut() -> Result<bool>>(timeout: Duration, mut done: F) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    while !done()? {
+        if Instant::now() >= deadline {
+            return Err(Error::Timeout(timeout));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+/// A port of localhost that is free for now.
+fn free_port() -> io::Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_args() {
+        let conf = DashDConf::new().with_arg("-txindex=1");
+        let args = conf.node_args(Path::new("/tmp/node"), 18443, 18444);
+        assert_eq!(args[0], "-regtest");
+        assert!(args.contains(&"-datadir=/tmp/node".to_owned()));
+        assert!(args.contains(&"-rpcport=18443".to_owned()));
+        assert!(args.contains(&"-port=18444".to_owned()));
+        assert_eq!(args.last().unwrap(), "-txindex=1");
+    }
+
+    #[test]
+    fn test_wait_until() {
+        let mut polls = 0;
+        wait_until(Duration::from_secs(10), || {
+            polls += 1;
+            Ok(polls == 3)
+        })
+        .unwrap();
+        assert_eq!(polls, 3);
+        match wait_until(Duration::from_millis(1), || Ok(false)) {
+            Err(Error::Timeout(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_missing_exe() {
+        let conf = DashDConf::new().with_exe("/nonexistent/dashd");
+        match DashD::with_conf(&conf) {
+            Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::NotFound => {}
+            r => panic!("unexpected result: {:?}", r.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Fixtures shared by the unit tests.
+
+use std::str::FromStr;
+
+use dashcore::{Address, Network, PublicKey};
+use serde;
+use serde_json;
+
+use client::{Result, RpcApi};
+
+/// A compressed public key, the generator point of secp256k1.
+pub const PUBKEY: &'static str =
+    "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+/// The mainnet P2PKH address of [PUBKEY].
+pub fn address() -> Address {
+    Address::p2pkh(&PublicKey::from_str(PUBKEY).unwrap(), Network::Dash)
+}
+
+/// A mock node that answers each call with a JSON string.
+///
+/// Every type implementing it is an [RpcApi] that deserializes the answer
+/// into the result of the call.
+pub trait MockRpc {
+    fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String>;
+}
+
+impl<M: MockRpc> RpcApi for M {
+    fn call<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        cmd: &str,
+        args: &[serde_json::Value],
+    ) -> Result<T> {
+        Ok(serde_json::from_str(&self.respond(cmd, args)?)?)
+    }
+}
@@ -0,0 +1,176 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Reading unspent outputs at a single chain tip.
+
+use std::cmp;
+
+use serde_json;
+
+use dashcore::{BlockHash, OutPoint};
+
+use client::{Result, RpcApi};
+use error::Error;
+use json;
+
+/// The number of attempts of [RpcApi::get_tx_outs_at_tip] when none is given.
+pub const DEFAULT_TIP_READ_ATTEMPTS: usize = 3;
+
+/// An output read at a chain tip, see [RpcApi::get_tx_out_at_tip].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TxOutAtTip {
+    /// The tip the output was read at.
+    pub tip: BlockHash,
+    /// The output, `None` if it is spent or does not exist.
+    pub output: Option<json::GetTxOutResult>,
+}
+
+/// Outputs read at the same chain tip, see [RpcApi::get_tx_outs_at_tip].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TxOutsAtTip {
+    /// The tip the outputs were read at.
+    pub tip: BlockHash,
+    /// The outputs in the order of the requested outpoints, `None` for those
+    /// that are spent or do not exist.
+    pub outputs: Vec<Option<json::GetTxOutResult>>,
+}
+
+/// Read the outputs in one batch of `gettxout` calls, `None` if the tip
+/// changed during the read.
+///
+/// The tip is read before and after the batch, and every unspent output must
+/// have been evaluated at it too.
+pub(crate) fn read_at_tip<C: RpcApi>(
+    rpc: &C,
+    outpoints: &[OutPoint],
+    include_mempool: Option<bool>,
+) -> Result<Option<TxOutsAtTip>> {
+    let tip = rpc.get_best_block_hash()?;
+    let mut calls = Vec::with_capacity(outpoints.len());
+    for outpoint in outpoints {
+        let mut args = vec![serde_json::to_value(outpoint.txid)?, outpoint.vout.into()];
+        if let Some(include_mempool) = include_mempool {
+            args.push(include_mempool.into());
+        }
+        calls.push(("gettxout", args));
+    }
+    let mut outputs = Vec::with_capacity(outpoints.len());
+    for result in rpc.call_batch::<Option<json::GetTxOutResult>>(&calls)? {
+        let output = result?;
+        if output.as_ref().map_or(false, |o| o.bestblock != tip) {
+            return Ok(None);
+        }
+        outputs.push(output);
+    }
+    if rpc.get_best_block_hash()? != tip {
+        return Ok(None);
+    }
+    Ok(Some(TxOutsAtTip {
+        tip,
+        outputs,
+    }))
+}
+
+/// See [RpcApi::get_tx_outs_at_tip], zero `attempts` read once.
+pub(crate) fn read_at_tip_with_retries<C: RpcApi>(
+    rpc: &C,
+    outpoints: &[OutPoint],
+    include_mempool: Option<bool>,
+    attempts: usize,
+) -> Result<TxOutsAtTip> {
+    let attempts = cmp::max(attempts, 1);
+    for _ in 0..attempts {
+        if let Some(read) = read_at_tip(rpc, outpoints, include_mempool)? {
+            return Ok(read);
+        }
+    }
+    Err(Error::TipChanged {
+        attempts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use dashcore::Txid;
+    use std::cell::Cell;
+    use testutil::MockRpc;
+
+    /// A node that mines a block after each of the first `blocks` batches.
+    struct MiningNode {
+        height: Cell<u8>,
+        blocks: Cell<u8>,
+    }
+
+    impl MockRpc for MiningNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let tip = BlockHash::hash(&[self.height.get()]);
+            let value = match cmd {
+                "getbestblockhash" => serde_json::to_value(tip)?,
+                "gettxout" => {
+                    if self.blocks.get() > 0 {
+                        self.blocks.set(self.blocks.get() - 1);
+                        self.height.set(self.height.get() + 1);
+                    }
+                    if args[1] == 1 {
+                        return Ok("null".to_owned());
+                    }
+                    serde_json::from_str(&format!(
+                        r#"{{"bestblock": "{}", "confirmations": 1, "value": 1.0,
+                             "scriptPubKey": {{"asm": "", "hex": "51", "type": "nonstandard"}},
+                             "coinbase": false}}"#,
+                        tip
+                    ))?
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_read_at_tip() {
+        let txid = Txid::hash(&[]);
+        let outpoints = [OutPoint::new(txid, 0), OutPoint::new(txid, 1)];
+        let node = MiningNode {
+            height: Cell::new(0),
+            blocks: Cell::new(2),
+        };
+        match node.get_tx_out_at_tip(&txid, 1, None) {
+            Err(Error::TipChanged {
+                attempts: 1,
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        let read = node.get_tx_outs_at_tip(&outpoints, None, None).unwrap();
+        assert_eq!(read.tip, BlockHash::hash(&[2]));
+        assert_eq!(read.outputs[0].as_ref().unwrap().bestblock, read.tip);
+        assert_eq!(read.outputs[1], None);
+
+        let at_tip = node.get_tx_out_at_tip(&txid, 0, Some(false)).unwrap();
+        assert_eq!(at_tip.tip, BlockHash::hash(&[2]));
+        assert!(at_tip.output.is_some());
+
+        node.blocks.set(10);
+        match node.get_tx_outs_at_tip(&outpoints, None, Some(4)) {
+            Err(Error::TipChanged {
+                attempts: 4,
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match node.get_tx_outs_at_tip(&outpoints, None, Some(0)) {
+            Err(Error::TipChanged {
+                attempts: 1,
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,345 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Reading the UTXO set snapshots written by `dumptxoutset`.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use dashcore::blockdata::opcodes::all::{
+    OP_CHECKSIG, OP_DUP, OP_EQUAL, OP_EQUALVERIFY, OP_HASH160,
+};
+use dashcore::blockdata::script::Builder;
+use dashcore::consensus::encode::{self, Decodable};
+use dashcore::secp256k1::PublicKey;
+use dashcore::{BlockHash, OutPoint, Script, TxOut};
+
+use client::Result;
+
+/// The header of a UTXO set snapshot.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UtxoSnapshotMetadata {
+    /// The block the snapshot is at, the last block whose outputs it includes.
+    pub base_block_hash: BlockHash,
+    /// The number of coins in the snapshot.
+    pub coins_count: u64,
+    /// The number of transactions in the chain up to the base block.
+    pub chain_tx_count: u32,
+}
+
+/// An unspent output of a UTXO set snapshot.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UtxoSnapshotCoin {
+    pub outpoint: OutPoint,
+    /// The height of the block of the transaction of the output.
+    pub height: u32,
+    pub is_coinbase: bool,
+    pub txout: TxOut,
+}
+
+/// A UTXO set snapshot written by `dumptxoutset`, see
+/// `RpcApi::dump_tx_out_set`.
+///
+/// The snapshot is read as an iterator over its coins, so the UTXO set never
+/// has to fit into memory. The iteration fails with an error if the snapshot
+/// ends before all the coins of its header were read.
+pub struct UtxoSnapshot<R> {
+    reader: R,
+    metadata: UtxoSnapshotMetadata,
+    remaining: u64,
+}
+
+impl UtxoSnapshot<BufReader<File>> {
+    /// Open a snapshot file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<UtxoSnapshot<BufReader<File>>> {
+        UtxoSnapshot::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> UtxoSnapshot<R> {
+    /// Read a snapshot, starting with its header.
+    pub fn read_from(mut reader: R) -> Result<UtxoSnapshot<R>> {
+        let metadata = UtxoSnapshotMetadata {
+            base_block_hash: Decodable::consensus_decode(&mut reader)?,
+            coins_count: Decodable::consensus_decode(&mut reader)?,
+            chain_tx_count: Decodable::consensus_decode(&mut reader)?,
+        };
+        Ok(UtxoSnapshot {
+            reader,
+            remaining: metadata.coins_count,
+            metadata,
+        })
+    }
+
+    pub fn metadata(&self) -> &UtxoSnapshotMetadata {
+        &self.metadata
+    }
+
+    fn read_coin(&mut self) -> Result<UtxoSnapshotCoin> {
+        let outpoint = Decodable::consensus_decode(&mut self.reader)?;
+        let code = read_varint(&mut self.reader)?;
+        let value = decompress_amount(read_varint(&mut self.reader)?);
+        let script_pubkey = read_compressed_script(&mut self.reader)?;
+        Ok(UtxoSnapshotCoin {
+            outpoint,
+            height: (code >> 1) as u32,
+            is_coinbase: code & 1 == 1,
+            txout: TxOut {
+                value,
+                script_pubkey,
+            },
+        })
+    }
+}
+
+impl<R: Read> Iterator for UtxoSnapshot<R> {
+    type Item = Result<UtxoSnapshotCoin>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let coin = self.read_coin();
+        if coin.is_err() {
+            // The position in the snapshot is lost.
+            self.remaining = 0;
+        }
+        Some(coin)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining as usize))
+    }
+}
+
+/// Read a `VARINT` of Dash Core, the MSB base-128 encoding of the coins
+/// database, which is not the `CompactSize` of the consensus encoding.
+fn read_varint<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut n: u64 = 0;
+    loop {
+        let byte: u8 = Decodable::consensus_decode(&mut *reader)?;
+        if n > (u64::max_value() >> 7) {
+            return Err(encode::Error::ParseFailed("VARINT too large").into());
+        }
+        n = (n << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+        if n == u64::max_value() {
+            return Err(encode::Error::ParseFailed("VARINT too large").into());
+        }
+        n += 1;
+    }
+}
+
+/// Undo the `CompressAmount` of Dash Core.
+fn decompress_amount(x: u64) -> u64 {
+    if x == 0 {
+        return 0;
+    }
+    let mut x = x - 1;
+    let mut e = x % 10;
+    x /= 10;
+    let mut n = if e < 9 {
+        let d = x % 9 + 1;
+        x /= 9;
+        x * 10 + d
+    } else {
+        x + 1
+    };
+    while e > 0 {
+        n *= 10;
+        e -= 1;
+    }
+    n
+}
+
+/// The number of special script types of `ScriptCompression` in Dash Core.
+const SPECIAL_SCRIPTS: u64 = 6;
+
+/// The maximum size of a script, `MAX_SCRIPT_SIZE` in Dash Core.
+const MAX_SCRIPT_SIZE: u64 = 10_000;
+
+/// Read a script compressed with the `ScriptCompression` of Dash Core.
+fn read_compressed_script<R: Read>(reader: &mut R) -> Result<Script> {
+    let size = read_varint(reader)?;
+    let len = match size {
+        0 | 1 => 20,
+        2..=5 => 32,
+        _ => size - SPECIAL_SCRIPTS,
+    };
+    // Unspendable scripts are not in the UTXO set, so a larger script is
+    // corrupted and must not be allocated.
+    if len > MAX_SCRIPT_SIZE {
+        return Err(encode::Error::ParseFailed("script too large").into());
+    }
+    let mut data = vec![0; len as usize];
+    reader.read_exact(&mut data)?;
+    Ok(match size {
+        0 => Builder::new()
+            .push_opcode(OP_DUP)
+            .push_opcode(OP_HASH160)
+            .push_slice(&data)
+            .push_opcode(OP_EQUALVERIFY)
+            .push_opcode(OP_CHECKSIG)
+            .into_script(),
+        1 => Builder::new()
+            .push_opcode(OP_HASH160)
+            .push_slice(&data)
+            .push_opcode(OP_EQUAL)
+            .into_script(),
+        2 | 3 => {
+            // A compressed public key, kept as is even if it is not on the
+            // curve, like Dash Core does.
+            let mut key = vec![size as u8];
+            key.extend_from_slice(&data);
+            Builder::new().push_slice(&key).push_opcode(OP_CHECKSIG).into_script()
+        }
+        4 | 5 => {
+            // An uncompressed public key, stored compressed with the prefix
+            // of its parity.
+            let mut key = vec![size as u8 - 2];
+            key.extend_from_slice(&data);
+            let key = PublicKey::from_slice(&key).map_err(|_| {
+                encode::Error::ParseFailed("invalid public key of compressed script")
+            })?;
+            Builder::new()
+                .push_slice(&key.serialize_uncompressed())
+                .push_opcode(OP_CHECKSIG)
+                .into_script()
+        }
+        _ => Script::from(data),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::consensus::encode::serialize;
+    use dashcore::hashes::hex::FromHex;
+    use dashcore::hashes::Hash;
+    use dashcore::Txid;
+    use error::Error;
+    use testutil::PUBKEY;
+
+    /// Write `n` as a `VARINT` of Dash Core.
+    fn varint(mut n: u64) -> Vec<u8> {
+        let mut bytes = vec![(n & 0x7f) as u8];
+        while n > 0x7f {
+            n = (n >> 7) - 1;
+            bytes.insert(0, (n & 0x7f) as u8 | 0x80);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_varint() {
+        let cases = [(0, "00"), (127, "7f"), (128, "8000"), (16511, "ff7f"), (65535, "82fe7f")];
+        for &(n, hex) in &cases {
+            assert_eq!(varint(n), Vec::<u8>::from_hex(hex).unwrap());
+            assert_eq!(read_varint(&mut &varint(n)[..]).unwrap(), n);
+        }
+        assert!(read_varint(&mut &[0xff; 11][..]).is_err());
+    }
+
+    #[test]
+    fn test_decompress_amount() {
+        assert_eq!(decompress_amount(0), 0);
+        assert_eq!(decompress_amount(1), 1);
+        assert_eq!(decompress_amount(0x7), 1_000_000);
+        assert_eq!(decompress_amount(0x9), 100_000_000);
+        assert_eq!(decompress_amount(0x32), 5_000_000_000);
+    }
+
+    #[test]
+    fn test_compressed_p2pk() {
+        // Compressed keys are not checked to be on the curve.
+        let mut compressed = varint(3);
+        compressed.extend(&[0xff; 32]);
+        let script = read_compressed_script(&mut &compressed[..]).unwrap();
+        assert!(script.is_p2pk());
+        assert_eq!(&script.as_bytes()[..2], &[0x21, 0x03]);
+
+        // Uncompressed keys need to be to decompress them.
+        let mut uncompressed = varint(5);
+        uncompressed.extend(&[0xff; 32]);
+        assert!(read_compressed_script(&mut &uncompressed[..]).is_err());
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let key = Vec::<u8>::from_hex(PUBKEY).unwrap();
+        let txid = Txid::hash(&[1]);
+
+        let mut snapshot = serialize(&BlockHash::hash(&[0]));
+        snapshot.extend(serialize(&4u64));
+        snapshot.extend(serialize(&100u32));
+        // A coinbase P2PKH output of 1 DASH at height 10.
+        snapshot.extend(serialize(&OutPoint::new(txid, 0)));
+        snapshot.extend(varint(10 * 2 + 1));
+        snapshot.extend(varint(0x9));
+        snapshot.extend(varint(0));
+        snapshot.extend(&[7; 20]);
+        // A P2SH output.
+        snapshot.extend(serialize(&OutPoint::new(txid, 1)));
+        snapshot.extend(varint(11 * 2));
+        snapshot.extend(varint(1));
+        snapshot.extend(varint(1));
+        snapshot.extend(&[8; 20]);
+        // An uncompressed P2PK output.
+        snapshot.extend(serialize(&OutPoint::new(txid, 2)));
+        snapshot.extend(varint(12 * 2));
+        snapshot.extend(varint(1));
+        snapshot.extend(varint(4));
+        snapshot.extend(&key[1..]);
+        // A script that is not compressed.
+        snapshot.extend(serialize(&OutPoint::new(txid, 3)));
+        snapshot.extend(varint(12 * 2));
+        snapshot.extend(varint(1));
+        snapshot.extend(varint(6 + 2));
+        snapshot.extend(&[0x51, 0x51]);
+
+        let mut reader = UtxoSnapshot::read_from(&snapshot[..]).unwrap();
+        assert_eq!(
+            *reader.metadata(),
+            UtxoSnapshotMetadata {
+                base_block_hash: BlockHash::hash(&[0]),
+                coins_count: 4,
+                chain_tx_count: 100,
+            }
+        );
+        let coin = reader.next().unwrap().unwrap();
+        assert_eq!(coin.outpoint, OutPoint::new(txid, 0));
+        assert_eq!(coin.height, 10);
+        assert!(coin.is_coinbase);
+        assert_eq!(coin.txout.value, 100_000_000);
+        assert!(coin.txout.script_pubkey.is_p2pkh());
+
+        let coins: Vec<UtxoSnapshotCoin> = reader.map(|c| c.unwrap()).collect();
+        assert_eq!(coins.len(), 3);
+        assert!(!coins[0].is_coinbase);
+        assert!(coins[0].txout.script_pubkey.is_p2sh());
+        assert_eq!(coins[1].txout.script_pubkey.len(), 67);
+        assert!(coins[1].txout.script_pubkey.is_p2pk());
+        assert_eq!(coins[2].txout.script_pubkey, Script::from(vec![0x51, 0x51]));
+
+        // A truncated snapshot fails once, then ends.
+        let mut truncated = UtxoSnapshot::read_from(&snapshot[..snapshot.len() - 1]).unwrap();
+        assert_eq!(truncated.by_ref().filter(|c| c.is_ok()).count(), 3);
+        let mut truncated = UtxoSnapshot::read_from(&snapshot[..snapshot.len() - 1]).unwrap();
+        match truncated.nth(3) {
+            Some(Err(Error::Io(_))) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(truncated.next().is_none());
+    }
+}
@@ -0,0 +1,423 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Parsing of the wallet dump files written by `dumpwallet`.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, result};
+
+use dashcore::hashes::hex::FromHex;
+use dashcore::util::bip32::{DerivationPath, ExtendedPrivKey, ExtendedPubKey};
+use dashcore::{Address, BlockHash, PrivateKey, Script};
+
+use client::Result;
+use error::Error;
+use raw::hex_val;
+
+/// The line that terminates a complete wallet dump.
+const END_OF_DUMP: &str = "# End of dump";
+
+/// What a key of a wallet dump is used for.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum WalletDumpKeyKind {
+    /// A key with an address book entry, with its label.
+    Label(String),
+    /// An unused key of the key pool.
+    Reserve,
+    /// A key without an address book entry, usually a change key.
+    Change,
+}
+
+/// A private key of a wallet dump.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WalletDumpKey {
+    pub key: PrivateKey,
+    /// The creation time of the key as a UNIX timestamp.
+    pub creation_time: u64,
+    pub kind: WalletDumpKeyKind,
+    /// The P2PKH address of the key.
+    pub address: Option<Address>,
+    /// The path the key was derived at, for the keys of an HD wallet.
+    pub hd_keypath: Option<DerivationPath>,
+}
+
+impl WalletDumpKey {
+    /// The label of the key, if it has an address book entry.
+    pub fn label(&self) -> Option<&str> {
+        match self.kind {
+            WalletDumpKeyKind::Label(ref label) => Some(label),
+            _ => None,
+        }
+    }
+}
+
+/// A redeem script of a wallet dump.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WalletDumpScript {
+    pub script: Script,
+    /// The creation time of the script as a UNIX timestamp, zero if unknown.
+    pub creation_time: u64,
+    /// The P2SH address of the script.
+    pub address: Option<Address>,
+}
+
+/// A wallet dump file written by `dumpwallet`.
+///
+/// The `Debug` implementation does not print the HD wallet secrets.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct WalletDump {
+    /// The node that wrote the dump, like "Dash Core v19.1.0".
+    pub version: Option<String>,
+    /// The time the dump was written as a UNIX timestamp.
+    pub creation_time: Option<u64>,
+    /// The height and hash of the best block at the time of the dump.
+    pub best_block: Option<(u64, BlockHash)>,
+    pub mnemonic: Option<String>,
+    pub mnemonic_passphrase: Option<String>,
+    pub hd_seed: Option<Vec<u8>>,
+    pub master_private_key: Option<ExtendedPrivKey>,
+    pub master_public_key: Option<ExtendedPubKey>,
+    pub keys: Vec<WalletDumpKey>,
+    pub scripts: Vec<WalletDumpScript>,
+}
+
+impl fmt::Debug for WalletDump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WalletDump")
+            .field("version", &self.version)
+            .field("creation_time", &self.creation_time)
+            .field("best_block", &self.best_block)
+            .field("mnemonic", &"[redacted]")
+            .field("mnemonic_passphrase", &"[redacted]")
+            .field("hd_seed", &"[redacted]")
+            .field("master_private_key", &"[redacted]")
+            .field("master_public_key", &self.master_public_key)
+            .field("keys", &self.keys)
+            .field("scripts", &self.scripts)
+            .finish()
+    }
+}
+
+impl WalletDump {
+    /// Read a wallet dump file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<WalletDump> {
+        WalletDump::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a wallet dump.
+    ///
+    /// A dump that does not end with the "End of dump" line is rejected, so
+    /// that a truncated file is never mistaken for the complete wallet.
+    pub fn read_from<R: BufRead>(reader: R) -> Result<WalletDump> {
+        let mut dump = WalletDump::default();
+        let mut lines = 0;
+        for line in reader.lines() {
+            let line = line?;
+            lines += 1;
+            if line.trim() == END_OF_DUMP {
+                return Ok(dump);
+            }
+            let result = if line.starts_with('#') {
+                dump.parse_comment(field(&line, "#").unwrap())
+            } else if line.trim().is_empty() {
+                Ok(())
+            } else {
+                dump.parse_entry(&line)
+            };
+            if let Err(reason) = result {
+                return Err(Error::InvalidWalletDump {
+                    line: lines,
+                    reason,
+                });
+            }
+        }
+        Err(Error::InvalidWalletDump {
+            line: lines,
+            reason: "missing end of dump, the file is truncated".into(),
+        })
+    }
+
+    /// Parse a header line, the unknown ones are ignored.
+    fn parse_comment(&mut self, comment: &str) -> result::Result<(), String> {
+        if let Some(version) = field(comment, "Wallet dump created by") {
+            self.version = Some(version.to_owned());
+        } else if let Some(time) = field(comment, "* Created on") {
+            self.creation_time = Some(parse_time(time)?);
+        } else if let Some(block) = field(comment, "* Best block at time of backup was") {
+            let mut parts = block.split_whitespace();
+            let height = parts.next().and_then(|h| h.parse().ok());
+            let hash = parts
+                .next()
+                .map(|h| h.trim_matches(|c| c == '(' || c == ')' || c == ','))
+                .and_then(|h| BlockHash::from_hex(h).ok());
+            match (height, hash) {
+                (Some(height), Some(hash)) => self.best_block = Some((height, hash)),
+                _ => return Err(format!("invalid best block: {}", block)),
+            }
+        } else if let Some(mnemonic) = field(comment, "mnemonic:") {
+            self.mnemonic = Some(mnemonic.to_owned());
+        } else if let Some(passphrase) = field(comment, "mnemonic passphrase:") {
+            self.mnemonic_passphrase = Some(passphrase.to_owned());
+        } else if let Some(seed) = field(comment, "HD seed:") {
+            let seed = Vec::from_hex(seed.trim()).map_err(|e| format!("invalid HD seed: {}", e))?;
+            self.hd_seed = Some(seed);
+        } else if let Some(key) = field(comment, "extended private masterkey:") {
+            let key = ExtendedPrivKey::from_str(key.trim())
+                .map_err(|e| format!("invalid extended private masterkey: {}", e))?;
+            self.master_private_key = Some(key);
+        } else if let Some(key) = field(comment, "extended public masterkey:") {
+            let key = ExtendedPubKey::from_str(key.trim())
+                .map_err(|e| format!("invalid extended public masterkey: {}", e))?;
+            self.master_public_key = Some(key);
+        }
+        Ok(())
+    }
+
+    /// Parse a key or script line, like
+    /// `<wif> <time> label=<label> # addr=<address> hdkeypath=<path>`.
+    fn parse_entry(&mut self, line: &str) -> result::Result<(), String> {
+        let (data, comment) = match line.find(" # ") {
+            Some(i) => (&line[..i], &line[i + 3..]),
+            None => (line, ""),
+        };
+        let mut fields = data.split_whitespace();
+        let (first, time) = match (fields.next(), fields.next()) {
+            (Some(first), Some(time)) => (first, time),
+            _ => return Err("missing creation time".into()),
+        };
+        let creation_time = parse_time(time)?;
+
+        let mut kind = None;
+        let mut is_script = false;
+        for attr in fields {
+            let mut kv = attr.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some("label"), Some(label)) => {
+                    kind = Some(WalletDumpKeyKind::Label(decode_dump_string(label)?))
+                }
+                (Some("reserve"), _) => kind = Some(WalletDumpKeyKind::Reserve),
+                (Some("change"), _) => kind = Some(WalletDumpKeyKind::Change),
+                (Some("script"), _) => is_script = true,
+                _ => {}
+            }
+        }
+
+        let mut address = None;
+        let mut hd_keypath = None;
+        for attr in comment.split_whitespace() {
+            let mut kv = attr.trim_end_matches(',').splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some("addr"), Some(addr)) => {
+                    // Bitcoin Core lists all the addresses of a key.
+                    let addr = addr.split(',').next().unwrap_or(addr);
+                    let addr = Address::from_str(addr)
+                        .map_err(|e| format!("invalid address {}: {}", addr, e))?;
+                    address = Some(addr);
+                }
+                (Some("hdkeypath"), Some(path)) => {
+                    let path = DerivationPath::from_str(path)
+                        .map_err(|e| format!("invalid HD keypath {}: {}", path, e))?;
+                    hd_keypath = Some(path);
+                }
+                _ => {}
+            }
+        }
+
+        if is_script {
+            let script = Vec::from_hex(first).map_err(|e| format!("invalid script: {}", e))?;
+            self.scripts.push(WalletDumpScript {
+                script: Script::from(script),
+                creation_time,
+                address,
+            });
+        } else {
+            let key = PrivateKey::from_wif(first).map_err(|e| format!("invalid key: {}", e))?;
+            self.keys.push(WalletDumpKey {
+                key,
+                creation_time,
+                kind: kind.ok_or("missing label, reserve or change flag")?,
+                address,
+                hd_keypath,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Strip the name of a header field and the space after it.
+fn field<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    if !line.starts_with(name) {
+        return None;
+    }
+    let value = &line[name.len()..];
+    if value.starts_with(' ') {
+        Some(&value[1..])
+    } else {
+        Some(value)
+    }
+}
+
+/// Parse a timestamp like "2021-01-31T12:00:00Z", the format the node uses.
+///
+/// The node writes "0" for the unknown creation time of a script.
+fn parse_time(s: &str) -> result::Result<u64, String> {
+    if s == "0" {
+        return Ok(0);
+    }
+    let invalid = || format!("invalid time: {}", s);
+    let b = s.as_bytes();
+    if !s.is_ascii()
+        || b.len() != 20
+        || b[4] != b'-'
+        || b[7] != b'-'
+        || b[10] != b'T'
+        || b[13] != b':'
+        || b[16] != b':'
+        || b[19] != b'Z'
+    {
+        return Err(invalid());
+    }
+    let num = |from: usize, to: usize| s[from..to].parse::<u64>().map_err(|_| invalid());
+    let (year, month, day) = (num(0, 4)?, num(5, 7)?, num(8, 10)?);
+    let (hour, minute, second) = (num(11, 13)?, num(14, 16)?, num(17, 19)?);
+    if year < 1970 || month < 1 || month > 12 || day < 1 || day > 31 {
+        return Err(invalid());
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return Err(invalid());
+    }
+
+    // Days since the epoch in the proleptic Gregorian calendar, see
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 {
+        year - 1
+    } else {
+        year
+    };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    Ok(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Decode a label, the node escapes spaces, control characters, non-ASCII
+/// bytes and '%' as "%XX".
+fn decode_dump_string(s: &str) -> result::Result<String, String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(c) = iter.next() {
+        if c != b'%' {
+            bytes.push(c);
+            continue;
+        }
+        match (iter.next().and_then(hex_val), iter.next().and_then(hex_val)) {
+            (Some(hi), Some(lo)) => bytes.push(hi << 4 | lo),
+            _ => return Err(format!("invalid escape in label: {}", s)),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("label is not valid UTF-8: {}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = "\
+# Wallet dump created by Dash Core v19.1.0
+# * Created on 2023-05-01T12:30:00Z
+# * Best block at time of backup was 1234 (000000000000000a1d3d58b39e76b4c4d1a1f3bb87a6b5b5c4a2f0d9e4d3c2b1),
+#   mined on 2023-05-01T12:29:12Z
+
+# mnemonic: abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about
+# mnemonic passphrase:
+
+# HD seed: 000102030405060708090a0b0c0d0e0f
+
+# extended private masterkey: tprv8ZgxMBicQKsPeDgjzdC36fs6bMjGApWDNLR9erAXMs5skhMv36j9MV5ecvfavji5khqjWaWSFhN3YcCUUdiKH6isR4Pwy3U5y5egddBr16m
+# extended public masterkey: tpubD6NzVbkrYhZ4XgiXtGrdW5XDAPFCL9h7we1vwNCpn8tGbBcgfVYjXyhWo4E1xkh56hjod1RhGjxbaTLV3X4FyWuejifB9jusQ46QzG87VKp
+
+# external chain counter: 2
+# internal chain counter: 1
+
+cMceqPhHedrhbcR9eXgzmfWy7kRqLyAxMYwFT6ABDWsiwUp9Nsq9 2023-05-01T12:00:00Z label=my%20savings%25 # addr=yMsgnH1xKGa85n4bq2imrZbG2KgrmGttAV hdkeypath=m/44'/1'/0'/0/0
+cMec2DGaTXkYJYfi7x3ZGjRXkeqmAvYAoWzMAcWj5fdLaqudWsNi 1970-01-01T00:00:01Z reserve=1 # addr=yPRviMJaEHGafsc5EKovpa5Nw2Jewo9mdj
+
+51 0 script=1 # addr=8j6BWHXLETu2JXH4frbX7QdJggkK3STMFT
+
+# End of dump
+";
+
+    #[test]
+    fn test_read_wallet_dump() {
+        let dump: WalletDump = WalletDump::read_from(DUMP.as_bytes()).unwrap();
+        assert_eq!(dump.version.as_ref().unwrap(), "Dash Core v19.1.0");
+        assert_eq!(dump.creation_time, Some(1682944200));
+        assert_eq!(dump.best_block.unwrap().0, 1234);
+        assert_eq!(dump.mnemonic.as_ref().unwrap().split(' ').count(), 12);
+        assert_eq!(dump.mnemonic_passphrase.as_ref().unwrap(), "");
+        assert_eq!(dump.hd_seed.as_ref().unwrap(), &(0..16).collect::<Vec<u8>>());
+        let xpriv = dump.master_private_key.unwrap();
+        assert_eq!(dump.master_public_key.unwrap().chain_code, xpriv.chain_code);
+        assert!(!format!("{:?}", dump).contains("abandon"));
+
+        assert_eq!(dump.keys.len(), 2);
+        let key = &dump.keys[0];
+        assert_eq!(key.key.to_wif(), "cMceqPhHedrhbcR9eXgzmfWy7kRqLyAxMYwFT6ABDWsiwUp9Nsq9");
+        assert_eq!(key.creation_time, 1682942400);
+        assert_eq!(key.label(), Some("my savings%"));
+        assert_eq!(key.address.as_ref().unwrap().to_string(), "yMsgnH1xKGa85n4bq2imrZbG2KgrmGttAV");
+        assert_eq!(key.hd_keypath.as_ref().unwrap().to_string(), "m/44'/1'/0'/0/0");
+        assert_eq!(dump.keys[1].kind, WalletDumpKeyKind::Reserve);
+        assert_eq!(dump.keys[1].creation_time, 1);
+        assert_eq!(dump.keys[1].hd_keypath, None);
+
+        assert_eq!(dump.scripts.len(), 1);
+        assert_eq!(dump.scripts[0].script.as_bytes(), &[0x51]);
+        assert_eq!(dump.scripts[0].creation_time, 0);
+    }
+
+    #[test]
+    fn test_invalid_wallet_dump() {
+        let truncated = &DUMP[..DUMP.find(END_OF_DUMP).unwrap()];
+        match WalletDump::read_from(truncated.as_bytes()) {
+            Err(Error::InvalidWalletDump {
+                line: 21,
+                ..
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let bad_time = DUMP.replace("1970-01-01T00:00:01Z", "1970-01-01 00:00:01");
+        match WalletDump::read_from(bad_time.as_bytes()) {
+            Err(Error::InvalidWalletDump {
+                line: 18,
+                ..
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let bad_label = DUMP.replace("%25", "%2");
+        assert!(WalletDump::read_from(bad_label.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("1970-01-01T00:00:00Z"), Ok(0));
+        assert_eq!(parse_time("2000-03-01T00:00:00Z"), Ok(951868800));
+        assert_eq!(parse_time("2024-02-29T23:59:59Z"), Ok(1709251199));
+        assert!(parse_time("2024-13-01T00:00:00Z").is_err());
+        assert!(parse_time("2024-01-01T00:00:00").is_err());
+    }
+}
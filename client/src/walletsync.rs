@@ -0,0 +1,193 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Following the transactions of a wallet.
+
+use dashcore::BlockHash;
+
+use client::{Result, RpcApi};
+use json::{GetTransactionResultDetailCategory, ListTransactionResult};
+
+/// A wallet transaction reported by a [WalletSync].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum WalletEvent {
+    /// Funds received by the wallet, including mined coins.
+    Incoming(ListTransactionResult),
+    /// Funds sent by the wallet.
+    Outgoing(ListTransactionResult),
+    /// A transaction that was removed from the main chain by a
+    /// reorganization, that conflicts with one in the main chain, or that
+    /// mined coins in a block that is no longer in the main chain. Funds it
+    /// was credited for must be taken back.
+    Conflicted(ListTransactionResult),
+}
+
+impl WalletEvent {
+    /// The transaction of the event.
+    pub fn transaction(&self) -> &ListTransactionResult {
+        match *self {
+            WalletEvent::Incoming(ref tx) => tx,
+            WalletEvent::Outgoing(ref tx) => tx,
+            WalletEvent::Conflicted(ref tx) => tx,
+        }
+    }
+}
+
+/// Follows the transactions of the wallet with `listsinceblock`, the usual
+/// way to detect deposits.
+///
+/// Every [WalletSync::poll] reports the wallet transactions since the block
+/// of the previous poll. That block is `target_confirmations` deep, so a
+/// transaction is reported on every poll until it has at least that many
+/// confirmations, and callers should credit it once it has. Transactions of
+/// blocks that were reorganized out are reported as
+/// [WalletEvent::Conflicted].
+///
+/// Persist [WalletSync::last_block] to resume with [WalletSync::starting_at]
+/// after a restart.
+#[derive(Clone, Debug)]
+pub struct WalletSync {
+    target_confirmations: usize,
+    last_block: Option<BlockHash>,
+}
+
+impl WalletSync {
+    /// Follow the wallet from the genesis block, so the first poll reports
+    /// the whole history of the wallet.
+    pub fn new(target_confirmations: usize) -> WalletSync {
+        WalletSync {
+            target_confirmations,
+            last_block: None,
+        }
+    }
+
+    /// Follow the wallet from a block returned by [WalletSync::last_block].
+    pub fn starting_at(target_confirmations: usize, block_hash: BlockHash) -> WalletSync {
+        WalletSync {
+            target_confirmations,
+            last_block: Some(block_hash),
+        }
+    }
+
+    /// The block the next poll starts from.
+    pub fn last_block(&self) -> Option<BlockHash> {
+        self.last_block
+    }
+
+    /// Fetch the wallet transactions since the last poll.
+    pub fn poll<C: RpcApi>(&mut self, rpc: &C) -> Result<Vec<WalletEvent>> {
+        let result = rpc.list_since_block(
+            self.last_block.as_ref(),
+            Some(self.target_confirmations),
+            None,
+            Some(true),
+        )?;
+        let mut events: Vec<_> = result.removed.into_iter().map(WalletEvent::Conflicted).collect();
+        for tx in result.transactions {
+            let orphan = tx.detail.category == GetTransactionResultDetailCategory::Orphan;
+            events.push(if tx.info.confirmations < 0 || orphan {
+                WalletEvent::Conflicted(tx)
+            } else if tx.detail.category == GetTransactionResultDetailCategory::Send {
+                WalletEvent::Outgoing(tx)
+            } else {
+                WalletEvent::Incoming(tx)
+            });
+        }
+        self.last_block = Some(result.lastblock);
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use dashcore::Txid;
+    use serde_json;
+    use std::cell::RefCell;
+    use testutil::MockRpc;
+
+    /// A wallet that answers `listsinceblock` with the queued results.
+    struct MockWallet {
+        results: RefCell<Vec<String>>,
+        requested: RefCell<Vec<serde_json::Value>>,
+    }
+
+    impl MockRpc for MockWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            assert_eq!(cmd, "listsinceblock");
+            self.requested.borrow_mut().push(args[0].clone());
+            Ok(self.results.borrow_mut().remove(0))
+        }
+    }
+
+    fn tx(n: u8, category: &str, confirmations: i32) -> String {
+        format!(
+            r#"{{"txid": "{}", "category": "{}", "amount": 1.0, "vout": 0,
+                "confirmations": {}, "time": 0, "timereceived": 0,
+                "bip125-replaceable": "no", "walletconflicts": []}}"#,
+            Txid::hash(&[n]),
+            category,
+            confirmations
+        )
+    }
+
+    fn result(transactions: &[String], removed: &[String], lastblock: u8) -> String {
+        format!(
+            r#"{{"transactions": [{}], "removed": [{}], "lastblock": "{}"}}"#,
+            transactions.join(","),
+            removed.join(","),
+            BlockHash::hash(&[lastblock])
+        )
+    }
+
+    #[test]
+    fn test_poll() {
+        let wallet = MockWallet {
+            results: RefCell::new(vec![
+                result(&[tx(1, "receive", 1), tx(2, "send", 3), tx(3, "generate", 1)], &[], 1),
+                result(&[tx(4, "receive", -1), tx(5, "orphan", 0)], &[tx(1, "receive", 0)], 2),
+            ]),
+            requested: RefCell::new(vec![]),
+        };
+        let mut sync = WalletSync::new(6);
+        let events = sync.poll(&wallet).unwrap();
+        assert_eq!(events.len(), 3);
+        match events[0] {
+            WalletEvent::Incoming(ref tx) => assert_eq!(tx.info.txid, Txid::hash(&[1])),
+            ref e => panic!("unexpected event: {:?}", e),
+        }
+        match events[1] {
+            WalletEvent::Outgoing(ref tx) => assert_eq!(tx.info.confirmations, 3),
+            ref e => panic!("unexpected event: {:?}", e),
+        }
+        match events[2] {
+            WalletEvent::Incoming(_) => {}
+            ref e => panic!("unexpected event: {:?}", e),
+        }
+        assert_eq!(sync.last_block(), Some(BlockHash::hash(&[1])));
+
+        let events = sync.poll(&wallet).unwrap();
+        assert_eq!(events.len(), 3);
+        for event in &events {
+            match *event {
+                WalletEvent::Conflicted(_) => {}
+                ref e => panic!("unexpected event: {:?}", e),
+            }
+        }
+        assert_eq!(events[0].transaction().info.txid, Txid::hash(&[1]));
+        assert_eq!(events[1].transaction().info.txid, Txid::hash(&[4]));
+        assert_eq!(events[2].transaction().info.txid, Txid::hash(&[5]));
+        assert_eq!(
+            *wallet.requested.borrow(),
+            [serde_json::Value::Null, serde_json::to_value(BlockHash::hash(&[1])).unwrap()]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Waiting for a node to finish starting up.
+
+use std::cmp;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use client::{Result, RpcApi};
+use error::Error;
+use operations::{self, OperationHandle};
+
+/// How often [RpcApi::wait_until_warmed_up] asks the node whether it is
+/// ready.
+pub const WARMUP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// See [RpcApi::wait_until_warmed_up].
+///
+/// With an `operation`, the wait stops with [Error::Cancelled] once
+/// cancellation is requested.
+pub fn wait_until_warmed_up<C: RpcApi>(
+    rpc: &C,
+    timeout: Duration,
+    operation: Option<&OperationHandle>,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        operations::check_cancelled(operation)?;
+        match rpc.get_block_count() {
+            Ok(_) => return Ok(()),
+            // Also wait for a node whose RPC server is not listening yet.
+            Err(ref e) if e.is_transient() => {}
+            Err(e) => return Err(e),
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(Error::Timeout(timeout));
+        }
+        thread::sleep(cmp::min(WARMUP_POLL_INTERVAL, deadline - now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc;
+    use operations::Operations;
+    use serde_json;
+    use std::cell::Cell;
+    use testutil::MockRpc;
+
+    /// A node that is warming up for the first `polls` calls.
+    struct StartingNode {
+        polls: Cell<usize>,
+    }
+
+    impl MockRpc for StartingNode {
+        fn respond(&self, cmd: &str, _: &[serde_json::Value]) -> Result<String> {
+            assert_eq!(cmd, "getblockcount");
+            if self.polls.get() > 0 {
+                self.polls.set(self.polls.get() - 1);
+                let e = jsonrpc::error::RpcError {
+                    code: -28,
+                    message: "Loading block index...".into(),
+                    data: None,
+                };
+                return Err(jsonrpc::Error::Rpc(e).into());
+            }
+            Ok("100".to_owned())
+        }
+    }
+
+    #[test]
+    fn test_wait_until_warmed_up() {
+        let node = StartingNode {
+            polls: Cell::new(1),
+        };
+        assert!(node.get_block_count().is_err());
+        assert_eq!(node.get_block_count().unwrap(), 100);
+
+        let node = StartingNode {
+            polls: Cell::new(2),
+        };
+        node.wait_until_warmed_up(Duration::from_secs(10)).unwrap();
+        assert_eq!(node.polls.get(), 0);
+
+        let node = StartingNode {
+            polls: Cell::new(usize::max_value()),
+        };
+        match node.wait_until_warmed_up(Duration::from_millis(10)) {
+            Err(Error::Timeout(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let ops = Operations::new();
+        let op = ops.register("warmup");
+        ops.cancel(op.id());
+        match wait_until_warmed_up(&node, Duration::from_secs(10), Some(&op)) {
+            Err(Error::Cancelled) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,360 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Watching external addresses for deposits.
+
+use std::cmp;
+use std::collections::{BTreeMap, HashSet};
+
+use jsonrpc;
+
+use dashcore::{Address, Amount, OutPoint};
+
+use client::{Result, RpcApi};
+use error::{Error, RPC_INVALID_ADDRESS_OR_KEY};
+use json::ConfirmationStatus;
+
+/// A payment to a watched address, reported by a [Watcher] whenever its
+/// status changes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Deposit {
+    pub address: Address,
+    pub outpoint: OutPoint,
+    pub amount: Amount,
+    pub status: ConfirmationStatus,
+}
+
+/// Where a [Watcher] finds the outputs paying its addresses.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Source {
+    /// `listunspent` of addresses imported watch-only into the wallet.
+    Wallet,
+    /// `getaddressdeltas` and `getaddressmempool` of the address index.
+    AddressIndex {
+        start_height: u32,
+        next_height: u32,
+    },
+}
+
+/// A deposit that is followed until it is settled.
+#[derive(Clone, Debug)]
+struct Pending {
+    address: Address,
+    amount: Amount,
+    status: Option<ConfirmationStatus>,
+}
+
+/// Watches addresses that don't belong to the wallet, typically deposit
+/// addresses generated from an xpub, and reports the payments to them.
+///
+/// Every [Watcher::poll] reports the deposits whose status changed since the
+/// previous poll. A deposit is followed until it is ChainLocked or has
+/// `target_confirmations` confirmations. Deposits are safe to credit once
+/// their status [is final](ConfirmationStatus::is_final), a deposit whose
+/// transaction was double-spent before that is dropped without being
+/// reported again.
+///
+/// The statuses are looked up with `getrawtransaction`, so the node needs
+/// the transaction index, enabled with `-txindex`.
+#[derive(Clone, Debug)]
+pub struct Watcher {
+    source: Source,
+    target_confirmations: u32,
+    addresses: Vec<Address>,
+    pending: BTreeMap<OutPoint, Pending>,
+    settled: HashSet<OutPoint>,
+}
+
+impl Watcher {
+    /// Watch addresses by importing them watch-only into the wallet.
+    pub fn new(target_confirmations: u32) -> Watcher {
+        Watcher::with_source(Source::Wallet, target_confirmations)
+    }
+
+    /// Watch addresses with the address index of the node, enabled with
+    /// `-addressindex`, which doesn't need a wallet. Deposits mined before
+    /// `start_height` are ignored.
+    pub fn with_address_index(target_confirmations: u32, start_height: u32) -> Watcher {
+        let source = Source::AddressIndex {
+            start_height,
+            next_height: start_height,
+        };
+        Watcher::with_source(source, target_confirmations)
+    }
+
+    fn with_source(source: Source, target_confirmations: u32) -> Watcher {
+        Watcher {
+            source,
+            target_confirmations: cmp::max(target_confirmations, 1),
+            addresses: Vec::new(),
+            pending: BTreeMap::new(),
+            settled: HashSet::new(),
+        }
+    }
+
+    /// Start watching an address.
+    ///
+    /// Without the address index, the address is imported into the wallet
+    /// without a rescan, so it must not have received payments yet.
+    pub fn watch<C: RpcApi>(&mut self, rpc: &C, address: Address) -> Result<()> {
+        if self.addresses.contains(&address) {
+            return Ok(());
+        }
+        match self.source {
+            Source::Wallet => rpc.import_address(&address, None, Some(false))?,
+            Source::AddressIndex {
+                ..
+            } => rpc.check_address_network(&address)?,
+        }
+        self.addresses.push(address);
+        Ok(())
+    }
+
+    /// Stop watching an address and drop its deposits that are not settled
+    /// yet. Returns whether the address was watched.
+    ///
+    /// An address imported into the wallet stays imported.
+    pub fn unwatch(&mut self, address: &Address) -> bool {
+        let watched = self.addresses.len();
+        self.addresses.retain(|a| a != address);
+        let dropped: Vec<OutPoint> = self
+            .pending
+            .iter()
+            .filter(|&(_, pending)| pending.address == *address)
+            .map(|(outpoint, _)| *outpoint)
+            .collect();
+        for outpoint in dropped {
+            self.pending.remove(&outpoint);
+        }
+        self.addresses.len() != watched
+    }
+
+    /// The watched addresses.
+    pub fn addresses(&self) -> &[Address] {
+        &self.addresses
+    }
+
+    /// Fetch the deposits whose status changed since the last poll.
+    pub fn poll<C: RpcApi>(&mut self, rpc: &C) -> Result<Vec<Deposit>> {
+        for (outpoint, address, amount) in self.fetch_outputs(rpc)? {
+            if !self.settled.contains(&outpoint) {
+                self.pending.entry(outpoint).or_insert(Pending {
+                    address,
+                    amount,
+                    status: None,
+                });
+            }
+        }
+
+        let mut deposits = Vec::new();
+        let mut done = Vec::new();
+        for (outpoint, pending) in self.pending.iter_mut() {
+            let status = match rpc.get_tx_confirmation_status(&outpoint.txid) {
+                Ok(status) => status,
+                Err(Error::JsonRpc(jsonrpc::Error::Rpc(ref e)))
+                    if e.code == RPC_INVALID_ADDRESS_OR_KEY =>
+                {
+                    done.push(*outpoint);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if pending.status != Some(status) {
+                pending.status = Some(status);
+                deposits.push(Deposit {
+                    address: pending.address.clone(),
+                    outpoint: *outpoint,
+                    amount: pending.amount,
+                    status,
+                });
+            }
+            let settled = match status {
+                ConfirmationStatus::ChainLocked {
+                    ..
+                } => true,
+                ConfirmationStatus::Confirmed {
+                    confirmations,
+                    ..
+                } => confirmations >= self.target_confirmations,
+                _ => false,
+            };
+            if settled {
+                self.settled.insert(*outpoint);
+                done.push(*outpoint);
+            }
+        }
+        for outpoint in done {
+            self.pending.remove(&outpoint);
+        }
+        Ok(deposits)
+    }
+
+    /// Fetch the outputs paying the watched addresses that may be new.
+    fn fetch_outputs<C: RpcApi>(&mut self, rpc: &C) -> Result<Vec<(OutPoint, Address, Amount)>> {
+        if self.addresses.is_empty() {
+            return Ok(vec![]);
+        }
+        let addresses: Vec<&Address> = self.addresses.iter().collect();
+        let mut outputs = Vec::new();
+        match self.source {
+            Source::Wallet => {
+                let unspent =
+                    rpc.list_unspent(Some(0), None, Some(&addresses), Some(true), None)?;
+                for entry in unspent {
+                    if let Some(address) = entry.address {
+                        let outpoint = OutPoint::new(entry.txid, entry.vout);
+                        outputs.push((outpoint, address, entry.amount));
+                    }
+                }
+            }
+            Source::AddressIndex {
+                start_height,
+                ref mut next_height,
+            } => {
+                let tip = rpc.get_block_count()? as u32;
+                // Blocks that may still be reorganized are scanned again, the
+                // deposits already known are skipped.
+                let reorg_start = (tip + 1).saturating_sub(self.target_confirmations);
+                let start = cmp::max(start_height, cmp::min(*next_height, reorg_start));
+                if start <= tip {
+                    for delta in rpc.get_address_deltas(&addresses, Some((start, tip)))? {
+                        if delta.satoshis.is_positive() {
+                            let outpoint = OutPoint::new(delta.txid, delta.index);
+                            let amount = Amount::from_sat(delta.satoshis.as_sat() as u64);
+                            outputs.push((outpoint, delta.address, amount));
+                        }
+                    }
+                    *next_height = tip + 1;
+                }
+                for entry in rpc.get_address_mempool(&addresses)? {
+                    if entry.satoshis.is_positive() {
+                        let outpoint = OutPoint::new(entry.txid, entry.index);
+                        let amount = Amount::from_sat(entry.satoshis.as_sat() as u64);
+                        outputs.push((outpoint, entry.address, amount));
+                    }
+                }
+            }
+        }
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dashcore::hashes::Hash;
+    use dashcore::Txid;
+    use serde_json;
+    use std::cell::RefCell;
+    use testutil::{address, MockRpc};
+
+    /// A node with the address index whose transactions have the queued
+    /// statuses, as `(confirmations, chainlock)`.
+    struct MockNode {
+        tip: u64,
+        statuses: RefCell<Vec<(Txid, u32, bool)>>,
+        requested: RefCell<Vec<serde_json::Value>>,
+    }
+
+    impl MockRpc for MockNode {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let addr = address();
+            let json = match cmd {
+                "getblockcount" => self.tip.to_string(),
+                "getaddressdeltas" => {
+                    self.requested.borrow_mut().push(args[0].clone());
+                    format!(
+                        r#"[{{"satoshis": 1000, "txid": "{0}", "index": 1, "blockindex": 1,
+                              "height": 10, "address": "{2}"}},
+                            {{"satoshis": -500, "txid": "{1}", "index": 0, "blockindex": 2,
+                              "height": 10, "address": "{2}"}}]"#,
+                        Txid::hash(&[1]),
+                        Txid::hash(&[2]),
+                        addr
+                    )
+                }
+                "getaddressmempool" => format!(
+                    r#"[{{"address": "{}", "txid": "{}", "index": 0, "satoshis": 2000,
+                          "timestamp": 0}}]"#,
+                    addr,
+                    Txid::hash(&[3])
+                ),
+                "getrawtransaction" => {
+                    let txid: Txid = serde_json::from_value(args[0].clone())?;
+                    let mut statuses = self.statuses.borrow_mut();
+                    let idx = statuses.iter().position(|s| s.0 == txid).unwrap();
+                    let (_, confirmations, chainlock) = statuses.remove(idx);
+                    format!(
+                        r#"{{"hex": "00", "txid": "{}", "hash": "{}", "size": 1, "vsize": 1,
+                             "version": 3, "locktime": 0, "vin": [], "vout": [],
+                             "confirmations": {}, "chainlock": {}}}"#,
+                        txid, txid, confirmations, chainlock
+                    )
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    #[test]
+    fn test_poll() {
+        let node = MockNode {
+            tip: 10,
+            statuses: RefCell::new(vec![
+                (Txid::hash(&[1]), 1, false),
+                (Txid::hash(&[3]), 0, false),
+                (Txid::hash(&[1]), 1, false),
+                (Txid::hash(&[3]), 1, true),
+            ]),
+            requested: RefCell::new(vec![]),
+        };
+        let mut watcher = Watcher::with_address_index(6, 8);
+        watcher.watch(&node, address()).unwrap();
+        watcher.watch(&node, address()).unwrap();
+        assert_eq!(watcher.addresses().len(), 1);
+
+        let deposits = watcher.poll(&node).unwrap();
+        assert_eq!(deposits.len(), 2);
+        assert_eq!(deposits[0].outpoint, OutPoint::new(Txid::hash(&[1]), 1));
+        assert_eq!(deposits[0].amount, Amount::from_sat(1000));
+        assert_eq!(
+            deposits[0].status,
+            ConfirmationStatus::Confirmed {
+                confirmations: 1,
+                instant_locked: false,
+            }
+        );
+        assert_eq!(deposits[1].outpoint, OutPoint::new(Txid::hash(&[3]), 0));
+        assert_eq!(deposits[1].status, ConfirmationStatus::Unconfirmed);
+
+        // Only the deposit that got mined and ChainLocked changed.
+        let deposits = watcher.poll(&node).unwrap();
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(
+            deposits[0].status,
+            ConfirmationStatus::ChainLocked {
+                confirmations: 1,
+            }
+        );
+        assert!(node.statuses.borrow().is_empty());
+
+        assert!(watcher.unwatch(&address()));
+        assert!(!watcher.unwatch(&address()));
+        assert!(watcher.addresses().is_empty());
+        assert!(watcher.poll(&node).unwrap().is_empty());
+
+        let requested = node.requested.borrow();
+        assert_eq!(requested[0]["start"], 8);
+        assert_eq!(requested[0]["end"], 10);
+        assert_eq!(requested[0]["addresses"][0], address().to_string());
+        assert_eq!(requested[1]["start"], 8);
+    }
+}
@@ -0,0 +1,363 @@
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Workflows that combine several calls for common tasks.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json;
+
+use dashcore::consensus::encode::deserialize;
+use dashcore::hashes::hex::ToHex;
+use dashcore::{Address, Amount, Transaction, Txid};
+
+use client::{Result, RpcApi};
+use coinselect::DEFAULT_FEE_RATE;
+use error::Error;
+use json;
+use operations::{self, OperationHandle};
+use signing::{self, Signer};
+
+/// The largest data an `OP_RETURN` output can carry to be relayed by nodes
+/// with the default `-datacarriersize`.
+pub const MAX_OP_RETURN_DATA: usize = 80;
+
+/// How often [RpcApi::send_and_wait_for_islock] asks the wallet whether the
+/// transaction was locked.
+pub const INSTANT_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The options of [RpcApi::create_and_sign_transaction].
+#[derive(Clone, Debug)]
+pub struct CreateTransactionOptions {
+    minconf: usize,
+    fee_rate: Amount,
+    change_address: Option<Address>,
+    fund_with_node: bool,
+    locktime: Option<i64>,
+}
+
+impl Default for CreateTransactionOptions {
+    fn default() -> CreateTransactionOptions {
+        CreateTransactionOptions {
+            minconf: 1,
+            fee_rate: Amount::from_sat(DEFAULT_FEE_RATE),
+            change_address: None,
+            fund_with_node: false,
+            locktime: None,
+        }
+    }
+}
+
+impl CreateTransactionOptions {
+    pub fn new() -> CreateTransactionOptions {
+        CreateTransactionOptions::default()
+    }
+
+    /// Only spend outputs with at least `minconf` confirmations, one by
+    /// default.
+    pub fn with_minconf(mut self, minconf: usize) -> Self {
+        self.minconf = minconf;
+        self
+    }
+
+    /// Set the fee rate per kB.
+    pub fn with_fee_rate(mut self, fee_rate: Amount) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// Send the change to `address` instead of a new change address of the
+    /// wallet.
+    pub fn with_change_address(mut self, address: Address) -> Self {
+        self.change_address = Some(address);
+        self
+    }
+
+    /// Let the node select the inputs with `fundrawtransaction` instead of
+    /// selecting them with a `CoinSelector`.
+    pub fn with_fund_with_node(mut self, fund_with_node: bool) -> Self {
+        self.fund_with_node = fund_with_node;
+        self
+    }
+
+    pub fn with_locktime(mut self, locktime: i64) -> Self {
+        self.locktime = Some(locktime);
+        self
+    }
+}
+
+/// See [RpcApi::create_and_sign_transaction].
+pub(crate) fn create_and_sign_transaction<C: RpcApi>(
+    rpc: &C,
+    recipients: &[(Address, Amount)],
+    options: &CreateTransactionOptions,
+) -> Result<Transaction> {
+    let mut outs: HashMap<String, Amount> = HashMap::new();
+    for &(ref address, amount) in recipients {
+        rpc.check_address_network(address)?;
+        *outs.entry(address.to_string()).or_insert(Amount::ZERO) += amount;
+    }
+
+    let unsigned = if options.fund_with_node {
+        let tx = rpc.create_raw_transaction(&[], &outs, options.locktime, None)?;
+        let fund_options = json::FundRawTransactionOptions {
+            change_address: options.change_address.clone(),
+            fee_rate: Some(options.fee_rate),
+            ..Default::default()
+        };
+        rpc.fund_raw_transaction(&tx, Some(&fund_options), None)?.hex.to_hex()
+    } else {
+        let target = outs.values().fold(Amount::ZERO, |sum, a| sum + *a);
+        let selection = rpc
+            .coin_selector(Some(options.minconf))?
+            .with_fee_rate(options.fee_rate)
+            .with_outputs(outs.len())
+            .select(target)?;
+        if selection.change > Amount::ZERO {
+            let change_address = match options.change_address {
+                Some(ref address) => address.clone(),
+                None => rpc.get_raw_change_address()?,
+            };
+            *outs.entry(change_address.to_string()).or_insert(Amount::ZERO) += selection.change;
+        }
+        let inputs: Vec<_> = selection
+            .outpoints()
+            .into_iter()
+            .map(|o| json::CreateRawTransactionInput {
+                txid: o.txid,
+                vout: o.vout,
+                sequence: None,
+            })
+            .collect();
+        rpc.create_raw_transaction_hex(&inputs, &outs, options.locktime, None)?
+    };
+
+    sign_with_wallet(rpc, unsigned)
+}
+
+/// See [RpcApi::create_and_sign_transaction_with_signer].
+pub(crate) fn create_and_sign_transaction_with_signer<C: RpcApi, S: Signer + ?Sized>(
+    rpc: &C,
+    recipients: &[(Address, Amount)],
+    options: &CreateTransactionOptions,
+    signer: &S,
+) -> Result<Transaction> {
+    let mut outs: HashMap<String, Amount> = HashMap::new();
+    for &(ref address, amount) in recipients {
+        rpc.check_address_network(address)?;
+        *outs.entry(address.to_string()).or_insert(Amount::ZERO) += amount;
+    }
+    let unfunded = rpc.create_raw_transaction_hex(&[], &outs, options.locktime, None)?;
+    let fund_options = json::FundRawTransactionOptions {
+        change_address: options.change_address.clone(),
+        include_watching: Some(true),
+        fee_rate: Some(options.fee_rate),
+        ..Default::default()
+    };
+    let funded = rpc.fund_raw_transaction(unfunded, Some(&fund_options), None)?;
+    let unsigned: Transaction = deserialize(&funded.hex)?;
+    let inputs = signing::get_signing_inputs(rpc, &unsigned)?;
+    signer.sign_transaction(&unsigned, &inputs)
+}
+
+/// See [RpcApi::create_data_transaction].
+pub(crate) fn create_data_transaction<C: RpcApi>(
+    rpc: &C,
+    data: &[u8],
+    options: &CreateTransactionOptions,
+) -> Result<Transaction> {
+    if data.len() > MAX_OP_RETURN_DATA {
+        return Err(Error::DataTooLarge {
+            size: data.len(),
+            max: MAX_OP_RETURN_DATA,
+        });
+    }
+    let mut outputs = serde_json::Map::new();
+    outputs.insert("data".to_owned(), data.to_hex().into());
+    let args = [vec![].into(), outputs.into(), options.locktime.unwrap_or(0).into()];
+    let unfunded: String = rpc.call("createrawtransaction", &args)?;
+
+    let fund_options = json::FundRawTransactionOptions {
+        change_address: options.change_address.clone(),
+        fee_rate: Some(options.fee_rate),
+        ..Default::default()
+    };
+    let funded = rpc.fund_raw_transaction(unfunded, Some(&fund_options), None)?;
+    sign_with_wallet(rpc, funded.hex.to_hex())
+}
+
+/// See [RpcApi::send_and_wait_for_islock].
+///
+/// With an `operation`, the wait stops with [Error::Cancelled] once
+/// cancellation is requested. The transaction was sent by then.
+pub fn send_and_wait_for_islock<C: RpcApi>(
+    rpc: &C,
+    address: &Address,
+    amount: Amount,
+    timeout: Duration,
+    operation: Option<&OperationHandle>,
+) -> Result<Txid> {
+    let deadline = Instant::now() + timeout;
+    let txid = rpc.send_to_address_instant(address, amount, None, None, None)?.txid;
+    loop {
+        // The wallet reports transactions locked by a ChainLock as locked too.
+        if rpc.get_transaction(&txid, None)?.info.instantlock == Some(true) {
+            return Ok(txid);
+        }
+        operations::check_cancelled(operation)?;
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(Error::InstantLockTimeout {
+                txid,
+                timeout,
+            });
+        }
+        thread::sleep(cmp::min(INSTANT_LOCK_POLL_INTERVAL, deadline - now));
+    }
+}
+
+/// Sign all the inputs of `unsigned` with the wallet.
+fn sign_with_wallet<C: RpcApi>(rpc: &C, unsigned: String) -> Result<Transaction> {
+    let signed = rpc.sign_raw_transaction_with_wallet(unsigned, None, None)?;
+    if !signed.complete {
+        return Err(Error::IncompleteSignature(signed.errors.unwrap_or_default()));
+    }
+    Ok(signed.transaction()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use operations::Operations;
+    use serde_json;
+    use std::cell::{Cell, RefCell};
+    use std::str::FromStr;
+    use testutil::MockRpc;
+
+    /// A node whose wallet has no funds, recording the arguments of
+    /// `createrawtransaction`.
+    struct EmptyWallet {
+        created: RefCell<Vec<serde_json::Value>>,
+    }
+
+    impl MockRpc for EmptyWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            match cmd {
+                "createrawtransaction" => {
+                    self.created.borrow_mut().extend_from_slice(args);
+                    Ok(r#""0300000000""#.to_owned())
+                }
+                "fundrawtransaction" => Err(Error::InsufficientFunds {
+                    needed: Amount::from_sat(1000),
+                    available: Amount::ZERO,
+                }),
+                _ => panic!("unexpected call: {}", cmd),
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_data_transaction() {
+        let node = EmptyWallet {
+            created: RefCell::new(vec![]),
+        };
+        let options = CreateTransactionOptions::new().with_locktime(100);
+        match node.create_data_transaction(b"\x01\x02", &options) {
+            Err(Error::InsufficientFunds {
+                ..
+            }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        let created = node.created.borrow();
+        assert_eq!(created[0], serde_json::Value::Array(vec![]));
+        assert_eq!(created[1]["data"], "0102");
+        assert_eq!(created[2], 100);
+
+        let data = [0; MAX_OP_RETURN_DATA + 1];
+        match node.create_data_transaction(&data, &options) {
+            Err(Error::DataTooLarge {
+                size,
+                max,
+            }) => assert_eq!((size, max), (MAX_OP_RETURN_DATA + 1, MAX_OP_RETURN_DATA)),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(created.len(), 3);
+    }
+
+    const TXID: &str = "b5d2f3bde4c4e5f2a7d6ae0aa2e1b0f0fbd4fcd1a0e6a9bc2a1b8f0c0d3e4f5a";
+
+    /// A wallet whose transactions are locked after they were polled `polls`
+    /// times.
+    struct LockingWallet {
+        polls: Cell<usize>,
+    }
+
+    impl MockRpc for LockingWallet {
+        fn respond(&self, cmd: &str, args: &[serde_json::Value]) -> Result<String> {
+            let json = match cmd {
+                "getnetworkinfo" => r#"{"version": 200000}"#.to_owned(),
+                "sendtoaddress" => format!(r#""{}""#, TXID),
+                "gettransaction" => {
+                    assert_eq!(args[0], TXID);
+                    let polls = self.polls.get();
+                    self.polls.set(polls.saturating_sub(1));
+                    format!(
+                        r#"{{"confirmations": 0, "txid": "{}", "time": 0, "timereceived": 0,
+                             "bip125-replaceable": "no", "walletconflicts": [],
+                             "instantlock": {}, "amount": -1.0, "details": [], "hex": "00"}}"#,
+                        TXID,
+                        polls == 0
+                    )
+                }
+                _ => panic!("unexpected call: {}", cmd),
+            };
+            Ok(json)
+        }
+    }
+
+    #[test]
+    fn test_send_and_wait_for_islock() {
+        let address = Address::from_str("yikEZUFcNj9zzCsvHGPr1SJwwDyF2ZvDgw").unwrap();
+        let wallet = LockingWallet {
+            polls: Cell::new(2),
+        };
+        let txid = wallet
+            .send_and_wait_for_islock(&address, Amount::ONE_BTC, Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(txid.to_string(), TXID);
+
+        let wallet = LockingWallet {
+            polls: Cell::new(usize::max_value()),
+        };
+        let timeout = Duration::from_millis(10);
+        match wallet.send_and_wait_for_islock(&address, Amount::ONE_BTC, timeout) {
+            Err(Error::InstantLockTimeout {
+                txid,
+                timeout: t,
+            }) => {
+                assert_eq!(txid.to_string(), TXID);
+                assert_eq!(t, timeout);
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let ops = Operations::new();
+        let op = ops.register("payment");
+        ops.cancel(op.id());
+        let timeout = Duration::from_secs(10);
+        match send_and_wait_for_islock(&wallet, &address, Amount::ONE_BTC, timeout, Some(&op)) {
+            Err(Error::Cancelled) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
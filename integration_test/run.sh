@@ -34,6 +34,8 @@ bitcoind -regtest $BLOCKFILTERARG $FALLBACKFEEARG \
     -rpcport=12349 \
     -server=1 \
     -txindex=1 \
+    -rest=1 \
+    -zmqpubhashblock=tcp://127.0.0.1:28332 \
     -printtoconsole=0 &
 PID2=$!
 
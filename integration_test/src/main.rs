@@ -17,10 +17,18 @@ extern crate lazy_static;
 extern crate log;
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
 use dashcore_rpc::json;
 use dashcore_rpc::jsonrpc::error::Error as JsonRpcError;
-use dashcore_rpc::{Auth, Client, Error, RpcApi};
+use dashcore_rpc::{
+    get_blocks_parallel, with_descriptor_checksum, Auth, BloomFilter, CachedClient, ChainEvent,
+    ChainTracker, Client, CreateTransactionOptions, Direction, Error, ExportRecord, FixedInterval,
+    ForkMonitor, MasternodeList, MempoolStatus, MultiClient, NodeHealth, NodeRole, OutputOwnership,
+    ParanoidClient, PaymentFailure, ProposalBuilder, RebroadcastEvent, Rebroadcaster, RestClient,
+    RpcApi, Signer, SigningInput, UtxoSnapshot, WalletDump, WalletEvent, WalletSync, Watcher,
+};
 
 use dashcore::consensus::encode::{deserialize, serialize};
 use dashcore::hashes::hex::{FromHex, ToHex};
@@ -62,22 +70,14 @@ impl log::Log for StdLogger {
 
 static LOGGER: StdLogger = StdLogger;
 
-/// Assert that the call returns a "deprecated" error.
-macro_rules! assert_deprecated {
-    ($call:expr) => {
-        match $call.unwrap_err() {
-            Error::JsonRpc(JsonRpcError::Rpc(ref e)) if e.code == -32 => {}
-            e => panic!("expected deprecated error for {}, got: {}", stringify!($call), e),
-        }
-    };
-}
-
-/// Assert that the call returns a "method not found" error.
-macro_rules! assert_not_found {
+/// Assert that the call fails because the node does not support it.
+macro_rules! assert_unsupported {
     ($call:expr) => {
         match $call.unwrap_err() {
-            Error::JsonRpc(JsonRpcError::Rpc(ref e)) if e.code == -32601 => {}
-            e => panic!("expected method not found error for {}, got: {}", stringify!($call), e),
+            Error::UnsupportedByNode {
+                ..
+            } => {}
+            e => panic!("expected unsupported error for {}, got: {}", stringify!($call), e),
         }
     };
 }
@@ -153,23 +153,56 @@ fn main() {
     test_get_address_info(&cl);
     test_set_label(&cl);
     test_send_to_address(&cl);
+    test_send_to_address_instant(&cl);
+    test_send_and_wait_for_islock(&cl);
     test_get_received_by_address(&cl);
+    test_get_received_by_label(&cl);
     test_list_unspent(&cl);
     test_get_difficulty(&cl);
     test_get_connection_count(&cl);
     test_get_raw_transaction(&cl);
+    test_cached_client(&cl);
+    test_get_tx_with_prevouts(&cl);
+    test_get_tx_fee(&cl);
+    test_get_tx_confirmation_status(&cl);
+    test_get_tx_chain_locks(&cl);
     test_get_raw_mempool(&cl);
     test_get_transaction(&cl);
     test_list_transactions(&cl);
+    test_export_transactions(&cl);
     test_list_since_block(&cl);
     test_get_tx_out(&cl);
+    test_get_tx_outs_at_tip(&cl);
+    test_classify_outputs(&cl);
     test_get_tx_out_proof(&cl);
+    test_verify_payment(&cl);
+    test_get_merkle_blocks(&cl);
+    test_iter_blocks(&cl);
+    test_get_blocks_parallel();
+    test_chain_tracker(&cl);
+    test_wallet_sync(&cl);
+    test_watcher(&cl);
     test_get_mempool_entry(&cl);
+    test_iter_mempool_entries(&cl);
+    test_mempool_fee_histogram(&cl);
     test_lock_unspent_unlock_unspent(&cl);
+    test_coin_selector(&cl);
+    test_create_and_sign_transaction(&cl);
+    test_create_and_sign_transaction_with_signer(&cl);
+    test_create_data_transaction(&cl);
+    test_get_conflicts(&cl);
+    test_advance_time_and_generate(&cl);
+    test_mine_block_with_transactions(&cl);
     test_get_block_filter(&cl);
+    test_get_index_info(&cl);
     test_sign_raw_transaction_with_send_raw_transaction(&cl);
     test_invalidate_block_reconsider_block(&cl);
+    test_import_pruned_funds(&cl);
+    test_watch_only(&cl);
     test_key_pool_refill(&cl);
+    test_upgrade_to_hd_dump_hd_info(&cl);
+    test_dump_wallet(&cl);
+    test_dump_tx_out_set(&cl);
     test_create_raw_transaction(&cl);
     test_fund_raw_transaction(&cl);
     test_test_mempool_accept(&cl);
@@ -177,6 +210,7 @@ fn main() {
     test_wallet_process_psbt(&cl);
     test_combine_psbt(&cl);
     test_finalize_psbt(&cl);
+    test_utxo_update_psbt(&cl);
     test_list_received_by_address(&cl);
     test_scantxoutset(&cl);
     test_import_public_key(&cl);
@@ -185,24 +219,39 @@ fn main() {
     test_import_address_script(&cl);
     test_estimate_smart_fee(&cl);
     test_ping(&cl);
+    test_auth_failed();
+    test_multi_client(&cl);
+    test_paranoid_client(&cl);
     test_get_peer_info(&cl);
     test_rescan_blockchain(&cl);
     test_create_wallet(&cl);
+    test_list_wallet_dir(&cl);
     test_get_tx_out_set_info(&cl);
     test_get_chain_tips(&cl);
+    test_fork_monitor(&cl);
+    test_submit_chain_lock(&cl);
+    test_mnauth(&cl);
+    test_verify_chain(&cl);
     test_get_net_totals(&cl);
     test_get_network_hash_ps(&cl);
     test_uptime(&cl);
+    test_get_zmq_notifications(&cl);
+    test_capabilities(&cl);
+    test_validate_address(&cl);
+    test_address_validation(&cl);
+    test_block_verification(&cl);
+    test_send_raw_transaction_max_fee(&cl);
+    test_rebroadcaster(&cl);
+    test_wait_until_warmed_up(&cl);
+    test_rest_client(&cl);
     test_getblocktemplate(&cl);
     //TODO import_multi(
-    //TODO verify_message(
     //TODO wait_for_new_block(&self, timeout: u64) -> Result<json::BlockRef> {
     //TODO wait_for_block(
-    //TODO get_descriptor_info(&self, desc: &str) -> Result<json::GetDescriptorInfoResult> {
-    //TODO derive_addresses(&self, descriptor: &str, range: Option<[u32; 2]>) -> Result<Vec<Address>> {
+    test_descriptors(&cl);
     //TODO encrypt_wallet(&self, passphrase: &str) -> Result<()> {
     //TODO get_by_id<T: queryable::Queryable<Self>>(
-    //TODO add_multisig_address(
+    test_add_multisig_address_create_multisig(&cl);
     //TODO load_wallet(&self, wallet: &str) -> Result<json::LoadWalletResult> {
     //TODO unload_wallet(&self, wallet: Option<&str>) -> Result<()> {
     //TODO backup_wallet(&self, destination: Option<&str>) -> Result<()> {
@@ -215,14 +264,18 @@ fn main() {
     test_stop(&cl);
     test_get_masternode_count(&cl);
     test_get_masternode_list(&cl);
-    test_get_masternode_outputs(&cl);
-    test_get_masternode_payments(&cl);
+    test_masternode_outputs(&cl);
+    test_collateral_proof(&cl);
+    test_masternode_payments(&cl);
+    test_masternode_payee(&cl);
     test_get_masternode_status(&cl);
     test_get_masternode_winners(&cl);
     test_get_quorum_list(&cl);
     test_get_quorum_info(&cl);
     test_get_quorum_dkgstatus(&cl);
     test_get_quorum_sign(&cl);
+    test_quorum_sign_and_wait(&cl);
+    test_quorum_select_and_verify(&cl);
     test_get_quorum_getrecsig(&cl);
     test_get_quorum_hasrecsig(&cl);
     test_get_quorum_isconflicting(&cl);
@@ -230,13 +283,20 @@ fn main() {
     test_get_quorum_rotationinfo(&cl);
     test_get_quorum_selectquorum(&cl);
     test_get_quorum_verify(&cl);
+    test_submit_proposal(&cl);
+    test_next_superblocks(&cl);
     test_get_bls_fromsecret(&cl);
     test_get_bls_generate(&cl);
+    test_get_credit_pool_balance(&cl);
+    test_cb_tx(&cl);
+    test_get_asset_unlock_statuses(&cl);
     test_get_protx_diff(&cl);
+    test_masternode_list(&cl);
     test_get_protx_info(&cl);
     test_get_protx_list(&cl);
     test_get_protx_register(&cl);
     test_get_protx_register_fund(&cl);
+    test_get_protx_register_fund_evo(&cl);
 }
 
 fn test_get_network_info(cl: &Client) {
@@ -270,20 +330,11 @@ fn test_dump_private_key(cl: &Client) {
 }
 
 fn test_generate(cl: &Client) {
-    if version() < 180000 {
-        let blocks = cl.generate(4, None).unwrap();
-        assert_eq!(blocks.len(), 4);
-        let blocks = cl.generate(6, Some(45)).unwrap();
-        assert_eq!(blocks.len(), 6);
-    } else if version() < 190000 {
-        assert_deprecated!(cl.generate(5, None));
-    } else if version() < 210000 {
-        assert_not_found!(cl.generate(5, None));
-    } else {
-        // Bitcoin Core v0.21 appears to return this with a generic -1 error code,
-        // rather than the expected -32601 code (RPC_METHOD_NOT_FOUND).
-        assert_error_message!(cl.generate(5, None), -1, "replaced by the -generate cli option");
-    }
+    // Nodes that deprecated `generate` get `generatetoaddress` instead.
+    let blocks = cl.generate(4, None).unwrap();
+    assert_eq!(blocks.len(), 4);
+    let blocks = cl.generate(6, Some(45)).unwrap();
+    assert_eq!(blocks.len(), 6);
 }
 
 fn test_get_balance_generate_to_address(cl: &Client) {
@@ -329,6 +380,19 @@ fn test_get_block(cl: &Client) {
     let info = cl.get_block_info(&tip).unwrap();
     assert_eq!(info.hash, tip);
     assert_eq!(info.confirmations, 1);
+
+    match cl.get_block_with_verbosity(&tip, json::BlockVerbosity::Raw).unwrap() {
+        json::GetBlockVerbosityResult::Raw(b) => assert_eq!(b.block_hash(), tip),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match cl.get_block_with_verbosity(&tip, json::BlockVerbosity::Full).unwrap() {
+        json::GetBlockVerbosityResult::Full(full) => {
+            assert_eq!(full.hash, tip);
+            let txids: Vec<_> = full.tx.iter().map(|tx| tx.txid).collect();
+            assert_eq!(txids, info.tx);
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
 }
 
 fn test_get_block_header_get_block_header_info(cl: &Client) {
@@ -425,6 +489,26 @@ fn test_send_to_address(cl: &Client) {
     let _ = cl.send_to_address(&addr, btc(1), None, None, None, None, None, Some(est)).unwrap();
 }
 
+fn test_send_to_address_instant(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let res = cl.send_to_address_instant(&addr, btc(1), None, None, None).unwrap();
+    let expected = if version() < 150000 {
+        json::InstantSendPath::Legacy
+    } else {
+        json::InstantSendPath::Automatic
+    };
+    assert_eq!(res.instant_send, expected);
+    assert_eq!(cl.get_transaction(&res.txid, None).unwrap().info.txid, res.txid);
+}
+
+fn test_send_and_wait_for_islock(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let timeout = Duration::from_secs(30);
+    let txid = cl.send_and_wait_for_islock(&addr, btc(1), timeout).unwrap();
+    let tx = cl.get_transaction(&txid, None).unwrap();
+    assert_eq!(tx.info.instantlock, Some(true));
+}
+
 fn test_get_received_by_address(cl: &Client) {
     let addr = cl.get_new_address(None, None).unwrap();
     let _ = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
@@ -435,6 +519,17 @@ fn test_get_received_by_address(cl: &Client) {
     assert_eq!(cl.get_received_by_address(&addr, None).unwrap(), btc(1));
 }
 
+fn test_get_received_by_label(cl: &Client) {
+    let first = cl.get_new_address(Some("donations"), None).unwrap();
+    let second = cl.get_new_address(Some("donations"), None).unwrap();
+    let _ = cl.send_to_address(&first, btc(1), None, None, None, None, None, None).unwrap();
+    let _ = cl.send_to_address(&second, btc(2), None, None, None, None, None, None).unwrap();
+    assert_eq!(cl.get_received_by_label("donations", Some(0)).unwrap(), btc(3));
+    assert_eq!(cl.get_received_by_label("donations", None).unwrap(), btc(0));
+    let _ = cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap();
+    assert_eq!(cl.get_received_by_label("donations", None).unwrap(), btc(3));
+}
+
 fn test_list_unspent(cl: &Client) {
     let addr = cl.get_new_address(None, None).unwrap();
     let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
@@ -444,11 +539,8 @@ fn test_list_unspent(cl: &Client) {
     assert_eq!(unspent[0].amount, btc(1));
 
     let txid = cl.send_to_address(&addr, btc(7), None, None, None, None, None, None).unwrap();
-    let options = json::ListUnspentQueryOptions {
-        minimum_amount: Some(btc(7)),
-        maximum_amount: Some(btc(7)),
-        ..Default::default()
-    };
+    let options =
+        json::ListUnspentQueryOptions::new().minimum_amount(btc(7)).maximum_amount(btc(7));
     let unspent = cl.list_unspent(Some(0), None, Some(&[&addr]), None, Some(options)).unwrap();
     assert_eq!(unspent.len(), 1);
     assert_eq!(unspent[0].txid, txid);
@@ -479,6 +571,81 @@ fn test_get_raw_transaction(cl: &Client) {
     let _ = cl.get_raw_transaction_info(&txid, Some(&blocks[0])).unwrap();
 }
 
+fn test_get_tx_confirmation_status(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let status = cl.get_tx_confirmation_status(&txid).unwrap();
+    assert!(
+        status == json::ConfirmationStatus::Unconfirmed
+            || status == json::ConfirmationStatus::InstantLocked
+    );
+
+    cl.generate_to_address(1, &addr).unwrap();
+    match cl.get_tx_confirmation_status(&txid).unwrap() {
+        json::ConfirmationStatus::Confirmed {
+            confirmations,
+            ..
+        }
+        | json::ConfirmationStatus::ChainLocked {
+            confirmations,
+        } => assert_eq!(confirmations, 1),
+        s => panic!("unexpected status: {:?}", s),
+    }
+}
+
+fn test_get_tx_chain_locks(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let mined = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let height = cl.get_block_count().unwrap() + 1;
+    cl.generate_to_address(1, &addr).unwrap();
+    let pending = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let unknown = Txid::hash(&[1]);
+
+    let statuses = cl.get_tx_chain_locks(&[mined, pending, unknown]).unwrap();
+    assert_eq!(statuses.len(), 3);
+    assert_eq!(statuses[0].block_height(), Some(height as u32));
+    assert!(statuses[1].mempool);
+    assert_eq!(statuses[1].block_height(), None);
+    assert!(!statuses[2].is_known());
+
+    // More txids than one call takes.
+    let many = vec![mined; 150];
+    assert!(cl.get_tx_chain_locks(&many).unwrap().iter().all(|s| s == &statuses[0]));
+}
+
+fn test_cached_client(cl: &Client) {
+    let cached = CachedClient::new(Client::new(&get_rpc_url(), get_auth()).unwrap());
+    let tip = cl.get_best_block_hash().unwrap();
+    let block = cl.get_block(&tip).unwrap();
+    let txid = block.txdata[0].txid();
+    assert_eq!(cached.get_raw_transaction(&txid, None).unwrap(), block.txdata[0]);
+    assert_eq!(cached.get_raw_transaction(&txid, None).unwrap(), block.txdata[0]);
+    assert_eq!(cached.get_block_header(&tip).unwrap(), block.header);
+    assert!(cached.invalidate_tx(&txid));
+    assert!(!cached.invalidate_tx(&txid));
+}
+
+fn test_get_tx_with_prevouts(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let resolved = cl.get_tx_with_prevouts(&txid).unwrap();
+    assert_eq!(resolved.prevouts.len(), resolved.tx.input.len());
+    for (input, prevout) in resolved.tx.input.iter().zip(&resolved.prevouts) {
+        let parent = cl.get_raw_transaction(&input.previous_output.txid, None).unwrap();
+        assert_eq!(prevout.as_ref(), Some(&parent.output[input.previous_output.vout as usize]));
+    }
+}
+
+fn test_get_tx_fee(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let fee = cl.get_transaction(&txid, None).unwrap().fee.unwrap();
+    assert_eq!(cl.get_tx_fee(&txid).unwrap().to_signed().unwrap(), -fee);
+
+    let coinbase = cl.get_block(&cl.get_best_block_hash().unwrap()).unwrap().txdata[0].txid();
+    assert_eq!(cl.get_tx_fee(&coinbase).unwrap(), Amount::ZERO);
+}
+
 fn test_get_raw_mempool(cl: &Client) {
     let _ = cl.get_raw_mempool().unwrap();
 }
@@ -502,10 +669,43 @@ fn test_list_transactions(cl: &Client) {
     let _ = cl.list_transactions(None, None, None, Some(true)).unwrap();
 }
 
+fn test_export_transactions(cl: &Client) {
+    let txid =
+        cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap();
+    let records = cl.export_transactions(None, None).unwrap();
+    assert!(records.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    let sent = records.iter().find(|r| r.txid == txid).unwrap();
+    assert_eq!(sent.direction, Direction::Outgoing);
+    assert_eq!(sent.amount, -btc(1).to_signed().unwrap());
+    assert!(sent.fee.is_some());
+
+    let mut csv = Vec::new();
+    ExportRecord::write_csv(&records, &mut csv).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap().lines().count(), records.len() + 1);
+}
+
 fn test_list_since_block(cl: &Client) {
     let r = cl.list_since_block(None, None, None, None).unwrap();
     assert_eq!(r.lastblock, cl.get_best_block_hash().unwrap());
     assert!(!r.transactions.is_empty());
+
+    // A transaction of a block that is disconnected is listed as removed.
+    let addr = cl.get_new_address(None, None).unwrap();
+    let since = cl.get_best_block_hash().unwrap();
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let block = cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap()[0];
+    let r = cl.list_since_block(Some(&since), Some(1), None, Some(true)).unwrap();
+    assert_eq!(r.lastblock, block);
+    assert!(r.transactions.iter().any(|t| t.info.txid == txid));
+    assert!(r.removed.is_empty());
+
+    cl.invalidate_block(&block).unwrap();
+    let r = cl.list_since_block(Some(&block), Some(1), Some(false), Some(true)).unwrap();
+    assert_eq!(r.lastblock, since);
+    assert!(r.removed.iter().any(|t| t.info.txid == txid));
+    let r = cl.list_since_block(Some(&block), None, None, Some(false)).unwrap();
+    assert!(r.removed.is_empty());
+    cl.reconsider_block(&block).unwrap();
 }
 
 fn test_get_tx_out(cl: &Client) {
@@ -514,10 +714,37 @@ fn test_get_tx_out(cl: &Client) {
     let out = cl.get_tx_out(&txid, 0, Some(false)).unwrap();
     assert!(out.is_none());
     let out = cl.get_tx_out(&txid, 0, Some(true)).unwrap();
-    assert!(out.is_some());
+    assert!(out.unwrap().script_kind().address().is_some());
     let _ = cl.get_tx_out(&txid, 0, None).unwrap();
 }
 
+fn test_get_tx_outs_at_tip(cl: &Client) {
+    let txid =
+        cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap();
+    let tip = cl.get_best_block_hash().unwrap();
+    let at_tip = cl.get_tx_out_at_tip(&txid, 0, Some(true)).unwrap();
+    assert_eq!(at_tip.tip, tip);
+    assert_eq!(at_tip.output.unwrap().bestblock, tip);
+
+    let outpoints = [OutPoint::new(txid, 0), OutPoint::new(txid, 5)];
+    let read = cl.get_tx_outs_at_tip(&outpoints, Some(true), None).unwrap();
+    assert_eq!(read.tip, tip);
+    assert!(read.outputs[0].is_some());
+    assert!(read.outputs[1].is_none());
+}
+
+fn test_classify_outputs(cl: &Client) {
+    let txid =
+        cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap();
+    let tx = cl.get_transaction(&txid, None).unwrap().transaction().unwrap();
+    let outputs = cl.classify_outputs(&tx).unwrap();
+    assert_eq!(outputs.len(), tx.output.len());
+    let external = outputs.iter().find(|o| o.address.as_ref() == Some(&RANDOM_ADDRESS)).unwrap();
+    assert_eq!(external.ownership, OutputOwnership::External);
+    assert_eq!(external.amount, btc(1));
+    assert!(outputs.iter().any(|o| o.ownership == OutputOwnership::Change));
+}
+
 fn test_get_tx_out_proof(cl: &Client) {
     let txid1 =
         cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap();
@@ -528,6 +755,171 @@ fn test_get_tx_out_proof(cl: &Client) {
     assert!(!proof.is_empty());
 }
 
+fn test_verify_payment(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let blocks = cl.generate_to_address(2, &cl.get_new_address(None, None).unwrap()).unwrap();
+
+    let payment = cl.verify_payment(&txid, &addr, btc(1), 2).unwrap();
+    assert_eq!(payment.block_hash, blocks[0]);
+    assert_eq!(payment.confirmations, 2);
+    assert_eq!(payment.amount, btc(1));
+    match cl.verify_payment(&txid, &addr, btc(2), 1) {
+        Err(Error::InvalidPayment {
+            reason: PaymentFailure::InsufficientAmount {
+                ..
+            },
+            ..
+        }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match cl.verify_payment(&txid, &addr, btc(1), 3) {
+        Err(Error::InvalidPayment {
+            reason: PaymentFailure::NotConfirmed {
+                confirmations: 2,
+            },
+            ..
+        }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+fn test_get_merkle_blocks(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let block_hash = cl.generate_to_address(1, &addr).unwrap()[0];
+
+    let filter = BloomFilter::builder().add_address(&addr).build();
+    let blocks = cl.get_merkle_blocks(&filter, &block_hash, Some(1)).unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].header.block_hash(), block_hash);
+    let mut matches = vec![];
+    let mut indexes = vec![];
+    blocks[0].txn.extract_matches(&mut matches, &mut indexes).unwrap();
+    assert!(matches.contains(&txid));
+}
+
+fn test_iter_blocks(cl: &Client) {
+    let tip = cl.get_block_count().unwrap();
+    let blocks: Vec<_> = cl.iter_blocks(tip - 9..).map(|r| r.unwrap()).collect();
+    assert_eq!(blocks.len(), 10);
+    assert_eq!(blocks[0].0, tip - 9);
+    assert_eq!(blocks[9].1.block_hash(), cl.get_block_hash(tip).unwrap());
+    for pair in blocks.windows(2) {
+        assert_eq!(pair[1].1.header.prev_blockhash, pair[0].1.block_hash());
+    }
+    assert_eq!(cl.iter_blocks(tip - 3..tip).batch_size(2).count(), 3);
+}
+
+fn test_get_blocks_parallel() {
+    let cl = Arc::new(Client::new(&get_rpc_url(), get_auth()).unwrap());
+    let tip = cl.get_block_count().unwrap();
+    let blocks: Vec<_> = get_blocks_parallel(cl.clone(), .., 4).unwrap().collect();
+    assert_eq!(blocks.len() as u64, tip + 1);
+    for (height, block) in (0..).zip(&blocks) {
+        assert_eq!(block.as_ref().unwrap().0, height);
+    }
+    let last = blocks.last().unwrap().as_ref().unwrap();
+    assert_eq!(last.1.block_hash(), cl.get_block_hash(tip).unwrap());
+}
+
+fn test_chain_tracker(cl: &Client) {
+    let mut tracker = ChainTracker::new(10);
+    assert!(tracker.poll(cl).unwrap().is_empty());
+
+    let addr = cl.get_new_address(None, None).unwrap();
+    let blocks = cl.generate_to_address(2, &addr).unwrap();
+    let events = tracker.poll(cl).unwrap();
+    assert_eq!(events.len(), 2);
+    match events[1] {
+        ChainEvent::BlockConnected {
+            hash,
+            ..
+        } => assert_eq!(hash, blocks[1]),
+        ref e => panic!("unexpected event: {:?}", e),
+    }
+
+    cl.invalidate_block(&blocks[1]).unwrap();
+    cl.generate_to_address(2, &addr).unwrap();
+    let events = tracker.poll(cl).unwrap();
+    assert_eq!(events.len(), 3);
+    match events[0] {
+        ChainEvent::BlockDisconnected {
+            hash,
+            ..
+        } => assert_eq!(hash, blocks[1]),
+        ref e => panic!("unexpected event: {:?}", e),
+    }
+}
+
+fn test_wallet_sync(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let mut sync = WalletSync::starting_at(1, cl.get_best_block_hash().unwrap());
+    assert!(sync.poll(cl).unwrap().is_empty());
+
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let events = sync.poll(cl).unwrap();
+    let find = |events: &[WalletEvent], incoming: bool| {
+        events.iter().any(|e| match *e {
+            WalletEvent::Incoming(ref tx) => incoming && tx.info.txid == txid,
+            WalletEvent::Outgoing(ref tx) => !incoming && tx.info.txid == txid,
+            _ => false,
+        })
+    };
+    assert!(find(&events, true));
+    assert!(find(&events, false));
+
+    // Reported again once confirmed.
+    let block = cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap()[0];
+    assert!(find(&sync.poll(cl).unwrap(), true));
+    assert_eq!(sync.last_block(), Some(block));
+
+    cl.invalidate_block(&block).unwrap();
+    let events = sync.poll(cl).unwrap();
+    assert!(events.iter().any(|e| match *e {
+        WalletEvent::Conflicted(ref tx) => tx.info.txid == txid,
+        _ => false,
+    }));
+    cl.reconsider_block(&block).unwrap();
+}
+
+fn test_watcher(cl: &Client) {
+    let sk = PrivateKey {
+        network: Network::Regtest,
+        inner: secp256k1::SecretKey::new(&mut secp256k1::rand::thread_rng()),
+        compressed: true,
+    };
+    let addr = Address::p2pkh(&sk.public_key(&SECP), Network::Regtest);
+    let mut watcher = Watcher::new(2);
+    watcher.watch(cl, addr.clone()).unwrap();
+    assert!(watcher.poll(cl).unwrap().is_empty());
+
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let deposits = watcher.poll(cl).unwrap();
+    assert_eq!(deposits.len(), 1);
+    assert_eq!(deposits[0].address, addr);
+    assert_eq!(deposits[0].outpoint.txid, txid);
+    assert_eq!(deposits[0].amount, btc(1));
+    assert!(watcher.poll(cl).unwrap().is_empty());
+
+    cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap();
+    let deposits = watcher.poll(cl).unwrap();
+    assert_eq!(deposits.len(), 1);
+    match deposits[0].status {
+        json::ConfirmationStatus::Confirmed {
+            confirmations: 1,
+            ..
+        } => {}
+        s => panic!("unexpected status: {:?}", s),
+    }
+
+    // Settled after two confirmations.
+    cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap();
+    assert_eq!(watcher.poll(cl).unwrap().len(), 1);
+    cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap();
+    assert!(watcher.poll(cl).unwrap().is_empty());
+}
+
 fn test_get_mempool_entry(cl: &Client) {
     let txid =
         cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap();
@@ -538,23 +930,194 @@ fn test_get_mempool_entry(cl: &Client) {
     assert!(cl.get_mempool_entry(&fake).is_err());
 }
 
+fn test_iter_mempool_entries(cl: &Client) {
+    let txids: Vec<_> = (0..3)
+        .map(|_| {
+            cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap()
+        })
+        .collect();
+    let mempool = cl.get_raw_mempool().unwrap();
+    let entries: Vec<_> =
+        cl.iter_mempool_entries().unwrap().batch_size(2).map(|r| r.unwrap()).collect();
+    assert_eq!(entries.len(), mempool.len());
+    for txid in &txids {
+        let &(_, ref entry) = entries.iter().find(|e| e.0 == *txid).unwrap();
+        assert_eq!(*entry, cl.get_mempool_entry(txid).unwrap());
+    }
+}
+
+fn test_mempool_fee_histogram(cl: &Client) {
+    cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap();
+    let mempool = cl.get_raw_mempool().unwrap();
+    let buckets = cl.mempool_fee_histogram(1000).unwrap();
+    assert_eq!(buckets.iter().map(|b| b.count).sum::<u64>(), mempool.len() as u64);
+    assert!(buckets.windows(2).all(|w| w[0].max_fee_rate <= w[1].min_fee_rate));
+}
+
 fn test_lock_unspent_unlock_unspent(cl: &Client) {
     let addr = cl.get_new_address(None, None).unwrap();
     let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
 
     assert!(cl.lock_unspent(&[OutPoint::new(txid, 0)]).unwrap());
+    assert_eq!(cl.list_lock_unspent().unwrap(), [OutPoint::new(txid, 0)]);
     assert!(cl.unlock_unspent(&[OutPoint::new(txid, 0)]).unwrap());
+    assert!(cl.list_lock_unspent().unwrap().is_empty());
 
     assert!(cl.lock_unspent(&[OutPoint::new(txid, 0)]).unwrap());
     assert!(cl.unlock_unspent_all().unwrap());
 }
 
+fn test_coin_selector(cl: &Client) {
+    let unspent = cl.list_unspent(Some(1), None, None, None, None).unwrap();
+    let locked = OutPoint::new(unspent[0].txid, unspent[0].vout);
+    assert!(cl.lock_unspent(&[locked]).unwrap());
+
+    let selection = cl.coin_selector(Some(1)).unwrap().select(btc(1)).unwrap();
+    assert!(!selection.outpoints().contains(&locked));
+    assert_eq!(selection.input_amount(), btc(1) + selection.fee + selection.change);
+    assert!(cl.unlock_unspent(&[locked]).unwrap());
+}
+
+fn test_create_and_sign_transaction(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let recipients = [(addr.clone(), btc(1))];
+    for fund_with_node in &[false, true] {
+        let options = CreateTransactionOptions::new().with_fund_with_node(*fund_with_node);
+        let tx = cl.create_and_sign_transaction(&recipients, &options).unwrap();
+        let paid = tx.output.iter().find(|o| o.script_pubkey == addr.script_pubkey()).unwrap();
+        assert_eq!(paid.value, btc(1).as_sat());
+        let txid = cl.send_raw_transaction(&tx, None).unwrap();
+        assert_eq!(txid, tx.txid());
+    }
+}
+
+/// A signer with the keys of the default wallet, like a hardware wallet
+/// would have.
+struct WalletSigner<'a> {
+    wallet: &'a Client,
+}
+
+impl<'a> Signer for WalletSigner<'a> {
+    fn sign_transaction(
+        &self,
+        tx: &Transaction,
+        inputs: &[SigningInput],
+    ) -> Result<Transaction, Error> {
+        assert_eq!(inputs.len(), tx.input.len());
+        Ok(self.wallet.sign_raw_transaction_with_wallet(tx, None, None)?.transaction()?)
+    }
+
+    fn sign_psbt(&self, psbt: &str, _inputs: &[SigningInput]) -> Result<String, Error> {
+        Ok(self.wallet.wallet_process_psbt(psbt, Some(true), None, None)?.psbt)
+    }
+}
+
+fn test_create_and_sign_transaction_with_signer(cl: &Client) {
+    cl.create_wallet("testsigner", Some(true), None, None, None).unwrap();
+    let wallet_client_url = format!("{}{}", get_rpc_url(), "/wallet/testsigner");
+    let watch_only = Client::new(&wallet_client_url, get_auth()).unwrap();
+    let addr = cl.get_new_address(None, None).unwrap();
+    let info = cl.get_address_info(&addr).unwrap();
+    watch_only.import_public_key(&info.pubkey.unwrap(), None, Some(false)).unwrap();
+    cl.send_to_address(&addr, btc(2), None, None, None, None, None, None).unwrap();
+    cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap();
+
+    let signer = WalletSigner {
+        wallet: cl,
+    };
+    let recipients = [(RANDOM_ADDRESS.clone(), btc(1))];
+    let options = CreateTransactionOptions::new().with_change_address(addr.clone());
+    let tx =
+        watch_only.create_and_sign_transaction_with_signer(&recipients, &options, &signer).unwrap();
+    let inputs = watch_only.get_signing_inputs(&tx).unwrap();
+    assert_eq!(inputs[0].prevout.script_pubkey, addr.script_pubkey());
+    // Only the wallet with the keys knows their derivation paths.
+    assert_eq!(cl.get_signing_inputs(&tx).unwrap()[0].path, info.hd_key_path);
+    assert_eq!(watch_only.send_raw_transaction(&tx, None).unwrap(), tx.txid());
+    cl.unload_wallet(Some("testsigner")).unwrap();
+}
+
+fn test_create_data_transaction(cl: &Client) {
+    let data = b"anchored document hash";
+    let tx = cl.create_data_transaction(data, &CreateTransactionOptions::new()).unwrap();
+    let kinds: Vec<_> =
+        tx.output.iter().map(|o| json::ScriptKind::from_script(&o.script_pubkey, *NET)).collect();
+    assert!(kinds.contains(&Some(json::ScriptKind::OpReturn(data.to_vec()))));
+    assert_eq!(cl.send_raw_transaction(&tx, None).unwrap(), tx.txid());
+
+    match cl.create_data_transaction(&[0; 81], &CreateTransactionOptions::new()) {
+        Err(Error::DataTooLarge {
+            size: 81,
+            ..
+        }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+fn test_get_conflicts(cl: &Client) {
+    let txid =
+        cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap();
+    let report = cl.get_conflicts(&txid).unwrap();
+    assert_eq!(report.mempool, MempoolStatus::InMempool);
+    assert!(report.wallet_conflicts.is_empty());
+    assert!(!report.is_double_spent());
+
+    cl.generate_to_address(1, &cl.get_new_address(None, None).unwrap()).unwrap();
+    assert_eq!(cl.get_conflicts(&txid).unwrap().mempool, MempoolStatus::Mined);
+}
+
+fn test_advance_time_and_generate(cl: &Client) {
+    let tip = cl.get_block_header(&cl.get_best_block_hash().unwrap()).unwrap();
+    let addr = cl.get_new_address(None, None).unwrap();
+    let blocks = cl.advance_time_and_generate(3600, 2, &addr).unwrap();
+    assert_eq!(blocks.len(), 2);
+    let mined = cl.get_block_header(&blocks[0]).unwrap();
+    assert!(mined.time >= tip.time + 3600);
+    cl.set_mock_time(0).unwrap();
+}
+
+fn test_mine_block_with_transactions(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let options = CreateTransactionOptions::new();
+    let tx = cl.create_and_sign_transaction(&[(RANDOM_ADDRESS.clone(), btc(1))], &options).unwrap();
+    let hash = cl.mine_block_with_transactions(&addr, &[tx.clone()]).unwrap();
+    assert_eq!(cl.get_best_block_hash().unwrap(), hash);
+    let info = cl.get_block_info(&hash).unwrap();
+    assert!(info.tx.contains(&tx.txid()));
+}
+
 fn test_get_block_filter(cl: &Client) {
-    let blocks = cl.generate_to_address(7, &cl.get_new_address(None, None).unwrap()).unwrap();
-    if version() >= 190000 {
-        let _ = cl.get_block_filter(&blocks[0]).unwrap();
+    let addr = cl.get_new_address(None, None).unwrap();
+    let blocks = cl.generate_to_address(7, &addr).unwrap();
+    if version() >= 180000 {
+        let filter = cl.get_block_filter(&blocks[0], Some(json::BlockFilterType::Basic)).unwrap();
+        assert_eq!(filter, cl.get_block_filter(&blocks[0], None).unwrap());
+        assert!(filter.match_any_script(&blocks[0], Some(&addr.script_pubkey())).unwrap());
+        assert!(cl.block_filter_matches(&blocks[0], &[addr.script_pubkey()]).unwrap());
     } else {
-        assert_not_found!(cl.get_block_filter(&blocks[0]));
+        assert_unsupported!(cl.get_block_filter(&blocks[0], None));
+    }
+}
+
+fn test_get_index_info(cl: &Client) {
+    match cl.get_index_info(None) {
+        Ok(indexes) => {
+            // The node runs with `-txindex`.
+            assert!(indexes.contains_key("txindex"));
+            assert!(cl.get_index_info(Some("txindex")).unwrap().contains_key("txindex"));
+            assert!(cl.get_index_info(Some("coinstatsindex")).unwrap().is_empty());
+            match cl.require_indexes(&["coinstatsindex"]) {
+                Err(Error::IndexUnavailable {
+                    best_block_height: None,
+                    ..
+                }) => {}
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+        Err(Error::UnsupportedByNode {
+            ..
+        }) => {}
+        Err(e) => panic!("unexpected error: {}", e),
     }
 }
 
@@ -600,7 +1163,7 @@ fn test_sign_raw_transaction_with_send_raw_transaction(cl: &Client) {
     };
     let res = cl.sign_raw_transaction_with_wallet(&tx, Some(&[input]), None).unwrap();
     assert!(res.complete);
-    let txid = cl.send_raw_transaction(&res.transaction().unwrap()).unwrap();
+    let txid = cl.send_raw_transaction(&res.transaction().unwrap(), None).unwrap();
 
     let tx = Transaction {
         version: 1,
@@ -623,7 +1186,7 @@ fn test_sign_raw_transaction_with_send_raw_transaction(cl: &Client) {
     let res =
         cl.sign_raw_transaction_with_key(&tx, &[sk], None, Some(EcdsaSighashType::All.into())).unwrap();
     assert!(res.complete);
-    let _ = cl.send_raw_transaction(&res.transaction().unwrap()).unwrap();
+    let _ = cl.send_raw_transaction(&res.transaction().unwrap(), None).unwrap();
 }
 
 fn test_invalidate_block_reconsider_block(cl: &Client) {
@@ -632,11 +1195,160 @@ fn test_invalidate_block_reconsider_block(cl: &Client) {
     cl.reconsider_block(&hash).unwrap();
 }
 
+fn test_add_multisig_address_create_multisig(cl: &Client) {
+    let addr1 = cl.get_new_address(None, None).unwrap();
+    let addr2 = cl.get_new_address(None, None).unwrap();
+    let pk1 = cl.get_address_info(&addr1).unwrap().pubkey.unwrap();
+    let pk2 = cl.get_address_info(&addr2).unwrap().pubkey.unwrap();
+
+    let created = cl.create_multisig(2, &[pk1, pk2]).unwrap();
+    let keys = [json::PubKeyOrAddress::PubKey(&pk1), json::PubKeyOrAddress::Address(&addr2)];
+    let added = cl.add_multisig(2, &keys, Some("multisig")).unwrap();
+    assert_eq!(created.address, added.address);
+    assert_eq!(created.redeem_script, added.redeem_script);
+}
+
+fn test_import_pruned_funds(cl: &Client) {
+    cl.create_wallet("testpruned", Some(true), None, None, None).unwrap();
+    let wallet_client_url = format!("{}{}", get_rpc_url(), "/wallet/testpruned");
+    let wallet = Client::new(&wallet_client_url, get_auth()).unwrap();
+    let addr = cl.get_new_address(None, None).unwrap();
+    wallet.import_address(&addr, None, Some(false)).unwrap();
+
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    let block = cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap()[0];
+    let proof = cl.get_tx_out_proof(&[txid], Some(&block)).unwrap();
+    let tx = cl.get_raw_transaction(&txid, Some(&block)).unwrap();
+    wallet.import_pruned_funds(&tx, &proof).unwrap();
+    assert_eq!(wallet.get_transaction(&txid, Some(true)).unwrap().info.txid, txid);
+
+    wallet.remove_pruned_funds(&txid).unwrap();
+    assert!(wallet.get_transaction(&txid, Some(true)).is_err());
+    cl.unload_wallet(Some("testpruned")).unwrap();
+}
+
+fn test_descriptors(cl: &Client) {
+    cl.create_descriptor_wallet("testdescriptors", None, None, None).unwrap();
+    let wallet_client_url = format!("{}{}", get_rpc_url(), "/wallet/testdescriptors");
+    let wallet = Client::new(&wallet_client_url, get_auth()).unwrap();
+
+    // The addresses of the receiving descriptor the wallet generated.
+    let listed = wallet.list_descriptors(None).unwrap();
+    assert_eq!(listed.wallet_name, "testdescriptors");
+    let receiving = listed
+        .descriptors
+        .iter()
+        .find(|d| d.active && d.internal == Some(false) && d.range.is_some())
+        .unwrap();
+    let addr = wallet.get_new_address(None, None).unwrap();
+    let derived = cl.derive_addresses(&receiving.descriptor, Some([0, 1])).unwrap();
+    assert_eq!(derived.len(), 2);
+    assert!(derived.contains(&addr));
+    cl.unload_wallet(Some("testdescriptors")).unwrap();
+
+    // Watch an address of the default wallet.
+    cl.create_descriptor_wallet("testdescriptorswatch", Some(true), Some(true), None).unwrap();
+    let wallet_client_url = format!("{}{}", get_rpc_url(), "/wallet/testdescriptorswatch");
+    let wallet = Client::new(&wallet_client_url, get_auth()).unwrap();
+    let addr = cl.get_new_address(None, None).unwrap();
+    let pubkey = cl.get_address_info(&addr).unwrap().pubkey.unwrap();
+    let descriptor = format!("pkh({})", pubkey);
+    let info = cl.get_descriptor_info(&descriptor).unwrap();
+    let descriptor = with_descriptor_checksum(&descriptor).unwrap();
+    assert!(descriptor.ends_with(&format!("#{}", info.checksum)));
+    assert_eq!(cl.derive_addresses(&descriptor, None).unwrap(), vec![addr.clone()]);
+
+    let results = wallet
+        .import_descriptors(&[json::ImportDescriptorsRequest {
+            descriptor: &descriptor,
+            timestamp: json::ImportMultiRescanSince::Now,
+            label: Some("watched"),
+            ..Default::default()
+        }])
+        .unwrap();
+    assert!(results[0].success);
+    let listed = wallet.list_descriptors(None).unwrap();
+    assert!(listed.descriptors.iter().any(|d| d.descriptor == descriptor && !d.active));
+    assert_eq!(wallet.get_address_info(&addr).unwrap().is_watchonly, Some(true));
+    cl.unload_wallet(Some("testdescriptorswatch")).unwrap();
+}
+
+fn test_watch_only(cl: &Client) {
+    cl.create_wallet("testwatchonly", None, None, None, None).unwrap();
+    let wallet_client_url = format!("{}{}", get_rpc_url(), "/wallet/testwatchonly");
+    let wallet = Client::new(&wallet_client_url, get_auth()).unwrap();
+    let addr = cl.get_new_address(None, None).unwrap();
+    wallet.import_address(&addr, Some("audit"), Some(false)).unwrap();
+
+    let since = cl.get_best_block_hash().unwrap();
+    let txid = cl.send_to_address(&addr, btc(1), None, None, None, None, None, None).unwrap();
+    cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap();
+
+    assert_eq!(wallet.get_balance(None, Some(false)).unwrap(), btc(0));
+    assert_eq!(wallet.get_balance(None, Some(true)).unwrap(), btc(1));
+    assert_eq!(wallet.get_balance(Some(1), Some(true)).unwrap(), btc(1));
+
+    let txs = wallet.list_transactions(None, None, None, Some(true)).unwrap();
+    assert!(txs.iter().any(|t| t.info.txid == txid && t.detail.involved_watch_only));
+    let tx = wallet.get_transaction(&txid, Some(true)).unwrap();
+    assert!(tx.details.iter().all(|d| d.involved_watch_only));
+    let r = wallet.list_since_block(Some(&since), None, Some(true), None).unwrap();
+    assert!(r.transactions.iter().any(|t| t.info.txid == txid && t.detail.involved_watch_only));
+
+    let received = wallet.list_received_by_address(Some(&addr), None, None, Some(true)).unwrap();
+    assert!(received[0].involved_watch_only);
+    cl.unload_wallet(Some("testwatchonly")).unwrap();
+}
+
 fn test_key_pool_refill(cl: &Client) {
     cl.key_pool_refill(Some(100)).unwrap();
     cl.key_pool_refill(None).unwrap();
 }
 
+fn test_upgrade_to_hd_dump_hd_info(cl: &Client) {
+    cl.create_wallet("testhd", None, Some(true), None, None).unwrap();
+    let wallet_client_url = format!("{}{}", get_rpc_url(), "/wallet/testhd");
+    let wallet = Client::new(&wallet_client_url, get_auth()).unwrap();
+    assert!(wallet.upgrade_to_hd(None, None, None, Some(false)).unwrap());
+    let info = wallet.dump_hd_info().unwrap();
+    assert!(!info.hd_seed.is_empty());
+    assert_eq!(info.mnemonic.split_whitespace().count(), 12);
+    wallet.key_pool_refill(Some(10)).unwrap();
+    cl.unload_wallet(Some("testhd")).unwrap();
+}
+
+fn test_dump_wallet(cl: &Client) {
+    let addr = cl.get_new_address(Some("dump label"), None).unwrap();
+    let path = std::env::temp_dir().join(format!("walletdump-{}", std::process::id()));
+    let result = cl.dump_wallet(path.to_str().unwrap()).unwrap();
+    let dump = WalletDump::from_file(&result.filename).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(dump.keys.len() as u64, result.keys);
+    assert_eq!(dump.best_block.unwrap().1, cl.get_best_block_hash().unwrap());
+    let key = dump.keys.iter().find(|k| k.address.as_ref() == Some(&addr)).unwrap();
+    assert_eq!(key.label(), Some("dump label"));
+    assert_eq!(key.key, cl.dump_private_key(&addr).unwrap());
+}
+
+fn test_dump_tx_out_set(cl: &Client) {
+    let path = std::env::temp_dir().join(format!("utxoset-{}", std::process::id()));
+    let result = cl.dump_tx_out_set(path.to_str().unwrap()).unwrap();
+    assert_eq!(result.base_hash, cl.get_best_block_hash().unwrap());
+
+    let snapshot = UtxoSnapshot::open(&result.path).unwrap();
+    assert_eq!(snapshot.metadata().base_block_hash, result.base_hash);
+    assert_eq!(snapshot.metadata().coins_count, result.coins_written);
+    let mut coins = 0;
+    for coin in snapshot {
+        let coin = coin.unwrap();
+        assert!(coin.height as u64 <= result.base_height);
+        coins += 1;
+    }
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(coins, result.coins_written);
+}
+
 fn test_create_raw_transaction(cl: &Client) {
     let options = json::ListUnspentQueryOptions {
         minimum_amount: Some(btc(2)),
@@ -830,6 +1542,32 @@ fn test_combine_psbt(cl: &Client) {
     assert!(!psbt.is_empty());
 }
 
+fn test_utxo_update_psbt(cl: &Client) {
+    let options = json::ListUnspentQueryOptions {
+        minimum_amount: Some(btc(2)),
+        ..Default::default()
+    };
+    let unspent = cl.list_unspent(Some(6), None, None, None, Some(options)).unwrap();
+    let unspent = unspent.into_iter().nth(0).unwrap();
+    let input = json::CreateRawTransactionInput {
+        txid: unspent.txid,
+        vout: unspent.vout,
+        sequence: None,
+    };
+    let mut output = HashMap::new();
+    output.insert(RANDOM_ADDRESS.to_string(), btc(1));
+    let psbt = cl
+        .wallet_create_funded_psbt(&[input.clone()], &output, Some(500_000), None, Some(true))
+        .unwrap();
+
+    let updated = cl.utxo_update_psbt(&psbt.psbt, None).unwrap();
+    assert!(!updated.is_empty());
+    let address = unspent.address.unwrap();
+    let descriptors = [json::ScanTxOutRequest::Single(format!("addr({})", address))];
+    let updated = cl.utxo_update_psbt(&psbt.psbt, Some(&descriptors)).unwrap();
+    assert!(!cl.finalize_psbt(&updated, Some(true)).unwrap().complete);
+}
+
 fn test_finalize_psbt(cl: &Client) {
     let options = json::ListUnspentQueryOptions {
         minimum_amount: Some(btc(2)),
@@ -932,8 +1670,60 @@ fn test_estimate_smart_fee(cl: &Client) {
     assert!(res.fee_rate.unwrap() >= btc(0));
 }
 
+fn test_multi_client(cl: &Client) {
+    // Nothing listens on the discard port.
+    let url = get_rpc_url();
+    let multi = MultiClient::new(&["http://127.0.0.1:9", &url], get_auth()).unwrap();
+    assert_eq!(multi.get_block_count().unwrap(), cl.get_block_count().unwrap());
+    assert!(!multi.is_healthy(0));
+
+    let health = multi.check_health();
+    match health[0] {
+        NodeHealth::Unavailable(ref e) => assert!(e.is_transient()),
+        ref h => panic!("unexpected health: {:?}", h),
+    }
+    assert!(health[1].is_healthy());
+
+    let split = MultiClient::new(&[&url, &url], get_auth())
+        .unwrap()
+        .with_role(0, NodeRole::Broadcast)
+        .with_role(1, NodeRole::Wallet);
+    assert_eq!(split.get_balance(None, None).unwrap(), cl.get_balance(None, None).unwrap());
+    let public = MultiClient::new(&[&url], get_auth()).unwrap().with_role(0, NodeRole::Broadcast);
+    match public.get_balance(None, None) {
+        Err(Error::NoNodeForCall(ref cmd)) => assert_eq!(cmd, "getbalance"),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+fn test_paranoid_client(cl: &Client) {
+    let new_client = || Client::new(&get_rpc_url(), get_auth()).unwrap();
+    let paranoid = ParanoidClient::new(new_client(), new_client());
+    let hash = paranoid.get_best_block_hash().unwrap();
+    assert_eq!(paranoid.get_block(&hash).unwrap(), cl.get_block(&hash).unwrap());
+    let height = paranoid.get_block_header_info(&hash).unwrap().height as u64;
+    assert_eq!(paranoid.get_block_hash(height).unwrap(), hash);
+}
+
+fn test_auth_failed() {
+    let auth = Auth::UserPass("nobody".to_owned(), "wrong".to_owned());
+    let client = Client::new(&get_rpc_url(), auth).unwrap();
+    match client.get_block_count() {
+        Err(Error::AuthFailed {
+            status,
+            ..
+        }) => assert_eq!(status, 401),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
 fn test_ping(cl: &Client) {
     let _ = cl.ping().unwrap();
+
+    let latency = cl.measure_latency(10).unwrap();
+    assert_eq!(latency.samples().len(), 10);
+    assert!(latency.min() <= latency.median());
+    assert!(latency.percentile(99.0) <= latency.max());
 }
 
 fn test_get_peer_info(cl: &Client) {
@@ -1053,15 +1843,62 @@ fn test_create_wallet(cl: &Client) {
     assert!(wallet_list.iter().zip(wallet_names).all(|(a, b)| a == b));
 }
 
+fn test_list_wallet_dir(cl: &Client) {
+    let wallets = cl.list_wallet_dir().unwrap();
+    assert!(wallets.iter().any(|w| w == "testwallet"));
+    // Unloaded after the HD upgrade test.
+    assert!(wallets.iter().any(|w| w == "testhd"));
+    assert!(!cl.list_wallets().unwrap().iter().any(|w| w == "testhd"));
+
+    assert_eq!(cl.load_wallet("testhd").unwrap().name, "testhd");
+    cl.unload_wallet(Some("testhd")).unwrap();
+}
+
 fn test_get_tx_out_set_info(cl: &Client) {
     cl.get_tx_out_set_info().unwrap();
 }
 
+fn test_verify_chain(cl: &Client) {
+    assert!(cl.verify_chain(None, None).unwrap());
+    assert!(cl.verify_chain(Some(4), Some(10)).unwrap());
+}
+
 fn test_get_chain_tips(cl: &Client) {
     let tips = cl.get_chain_tips().unwrap();
     assert_eq!(tips.len(), 1);
 }
 
+fn test_fork_monitor(cl: &Client) {
+    let mut monitor = ForkMonitor::new(6);
+    assert!(monitor.poll(cl).unwrap().is_empty());
+    if let Some(chain_lock) = cl.get_best_chain_lock().unwrap() {
+        assert!(chain_lock.height as u64 <= cl.get_block_count().unwrap());
+    }
+}
+
+fn test_submit_chain_lock(cl: &Client) {
+    if let Some(chain_lock) = cl.get_best_chain_lock().unwrap() {
+        let best = cl
+            .submit_chain_lock(&chain_lock.blockhash, &chain_lock.signature, chain_lock.height)
+            .unwrap();
+        assert!(best >= chain_lock.height);
+
+        let mut signature = chain_lock.signature.clone();
+        signature[10] ^= 1;
+        let invalid = cl.submit_chain_lock(&chain_lock.blockhash, &signature, chain_lock.height);
+        assert!(invalid.is_err());
+    }
+}
+
+fn test_mnauth(cl: &Client) {
+    let peers = cl.get_peer_info().unwrap();
+    let masternodes = cl.get_masternode_list_json(None).unwrap();
+    if let (Some(peer), Some(masternode)) = (peers.first(), masternodes.values().next()) {
+        let pro_tx_hash = masternode.pro_tx_hash;
+        assert!(cl.mnauth(peer.id, &pro_tx_hash, &masternode.pubkey_operator).unwrap());
+    }
+}
+
 fn test_add_node(cl: &Client) {
     cl.add_node("127.0.0.1:1234").unwrap();
     assert_error_message!(cl.add_node("127.0.0.1:1234"), -23, "Error: Node already added");
@@ -1076,7 +1913,11 @@ fn test_get_added_node_info(cl: &Client) {
 }
 
 fn test_get_node_addresses(cl: &Client) {
-    cl.get_node_addresses(None).unwrap();
+    assert!(cl.get_node_addresses(None).unwrap().len() <= 1);
+    for address in cl.get_node_addresses(Some(0)).unwrap() {
+        assert!(!address.address.is_empty());
+        assert_ne!(address.port, 0);
+    }
 }
 
 fn test_disconnect_node(cl: &Client) {
@@ -1129,6 +1970,159 @@ fn test_uptime(cl: &Client) {
     cl.uptime().unwrap();
 }
 
+fn test_get_zmq_notifications(cl: &Client) {
+    let notifications = cl.get_zmq_notifications().unwrap();
+    assert!(notifications.iter().any(|n| {
+        n.notification_type == "pubhashblock" && n.address == "tcp://127.0.0.1:28332"
+    }));
+}
+
+fn test_capabilities(cl: &Client) {
+    let capabilities = cl.capabilities().unwrap();
+    assert!(capabilities.supports("getblockchaininfo"));
+    assert!(capabilities.supports("quorum"));
+    assert!(capabilities.supports("protx"));
+    assert!(!capabilities.supports("nonexistentcall"));
+    assert_eq!(cl.capabilities().unwrap(), capabilities);
+}
+
+fn test_validate_address(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let res = cl.validate_address(&addr.to_string()).unwrap();
+    assert!(res.is_valid);
+    assert_eq!(res.address, Some(addr.clone()));
+    assert_eq!(res.script_pub_key, Some(addr.script_pubkey()));
+    assert_eq!(res.is_script, Some(false));
+
+    let res = cl.validate_address("notanaddress").unwrap();
+    assert!(!res.is_valid);
+    assert_eq!(res.address, None);
+}
+
+fn test_address_validation(cl: &Client) {
+    assert_eq!(cl.network().unwrap(), Network::Regtest);
+
+    let validating = Client::new(&get_rpc_url(), get_auth()).unwrap().with_address_validation(true);
+    let sk = PrivateKey {
+        network: Network::Regtest,
+        inner: secp256k1::SecretKey::new(&mut secp256k1::rand::thread_rng()),
+        compressed: true,
+    };
+    let pk = sk.public_key(&SECP);
+    let mainnet = Address::p2pkh(&pk, Network::Dash);
+    match validating.get_address_info(&mainnet) {
+        Err(Error::NetworkMismatch {
+            address: Network::Dash,
+            node: Network::Regtest,
+        }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    let regtest = Address::p2pkh(&pk, Network::Regtest);
+    assert!(validating.get_address_info(&regtest).is_ok());
+}
+
+fn test_block_verification(cl: &Client) {
+    let verifying = Client::new(&get_rpc_url(), get_auth()).unwrap().with_block_verification(true);
+    let hash = cl.get_best_block_hash().unwrap();
+    assert_eq!(verifying.get_block(&hash).unwrap().block_hash(), hash);
+    assert_eq!(verifying.get_block_header(&hash).unwrap(), cl.get_block_header(&hash).unwrap());
+    assert_eq!(verifying.get_block_header_info(&hash).unwrap().hash, hash);
+    assert!(verifying.get_block_hex(&hash).is_ok());
+}
+
+fn test_send_raw_transaction_max_fee(cl: &Client) {
+    let options = json::ListUnspentQueryOptions {
+        minimum_amount: Some(btc(2)),
+        ..Default::default()
+    };
+    let unspent = cl.list_unspent(Some(1), None, None, None, Some(options)).unwrap();
+    let unspent = unspent.into_iter().nth(0).unwrap();
+    // Pays a fee of one coin.
+    let tx = Transaction {
+        version: 1,
+        lock_time: 0,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(unspent.txid, unspent.vout),
+            sequence: 0xFFFFFFFF,
+            script_sig: Script::new(),
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: (unspent.amount - btc(1)).as_sat(),
+            script_pubkey: RANDOM_ADDRESS.script_pubkey(),
+        }],
+    };
+    let tx = cl.sign_raw_transaction_with_wallet(&tx, None, None).unwrap().transaction().unwrap();
+
+    let guarded = Client::new(&get_rpc_url(), get_auth()).unwrap().with_max_fee(btc(0.1));
+    match guarded.send_raw_transaction(&tx, None).unwrap_err() {
+        Error::FeeTooHigh {
+            txid,
+            fee,
+            max_fee,
+        } => {
+            assert_eq!(txid, tx.txid());
+            assert_eq!(fee, btc(1));
+            assert_eq!(max_fee, btc(0.1));
+        }
+        e => panic!("unexpected error: {}", e),
+    }
+
+    if version() >= 170000 {
+        assert!(cl.send_raw_transaction(&tx, Some(btc(0.01))).is_err());
+        assert_eq!(cl.send_raw_transaction(&tx, Some(Amount::ZERO)).unwrap(), tx.txid());
+    } else {
+        assert_unsupported!(cl.send_raw_transaction(&tx, Some(Amount::ZERO)));
+    }
+}
+
+fn test_rebroadcaster(cl: &Client) {
+    let txid =
+        cl.send_to_address(&RANDOM_ADDRESS, btc(1), None, None, None, None, None, None).unwrap();
+    let tx = cl.get_raw_transaction(&txid, None).unwrap();
+    let mut rebroadcaster = Rebroadcaster::new(FixedInterval(Duration::from_secs(0)));
+    rebroadcaster.track(tx);
+    assert_eq!(rebroadcaster.poll(cl).unwrap(), vec![RebroadcastEvent::Rebroadcast(txid)]);
+
+    cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap();
+    match rebroadcaster.poll(cl).unwrap()[0] {
+        RebroadcastEvent::Confirmed(t, Some(_)) => assert_eq!(t, txid),
+        ref e => panic!("unexpected event: {:?}", e),
+    }
+    assert!(rebroadcaster.tracked().is_empty());
+}
+
+fn test_wait_until_warmed_up(cl: &Client) {
+    cl.wait_until_warmed_up(Duration::from_secs(1)).unwrap();
+
+    // A node that is not running never warms up.
+    let offline = Client::new("http://127.0.0.1:1", Auth::None).unwrap();
+    match offline.wait_until_warmed_up(Duration::from_secs(1)) {
+        Err(Error::Timeout(_)) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+fn test_rest_client(cl: &Client) {
+    let rest = RestClient::new(&get_rpc_url()).unwrap();
+    let tip = cl.get_best_block_hash().unwrap();
+    assert_eq!(rest.block(&tip).unwrap(), cl.get_block(&tip).unwrap());
+    assert_eq!(rest.chain_info().unwrap().best_block_hash, tip);
+
+    let parent = cl.get_block_header(&tip).unwrap().prev_blockhash;
+    let headers = rest.headers(5, &parent).unwrap();
+    assert_eq!(headers.len(), 2);
+    assert_eq!(headers[1].block_hash(), tip);
+
+    let unspent = cl.list_unspent(Some(1), None, None, None, None).unwrap();
+    let outpoints =
+        [OutPoint::new(unspent[0].txid, unspent[0].vout), OutPoint::new(unspent[0].txid, 1000)];
+    let utxos = rest.get_utxos(&outpoints, false).unwrap();
+    assert_eq!(utxos.chain_tip, tip);
+    assert_eq!(utxos.utxos[0].as_ref().unwrap().tx_out.value, unspent[0].amount.as_sat());
+    assert!(utxos.utxos[1].is_none());
+}
+
 fn test_scantxoutset(cl: &Client) {
     let addr = cl.get_new_address(None, None).unwrap();
 
@@ -1172,21 +2166,74 @@ fn test_get_masternode_count(cl: &Client) {
 }
 
 fn test_get_masternode_list(cl: &Client) {
-    let masternode_list = rpc.get_masternode_list(Some("json"), None).unwrap();
+    let masternode_list = cl.get_masternode_list_json(None).unwrap();
+
+    let statuses = cl.get_masternode_list_mode(json::MasternodeListMode::Status, None).unwrap();
+    assert_eq!(statuses.len(), masternode_list.len());
+    for (outpoint, status) in statuses {
+        assert_eq!(status.as_str(), Some(masternode_list[&outpoint].status.as_str()));
+    }
+    let paid = cl.get_masternode_list_mode(json::MasternodeListMode::LastPaidBlock, None).unwrap();
+    assert!(paid.values().all(|v| v.as_u64().is_some()));
 }
 
-fn test_get_masternode_outputs(cl: &Client) {
-    let masternode_outputs = rpc.get_masternode_outputs().unwrap();
+fn test_masternode_outputs(cl: &Client) {
+    let masternode_outputs = cl.masternode_outputs().unwrap();
+    for outpoint in masternode_outputs {
+        let tx = cl.get_transaction(&outpoint.txid, None).unwrap().transaction().unwrap();
+        // Regular and evo masternodes.
+        let value = Amount::from_sat(tx.output[outpoint.vout as usize].value);
+        assert!(value == btc(1000) || value == btc(4000));
+    }
 }
 
-fn test_get_masternode_payments(cl: &Client) {
-    let masternode_payments = rpc.get_masternode_payments(None, None).unwrap();
+fn test_collateral_proof(cl: &Client) {
+    let addr = cl.get_new_address(None, None).unwrap();
+    let txid = cl.send_to_address(&addr, btc(1000), None, None, None, None, None, None).unwrap();
+    cl.generate_to_address(1, &RANDOM_ADDRESS).unwrap();
+    let tx = cl.get_transaction(&txid, None).unwrap().transaction().unwrap();
+    let vout = tx.output.iter().position(|o| o.script_pubkey == addr.script_pubkey()).unwrap();
+    let outpoint = OutPoint::new(txid, vout as u32);
+
+    let proof = cl.prove_collateral_ownership(&outpoint, "challenge").unwrap();
+    assert_eq!(proof.address, addr);
+    assert_eq!(cl.verify_collateral_proof(&proof).unwrap(), btc(1000));
+    assert!(cl.verify_message_base64(&addr, &proof.signature, "challenge").unwrap());
+    let key = cl.dump_private_key(&addr).unwrap();
+    let signature = cl.sign_message_with_priv_key(&key, "other challenge").unwrap();
+    assert_ne!(signature, proof.signature);
+    let forged = dashcore_rpc::CollateralProof {
+        signature,
+        ..proof
+    };
+    assert!(cl.verify_collateral_proof(&forged).is_err());
+}
+
+fn test_masternode_payments(cl: &Client) {
+    let masternode_payments = cl.masternode_payments(None, None).unwrap();
     assert!(masternode_payments[0].height > 0);
-    assert!(masternode_payments[0].amount > 0);
-    assert!(masternode_payments[0].masternodes[0].amount > 0);
-    assert!(masternode_payments[0].masternodes[0].payees[0].amount > 0);
+    assert!(masternode_payments[0].amount > Amount::ZERO);
+    assert!(masternode_payments[0].masternodes[0].amount > Amount::ZERO);
+    assert!(masternode_payments[0].masternodes[0].payees[0].amount > Amount::ZERO);
     assert_eq!(masternode_payments[0].amount, masternode_payments[0].masternodes[0].amount);
     assert_eq!(masternode_payments[0].amount, masternode_payments[0].masternodes[0].payees[0].amount);
+
+    let tip = cl.get_best_block_hash().unwrap();
+    let previous = cl.masternode_payments(Some(&tip), Some(-2)).unwrap();
+    assert_eq!(previous.len(), 2);
+}
+
+fn test_masternode_payee(cl: &Client) {
+    let tip = cl.get_block_count().unwrap() as u32;
+    let current = cl.masternode_current().unwrap();
+    assert_eq!(current.height, tip + 1);
+    assert!(current.payee.is_some());
+    let mn = cl.get_protx_info(&current.pro_tx_hash).unwrap();
+    assert_eq!(mn.collateral_hash, current.outpoint.txid);
+    assert_eq!(mn.collateral_index, current.outpoint.vout);
+
+    let winner = cl.masternode_winner().unwrap();
+    assert!(winner.height > current.height);
 }
 
 fn test_get_masternode_status(cl: &Client) {
@@ -1205,7 +2252,13 @@ fn test_get_quorum_list(cl: &Client) {
 }
 
 fn test_get_quorum_info(cl: &Client) {
-    let quorum_info = rpc.get_quorum_info("1", "000000000c9eddd5d2a707281b7e30d5aac974dac600ff10f01937e1ca36066f", None).unwrap();
+    let quorum_info = rpc
+        .get_quorum_info(
+            json::LLMQType::Llmq50_60,
+            &"000000000c9eddd5d2a707281b7e30d5aac974dac600ff10f01937e1ca36066f".parse().unwrap(),
+            None,
+        )
+        .unwrap();
     assert!(quorum_info.height > 0);
     assert!(quorum_info.quorum_index >= 0);
     assert!(quorum_info.members.len() >= 0);
@@ -1220,36 +2273,140 @@ fn test_get_quorum_dkgstatus(cl: &Client) {
 }
 
 fn test_get_quorum_sign(cl: &Client) {
-    let quorum_dkgstatus = rpc.get_quorum_sign(1, "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234", "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239", None, None).unwrap();
+    let quorum_dkgstatus = rpc
+        .get_quorum_sign(
+            json::LLMQType::Llmq50_60,
+            "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234",
+            "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239",
+            None,
+            None,
+        )
+        .unwrap();
+}
+
+fn test_quorum_sign_and_wait(cl: &Client) {
+    let llmq_type = json::LLMQType::Llmq50_60;
+    let id = "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234";
+    let msg_hash = "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239";
+    let sig = cl.quorum_sign_and_wait(llmq_type, id, msg_hash, Duration::from_secs(30)).unwrap();
+    assert_eq!(sig.msg_hash.to_hex(), msg_hash);
+    assert!(cl.get_quorum_hasrecsig(llmq_type, id, msg_hash).unwrap());
+}
+
+fn test_quorum_select_and_verify(cl: &Client) {
+    let llmq_type = json::LLMQType::Llmq50_60;
+    let id = "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234";
+    let msg_hash = "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239";
+    let sig = cl.quorum_sign_and_wait(llmq_type, id, msg_hash, Duration::from_secs(30)).unwrap();
+    let verification =
+        cl.quorum_select_and_verify(llmq_type, id, msg_hash, &sig.signature.to_hex()).unwrap();
+    assert!(verification.valid);
+    assert_eq!(verification.quorum_hash, sig.quorum_hash);
+
+    let other_msg_hash = "00".repeat(32);
+    let verification =
+        cl.quorum_select_and_verify(llmq_type, id, &other_msg_hash, &sig.signature.to_hex());
+    assert!(!verification.unwrap().valid);
 }
 
 fn test_get_quorum_getrecsig(cl: &Client) {
-    let quorum_getrecsig = rpc.get_quorum_getrecsig(1, "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234", "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239").unwrap();
+    let quorum_getrecsig = rpc
+        .get_quorum_getrecsig(
+            json::LLMQType::Llmq50_60,
+            "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234",
+            "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239",
+        )
+        .unwrap();
 }
 
 fn test_get_quorum_hasrecsig(cl: &Client) {
-    let quorum_hasrecsig = rpc.get_quorum_hasrecsig(1, "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234", "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239").unwrap();
+    let quorum_hasrecsig = rpc
+        .get_quorum_hasrecsig(
+            json::LLMQType::Llmq50_60,
+            "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234",
+            "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239",
+        )
+        .unwrap();
 }
 
 fn test_get_quorum_isconflicting(cl: &Client) {
-    let quorum_isconflicting = rpc.get_quorum_isconflicting(1, "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234", "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239").unwrap();
+    let quorum_isconflicting = rpc
+        .get_quorum_isconflicting(
+            json::LLMQType::Llmq50_60,
+            "abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234abcd1234",
+            "51c11d287dfa85aef3eebb5420834c8e443e01d15c0b0a8e397d67e2e51aa239",
+        )
+        .unwrap();
 }
 
 fn test_get_quorum_memberof(cl: &Client) {
-    let quorum_memberof = rpc.get_quorum_memberof("39c07d2c9c6d0ead56f52726b63c15e295cb5c3ecf7fe1fefcfb23b2e3cfed1f", Some(1)).unwrap();
+    let quorum_memberof = rpc
+        .get_quorum_memberof(
+            &"39c07d2c9c6d0ead56f52726b63c15e295cb5c3ecf7fe1fefcfb23b2e3cfed1f".parse().unwrap(),
+            Some(1),
+        )
+        .unwrap();
     assert!(quorum_memberof[0].height > 0);
 }
 
 fn test_get_quorum_rotationinfo(cl: &Client) {
-    let quorum_rotationinfo = rpc.get_quorum_rotationinfo("0000012197b7ca6360af3756c6a49c217dbbdf8b595fd55e0fcef7ffcd546044", None, None).unwrap();
+    let block_hash = cl.get_best_block_hash().unwrap();
+    let quorum_rotationinfo = cl.get_quorum_rotationinfo(&block_hash, Some(true), &[]).unwrap();
+    assert!(quorum_rotationinfo.extra_share);
+    assert!(quorum_rotationinfo.quorum_snapshot_at_h_minus_4c.is_some());
+    assert!(quorum_rotationinfo.mn_list_diff_at_h_minus_4c.is_some());
 }
 
 fn test_get_quorum_selectquorum(cl: &Client) {
-    let quorum_selectquorum = rpc.get_quorum_selectquorum(1, "b95205c3bba72e9edfbe7380ec91fe5a97e16a189e28f39b03c6822757ad1a34").unwrap();
+    let quorum_selectquorum = rpc
+        .get_quorum_selectquorum(
+            json::LLMQType::Llmq50_60,
+            "b95205c3bba72e9edfbe7380ec91fe5a97e16a189e28f39b03c6822757ad1a34",
+        )
+        .unwrap();
 }
 
 fn test_get_quorum_verify(cl: &Client) {
-    let quorum_verify = rpc.get_quorum_verify(1, "2ceeaa7ff20de327ef65b14de692199d15b67b9458d0ded7d68735cce98dd039", "8b5174d0e95b5642ebec23c3fe8f0bbf8f6993502f4210322871bba0e818ff3b", "99cf2a0deb08286a2d1ffdd2564b35522fd748c8802e561abed330dea20df5cb5a5dffeddbe627ea32cb36de13d5b4a516fdfaebae9886b2f7969a5d112416cf8d1983ebcbf1463a64f7522505627e08b9c76c036616fbb1649271a2773a1653", Some("000000583a348d1a0a5f753ef98e6a69f9bcd9b27919f10eb1a1c3edb6c79182"), None).unwrap();
+    let quorum_verify = rpc.get_quorum_verify(json::LLMQType::Llmq50_60, "2ceeaa7ff20de327ef65b14de692199d15b67b9458d0ded7d68735cce98dd039", "8b5174d0e95b5642ebec23c3fe8f0bbf8f6993502f4210322871bba0e818ff3b", "99cf2a0deb08286a2d1ffdd2564b35522fd748c8802e561abed330dea20df5cb5a5dffeddbe627ea32cb36de13d5b4a516fdfaebae9886b2f7969a5d112416cf8d1983ebcbf1463a64f7522505627e08b9c76c036616fbb1649271a2773a1653", Some(&"000000583a348d1a0a5f753ef98e6a69f9bcd9b27919f10eb1a1c3edb6c79182".parse().unwrap()), None).unwrap();
+}
+
+// ---------------------- Governance RPC tests---------------------
+
+fn test_submit_proposal(cl: &Client) {
+    let address = cl.get_new_address(None, None).unwrap();
+    let now = cl.get_block_header_info(&cl.get_best_block_hash().unwrap()).unwrap().time as u64;
+    let proposal = ProposalBuilder::new("test-proposal", address, btc(1), now, now + 3600)
+        .with_url("https://dash.org")
+        .with_confirmations(1);
+    assert_eq!(cl.gobject_check(&proposal.data_hex().unwrap()).unwrap().object_status, "OK");
+
+    let prepared = proposal.prepare(cl).unwrap();
+    match prepared.wait_and_submit(cl, Duration::from_millis(10)) {
+        Err(Error::Timeout(_)) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    cl.generate_to_address(1, &cl.get_new_address(None, None).unwrap()).unwrap();
+    let hash = prepared.wait_and_submit(cl, Duration::from_secs(10)).unwrap();
+    test_gobject_votes(cl, &hash);
+}
+
+fn test_next_superblocks(cl: &Client) {
+    let info = cl.get_governance_info().unwrap();
+    let superblocks = cl.next_superblocks(2).unwrap();
+    assert_eq!(superblocks.len(), 2);
+    assert!(superblocks[0].height as u64 > cl.get_block_count().unwrap());
+    assert_eq!(superblocks[1].height - superblocks[0].height, info.superblock_cycle);
+    assert!(superblocks[0].estimated_time < superblocks[1].estimated_time);
+}
+
+fn test_gobject_votes(cl: &Client, hash: &json::GovernanceObjectHash) {
+    let votes = cl.gobject_votes(hash).unwrap();
+    let tally = cl.gobject_vote_tally(hash).unwrap();
+    let funding = votes
+        .values()
+        .filter(|v| v.signal == json::VoteSignal::Funding && v.outcome != json::VoteOutcome::None)
+        .count();
+    assert_eq!(tally.funding().total() as usize, funding);
 }
 
 // ---------------------- BLS RPC tests---------------------
@@ -1266,12 +2423,90 @@ fn test_get_bls_generate(cl: &Client) {
 
 // ---------------------- ProTx RPC tests---------------------
 
+fn test_get_credit_pool_balance(cl: &Client) {
+    let tip = cl.get_best_block_hash().unwrap();
+    let cb_tx = cl.get_block_info(&tip).unwrap().cb_tx.unwrap();
+    assert_eq!(cl.get_credit_pool_balance(None).unwrap(), cb_tx.credit_pool_balance);
+    assert_eq!(cl.get_credit_pool_balance(Some(&tip)).unwrap(), cb_tx.credit_pool_balance);
+}
+
+fn test_cb_tx(cl: &Client) {
+    let tip = cl.get_best_block_hash().unwrap();
+    let info = cl.get_block_info(&tip).unwrap();
+    let cb_tx = info.cb_tx.clone().unwrap();
+    assert_eq!(info.merkle_root_mn_list(), Some(cb_tx.merkle_root_mn_list));
+    assert_eq!(info.merkle_root_quorums(), cb_tx.merkle_root_quorums);
+
+    let coinbase = cl.get_raw_transaction_info(&info.tx[0], Some(&tip)).unwrap();
+    assert_eq!(coinbase.cb_tx_payload().unwrap().unwrap(), cb_tx);
+    let raw = Vec::<u8>::from_hex(&cl.get_raw_transaction_hex(&info.tx[0], Some(&tip)).unwrap());
+    assert_eq!(json::CbTx::from_coinbase(&raw.unwrap()).unwrap(), cb_tx);
+
+    let height = cl.get_block_count().unwrap() as u32;
+    let diff = cl.get_protx_diff(1, height).unwrap();
+    assert_eq!(diff.cb_tx_payload().unwrap().merkle_root_mn_list, diff.merkle_root_mn_list);
+}
+
+fn test_get_asset_unlock_statuses(cl: &Client) {
+    let unused = 1_000_000_000;
+    let statuses = cl.get_asset_unlock_statuses(&[unused], None).unwrap();
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].index, unused);
+    assert_eq!(statuses[0].status, json::AssetUnlockStatus::Unknown);
+
+    let height = cl.get_block_count().unwrap() as u32;
+    let statuses = cl.get_asset_unlock_statuses(&[unused], Some(height)).unwrap();
+    assert_eq!(statuses[0].status, json::AssetUnlockStatus::Unknown);
+}
+
 fn test_get_protx_diff(cl: &Client) {
-    let protx_diff = rpc.get_protx_diff(75000, 76000).unwrap();
+    let count = cl.get_block_count().unwrap() as u32;
+    let protx_diff = cl.get_protx_diff(1, count).unwrap();
+    assert_eq!(protx_diff.block_hash, cl.get_best_block_hash().unwrap());
+    let cb_tx = protx_diff.cb_tx().unwrap();
+    assert!(cb_tx.is_coin_base());
+    let mut matches = vec![];
+    let mut indexes = vec![];
+    protx_diff.cb_tx_merkle_tree().unwrap().extract_matches(&mut matches, &mut indexes).unwrap();
+    assert_eq!(matches, vec![cb_tx.txid()]);
+}
+
+fn test_masternode_list(cl: &Client) {
+    let mut list = cl.masternode_list().unwrap();
+    assert_eq!(list.block_hash(), cl.get_best_block_hash().unwrap());
+    assert!(!list.sync(cl).unwrap());
+    for entry in list.iter() {
+        assert_eq!(list.get(&entry.pro_reg_tx_hash), Some(entry));
+        assert_eq!(list.by_service(&entry.service), Some(entry));
+    }
+
+    cl.generate_to_address(1, &cl.get_new_address(None, None).unwrap()).unwrap();
+    assert!(list.sync(cl).unwrap());
+    assert_eq!(list.height() as u64, cl.get_block_count().unwrap());
+    assert_eq!(list, cl.masternode_list().unwrap());
+
+    let cb_tx = cl.get_block_info(&list.block_hash()).unwrap().cb_tx.unwrap();
+    list.verify_merkle_root(&cb_tx).unwrap();
+
+    let tip = cl.get_block_count().unwrap() as u32;
+    let mut verified = MasternodeList::new(cl.get_block_hash(0).unwrap());
+    verified.apply_verified_diff(&cl.get_protx_diff(0, tip).unwrap(), tip).unwrap();
+    assert_eq!(verified, list);
+
+    let mut replayed = MasternodeList::new(cl.get_block_hash(0).unwrap());
+    for item in cl.iter_protx_diffs(1..=tip).after(replayed.block_hash()) {
+        let (height, diff) = item.unwrap();
+        replayed.apply_diff(&diff, height).unwrap();
+    }
+    assert_eq!(replayed, list);
 }
 
 fn test_get_protx_info(cl: &Client) {
-    let protx_info = rpc.get_protx_info("000000000c9eddd5d2a707281b7e30d5aac974dac600ff10f01937e1ca36066f").unwrap();
+    let protx_info = rpc
+        .get_protx_info(
+            &"000000000c9eddd5d2a707281b7e30d5aac974dac600ff10f01937e1ca36066f".parse().unwrap(),
+        )
+        .unwrap();
     assert!(protx_info.collateralIndex >= 0);
     assert!(protx_info.operatorReward >= 0);
 }
@@ -1281,9 +2516,32 @@ fn test_get_protx_list(cl: &Client) {
 }
 
 fn test_get_protx_register(cl: &Client) {
-    let protx_register = rpc.get_protx_register("8b2eab3413abb6e04d17d1defe2b71039ba6b6f72ea1e5dab29bb10e7b745948", 1, "2.3.4.5:2345", "yNLuVTXJbjbxgrQX5LSMi7hV19We8hT2d6", "88d719278eef605d9c19037366910b59bc28d437de4a8db4d76fda6d6985dbdf10404fb9bb5cd0e8c22f4a914a6c5566", "yNLuVTXJbjbxgrQX5LSMi7hV19We8hT2d6", 5, "yjJJLkYDUN6X8gWjXbCoKEXoiLeKxxMMRt", None, Some(false)).unwrap();
+    let protx_register = rpc.get_protx_register(&"8b2eab3413abb6e04d17d1defe2b71039ba6b6f72ea1e5dab29bb10e7b745948".parse().unwrap(), 1, "2.3.4.5:2345", "yNLuVTXJbjbxgrQX5LSMi7hV19We8hT2d6", "88d719278eef605d9c19037366910b59bc28d437de4a8db4d76fda6d6985dbdf10404fb9bb5cd0e8c22f4a914a6c5566", "yNLuVTXJbjbxgrQX5LSMi7hV19We8hT2d6", 5, "yjJJLkYDUN6X8gWjXbCoKEXoiLeKxxMMRt", None, Some(false)).unwrap();
 }
 
 fn test_get_protx_register_fund(cl: &Client) {
     let protx_register_fund = rpc.get_protx_register_fund("yakx4mMRptKhgfjedNzX5FGQq7kSSBF2e7", "3.4.5.6:3456", "yURczr3qY31xkQZfFu8eZvKz19eAEPQxsd", "0e02146e9c34cfbcb3f3037574a1abb35525e2ca0c3c6901dbf82ac591e30218d1711223b7ca956edf39f3d984d06d51", "yURczr3qY31xkQZfFu8eZvKz19eAEPQxsd", 5, "yUYTxqjpCfAAK4vgxXtBPywRBtZqsxN7Vy", Some("yRMFHxcJ2aS2vfo5whhE2Gg73dfQVm8LAF"), Some(false)).unwrap();
 }
+
+fn test_get_protx_register_fund_evo(cl: &Client) {
+    let protx_register_fund_evo = cl
+        .get_protx_register_fund_evo(
+            "yakx4mMRptKhgfjedNzX5FGQq7kSSBF2e7",
+            "3.4.5.6:3456",
+            "yURczr3qY31xkQZfFu8eZvKz19eAEPQxsd",
+            "0e02146e9c34cfbcb3f3037574a1abb35525e2ca0c3c6901dbf82ac591e30218d1711223b7ca956edf39f3d984d06d51",
+            "yURczr3qY31xkQZfFu8eZvKz19eAEPQxsd",
+            5,
+            "yUYTxqjpCfAAK4vgxXtBPywRBtZqsxN7Vy",
+            "f2dbd9b0a1f541a7c44d34a58674d0262f5feca5",
+            22821,
+            22822,
+            Some("yRMFHxcJ2aS2vfo5whhE2Gg73dfQVm8LAF"),
+            Some(false),
+        )
+        .unwrap();
+    match protx_register_fund_evo {
+        json::ProRegTxHash::Transaction(tx) => assert!(!tx.is_empty()),
+        json::ProRegTxHash::Hash(_) => panic!("the ProRegTx was submitted"),
+    }
+}
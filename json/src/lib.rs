@@ -12,6 +12,19 @@
 //!
 //! This is a client library for the Dash Core JSON-RPC API.
 //!
+//! ## Compatibility
+//!
+//! New versions of Dash Core add fields to the results of calls and drop
+//! deprecated ones, so the result types are lenient:
+//!
+//! - Fields a type does not know are ignored and never fail the
+//!   deserialization, `deny_unknown_fields` is not used.
+//! - Fields that are missing in some supported versions are `Option`s.
+//! - The main result types keep the fields they don't know in their `extra`
+//!   map, so new fields can be used before they are typed. A field leaves
+//!   `extra` once it is typed, so code reading it from `extra` should not rely
+//!   on it being there after an upgrade of this library.
+//!
 
 #![crate_name = "dashcore_rpc_json"]
 #![crate_type = "rlib"]
@@ -24,14 +37,19 @@ extern crate serde_json;
 extern crate serde_with;
 
 use std::collections::HashMap;
-use std::fmt;
+use std::str::FromStr;
+use std::{fmt, io};
 use std::net::{SocketAddr};
 
-use dashcore::consensus::encode;
+use dashcore::blockdata::script::Instruction;
+use dashcore::consensus::{encode, Decodable, Encodable};
 use dashcore::hashes::hex::{FromHex, ToHex};
-use dashcore::hashes::sha256;
+use dashcore::hashes::{sha256, sha256d};
+use dashcore::util::merkleblock::PartialMerkleTree;
 use dashcore::util::{bip158, bip32};
-use dashcore::{Address, Amount, PrivateKey, PublicKey, Script, SignedAmount, Transaction};
+use dashcore::{
+    Address, Amount, PrivateKey, PublicKey, Script, SignedAmount, Transaction, TxOut,
+};
 use serde::de::Error as SerdeError;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr, Bytes};
@@ -75,6 +93,171 @@ pub mod serde_hex {
     }
 }
 
+/// The maximum number of duffs a single amount can hold, see `MoneyRange`
+/// in Dash Core.
+pub const MAX_MONEY_SAT: i64 = 21_000_000 * 100_000_000;
+
+/// An amount received from the node that failed strict validation.
+#[derive(Clone, PartialEq, Debug)]
+pub enum AmountError {
+    /// The value is NaN or infinite.
+    NotFinite(f64),
+    /// The value is negative while the field can only hold positive amounts.
+    Negative(f64),
+    /// The value exceeds the maximum amount of money.
+    OutOfRange(f64),
+    /// The value can not be represented as an amount.
+    Invalid(dashcore::util::amount::ParseAmountError),
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AmountError::NotFinite(v) => write!(f, "amount is not a finite number: {}", v),
+            AmountError::Negative(v) => write!(f, "amount is negative: {}", v),
+            AmountError::OutOfRange(v) => write!(f, "amount is out of money range: {}", v),
+            AmountError::Invalid(ref e) => write!(f, "invalid amount: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AmountError {
+    fn description(&self) -> &str {
+        "invalid amount"
+    }
+
+    fn cause(&self) -> Option<&dyn std::error::Error> {
+        match *self {
+            AmountError::Invalid(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Amount types that can be strictly validated when deserialized.
+pub trait StrictAmount: dashcore::util::amount::serde::SerdeAmountForOpt {
+    /// Convert a value denominated in DASH, rejecting values outside of the money range.
+    fn from_btc_strict(btc: f64) -> Result<Self, AmountError>;
+    /// Convert a value denominated in duffs, rejecting values outside of the money range.
+    fn from_sat_strict(sat: i64) -> Result<Self, AmountError>;
+}
+
+fn check_money_range(value: f64, max: f64, allow_negative: bool) -> Result<(), AmountError> {
+    if !value.is_finite() {
+        return Err(AmountError::NotFinite(value));
+    }
+    if value < 0.0 && !allow_negative {
+        return Err(AmountError::Negative(value));
+    }
+    if value.abs() > max {
+        return Err(AmountError::OutOfRange(value));
+    }
+    Ok(())
+}
+
+impl StrictAmount for Amount {
+    fn from_btc_strict(btc: f64) -> Result<Self, AmountError> {
+        check_money_range(btc, MAX_MONEY_SAT as f64 / 100_000_000.0, false)?;
+        Amount::from_btc(btc).map_err(AmountError::Invalid)
+    }
+
+    fn from_sat_strict(sat: i64) -> Result<Self, AmountError> {
+        check_money_range(sat as f64, MAX_MONEY_SAT as f64, false)?;
+        Ok(Amount::from_sat(sat as u64))
+    }
+}
+
+impl StrictAmount for SignedAmount {
+    fn from_btc_strict(btc: f64) -> Result<Self, AmountError> {
+        check_money_range(btc, MAX_MONEY_SAT as f64 / 100_000_000.0, true)?;
+        SignedAmount::from_btc(btc).map_err(AmountError::Invalid)
+    }
+
+    fn from_sat_strict(sat: i64) -> Result<Self, AmountError> {
+        check_money_range(sat as f64, MAX_MONEY_SAT as f64, true)?;
+        Ok(SignedAmount::from_sat(sat))
+    }
+}
+
+/// A module used for strict serde (de)serialization of amounts.
+///
+/// Serialization is the same as `dashcore::util::amount::serde`, but
+/// deserialization rejects NaN, negative (for unsigned amounts) and out of
+/// range values with an [AmountError].
+pub mod serde_amount {
+    pub mod as_btc {
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+        use StrictAmount;
+
+        pub fn serialize<A: StrictAmount, S: Serializer>(a: &A, s: S) -> Result<S::Ok, S::Error> {
+            ::dashcore::util::amount::serde::as_btc::serialize(a, s)
+        }
+
+        pub fn deserialize<'de, A: StrictAmount, D: Deserializer<'de>>(d: D) -> Result<A, D::Error> {
+            A::from_btc_strict(f64::deserialize(d)?).map_err(D::Error::custom)
+        }
+
+        pub mod opt {
+            use serde::de::Error;
+            use serde::{Deserialize, Deserializer, Serializer};
+            use StrictAmount;
+
+            pub fn serialize<A: StrictAmount, S: Serializer>(
+                a: &Option<A>,
+                s: S,
+            ) -> Result<S::Ok, S::Error> {
+                ::dashcore::util::amount::serde::as_btc::opt::serialize(a, s)
+            }
+
+            pub fn deserialize<'de, A: StrictAmount, D: Deserializer<'de>>(
+                d: D,
+            ) -> Result<Option<A>, D::Error> {
+                match Option::<f64>::deserialize(d)? {
+                    Some(btc) => Ok(Some(A::from_btc_strict(btc).map_err(D::Error::custom)?)),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    pub mod as_sat {
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+        use StrictAmount;
+
+        pub fn serialize<A: StrictAmount, S: Serializer>(a: &A, s: S) -> Result<S::Ok, S::Error> {
+            ::dashcore::util::amount::serde::as_sat::serialize(a, s)
+        }
+
+        pub fn deserialize<'de, A: StrictAmount, D: Deserializer<'de>>(d: D) -> Result<A, D::Error> {
+            A::from_sat_strict(i64::deserialize(d)?).map_err(D::Error::custom)
+        }
+
+        pub mod opt {
+            use serde::de::Error;
+            use serde::{Deserialize, Deserializer, Serializer};
+            use StrictAmount;
+
+            pub fn serialize<A: StrictAmount, S: Serializer>(
+                a: &Option<A>,
+                s: S,
+            ) -> Result<S::Ok, S::Error> {
+                ::dashcore::util::amount::serde::as_sat::opt::serialize(a, s)
+            }
+
+            pub fn deserialize<'de, A: StrictAmount, D: Deserializer<'de>>(
+                d: D,
+            ) -> Result<Option<A>, D::Error> {
+                match Option::<i64>::deserialize(d)? {
+                    Some(sat) => Ok(Some(A::from_sat_strict(sat).map_err(D::Error::custom)?)),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct GetNetworkInfoResultNetwork {
     pub name: String,
@@ -113,13 +296,17 @@ pub struct GetNetworkInfoResult {
     #[serde(rename = "networkactive")]
     pub network_active: bool,
     pub networks: Vec<GetNetworkInfoResultNetwork>,
-    #[serde(rename = "relayfee", with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(rename = "relayfee", with = "::serde_amount::as_btc")]
     pub relay_fee: Amount,
-    #[serde(rename = "incrementalfee", with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(rename = "incrementalfee", with = "::serde_amount::as_btc")]
     pub incremental_fee: Amount,
     #[serde(rename = "localaddresses")]
     pub local_addresses: Vec<GetNetworkInfoResultAddress>,
     pub warnings: String,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
@@ -129,6 +316,62 @@ pub struct AddMultiSigAddressResult {
     pub redeem_script: Script,
 }
 
+/// Models the result of "createmultisig"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMultiSigResult {
+    pub address: Address,
+    pub redeem_script: Script,
+}
+
+/// Models the result of "dumphdinfo"
+///
+/// The `Debug` implementation does not print the wallet secrets.
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DumpHdInfoResult {
+    /// The BIP32 HD seed
+    #[serde(rename = "hdseed", with = "::serde_hex")]
+    pub hd_seed: Vec<u8>,
+    /// The BIP39 mnemonic for this HD wallet
+    pub mnemonic: String,
+    /// The BIP39 mnemonic passphrase for this HD wallet
+    #[serde(rename = "mnemonicpassphrase")]
+    pub mnemonic_passphrase: String,
+}
+
+impl fmt::Debug for DumpHdInfoResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DumpHdInfoResult")
+            .field("hd_seed", &"[redacted]")
+            .field("mnemonic", &"[redacted]")
+            .field("mnemonic_passphrase", &"[redacted]")
+            .finish()
+    }
+}
+
+/// Result of the `dumpwallet` call, the dump itself is written to a file on
+/// the node.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct DumpWalletResult {
+    /// The number of keys contained in the wallet dump
+    pub keys: u64,
+    /// The full path of the destination file
+    pub filename: String,
+    pub warning: Option<String>,
+}
+
+/// Result of the `listwalletdir` call.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct ListWalletDirResult {
+    pub wallets: Vec<ListWalletDirItem>,
+}
+
+/// A wallet in the wallet directory of the node.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct ListWalletDirItem {
+    pub name: String,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct LoadWalletResult {
     pub name: String,
@@ -141,11 +384,11 @@ pub struct GetWalletInfoResult {
     pub wallet_name: String,
     #[serde(rename = "walletversion")]
     pub wallet_version: u32,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub balance: Amount,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub unconfirmed_balance: Amount,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub immature_balance: Amount,
     #[serde(rename = "txcount")]
     pub tx_count: usize,
@@ -156,13 +399,17 @@ pub struct GetWalletInfoResult {
     #[serde(rename = "keypoolsize_hd_internal")]
     pub keypool_size_hd_internal: usize,
     pub unlocked_until: Option<u64>,
-    #[serde(rename = "paytxfee", with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(rename = "paytxfee", with = "::serde_amount::as_btc")]
     pub pay_tx_fee: Amount,
     #[serde(rename = "hdseedid")]
     pub hd_seed_id: Option<dashcore::XpubIdentifier>,
     pub private_keys_enabled: bool,
     pub avoid_reuse: Option<bool>,
     pub scanning: Option<ScanningDetails>,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
@@ -202,6 +449,59 @@ pub struct GetBlockResult {
     pub n_tx: usize,
     pub previousblockhash: Option<dashcore::BlockHash>,
     pub nextblockhash: Option<dashcore::BlockHash>,
+    /// The coinbase special transaction payload
+    pub cb_tx: Option<CbTx>,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl GetBlockResult {
+    /// The merkle root of the simplified masternode list the coinbase of the
+    /// block commits to, `None` before DIP3 activation.
+    pub fn merkle_root_mn_list(&self) -> Option<sha256d::Hash> {
+        self.cb_tx.as_ref().map(|cb_tx| cb_tx.merkle_root_mn_list)
+    }
+
+    /// The merkle root of the active quorums the coinbase of the block
+    /// commits to, `None` before DIP8 activation.
+    pub fn merkle_root_quorums(&self) -> Option<sha256d::Hash> {
+        self.cb_tx.as_ref().and_then(|cb_tx| cb_tx.merkle_root_quorums)
+    }
+}
+
+/// Models the result of "getblock" with verbosity 2
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBlockFullResult {
+    pub hash: dashcore::BlockHash,
+    pub confirmations: i32,
+    pub size: usize,
+    pub height: usize,
+    pub version: i32,
+    #[serde(default, with = "::serde_hex::opt")]
+    pub version_hex: Option<Vec<u8>>,
+    pub merkleroot: dashcore::TxMerkleNode,
+    pub tx: Vec<GetRawTransactionResult>,
+    pub time: usize,
+    pub mediantime: Option<usize>,
+    pub nonce: u32,
+    pub bits: String,
+    pub difficulty: f64,
+    #[serde(with = "::serde_hex")]
+    pub chainwork: Vec<u8>,
+    pub n_tx: usize,
+    pub previousblockhash: Option<dashcore::BlockHash>,
+    pub nextblockhash: Option<dashcore::BlockHash>,
+}
+
+/// The result of "getblock" for any [BlockVerbosity].
+#[derive(Clone, PartialEq, Debug)]
+pub enum GetBlockVerbosityResult {
+    Raw(dashcore::Block),
+    Info(GetBlockResult),
+    Full(GetBlockFullResult),
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
@@ -228,13 +528,31 @@ pub struct GetBlockHeaderResult {
     pub previous_block_hash: Option<dashcore::BlockHash>,
     #[serde(rename = "nextblockhash")]
     pub next_block_hash: Option<dashcore::BlockHash>,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl GetBlockHeaderResult {
+    /// The header rebuilt from its fields, `None` if `bits` is not valid hex.
+    pub fn block_header(&self) -> Option<dashcore::BlockHeader> {
+        Some(dashcore::BlockHeader {
+            version: self.version,
+            prev_blockhash: self.previous_block_hash.unwrap_or_default(),
+            merkle_root: self.merkle_root,
+            time: self.time as u32,
+            bits: u32::from_str_radix(&self.bits, 16).ok()?,
+            nonce: self.nonce,
+        })
+    }
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct GetBlockStatsResult {
-    #[serde(rename = "avgfee", with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(rename = "avgfee", with = "::serde_amount::as_sat")]
     pub avg_fee: Amount,
-    #[serde(rename = "avgfeerate", with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(rename = "avgfeerate", with = "::serde_amount::as_sat")]
     pub avg_fee_rate: Amount,
     #[serde(rename = "avgtxsize")]
     pub avg_tx_size: u32,
@@ -244,26 +562,26 @@ pub struct GetBlockStatsResult {
     pub fee_rate_percentiles: FeeRatePercentiles,
     pub height: u64,
     pub ins: usize,
-    #[serde(rename = "maxfee", with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(rename = "maxfee", with = "::serde_amount::as_sat")]
     pub max_fee: Amount,
-    #[serde(rename = "maxfeerate", with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(rename = "maxfeerate", with = "::serde_amount::as_sat")]
     pub max_fee_rate: Amount,
     #[serde(rename = "maxtxsize")]
     pub max_tx_size: u32,
-    #[serde(rename = "medianfee", with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(rename = "medianfee", with = "::serde_amount::as_sat")]
     pub median_fee: Amount,
     #[serde(rename = "mediantime")]
     pub median_time: u64,
     #[serde(rename = "mediantxsize")]
     pub median_tx_size: u32,
-    #[serde(rename = "minfee", with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(rename = "minfee", with = "::serde_amount::as_sat")]
     pub min_fee: Amount,
-    #[serde(rename = "minfeerate", with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(rename = "minfeerate", with = "::serde_amount::as_sat")]
     pub min_fee_rate: Amount,
     #[serde(rename = "mintxsize")]
     pub min_tx_size: u32,
     pub outs: usize,
-    #[serde(with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(with = "::serde_amount::as_sat")]
     pub subsidy: Amount,
     #[serde(rename = "swtotal_size")]
     pub sw_total_size: usize,
@@ -272,11 +590,11 @@ pub struct GetBlockStatsResult {
     #[serde(rename = "swtxs")]
     pub sw_txs: usize,
     pub time: u64,
-    #[serde(with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(with = "::serde_amount::as_sat")]
     pub total_out: Amount,
     pub total_size: usize,
     pub total_weight: usize,
-    #[serde(rename = "totalfee", with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(rename = "totalfee", with = "::serde_amount::as_sat")]
     pub total_fee: Amount,
     pub txs: usize,
     pub utxo_increase: i32,
@@ -288,14 +606,14 @@ pub struct GetBlockStatsResultPartial {
     #[serde(
         default,
         rename = "avgfee",
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub avg_fee: Option<Amount>,
     #[serde(
         default,
         rename = "avgfeerate",
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub avg_fee_rate: Option<Amount>,
@@ -312,14 +630,14 @@ pub struct GetBlockStatsResultPartial {
     #[serde(
         default,
         rename = "maxfee",
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub max_fee: Option<Amount>,
     #[serde(
         default,
         rename = "maxfeerate",
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub max_fee_rate: Option<Amount>,
@@ -328,7 +646,7 @@ pub struct GetBlockStatsResultPartial {
     #[serde(
         default,
         rename = "medianfee",
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub median_fee: Option<Amount>,
@@ -339,14 +657,14 @@ pub struct GetBlockStatsResultPartial {
     #[serde(
         default,
         rename = "minfee",
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub min_fee: Option<Amount>,
     #[serde(
         default,
         rename = "minfeerate",
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub min_fee_rate: Option<Amount>,
@@ -356,7 +674,7 @@ pub struct GetBlockStatsResultPartial {
     pub outs: Option<usize>,
     #[serde(
         default,
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub subsidy: Option<Amount>,
@@ -370,7 +688,7 @@ pub struct GetBlockStatsResultPartial {
     pub time: Option<u64>,
     #[serde(
         default,
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub total_out: Option<Amount>,
@@ -381,7 +699,7 @@ pub struct GetBlockStatsResultPartial {
     #[serde(
         default,
         rename = "totalfee",
-        with = "dashcore::util::amount::serde::as_sat::opt",
+        with = "::serde_amount::as_sat::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub total_fee: Option<Amount>,
@@ -395,15 +713,15 @@ pub struct GetBlockStatsResultPartial {
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct FeeRatePercentiles {
-    #[serde(with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(with = "::serde_amount::as_sat")]
     pub fr_10th: Amount,
-    #[serde(with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(with = "::serde_amount::as_sat")]
     pub fr_25th: Amount,
-    #[serde(with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(with = "::serde_amount::as_sat")]
     pub fr_50th: Amount,
-    #[serde(with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(with = "::serde_amount::as_sat")]
     pub fr_75th: Amount,
-    #[serde(with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(with = "::serde_amount::as_sat")]
     pub fr_90th: Amount,
 }
 
@@ -503,6 +821,10 @@ pub struct GetMiningInfoResult {
     pub pooled_tx: usize,
     pub chain: String,
     pub warnings: String,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
@@ -547,27 +869,117 @@ impl GetRawTransactionResultVin {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", from = "ScriptPubKeyFields")]
 pub struct GetRawTransactionResultVoutScriptPubKey {
     pub asm: String,
-    #[serde(with = "::serde_hex")]
-    pub hex: Vec<u8>,
+    pub hex: Script,
     pub req_sigs: Option<usize>,
     #[serde(rename = "type")]
     pub type_: Option<ScriptPubkeyType>,
     pub addresses: Option<Vec<Address>>,
+    /// The script classified by its template, see [ScriptKind].
+    #[serde(skip_serializing)]
+    pub kind: ScriptKind,
+}
+
+/// The fields of a `scriptPubKey` object as sent by the node.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScriptPubKeyFields {
+    asm: String,
+    hex: Script,
+    req_sigs: Option<usize>,
+    #[serde(rename = "type")]
+    type_: Option<ScriptPubkeyType>,
+    addresses: Option<Vec<Address>>,
+}
+
+impl From<ScriptPubKeyFields> for GetRawTransactionResultVoutScriptPubKey {
+    fn from(fields: ScriptPubKeyFields) -> GetRawTransactionResultVoutScriptPubKey {
+        let kind = {
+            let address = match fields.addresses {
+                Some(ref addresses) if addresses.len() == 1 => Some(&addresses[0]),
+                _ => None,
+            };
+            ScriptKind::with_address(&fields.hex, address)
+        };
+        GetRawTransactionResultVoutScriptPubKey {
+            asm: fields.asm,
+            hex: fields.hex,
+            req_sigs: fields.req_sigs,
+            type_: fields.type_,
+            addresses: fields.addresses,
+            kind,
+        }
+    }
 }
 
 impl GetRawTransactionResultVoutScriptPubKey {
     pub fn script(&self) -> Result<Script, encode::Error> {
-        Ok(Script::from(self.hex.clone()))
+        Ok(self.hex.clone())
+    }
+}
+
+/// A `scriptPubKey` classified by its template.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ScriptKind {
+    /// Pay to public key hash, with the address it pays to.
+    P2pkh(Address),
+    /// Pay to script hash, with the address it pays to.
+    P2sh(Address),
+    /// An unspendable output, with the data pushed after the `OP_RETURN`.
+    OpReturn(Vec<u8>),
+    /// Any other script, like a pay to public key or a bare multisig.
+    Nonstandard,
+}
+
+impl ScriptKind {
+    /// Classify `script`, the addresses being those of `network`.
+    ///
+    /// Returns `None` if no address of `network` can be built for a P2PKH
+    /// or P2SH script.
+    pub fn from_script(script: &Script, network: dashcore::Network) -> Option<ScriptKind> {
+        if script.is_p2pkh() || script.is_p2sh() {
+            let address = Address::from_script(script, network)?;
+            Some(ScriptKind::with_address(script, Some(&address)))
+        } else {
+            Some(ScriptKind::with_address(script, None))
+        }
+    }
+
+    /// Classify `script` paying to `address`, as reported by the node.
+    ///
+    /// A P2PKH or P2SH script without an address is [ScriptKind::Nonstandard].
+    fn with_address(script: &Script, address: Option<&Address>) -> ScriptKind {
+        match address {
+            Some(address) if script.is_p2pkh() => ScriptKind::P2pkh(address.clone()),
+            Some(address) if script.is_p2sh() => ScriptKind::P2sh(address.clone()),
+            _ if script.is_op_return() => {
+                let mut data = Vec::new();
+                for instruction in script.instructions().skip(1) {
+                    if let Ok(Instruction::PushBytes(bytes)) = instruction {
+                        data.extend_from_slice(bytes);
+                    }
+                }
+                ScriptKind::OpReturn(data)
+            }
+            _ => ScriptKind::Nonstandard,
+        }
+    }
+
+    /// The address the script pays to, if it has one.
+    pub fn address(&self) -> Option<&Address> {
+        match *self {
+            ScriptKind::P2pkh(ref address) | ScriptKind::P2sh(ref address) => Some(address),
+            _ => None,
+        }
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetRawTransactionResultVout {
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub value: Amount,
     pub n: u32,
     pub script_pub_key: GetRawTransactionResultVoutScriptPubKey,
@@ -585,13 +997,101 @@ pub struct GetRawTransactionResult {
     pub size: usize,
     pub vsize: usize,
     pub version: u32,
+    /// The special transaction type, 0 for normal transactions
+    #[serde(rename = "type", default)]
+    pub tx_type: u16,
     pub locktime: u32,
     pub vin: Vec<GetRawTransactionResultVin>,
     pub vout: Vec<GetRawTransactionResultVout>,
+    /// The special transaction payload
+    #[serde(default, with = "::serde_hex::opt")]
+    pub extra_payload: Option<Vec<u8>>,
     pub blockhash: Option<dashcore::BlockHash>,
     pub confirmations: Option<u32>,
     pub time: Option<usize>,
     pub blocktime: Option<usize>,
+    /// Whether the transaction is locked by InstantSend or by a ChainLock
+    pub instantlock: Option<bool>,
+    /// Whether the transaction is locked by InstantSend
+    pub instantlock_internal: Option<bool>,
+    /// Whether the block containing the transaction is ChainLocked
+    pub chainlock: Option<bool>,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl GetRawTransactionResult {
+    /// Combine the confirmation and lock flags into a single status.
+    pub fn confirmation_status(&self) -> ConfirmationStatus {
+        let confirmations = self.confirmations.unwrap_or(0);
+        let instant_locked = self.instantlock_internal.or(self.instantlock).unwrap_or(false);
+        if confirmations > 0 && self.chainlock == Some(true) {
+            ConfirmationStatus::ChainLocked {
+                confirmations,
+            }
+        } else if confirmations > 0 {
+            ConfirmationStatus::Confirmed {
+                confirmations,
+                instant_locked,
+            }
+        } else if instant_locked {
+            ConfirmationStatus::InstantLocked
+        } else {
+            ConfirmationStatus::Unconfirmed
+        }
+    }
+
+    /// Decode the payload of a coinbase special transaction.
+    ///
+    /// Returns `None` if this is not a coinbase special transaction.
+    pub fn cb_tx_payload(&self) -> Option<Result<CbTx, encode::Error>> {
+        match (self.tx_type, &self.extra_payload) {
+            (TRANSACTION_TYPE_COINBASE, &Some(ref p)) => Some(CbTx::from_bytes(p)),
+            _ => None,
+        }
+    }
+
+    /// Decode the payload of an asset lock transaction.
+    ///
+    /// Returns `None` if this is not an asset lock transaction.
+    pub fn asset_lock_payload(&self) -> Option<Result<AssetLockPayload, encode::Error>> {
+        match (self.tx_type, &self.extra_payload) {
+            (TRANSACTION_TYPE_ASSET_LOCK, &Some(ref p)) => Some(AssetLockPayload::from_bytes(p)),
+            _ => None,
+        }
+    }
+
+    /// Decode the payload of an asset unlock transaction.
+    ///
+    /// Returns `None` if this is not an asset unlock transaction.
+    pub fn asset_unlock_payload(&self) -> Option<Result<AssetUnlockPayload, encode::Error>> {
+        match (self.tx_type, &self.extra_payload) {
+            (TRANSACTION_TYPE_ASSET_UNLOCK, &Some(ref p)) => {
+                Some(AssetUnlockPayload::from_bytes(p))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The status of an index, an entry of the result of "getindexinfo"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct IndexInfo {
+    /// Whether the index caught up with the chain tip
+    pub synced: bool,
+    /// The height of the last block in the index
+    pub best_block_height: u64,
+}
+
+/// The type of a compact block filter, only BIP158 basic filters exist
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockFilterType {
+    /// The BIP158 filter of the output scripts and the scripts spent by the
+    /// inputs of a block
+    Basic,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
@@ -615,6 +1115,36 @@ impl GetBlockFilterResult {
             content: self.filter,
         }
     }
+
+    /// Whether any of `scripts` is in the filter of the block `block_hash`.
+    ///
+    /// As filters are probabilistic, a match can be a false positive, but
+    /// a script of the block always matches.
+    pub fn match_any_script<'a, I>(
+        &self,
+        block_hash: &dashcore::BlockHash,
+        scripts: I,
+    ) -> Result<bool, bip158::Error>
+    where
+        I: IntoIterator<Item = &'a Script>,
+    {
+        let mut query = scripts.into_iter().map(|s| s.as_bytes());
+        bip158::BlockFilterReader::new(block_hash).match_any(&mut &self.filter[..], &mut query)
+    }
+
+    /// Whether all of `scripts` are in the filter of the block `block_hash`,
+    /// see [GetBlockFilterResult::match_any_script].
+    pub fn match_all_scripts<'a, I>(
+        &self,
+        block_hash: &dashcore::BlockHash,
+        scripts: I,
+    ) -> Result<bool, bip158::Error>
+    where
+        I: IntoIterator<Item = &'a Script>,
+    {
+        let mut query = scripts.into_iter().map(|s| s.as_bytes());
+        bip158::BlockFilterReader::new(block_hash).match_all(&mut &self.filter[..], &mut query)
+    }
 }
 
 impl GetRawTransactionResult {
@@ -652,13 +1182,16 @@ pub enum GetTransactionResultDetailCategory {
 pub struct GetTransactionResultDetail {
     pub address: Option<Address>,
     pub category: GetTransactionResultDetailCategory,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub amount: SignedAmount,
     pub label: Option<String>,
     pub vout: u32,
-    #[serde(default, with = "dashcore::util::amount::serde::as_btc::opt")]
+    #[serde(default, with = "::serde_amount::as_btc::opt")]
     pub fee: Option<SignedAmount>,
     pub abandoned: Option<bool>,
+    /// Whether the output or input is of a watch-only address
+    #[serde(default, rename = "involvesWatchonly")]
+    pub involved_watch_only: bool,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
@@ -676,19 +1209,27 @@ pub struct WalletTxInfo {
     /// Conflicting transaction ids
     #[serde(rename = "walletconflicts")]
     pub wallet_conflicts: Vec<dashcore::Txid>,
+    /// Whether the transaction is locked by InstantSend or by a ChainLock
+    pub instantlock: Option<bool>,
+    /// Whether the block containing the transaction is ChainLocked
+    pub chainlock: Option<bool>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
 pub struct GetTransactionResult {
     #[serde(flatten)]
     pub info: WalletTxInfo,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub amount: SignedAmount,
-    #[serde(default, with = "dashcore::util::amount::serde::as_btc::opt")]
+    #[serde(default, with = "::serde_amount::as_btc::opt")]
     pub fee: Option<SignedAmount>,
     pub details: Vec<GetTransactionResultDetail>,
     #[serde(with = "::serde_hex")]
     pub hex: Vec<u8>,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 impl GetTransactionResult {
@@ -708,11 +1249,20 @@ pub struct ListTransactionResult {
     pub comment: Option<String>,
 }
 
+/// Models the result of "listsinceblock"
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
 pub struct ListSinceBlockResult {
+    /// The transactions in the blocks after the given block or in the
+    /// mempool
     pub transactions: Vec<ListTransactionResult>,
+    /// The transactions of the blocks that were disconnected by a
+    /// reorganization since the given block, only listed with
+    /// `include_removed`. Those that were mined again are also in
+    /// `transactions`.
     #[serde(default)]
     pub removed: Vec<ListTransactionResult>,
+    /// The hash of the block `target_confirmations` - 1 from the tip, to
+    /// pass to the next call
     pub lastblock: dashcore::BlockHash,
 }
 
@@ -721,24 +1271,31 @@ pub struct ListSinceBlockResult {
 pub struct GetTxOutResult {
     pub bestblock: dashcore::BlockHash,
     pub confirmations: u32,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub value: Amount,
     pub script_pub_key: GetRawTransactionResultVoutScriptPubKey,
     pub coinbase: bool,
 }
 
+impl GetTxOutResult {
+    /// The script of the output classified by its template, see [ScriptKind].
+    pub fn script_kind(&self) -> &ScriptKind {
+        &self.script_pub_key.kind
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ListUnspentQueryOptions {
     #[serde(
         rename = "minimumAmount",
-        with = "dashcore::util::amount::serde::as_btc::opt",
+        with = "::serde_amount::as_btc::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub minimum_amount: Option<Amount>,
     #[serde(
         rename = "maximumAmount",
-        with = "dashcore::util::amount::serde::as_btc::opt",
+        with = "::serde_amount::as_btc::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub maximum_amount: Option<Amount>,
@@ -746,12 +1303,43 @@ pub struct ListUnspentQueryOptions {
     pub maximum_count: Option<usize>,
     #[serde(
         rename = "minimumSumAmount",
-        with = "dashcore::util::amount::serde::as_btc::opt",
+        with = "::serde_amount::as_btc::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub minimum_sum_amount: Option<Amount>,
 }
 
+impl ListUnspentQueryOptions {
+    /// Options that don't restrict the outputs.
+    pub fn new() -> ListUnspentQueryOptions {
+        Default::default()
+    }
+
+    /// Only list outputs of at least `amount`.
+    pub fn minimum_amount(mut self, amount: Amount) -> Self {
+        self.minimum_amount = Some(amount);
+        self
+    }
+
+    /// Only list outputs of at most `amount`.
+    pub fn maximum_amount(mut self, amount: Amount) -> Self {
+        self.maximum_amount = Some(amount);
+        self
+    }
+
+    /// List at most `count` outputs.
+    pub fn maximum_count(mut self, count: usize) -> Self {
+        self.maximum_count = Some(count);
+        self
+    }
+
+    /// Stop listing outputs once they add up to at least `amount`.
+    pub fn minimum_sum_amount(mut self, amount: Amount) -> Self {
+        self.minimum_sum_amount = Some(amount);
+        self
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListUnspentResultEntry {
@@ -762,7 +1350,7 @@ pub struct ListUnspentResultEntry {
     pub redeem_script: Option<Script>,
     pub witness_script: Option<Script>,
     pub script_pub_key: Script,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub amount: Amount,
     pub confirmations: u32,
     pub spendable: bool,
@@ -772,13 +1360,20 @@ pub struct ListUnspentResultEntry {
     pub safe: bool,
 }
 
+impl ListUnspentResultEntry {
+    /// Classify the script of the output, see [ScriptKind].
+    pub fn script_kind(&self) -> ScriptKind {
+        ScriptKind::with_address(&self.script_pub_key, self.address.as_ref())
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListReceivedByAddressResult {
     #[serde(default, rename = "involvesWatchonly")]
     pub involved_watch_only: bool,
     pub address: Address,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub amount: Amount,
     pub confirmations: u32,
     pub label: String,
@@ -827,7 +1422,7 @@ pub struct TestMempoolAcceptResult {
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct TestMempoolAcceptResultFees {
     /// Transaction fee in BTC
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub base: Amount,
     // unlike GetMempoolEntryResultFees, this only has the `base` fee
 }
@@ -952,6 +1547,9 @@ pub struct GetAddressInfoResult {
     pub is_mine: Option<bool>,
     #[serde(rename = "iswatchonly")]
     pub is_watchonly: Option<bool>,
+    /// Whether the address is a change address of the wallet
+    #[serde(rename = "ischange")]
+    pub is_change: Option<bool>,
     #[serde(rename = "isscript")]
     pub is_script: Option<bool>,
     #[serde(rename = "iswitness")]
@@ -976,12 +1574,33 @@ pub struct GetAddressInfoResult {
     pub hd_key_path: Option<bip32::DerivationPath>,
     #[serde(rename = "hdseedid")]
     pub hd_seed_id: Option<dashcore::XpubIdentifier>,
+    /// The fingerprint of the master key of `hd_key_path`.
+    #[serde(rename = "hdmasterfingerprint")]
+    pub hd_master_fingerprint: Option<bip32::Fingerprint>,
     pub labels: Vec<GetAddressInfoResultLabel>,
     /// Deprecated in v0.20.0. See `labels` field instead.
     #[deprecated(note = "since Core v0.20.0")]
     pub label: Option<String>,
 }
 
+/// Models the result of "validateaddress"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct ValidateAddressResult {
+    #[serde(rename = "isvalid")]
+    pub is_valid: bool,
+    /// The other fields are only set for a valid address.
+    pub address: Option<Address>,
+    #[serde(rename = "scriptPubKey")]
+    pub script_pub_key: Option<Script>,
+    #[serde(rename = "isscript")]
+    pub is_script: Option<bool>,
+    #[serde(rename = "iswitness")]
+    pub is_witness: Option<bool>,
+    pub witness_version: Option<u32>,
+    #[serde(default, with = "::serde_hex::opt")]
+    pub witness_program: Option<Vec<u8>>,
+}
+
 /// Models the result of "getblockchaininfo"
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GetBlockchainInfoResult {
@@ -1024,6 +1643,10 @@ pub struct GetBlockchainInfoResult {
     pub softforks: HashMap<String, Softfork>,
     /// Any network and blockchain warnings.
     pub warnings: String,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -1056,8 +1679,8 @@ pub struct GetMempoolEntryResult {
     /// Virtual transaction size of in-mempool ancestors (including this one)
     #[serde(rename = "ancestorsize")]
     pub ancestor_size: u64,
-    /// Hash of serialized transaction, including witness data
-    pub wtxid: dashcore::Txid,
+    /// Hash of serialized transaction, including witness data. Not returned by Dash Core.
+    pub wtxid: Option<dashcore::Txid>,
     /// Fee information
     pub fees: GetMempoolEntryResultFees,
     /// Unconfirmed transactions used as inputs for this transaction
@@ -1066,26 +1689,31 @@ pub struct GetMempoolEntryResult {
     #[serde(rename = "spentby")]
     pub spent_by: Vec<dashcore::Txid>,
     /// Whether this transaction could be replaced due to BIP125 (replace-by-fee)
-    #[serde(rename = "bip125-replaceable")]
+    /// Not returned by Dash Core, which does not support replace-by-fee.
+    #[serde(rename = "bip125-replaceable", default)]
     pub bip125_replaceable: bool,
     /// Whether this transaction is currently unbroadcast (initial broadcast not yet acknowledged by any peers)
     /// Added in dashcore Core v0.21
     pub unbroadcast: Option<bool>,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct GetMempoolEntryResultFees {
     /// Transaction fee in BTC
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub base: Amount,
     /// Transaction fee with fee deltas used for mining priority in BTC
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub modified: Amount,
     /// Modified fees (see above) of in-mempool ancestors (including this one) in BTC
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub ancestor: Amount,
     /// Modified fees (see above) of in-mempool descendants (including this one) in BTC
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub descendant: Amount,
 }
 
@@ -1317,7 +1945,7 @@ pub struct GetPeerInfoResult {
     /// Whether the peer is whitelisted
     /// Deprecated in Bitcoin Core v0.21
     pub whitelisted: Option<bool>,
-    #[serde(rename = "minfeefilter", default, with = "dashcore::util::amount::serde::as_btc::opt")]
+    #[serde(rename = "minfeefilter", default, with = "::serde_amount::as_btc::opt")]
     pub min_fee_filter: Option<Amount>,
     /// The total bytes sent aggregated by message type
     pub bytessent_per_msg: HashMap<String, u64>,
@@ -1326,6 +1954,10 @@ pub struct GetPeerInfoResult {
     /// The type of the connection
     /// Added in Bitcoin Core v0.21
     pub connection_type: Option<GetPeerInfoResultConnectionType>,
+    /// The fields this version of the library does not know, see
+    /// [the compatibility policy](index.html#compatibility).
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Copy, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
@@ -1389,6 +2021,9 @@ pub struct GetNodeAddressesResult {
     pub address: String,
     /// The port of the node
     pub port: u16,
+    /// The network (ipv4, ipv6 or onion) of the address
+    /// Not returned by older versions
+    pub network: Option<GetPeerInfoResultNetwork>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
@@ -1406,7 +2041,7 @@ pub struct EstimateSmartFeeResult {
         default,
         rename = "feerate",
         skip_serializing_if = "Option::is_none",
-        with = "dashcore::util::amount::serde::as_btc::opt"
+        with = "::serde_amount::as_btc::opt"
     )]
     pub fee_rate: Option<Amount>,
     /// Errors encountered during processing.
@@ -1435,6 +2070,60 @@ pub struct GetDescriptorInfoResult {
     pub has_private_keys: bool,
 }
 
+/// A descriptor to import with "importdescriptors".
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
+pub struct ImportDescriptorsRequest<'a> {
+    /// The descriptor, with its checksum.
+    #[serde(rename = "desc")]
+    pub descriptor: &'a str,
+    /// Whether the descriptor of a descriptor wallet is used for new
+    /// addresses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    /// The range of the child indexes of a ranged descriptor to import.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<[u32; 2]>,
+    /// The child index of the next address of an active ranged descriptor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_index: Option<u32>,
+    /// The time of the oldest key of the descriptor, the rescan starts there.
+    pub timestamp: ImportMultiRescanSince,
+    /// Whether the descriptor is used for change addresses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<&'a str>,
+}
+
+/// Models the result of "listdescriptors"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct ListDescriptorsResult {
+    pub wallet_name: String,
+    pub descriptors: Vec<ListDescriptorsResultDescriptor>,
+}
+
+/// A descriptor of a descriptor wallet.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct ListDescriptorsResultDescriptor {
+    /// The descriptor, with its checksum.
+    #[serde(rename = "desc")]
+    pub descriptor: String,
+    /// The time of the oldest key of the descriptor.
+    pub timestamp: u64,
+    /// Whether the descriptor is used for new addresses.
+    pub active: bool,
+    /// Whether the descriptor is used for change addresses, only given for
+    /// active descriptors.
+    #[serde(default)]
+    pub internal: Option<bool>,
+    /// The range of the child indexes of a ranged descriptor.
+    #[serde(default)]
+    pub range: Option<[u32; 2]>,
+    /// The child index of the next address of a ranged descriptor.
+    #[serde(default)]
+    pub next: Option<u32>,
+}
+
 /// Models the request options of "getblocktemplate"
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct GetBlockTemplateOptions {
@@ -1496,8 +2185,8 @@ pub struct GetBlockTemplateResult {
     /// Block size limit
     #[serde(rename = "sizelimit")]
     pub size_limit: u32,
-    /// Block weight limit
-    #[serde(rename = "weightlimit")]
+    /// Block weight limit, zero on Dash Core which has no block weight
+    #[serde(rename = "weightlimit", default)]
     pub weight_limit: u32,
     /// Block header version
     pub version: u32,
@@ -1531,7 +2220,7 @@ pub struct GetBlockTemplateResult {
     /// (which are counted toward limits, despite not being executed).
     pub coinbaseaux: HashMap<String, String>,
     /// Total funds available for the coinbase
-    #[serde(rename = "coinbasevalue", with = "dashcore::util::amount::serde::as_sat", default)]
+    #[serde(rename = "coinbasevalue", with = "::serde_amount::as_sat", default)]
     pub coinbase_value: Amount,
     /// The number which valid hashes must be less than, in big-endian
     #[serde(with = "::serde_hex")]
@@ -1546,6 +2235,28 @@ pub struct GetBlockTemplateResult {
     /// A range of valid nonces
     #[serde(with = "::serde_hex", rename = "noncerange")]
     pub nonce_range: Vec<u8>,
+    /// The masternode payments the coinbase must make, included in
+    /// `coinbase_value`
+    #[serde(default)]
+    pub masternode: Vec<GetBlockTemplateResultPayment>,
+    /// The superblock payments the coinbase must make, included in
+    /// `coinbase_value`
+    #[serde(default)]
+    pub superblock: Vec<GetBlockTemplateResultPayment>,
+    /// The payload of the coinbase special transaction, empty before DIP3
+    #[serde(default, with = "::serde_hex")]
+    pub coinbase_payload: Vec<u8>,
+}
+
+/// Models a payment the coinbase must make in the result of
+/// "getblocktemplate"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct GetBlockTemplateResultPayment {
+    /// The address paid, empty if the script has none
+    pub payee: String,
+    pub script: Script,
+    #[serde(with = "::serde_amount::as_sat")]
+    pub amount: Amount,
 }
 
 /// Models a single transaction entry in the result of "getblocktemplate"
@@ -1560,11 +2271,12 @@ pub struct GetBlockTemplateResultTransaction {
     #[serde(with = "::serde_hex", rename = "data")]
     pub raw_tx: Vec<u8>,
     // The transaction fee
-    #[serde(with = "dashcore::util::amount::serde::as_sat")]
+    #[serde(with = "::serde_amount::as_sat")]
     pub fee: Amount,
     /// Transaction sigops
     pub sigops: u32,
-    /// Transaction weight in weight units
+    /// Transaction weight in weight units, zero on Dash Core
+    #[serde(default)]
     pub weight: usize,
     /// Transactions that must be in present in the final block if this one is.
     /// Indexed by a 1-based index in the `GetBlockTemplateResult.transactions`
@@ -1607,6 +2319,12 @@ pub enum GetBlockTemplateResultRules {
     /// Indicates that the client must support the Regtest rules when using this
     /// template. TestDummy is a test soft-fork only used on the regtest network.
     Testdummy,
+    /// Indicates that the client must support the DIP3 rules, like the
+    /// coinbase special transaction, when using this template.
+    Dip0003,
+    /// A rule of a deployment this version of the library doesn't know.
+    #[serde(other)]
+    Other,
 }
 
 /// Enum to representing mutable parts of the block template. This does only
@@ -1631,7 +2349,7 @@ pub enum GetBlockTemplateResulMutations {
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct WalletCreateFundedPsbtResult {
     pub psbt: String,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub fee: Amount,
     #[serde(rename = "changepos")]
     pub change_position: i32,
@@ -1664,7 +2382,7 @@ pub struct WalletCreateFundedPsbtOptions {
     #[serde(
         rename = "feeRate",
         skip_serializing_if = "Option::is_none",
-        with = "dashcore::util::amount::serde::as_btc::opt"
+        with = "::serde_amount::as_btc::opt"
     )]
     pub fee_rate: Option<Amount>,
     #[serde(rename = "subtractFeeFromOutputs", skip_serializing_if = "Vec::is_empty")]
@@ -1719,6 +2437,52 @@ pub enum GetChainTipsResultStatus {
     ValidFork,
     /// This is the tip of the active main chain, which is certainly valid
     Active,
+    /// The branch contains a block that conflicts with a ChainLock
+    Conflicting,
+}
+
+/// Models the result of "getbestchainlock"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct GetBestChainLockResult {
+    /// The hash of the ChainLocked block
+    pub blockhash: dashcore::BlockHash,
+    /// The height of the ChainLocked block
+    pub height: u32,
+    /// The BLS signature of the ChainLock
+    #[serde(with = "::serde_hex")]
+    pub signature: Vec<u8>,
+    /// Whether the node has the ChainLocked block
+    pub known_block: bool,
+}
+
+/// The ChainLock status of a transaction, an entry of the result of
+/// "gettxchainlocks"
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct GetTxChainLocksResultEntry {
+    /// The height of the block containing the transaction, -1 if it is not
+    /// mined or not known
+    pub height: i32,
+    /// Whether the block containing the transaction is ChainLocked
+    pub chainlock: bool,
+    /// Whether the transaction is in the mempool
+    pub mempool: bool,
+}
+
+impl GetTxChainLocksResultEntry {
+    /// The height of the block containing the transaction, `None` if it is
+    /// not mined.
+    pub fn block_height(&self) -> Option<u32> {
+        if self.height >= 0 {
+            Some(self.height as u32)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the node knows the transaction, mined or in the mempool.
+    pub fn is_known(&self) -> bool {
+        self.height >= 0 || self.mempool
+    }
 }
 
 impl FinalizePsbtResult {
@@ -1727,6 +2491,112 @@ impl FinalizePsbtResult {
     }
 }
 
+/// How final a transaction is, taking InstantSend and ChainLocks into account.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ConfirmationStatus {
+    /// In the mempool without an InstantSend lock.
+    Unconfirmed,
+    /// In the mempool and locked by InstantSend, so it can't be double-spent.
+    InstantLocked,
+    /// Mined in a block that is not ChainLocked yet.
+    Confirmed {
+        confirmations: u32,
+        /// Whether the transaction was locked by InstantSend before being mined.
+        instant_locked: bool,
+    },
+    /// Mined in a ChainLocked block, so it can't be reorged out.
+    ChainLocked {
+        confirmations: u32,
+    },
+}
+
+impl ConfirmationStatus {
+    /// Whether the transaction can no longer be reversed, either because it
+    /// is InstantSend locked or because its block is ChainLocked.
+    pub fn is_final(&self) -> bool {
+        match *self {
+            ConfirmationStatus::Unconfirmed => false,
+            ConfirmationStatus::InstantLocked => true,
+            ConfirmationStatus::Confirmed {
+                instant_locked,
+                ..
+            } => instant_locked,
+            ConfirmationStatus::ChainLocked {
+                ..
+            } => true,
+        }
+    }
+}
+
+/// A change of the balance of an address by a mined transaction, an entry of
+/// the result of "getaddressdeltas"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct AddressDelta {
+    /// Positive for an output paying the address, negative for an input
+    /// spending from it
+    #[serde(with = "::serde_amount::as_sat")]
+    pub satoshis: SignedAmount,
+    pub txid: dashcore::Txid,
+    /// The index of the output or of the input
+    pub index: u32,
+    /// The index of the transaction in its block
+    pub blockindex: u32,
+    pub height: u32,
+    pub address: Address,
+}
+
+/// A change of the balance of an address by a mempool transaction, an entry
+/// of the result of "getaddressmempool"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct AddressMempoolEntry {
+    pub address: Address,
+    pub txid: dashcore::Txid,
+    /// The index of the output or of the input
+    pub index: u32,
+    /// Positive for an output paying the address, negative for an input
+    /// spending from it
+    #[serde(with = "::serde_amount::as_sat")]
+    pub satoshis: SignedAmount,
+    /// The time the transaction entered the mempool
+    pub timestamp: u64,
+    /// The output spent, for inputs
+    pub prevtxid: Option<dashcore::Txid>,
+    pub prevout: Option<u32>,
+}
+
+/// An unspent output paying an address, an entry of the result of
+/// "getaddressutxos"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressUtxo {
+    pub address: Address,
+    pub txid: dashcore::Txid,
+    pub output_index: u32,
+    pub script: Script,
+    #[serde(with = "::serde_amount::as_sat")]
+    pub satoshis: Amount,
+    /// The height of the block of the transaction
+    pub height: u32,
+}
+
+/// The way a transaction sent by `send_to_address_instant` gets InstantSend locked.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstantSendPath {
+    /// Sent with the legacy "instantsendtoaddress" RPC (Dash Core before v0.15).
+    Legacy,
+    /// Sent with "sendtoaddress", relying on automatic InstantSend.
+    Automatic,
+}
+
+/// The result of `send_to_address_instant`.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct SendResult {
+    pub txid: dashcore::Txid,
+    /// The RPC path that was used to send the transaction
+    pub instant_send: InstantSendPath,
+}
+
 // Custom types for input arguments.
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -1737,6 +2607,48 @@ pub enum EstimateMode {
     Conservative,
 }
 
+/// The verbosity of "getblock" results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum BlockVerbosity {
+    /// The serialized block, hex-encoded.
+    Raw,
+    /// Block information with transaction ids.
+    Info,
+    /// Block information with decoded transactions.
+    Full,
+}
+
+impl serde::Serialize for BlockVerbosity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u8(match *self {
+            BlockVerbosity::Raw => 0,
+            BlockVerbosity::Info => 1,
+            BlockVerbosity::Full => 2,
+        })
+    }
+}
+
+/// The verbosity of "getrawtransaction" results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum TxVerbosity {
+    /// The serialized transaction, hex-encoded.
+    Raw,
+    /// The decoded transaction.
+    Info,
+}
+
+impl serde::Serialize for TxVerbosity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bool(*self == TxVerbosity::Info)
+    }
+}
+
 /// A wrapper around dashcore::EcdsaSighashType that will be serialized
 /// according to what the RPC expects.
 pub struct SigHashType(dashcore::EcdsaSighashType);
@@ -1791,7 +2703,7 @@ pub struct FundRawTransactionOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub lock_unspents: Option<bool>,
     #[serde(
-        with = "dashcore::util::amount::serde::as_btc::opt",
+        with = "::serde_amount::as_btc::opt",
         skip_serializing_if = "Option::is_none"
     )]
     pub fee_rate: Option<Amount>,
@@ -1810,7 +2722,7 @@ pub struct FundRawTransactionOptions {
 pub struct FundRawTransactionResult {
     #[serde(with = "::serde_hex")]
     pub hex: Vec<u8>,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub fee: Amount,
     #[serde(rename = "changepos")]
     pub change_position: i32,
@@ -1818,11 +2730,11 @@ pub struct FundRawTransactionResult {
 
 #[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
 pub struct GetBalancesResultEntry {
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub trusted: Amount,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub untrusted_pending: Amount,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub immature: Amount,
 }
 
@@ -1851,11 +2763,25 @@ pub struct SignRawTransactionInput {
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
-        with = "dashcore::util::amount::serde::as_btc::opt"
+        with = "::serde_amount::as_btc::opt"
     )]
     pub amount: Option<Amount>,
 }
 
+/// Result of the `dumptxoutset` call, the snapshot itself is written to a
+/// file on the node.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct DumpTxOutSetResult {
+    /// The number of coins written to the snapshot
+    pub coins_written: u64,
+    /// The hash of the block the snapshot is at
+    pub base_hash: dashcore::BlockHash,
+    /// The height of the block the snapshot is at
+    pub base_height: u64,
+    /// The full path of the snapshot file
+    pub path: String,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct GetTxOutSetInfoResult {
     /// The current block height (index)
@@ -1875,7 +2801,7 @@ pub struct GetTxOutSetInfoResult {
     /// The estimated size of the chainstate on disk
     pub disk_size: u64,
     /// The total amount
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub total_amount: Amount,
 }
 
@@ -1912,6 +2838,18 @@ pub struct GetNetTotalsResultUploadTarget {
     pub time_left_in_cycle: u64,
 }
 
+/// A ZMQ notification the node publishes, see `getzmqnotifications`.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct GetZmqNotificationsResult {
+    /// Type of notification, like "pubhashblock" or "pubrawtxlock"
+    #[serde(rename = "type")]
+    pub notification_type: String,
+    /// Address of the publisher, like "tcp://127.0.0.1:28332"
+    pub address: String,
+    /// Outbound message high water mark
+    pub hwm: u64,
+}
+
 /// Used to represent an address type.
 #[derive(Copy, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
 #[serde(rename_all = "kebab-case")]
@@ -1952,7 +2890,7 @@ pub struct ScanTxOutResult {
     #[serde(rename = "bestblock")]
     pub best_block_hash: Option<dashcore::BlockHash>,
     pub unspents: Vec<Utxo>,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub total_amount: dashcore::Amount,
 }
 
@@ -1964,7 +2902,7 @@ pub struct Utxo {
     pub script_pub_key: dashcore::Script,
     #[serde(rename = "desc")]
     pub descriptor: String,
-    #[serde(with = "dashcore::util::amount::serde::as_btc")]
+    #[serde(with = "::serde_amount::as_btc")]
     pub amount: dashcore::Amount,
     pub height: u64,
 }
@@ -1984,17 +2922,63 @@ impl<'a> serde::Serialize for PubKeyOrAddress<'a> {
 
 // --------------------------- Masternode -------------------------------
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
-pub struct ProTxHash(
-    #[serde(with = "::serde_hex")]
-    pub Vec<u8>
-);
+/// Defines a newtype of a double SHA256 hash, shown in reverse byte order
+/// like a txid, so hashes of different things can't be mixed up.
+macro_rules! sha256d_newtype {
+    ($name:ident, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(
+            Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug, Deserialize, Serialize,
+        )]
+        pub struct $name(pub sha256d::Hash);
+
+        impl From<sha256d::Hash> for $name {
+            fn from(hash: sha256d::Hash) -> $name {
+                $name(hash)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
 
+        impl FromStr for $name {
+            type Err = dashcore::hashes::hex::Error;
+
+            fn from_str(s: &str) -> Result<$name, Self::Err> {
+                Ok($name(FromHex::from_hex(s)?))
+            }
+        }
+
+        impl Encodable for $name {
+            fn consensus_encode<W: io::Write>(&self, writer: W) -> Result<usize, io::Error> {
+                self.0.consensus_encode(writer)
+            }
+        }
+
+        impl Decodable for $name {
+            fn consensus_decode<D: io::Read>(d: D) -> Result<$name, encode::Error> {
+                Ok($name(Decodable::consensus_decode(d)?))
+            }
+        }
+    };
+}
+
+sha256d_newtype!(ProTxHash, "The hash of the ProRegTx that registered a masternode.");
+sha256d_newtype!(QuorumHash, "The hash of a quorum, the hash of the block it was formed at.");
+
+/// The result of registering a masternode with `protx register` and its
+/// variants.
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
-pub struct ProRegTxHash(
-    #[serde(with = "::serde_hex")]
-    pub Vec<u8>
-);
+#[serde(untagged)]
+pub enum ProRegTxHash {
+    /// The hash of the ProRegTx, when it was submitted.
+    Hash(ProTxHash),
+    /// The serialized ProRegTx, when it was not submitted.
+    Transaction(#[serde(with = "::serde_hex")] Vec<u8>),
+}
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct GetMasternodeCountResult {
@@ -2029,31 +3013,137 @@ pub struct Masternode {
     pub pubkey_operator: Vec<u8>,
 }
 
-#[serde_as]
+/// The projection returned by "masternode list"
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Deserialize, Serialize)]
+pub enum MasternodeListMode {
+    /// The IP address and port
+    #[serde(rename = "addr")]
+    Addr,
+    /// A space separated summary of all fields
+    #[serde(rename = "full")]
+    Full,
+    /// A space separated summary of the status, payee and address
+    #[serde(rename = "info")]
+    Info,
+    /// All details, see [Masternode]
+    #[serde(rename = "json")]
+    Json,
+    /// The height of the block the masternode was last paid in
+    #[serde(rename = "lastpaidblock")]
+    LastPaidBlock,
+    /// The time the masternode was last paid
+    #[serde(rename = "lastpaidtime")]
+    LastPaidTime,
+    #[serde(rename = "owneraddress")]
+    OwnerAddress,
+    #[serde(rename = "payee")]
+    Payee,
+    #[serde(rename = "pubKeyOperator")]
+    PubKeyOperator,
+    /// Like `Full`, but limited to enabled and recently active masternodes
+    #[serde(rename = "recent")]
+    Recent,
+    #[serde(rename = "status")]
+    Status,
+    #[serde(rename = "votingaddress")]
+    VotingAddress,
+}
+
+/// A single entry of "masternode list", depending on the [MasternodeListMode]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MasternodeListValue {
+    /// Returned for [MasternodeListMode::Json]
+    Details(Masternode),
+    /// Returned for [MasternodeListMode::LastPaidBlock] and [MasternodeListMode::LastPaidTime]
+    Number(u64),
+    /// Returned for all other modes
+    Text(String),
+}
+
+impl MasternodeListValue {
+    pub fn as_details(&self) -> Option<&Masternode> {
+        match *self {
+            MasternodeListValue::Details(ref mn) => Some(mn),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            MasternodeListValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            MasternodeListValue::Text(ref s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// A payee of a masternode in "masternode payments"
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct Payee {
-    #[serde_as(as = "Bytes")]
-    pub address: Vec<u8>,
+    pub address: Address,
     pub script: Script,
-    pub amount: u32,
+    #[serde(with = "::serde_amount::as_sat")]
+    pub amount: Amount,
 }
 
+/// The payment of a masternode in "masternode payments"
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MasternodePayment {
     pub pro_tx_hash: ProTxHash,
-    pub amount: u32,
+    /// The total amount paid to the payees
+    #[serde(with = "::serde_amount::as_sat")]
+    pub amount: Amount,
+    /// The owner and, if it takes a share of the reward, the operator
     pub payees: Vec<Payee>,
 }
 
+/// Models a block of the result of "masternode payments"
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct GetMasternodePaymentsResult {
     pub height: u32,
     #[serde(rename = "blockhash")]
     pub block_hash: dashcore::BlockHash,
-    pub amount: u32,
+    /// The total amount paid to masternodes in the block
+    #[serde(with = "::serde_amount::as_sat")]
+    pub amount: Amount,
     pub masternodes: Vec<MasternodePayment>,
 }
 
+/// Models the result of "masternode current" and "masternode winner", the
+/// masternode expected to be paid in an upcoming block
+#[serde_as]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+pub struct GetMasternodePayeeResult {
+    /// The height of the block the masternode is expected to be paid in
+    pub height: u32,
+    #[serde(rename = "IP:port")]
+    #[serde_as(as = "DisplayFromStr")]
+    pub service: SocketAddr,
+    #[serde(rename = "proTxHash")]
+    pub pro_tx_hash: ProTxHash,
+    /// The collateral of the masternode
+    #[serde(deserialize_with = "deserialize_outpoint")]
+    pub outpoint: dashcore::OutPoint,
+    /// The payout address, `None` if the payout script has no address
+    #[serde(deserialize_with = "deserialize_payee_address")]
+    pub payee: Option<Address>,
+}
+
+/// Models the result of "masternode outputs", the outputs of the wallet that
+/// can be the collateral of a masternode
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+pub struct GetMasternodeOutputsResult(
+    #[serde(deserialize_with = "deserialize_outpoints")] pub Vec<dashcore::OutPoint>,
+);
+
 #[serde_as]
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -2077,6 +3167,15 @@ pub struct DMNState {
     pub payout_address: Vec<u8>,
     #[serde_as(as = "Bytes")]
     pub pub_key_operator: Vec<u8>,
+    /// Only set for Evo masternodes
+    #[serde(rename = "platformNodeID", default, with = "::serde_hex::opt")]
+    pub platform_node_id: Option<Vec<u8>>,
+    /// Only set for Evo masternodes
+    #[serde(rename = "platformP2PPort")]
+    pub platform_p2p_port: Option<u16>,
+    /// Only set for Evo masternodes
+    #[serde(rename = "platformHTTPPort")]
+    pub platform_http_port: Option<u16>,
 }
 
 #[serde(untagged)]
@@ -2101,8 +3200,8 @@ pub struct MasternodeStatus {
     #[serde_as(as = "DisplayFromStr")]
     pub service: SocketAddr,
     pub pro_tx_hash: ProTxHash,
-    #[serde(rename = "collateralHash", with = "::serde_hex")]
-    pub collateral_hash: Vec<u8>,
+    #[serde(rename = "collateralHash")]
+    pub collateral_hash: dashcore::Txid,
     #[serde(rename = "collateralIndex")]
     pub collateral_index: u32,
     #[serde(rename = "dmnState")]
@@ -2124,21 +3223,133 @@ pub struct BLS {
     
 // --------------------------- Quorum -------------------------------
 
-#[serde(untagged)]
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
-pub enum QuorumType{
-    LLMQ_50_60,
-    LLMQ_400_60,
-    LLMQ_400_85,
-    LLMQ_100_67,
-    UNKNOWN,
+/// The type of an LLMQ (long living masternode quorum), which sets its size
+/// and signing threshold.
+///
+/// Quorum calls take the numeric form, results use either the numeric or the
+/// name form like "llmq_50_60", both of which are accepted.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum LLMQType {
+    Llmq50_60,
+    Llmq400_60,
+    Llmq400_85,
+    Llmq100_67,
+    Llmq60_75,
+    Llmq25_67,
+    LlmqTest,
+    LlmqDevnet,
+    LlmqTestV17,
+    LlmqTestDip0024,
+    LlmqTestInstantSend,
+    LlmqDevnetDip0024,
+    LlmqTestPlatform,
+    LlmqDevnetPlatform,
+    /// A type not known to this library. Unknown names are read as
+    /// `Unknown(255)`, the `LLMQ_NONE` of Dash Core, as their number is not
+    /// known.
+    Unknown(u8),
+}
+
+/// The number of Dash Core for no LLMQ type, used for unknown names.
+const LLMQ_NONE: u8 = 0xff;
+
+/// The known types with their numeric and name forms.
+const LLMQ_TYPES: [(LLMQType, u8, &str); 14] = [
+    (LLMQType::Llmq50_60, 1, "llmq_50_60"),
+    (LLMQType::Llmq400_60, 2, "llmq_400_60"),
+    (LLMQType::Llmq400_85, 3, "llmq_400_85"),
+    (LLMQType::Llmq100_67, 4, "llmq_100_67"),
+    (LLMQType::Llmq60_75, 5, "llmq_60_75"),
+    (LLMQType::Llmq25_67, 6, "llmq_25_67"),
+    (LLMQType::LlmqTest, 100, "llmq_test"),
+    (LLMQType::LlmqDevnet, 101, "llmq_devnet"),
+    (LLMQType::LlmqTestV17, 102, "llmq_test_v17"),
+    (LLMQType::LlmqTestDip0024, 103, "llmq_test_dip0024"),
+    (LLMQType::LlmqTestInstantSend, 104, "llmq_test_instantsend"),
+    (LLMQType::LlmqDevnetDip0024, 105, "llmq_devnet_dip0024"),
+    (LLMQType::LlmqTestPlatform, 106, "llmq_test_platform"),
+    (LLMQType::LlmqDevnetPlatform, 107, "llmq_devnet_platform"),
+];
+
+impl LLMQType {
+    /// The name used by Dash Core, `None` for unknown types.
+    pub fn name(self) -> Option<&'static str> {
+        LLMQ_TYPES.iter().find(|t| t.0 == self).map(|t| t.2)
+    }
+
+    /// The type with the given name, `None` for unknown names.
+    pub fn from_name(name: &str) -> Option<LLMQType> {
+        LLMQ_TYPES.iter().find(|t| t.2 == name).map(|t| t.0)
+    }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
-pub struct QuorumHash(
-    #[serde(with = "::serde_hex")]
-    pub Vec<u8>
-);
+impl From<u8> for LLMQType {
+    fn from(n: u8) -> LLMQType {
+        LLMQ_TYPES.iter().find(|t| t.1 == n).map(|t| t.0).unwrap_or(LLMQType::Unknown(n))
+    }
+}
+
+impl From<LLMQType> for u8 {
+    fn from(t: LLMQType) -> u8 {
+        match t {
+            LLMQType::Unknown(n) => n,
+            t => LLMQ_TYPES.iter().find(|k| k.0 == t).map(|k| k.1).expect("all known types"),
+        }
+    }
+}
+
+impl fmt::Display for LLMQType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", u8::from(*self)),
+        }
+    }
+}
+
+impl serde::Serialize for LLMQType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u8((*self).into())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for LLMQType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de;
+        struct Visitor;
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = LLMQType;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "an LLMQ type number or name")
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                if value > u8::max_value() as u64 {
+                    return Err(de::Error::custom(format!("invalid LLMQ type {}", value)));
+                }
+                Ok(LLMQType::from(value as u8))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(LLMQType::from_name(value).unwrap_or(LLMQType::Unknown(LLMQ_NONE)))
+            }
+        }
+        deserializer.deserialize_any(Visitor)
+    }
+}
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct QuorumListResult {
@@ -2165,8 +3376,8 @@ pub struct QuorumMember {
 #[serde(rename_all = "camelCase")]
 pub struct QuorumInfoResult {
     pub height: u32,
-    #[serde(rename = "type", deserialize_with = "deserialize_quorum_type")]
-    pub quorum_type: QuorumType,
+    #[serde(rename = "type")]
+    pub quorum_type: LLMQType,
     pub quorum_hash: QuorumHash,
     pub quorum_index: u32,
     #[serde(with = "::serde_hex")]
@@ -2196,8 +3407,7 @@ pub enum MemberDetail{
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QuorumSessionStatus {
-    #[serde(deserialize_with = "deserialize_quorum_type")]
-    pub llmq_type: QuorumType,
+    pub llmq_type: LLMQType,
     pub quorum_hash: QuorumHash,
     pub quorum_height: u32,
     pub phase: u8,
@@ -2212,14 +3422,13 @@ pub struct QuorumSessionStatus {
     pub received_complaints: MemberDetail,
     pub received_justifications: MemberDetail,
     pub received_premature_commitments: MemberDetail,
-    pub all_members: Option<Vec<QuorumHash>>,
+    pub all_members: Option<Vec<ProTxHash>>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QuorumSession {
-    #[serde(deserialize_with = "deserialize_quorum_type")]
-    pub llmq_type: QuorumType,
+    pub llmq_type: LLMQType,
     pub quorum_index: u32,
     pub status: QuorumSessionStatus,
 }
@@ -2238,8 +3447,7 @@ pub struct QuorumConnectionInfo {
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QuorumConnection {
-    #[serde(deserialize_with = "deserialize_quorum_type")]
-    pub llmq_type: QuorumType,
+    pub llmq_type: LLMQType,
     pub quorum_index: u32,
     pub p_quorum_base_block_index: u32,
     pub quorum_hash: QuorumHash,
@@ -2247,28 +3455,28 @@ pub struct QuorumConnection {
     pub quorum_connections: Vec<QuorumConnectionInfo>
 }
 
-#[serde_as]
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QuorumMinableCommitments {
     pub version: u8,
-    #[serde(deserialize_with = "deserialize_quorum_type")]
-    pub llmq_type: QuorumType,
+    pub llmq_type: LLMQType,
     pub quorum_hash: QuorumHash,
     pub quorum_index: u32,
     pub signers_count: u32,
-    #[serde_as(as = "Bytes")]
+    /// Bitset of the members that signed the commitment
+    #[serde(with = "::serde_hex")]
     pub signers: Vec<u8>,
     pub valid_members_count: u32,
-    #[serde_as(as = "Bytes")]
+    /// Bitset of the members that are valid
+    #[serde(with = "::serde_hex")]
     pub valid_members: Vec<u8>,
-    #[serde_as(as = "Bytes")]
+    #[serde(with = "::serde_hex")]
     pub quorum_public_key: Vec<u8>,
-    #[serde_as(as = "Bytes")]
+    #[serde(with = "::serde_hex")]
     pub quorum_vvec_hash: Vec<u8>,
-    #[serde_as(as = "Bytes")]
+    #[serde(with = "::serde_hex")]
     pub quorum_sig: Vec<u8>,
-    #[serde_as(as = "Bytes")]
+    #[serde(with = "::serde_hex")]
     pub members_sig: Vec<u8>,
 }
 
@@ -2285,8 +3493,7 @@ pub struct QuorumDKGStatus {
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QuorumSignature {
-    #[serde(deserialize_with = "deserialize_quorum_type")]
-    pub llmq_type: QuorumType,
+    pub llmq_type: LLMQType,
     pub quorum_hash: QuorumHash,
     pub quorum_member: Option<u8>,
     #[serde(with = "::serde_hex")]
@@ -2310,8 +3517,8 @@ pub enum QuorumSignResult{
 #[serde(rename_all = "camelCase")]
 pub struct QuorumMemberOf {
     pub height: u32,
-    #[serde(rename = "type", deserialize_with = "deserialize_quorum_type")]
-    pub quorum_type: QuorumType,
+    #[serde(rename = "type")]
+    pub quorum_type: LLMQType,
     pub quorum_hash: QuorumHash,
     #[serde(with = "::serde_hex")]
     pub mined_block: Vec<u8>,
@@ -2331,45 +3538,87 @@ pub struct QuorumMemberOfResult(
 pub struct QuorumSnapshot {
     pub active_quorum_members: Vec<bool>,
     pub mn_skip_list_mode: u8,
-    pub mn_skip_list: Vec<u8>,
+    pub mn_skip_list: Vec<i32>,
 }
 
+/// An entry of the simplified masternode list (SML)
 #[serde_as]
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QuorumMasternodeListItem {
-    #[serde(with = "::serde_hex")]
-    pub pro_reg_tx_hash: Vec<u8>,
-    #[serde(with = "::serde_hex")]
-    pub confirmed_hash: Vec<u8>,
+    /// The entry version, added in Dash Core v19
+    #[serde(rename = "nVersion")]
+    pub version: Option<u16>,
+    /// The masternode type (0 for regular, 1 for Evo), added in Dash Core v19
+    #[serde(rename = "nType")]
+    pub mn_type: Option<u16>,
+    pub pro_reg_tx_hash: ProTxHash,
+    /// The hash of the block that confirmed the ProRegTx, all zeros while
+    /// it is unconfirmed.
+    pub confirmed_hash: dashcore::BlockHash,
     #[serde_as(as = "DisplayFromStr")]
     pub service: SocketAddr,
-    #[serde_as(as = "Bytes")]
+    #[serde(with = "::serde_hex")]
     pub pub_key_operator: Vec<u8>,
-    #[serde_as(as = "Bytes")]
-    pub voting_address: Vec<u8>,
+    pub voting_address: Address,
     pub is_valid: bool,
+    /// Only set for Evo masternodes
+    #[serde(rename = "platformHTTPPort")]
+    pub platform_http_port: Option<u16>,
+    /// Only set for Evo masternodes
+    #[serde(rename = "platformNodeID", default, with = "::serde_hex::opt")]
+    pub platform_node_id: Option<Vec<u8>>,
 }
 
-#[serde_as]
+/// A quorum that was removed in a [MasternodeListDiff]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedQuorum {
+    pub llmq_type: LLMQType,
+    pub quorum_hash: QuorumHash,
+}
+
+/// Models the result of "protx diff", the simplified masternode list delta
+/// between two blocks
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MasternodeListDiff {
     pub base_block_hash: dashcore::BlockHash,
     pub block_hash: dashcore::BlockHash,
-    #[serde_as(as = "Bytes")]
-    pub cb_tx_merkle_tree: Vec<u8>,
-    #[serde_as(as = "Bytes")]
-    pub cb_tx: Vec<u8>,
+    /// The partial merkle tree proving the coinbase transaction, see
+    /// [MasternodeListDiff::cb_tx_merkle_tree]
+    #[serde(rename = "cbTxMerkleTree", with = "::serde_hex")]
+    pub cb_tx_merkle_tree_hex: Vec<u8>,
+    /// The coinbase transaction, see [MasternodeListDiff::cb_tx]
+    #[serde(rename = "cbTx", with = "::serde_hex")]
+    pub cb_tx_hex: Vec<u8>,
     #[serde(rename = "deletedMNs")]
-    pub deleted_mns: Vec<QuorumMasternodeListItem>,
+    pub deleted_mns: Vec<ProTxHash>,
     pub mn_list: Vec<QuorumMasternodeListItem>,
-    pub deleted_quorums: Vec<QuorumMinableCommitments>,
+    pub deleted_quorums: Vec<DeletedQuorum>,
     pub new_quorums: Vec<QuorumMinableCommitments>,
-    #[serde(rename = "merkleRootMNList", with = "::serde_hex")]
-    pub merkle_root_mn_list: Vec<u8>,
-    #[serde(rename = "merkleRootQuorums", with = "::serde_hex")]
-    pub merkle_root_quorums: Vec<u8>,
+    #[serde(rename = "merkleRootMNList")]
+    pub merkle_root_mn_list: sha256d::Hash,
+    #[serde(rename = "merkleRootQuorums")]
+    pub merkle_root_quorums: sha256d::Hash,
+}
+
+impl MasternodeListDiff {
+    /// Decode the coinbase transaction of the target block.
+    pub fn cb_tx(&self) -> Result<Transaction, encode::Error> {
+        encode::deserialize(&self.cb_tx_hex)
+    }
+
+    /// Decode the partial merkle tree proving the coinbase transaction.
+    pub fn cb_tx_merkle_tree(&self) -> Result<PartialMerkleTree, encode::Error> {
+        encode::deserialize(&self.cb_tx_merkle_tree_hex)
+    }
+
+    /// Decode the coinbase payload of the target block, which commits to the
+    /// merkle roots of its masternode list and quorums.
+    pub fn cb_tx_payload(&self) -> Result<CbTx, encode::Error> {
+        CbTx::from_coinbase(&self.cb_tx_hex)
+    }
 }
 
 
@@ -2380,11 +3629,19 @@ pub struct QuorumRotationInfo {
     pub quorum_snapshot_at_h_minus_c: QuorumSnapshot,
     pub quorum_snapshot_at_h_minus_2c: QuorumSnapshot,
     pub quorum_snapshot_at_h_minus_3c: QuorumSnapshot,
+    /// Only present when `extra_share` is set
+    pub quorum_snapshot_at_h_minus_4c: Option<QuorumSnapshot>,
     pub mn_list_diff_tip: MasternodeListDiff,
     pub mn_list_diff_h: MasternodeListDiff,
     pub mn_list_diff_at_h_minus_c: MasternodeListDiff,
     pub mn_list_diff_at_h_minus_2c: MasternodeListDiff,
     pub mn_list_diff_at_h_minus_3c: MasternodeListDiff,
+    /// Only present when `extra_share` is set
+    pub mn_list_diff_at_h_minus_4c: Option<MasternodeListDiff>,
+    /// The last commitment of each quorum index, with its member data
+    #[serde(default)]
+    pub last_commitment_per_index: Vec<QuorumMinableCommitments>,
+    #[serde(default)]
     pub block_hash_list: Vec<dashcore::BlockHash>,
     pub quorum_snapshot_list: Vec<QuorumSnapshot>,
     pub mn_list_diff_list: Vec<MasternodeListDiff>,
@@ -2395,14 +3652,234 @@ pub struct QuorumRotationInfo {
 #[serde(rename_all = "camelCase")]
 pub struct SelectQuorumResult {
     pub quorum_hash: QuorumHash,
-    pub recovery_members: Vec<QuorumHash>
+    pub recovery_members: Vec<ProTxHash>
 }
 
-#[serde(untagged)]
-#[derive(Deserialize)]
-enum IntegerOrString<'a> {
-    Integer(u32),
-    String(&'a str),
+// --------------------------- Credit pool -------------------------------
+
+/// The special transaction type of asset lock transactions
+pub const TRANSACTION_TYPE_ASSET_LOCK: u16 = 8;
+/// The special transaction type of asset unlock transactions
+pub const TRANSACTION_TYPE_ASSET_UNLOCK: u16 = 9;
+/// The special transaction type of coinbase transactions
+pub const TRANSACTION_TYPE_COINBASE: u16 = 5;
+
+/// Decode the special transaction type and the extra payload of a raw
+/// transaction, which `Transaction` leaves out.
+///
+/// Returns a type of 0 and an empty payload for a classic transaction.
+pub fn decode_special_transaction(raw: &[u8]) -> Result<(u16, Vec<u8>), encode::Error> {
+    let mut cursor = io::Cursor::new(raw);
+    let version: u16 = Decodable::consensus_decode(&mut cursor)?;
+    let tx_type: u16 = Decodable::consensus_decode(&mut cursor)?;
+    let _: Vec<dashcore::TxIn> = Decodable::consensus_decode(&mut cursor)?;
+    let _: Vec<TxOut> = Decodable::consensus_decode(&mut cursor)?;
+    let _lock_time: u32 = Decodable::consensus_decode(&mut cursor)?;
+    if version >= 3 && tx_type != 0 {
+        Ok((tx_type, Decodable::consensus_decode(&mut cursor)?))
+    } else {
+        Ok((0, vec![]))
+    }
+}
+
+/// The coinbase special transaction payload, as found in "getblock" results
+/// and in coinbase transactions, see [CbTx::from_bytes]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CbTx {
+    pub version: u16,
+    pub height: u32,
+    /// The merkle root of the simplified masternode list after the block
+    #[serde(rename = "merkleRootMNList")]
+    pub merkle_root_mn_list: sha256d::Hash,
+    /// The merkle root of the active quorums after the block, added in CbTx
+    /// version 2
+    pub merkle_root_quorums: Option<sha256d::Hash>,
+    /// The number of blocks between the parent block and the block of the
+    /// best ChainLock the miner knew, added in CbTx version 3
+    #[serde(default, rename = "bestCLHeightDiff")]
+    pub best_cl_height_diff: Option<u32>,
+    /// The BLS signature of that ChainLock, added in CbTx version 3
+    #[serde(default, rename = "bestCLSignature", with = "::serde_hex::opt")]
+    pub best_cl_signature: Option<Vec<u8>>,
+    /// The balance of the Platform credit pool, added in CbTx version 3
+    #[serde(default, with = "::serde_amount::as_btc::opt")]
+    pub credit_pool_balance: Option<Amount>,
+}
+
+impl CbTx {
+    /// Decode the payload from the extra payload of a coinbase transaction.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CbTx, encode::Error> {
+        let mut cursor = io::Cursor::new(bytes);
+        let version: u16 = Decodable::consensus_decode(&mut cursor)?;
+        let height = Decodable::consensus_decode(&mut cursor)?;
+        let merkle_root_mn_list = Decodable::consensus_decode(&mut cursor)?;
+        let merkle_root_quorums = if version >= 2 {
+            Some(Decodable::consensus_decode(&mut cursor)?)
+        } else {
+            None
+        };
+        let (best_cl_height_diff, best_cl_signature, credit_pool_balance) = if version >= 3 {
+            let diff: encode::VarInt = Decodable::consensus_decode(&mut cursor)?;
+            let mut signature = vec![0; 96];
+            io::Read::read_exact(&mut cursor, &mut signature)?;
+            let balance: i64 = Decodable::consensus_decode(&mut cursor)?;
+            (Some(diff.0 as u32), Some(signature), Some(Amount::from_sat(balance as u64)))
+        } else {
+            (None, None, None)
+        };
+        Ok(CbTx {
+            version,
+            height,
+            merkle_root_mn_list,
+            merkle_root_quorums,
+            best_cl_height_diff,
+            best_cl_signature,
+            credit_pool_balance,
+        })
+    }
+
+    /// Encode the payload as extra payload of a coinbase transaction.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut ret = encode::serialize(&self.version);
+        ret.extend(encode::serialize(&self.height));
+        ret.extend(encode::serialize(&self.merkle_root_mn_list));
+        if self.version >= 2 {
+            ret.extend(encode::serialize(&self.merkle_root_quorums.unwrap_or_default()));
+        }
+        if self.version >= 3 {
+            let diff = self.best_cl_height_diff.unwrap_or(0) as u64;
+            ret.extend(encode::serialize(&encode::VarInt(diff)));
+            match self.best_cl_signature {
+                Some(ref signature) => ret.extend_from_slice(signature),
+                None => ret.extend_from_slice(&[0; 96]),
+            }
+            let balance = self.credit_pool_balance.unwrap_or(Amount::ZERO).as_sat() as i64;
+            ret.extend(encode::serialize(&balance));
+        }
+        ret
+    }
+
+    /// Decode the payload of a raw coinbase transaction.
+    pub fn from_coinbase(raw: &[u8]) -> Result<CbTx, encode::Error> {
+        match decode_special_transaction(raw)? {
+            (TRANSACTION_TYPE_COINBASE, payload) => CbTx::from_bytes(&payload),
+            _ => Err(encode::Error::ParseFailed("not a coinbase special transaction")),
+        }
+    }
+
+    /// The height of the best ChainLock the miner of the block knew, added in
+    /// CbTx version 3.
+    pub fn best_cl_height(&self) -> Option<u32> {
+        self.best_cl_height_diff.map(|diff| self.height.saturating_sub(diff).saturating_sub(1))
+    }
+}
+
+/// The payload of an asset lock transaction, which moves funds from the
+/// core chain into Platform credits
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AssetLockPayload {
+    pub version: u8,
+    /// The outputs credited on Platform
+    pub credit_outputs: Vec<TxOut>,
+}
+
+impl AssetLockPayload {
+    pub fn new(credit_outputs: Vec<TxOut>) -> AssetLockPayload {
+        AssetLockPayload {
+            version: 1,
+            credit_outputs,
+        }
+    }
+
+    /// Decode the payload from the special transaction's extra payload.
+    pub fn from_bytes(bytes: &[u8]) -> Result<AssetLockPayload, encode::Error> {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(AssetLockPayload {
+            version: Decodable::consensus_decode(&mut cursor)?,
+            credit_outputs: Decodable::consensus_decode(&mut cursor)?,
+        })
+    }
+
+    /// Encode the payload as extra payload of a special transaction.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut ret = vec![self.version];
+        ret.extend(encode::serialize(&self.credit_outputs));
+        ret
+    }
+}
+
+/// The payload of an asset unlock transaction, which withdraws Platform
+/// credits back to the core chain
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AssetUnlockPayload {
+    pub version: u8,
+    /// The unique index of the withdrawal
+    pub index: u64,
+    /// The fee for the withdrawal in duffs
+    pub fee: u32,
+    /// The core chain height the withdrawal was requested at
+    pub requested_height: u32,
+    /// The hash of the quorum that signed the withdrawal
+    pub quorum_hash: QuorumHash,
+    /// The BLS signature of the quorum
+    pub quorum_sig: Vec<u8>,
+}
+
+impl AssetUnlockPayload {
+    /// Decode the payload from the special transaction's extra payload.
+    pub fn from_bytes(bytes: &[u8]) -> Result<AssetUnlockPayload, encode::Error> {
+        let mut cursor = io::Cursor::new(bytes);
+        let version = Decodable::consensus_decode(&mut cursor)?;
+        let index = Decodable::consensus_decode(&mut cursor)?;
+        let fee = Decodable::consensus_decode(&mut cursor)?;
+        let requested_height = Decodable::consensus_decode(&mut cursor)?;
+        let quorum_hash = Decodable::consensus_decode(&mut cursor)?;
+        let mut quorum_sig = vec![0; 96];
+        io::Read::read_exact(&mut cursor, &mut quorum_sig)?;
+        Ok(AssetUnlockPayload {
+            version,
+            index,
+            fee,
+            requested_height,
+            quorum_hash,
+            quorum_sig,
+        })
+    }
+
+    /// Encode the payload as extra payload of a special transaction.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut ret = vec![self.version];
+        ret.extend(encode::serialize(&self.index));
+        ret.extend(encode::serialize(&self.fee));
+        ret.extend(encode::serialize(&self.requested_height));
+        ret.extend(encode::serialize(&self.quorum_hash));
+        ret.extend_from_slice(&self.quorum_sig);
+        ret
+    }
+}
+
+/// How far an asset unlock, a withdrawal of Platform credits, got
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetUnlockStatus {
+    /// Mined in a ChainLocked block, so the withdrawal is final
+    Chainlocked,
+    /// Mined in a block that is not ChainLocked yet
+    Mined,
+    /// In the mempool
+    Mempooled,
+    /// Not known to the node
+    Unknown,
+}
+
+/// The status of an asset unlock, an entry of the result of
+/// "getassetunlockstatuses"
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct AssetUnlockStatusResult {
+    /// The index of the withdrawal
+    pub index: u64,
+    pub status: AssetUnlockStatus,
 }
 
 // --------------------------- ProTx -------------------------------
@@ -2434,9 +3911,11 @@ pub struct MetaInfo{
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProTxInfo {
+    /// The masternode type, "Regular" or "Evo", added in Dash Core v19
+    #[serde(rename = "type")]
+    pub mn_type: Option<String>,
     pub pro_tx_hash: ProTxHash,
-    #[serde(with = "::serde_hex")]
-    pub collateral_hash: Vec<u8>,
+    pub collateral_hash: dashcore::Txid,
     pub collateral_index: u32,
     #[serde_as(as = "Bytes")]
     pub collateral_address: Vec<u8>,
@@ -2454,6 +3933,162 @@ pub enum ProTxList{
     Info(Vec<ProTxInfo>)
 }
 
+// --------------------------- Governance -------------------------------
+
+sha256d_newtype!(GovernanceObjectHash, "The hash of a governance object, like a proposal.");
+
+/// Models the result of "gobject check"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct GObjectCheckResult {
+    /// "OK" for a valid object
+    #[serde(rename = "Object status")]
+    pub object_status: String,
+}
+
+/// Models the result of "getgovernanceinfo"
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct GetGovernanceInfoResult {
+    /// The number of yes votes a proposal needs to be funded, above the no
+    /// votes
+    #[serde(rename = "governanceminquorum")]
+    pub governance_min_quorum: u32,
+    /// The collateral a proposal costs
+    #[serde(rename = "proposalfee", with = "::serde_amount::as_btc")]
+    pub proposal_fee: Amount,
+    /// The number of blocks between two superblocks
+    #[serde(rename = "superblockcycle")]
+    pub superblock_cycle: u32,
+    /// The number of blocks before a superblock in which the budget is
+    /// finalized, since Dash Core 0.17
+    #[serde(rename = "superblockmaturitywindow", default)]
+    pub superblock_maturity_window: Option<u32>,
+    #[serde(rename = "lastsuperblock")]
+    pub last_superblock: u32,
+    #[serde(rename = "nextsuperblock")]
+    pub next_superblock: u32,
+    /// The budget paid by the next superblock, since Dash Core 18
+    #[serde(rename = "governancebudget", default, with = "::serde_amount::as_btc::opt")]
+    pub governance_budget: Option<Amount>,
+}
+
+/// Defines a governance vote enum that is shown by its lowercase name.
+macro_rules! vote_enum {
+    (
+        $(#[$attr:meta])*
+        pub enum $name:ident { $($(#[$vattr:meta])* $variant:ident => $str:tt,)* }
+    ) => {
+        $(#[$attr])*
+        #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+        #[serde(rename_all = "lowercase")]
+        pub enum $name {
+            $($(#[$vattr])* $variant,)*
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(match *self {
+                    $($name::$variant => $str,)*
+                })
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<$name, String> {
+                match s {
+                    $($str => Ok($name::$variant),)*
+                    _ => Err(format!("invalid {}: {}", stringify!($name), s)),
+                }
+            }
+        }
+    };
+}
+
+vote_enum! {
+    /// The outcome of a governance vote.
+    pub enum VoteOutcome {
+        None => "none",
+        Yes => "yes",
+        No => "no",
+        Abstain => "abstain",
+    }
+}
+
+vote_enum! {
+    /// What a governance vote is about.
+    pub enum VoteSignal {
+        None => "none",
+        /// Whether the proposal should be paid.
+        Funding => "funding",
+        /// Whether the object is valid.
+        Valid => "valid",
+        /// Whether the object should be deleted.
+        Delete => "delete",
+        Endorsed => "endorsed",
+    }
+}
+
+/// A vote of a masternode as listed by "gobject getcurrentvotes", in the
+/// `<collateral txid>-<vout>:<time>:<outcome>:<signal>` form of the node.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GovernanceVote {
+    /// The collateral of the masternode that voted.
+    pub outpoint: dashcore::OutPoint,
+    pub time: u64,
+    pub outcome: VoteOutcome,
+    pub signal: VoteSignal,
+}
+
+impl fmt::Display for GovernanceVote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}:{}:{}:{}",
+            self.outpoint.txid, self.outpoint.vout, self.time, self.outcome, self.signal
+        )
+    }
+}
+
+impl FromStr for GovernanceVote {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GovernanceVote, String> {
+        let err = || format!("invalid governance vote: {}", s);
+        // Newer nodes append more fields, which are ignored.
+        let fields: Vec<&str> = s.split(':').collect();
+        if fields.len() < 4 {
+            return Err(err());
+        }
+        let outpoint: Vec<&str> = fields[0].split('-').collect();
+        if outpoint.len() != 2 {
+            return Err(err());
+        }
+        Ok(GovernanceVote {
+            outpoint: dashcore::OutPoint {
+                txid: outpoint[0].parse().map_err(|_| err())?,
+                vout: outpoint[1].parse().map_err(|_| err())?,
+            },
+            time: fields[1].parse().map_err(|_| err())?,
+            outcome: fields[2].parse().map_err(|_| err())?,
+            signal: fields[3].parse().map_err(|_| err())?,
+        })
+    }
+}
+
+impl serde::Serialize for GovernanceVote {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for GovernanceVote {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
 // Custom deserializer functions.
 
 /// deserialize_hex_array_opt deserializes a vector of hex-encoded byte arrays.
@@ -2472,25 +4107,47 @@ where
     Ok(Some(res))
 }
 
+/// parse_outpoint parses an outpoint in the `<txid>-<vout>` form of the node
+fn parse_outpoint<E: SerdeError>(s: &str) -> Result<dashcore::OutPoint, E> {
+    let mut parts = s.splitn(2, '-');
+    let txid = parts.next().and_then(|txid| dashcore::Txid::from_hex(txid).ok());
+    let vout = parts.next().and_then(|vout| vout.parse().ok());
+    match (txid, vout) {
+        (Some(txid), Some(vout)) => Ok(dashcore::OutPoint {
+            txid,
+            vout,
+        }),
+        _ => Err(E::custom(format!("invalid outpoint: {}", s))),
+    }
+}
+
 /// deserialize_outpoint deserializes a hex-encoded outpoint
 fn deserialize_outpoint<'de, D>(deserializer: D) -> Result<dashcore::OutPoint, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let str_sequence = String::deserialize(deserializer)?;
-    let str_array: Vec<String> = str_sequence
-        .split('-')
-        .map(|item| item.to_owned())
-        .collect();
+    parse_outpoint(&String::deserialize(deserializer)?)
+}
 
-    let txid: dashcore::Txid = dashcore::Txid::from_hex(&str_array[0]).unwrap();
-    let vout: u32 = str_array[1].parse().unwrap();
+/// deserialize_payee_address deserializes an address the node shows as
+/// `UNKNOWN` when a script has none
+fn deserialize_payee_address<'de, D>(deserializer: D) -> Result<Option<Address>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match String::deserialize(deserializer)?.as_str() {
+        "UNKNOWN" => Ok(None),
+        s => Address::from_str(s).map(Some).map_err(D::Error::custom),
+    }
+}
 
-    let outpoint = dashcore::OutPoint{
-        txid: txid,
-        vout: vout,
-    };
-    Ok(outpoint)
+/// deserialize_outpoints deserializes a vector of outpoints
+fn deserialize_outpoints<'de, D>(deserializer: D) -> Result<Vec<dashcore::OutPoint>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let v: Vec<String> = Vec::deserialize(deserializer)?;
+    v.iter().map(|s| parse_outpoint(s)).collect()
 }
 
 /// deserialize_mn_state deserializes a masternode state
@@ -2515,35 +4172,497 @@ where
 )
 }
 
-/// deserialize_quorum_type deserializes a quorum type
-fn deserialize_quorum_type<'de, D>(deserializer: D) -> Result<QuorumType, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let type_value = IntegerOrString::deserialize(deserializer)?;
-    
-    match type_value {
-        IntegerOrString::Integer(type_value) => {
-            Ok(
-                match type_value {
-                    1 => QuorumType::LLMQ_50_60,
-                    2 => QuorumType::LLMQ_400_60,
-                    3 => QuorumType::LLMQ_400_85,
-                    4 => QuorumType::LLMQ_100_67,
-                    _ => QuorumType::UNKNOWN
-                }
-            )
-        },
-        IntegerOrString::String(type_value) => {
-            Ok(
-                match type_value {
-                    "llmq_50_60" => QuorumType::LLMQ_50_60,
-                    "llmq_400_60" => QuorumType::LLMQ_400_60,
-                    "llmq_400_85" => QuorumType::LLMQ_400_85,
-                    "llmq_100_67" => QuorumType::LLMQ_100_67,
-                    _ => QuorumType::UNKNOWN
-                }
-            )
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Strict {
+        #[serde(with = "::serde_amount::as_btc")]
+        amount: Amount,
+        #[serde(default, with = "::serde_amount::as_btc::opt")]
+        signed: Option<SignedAmount>,
+    }
+
+    #[test]
+    fn test_strict_amounts() {
+        let ok: Strict = serde_json::from_str(r#"{"amount": 1.5, "signed": -2.0}"#).unwrap();
+        assert_eq!(ok.amount, Amount::from_sat(150_000_000));
+        assert_eq!(ok.signed, Some(SignedAmount::from_sat(-200_000_000)));
+
+        assert!(serde_json::from_str::<Strict>(r#"{"amount": -1.0}"#).is_err());
+        assert!(serde_json::from_str::<Strict>(r#"{"amount": 21000001.0}"#).is_err());
+        assert!(serde_json::from_str::<Strict>(r#"{"amount": 1.0, "signed": -21000001.0}"#).is_err());
+
+        assert!(Amount::from_btc_strict(::std::f64::NAN).is_err());
+        assert_eq!(Amount::from_btc_strict(-0.1), Err(AmountError::Negative(-0.1)));
+        match Amount::from_btc_strict(1e-9) {
+            Err(AmountError::Invalid(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
         }
+        assert!(SignedAmount::from_sat_strict(MAX_MONEY_SAT + 1).is_err());
+    }
+
+    #[test]
+    fn test_asset_lock_payloads() {
+        let lock = AssetLockPayload::new(vec![TxOut {
+            value: 100_000,
+            script_pubkey: Script::from(vec![0x6a]),
+        }]);
+        let bytes = lock.to_bytes();
+        assert_eq!(bytes[..2], [1, 1]);
+        assert_eq!(AssetLockPayload::from_bytes(&bytes).unwrap(), lock);
+
+        let unlock = AssetUnlockPayload {
+            version: 1,
+            index: 42,
+            fee: 1000,
+            requested_height: 1200,
+            quorum_hash: Default::default(),
+            quorum_sig: vec![7; 96],
+        };
+        let bytes = unlock.to_bytes();
+        assert_eq!(bytes.len(), 1 + 8 + 4 + 4 + 32 + 96);
+        assert_eq!(AssetUnlockPayload::from_bytes(&bytes).unwrap(), unlock);
+        assert!(AssetUnlockPayload::from_bytes(&bytes[..100]).is_err());
+    }
+
+    #[test]
+    fn test_cb_tx() {
+        use dashcore::hashes::Hash;
+
+        let cb_tx = CbTx {
+            version: 3,
+            height: 1000,
+            merkle_root_mn_list: sha256d::Hash::hash(&[1]),
+            merkle_root_quorums: Some(sha256d::Hash::hash(&[2])),
+            best_cl_height_diff: Some(2),
+            best_cl_signature: Some(vec![7; 96]),
+            credit_pool_balance: Some(Amount::from_sat(5000)),
+        };
+        let payload = cb_tx.to_bytes();
+        assert_eq!(payload.len(), 2 + 4 + 32 + 32 + 1 + 96 + 8);
+        assert_eq!(CbTx::from_bytes(&payload).unwrap(), cb_tx);
+        assert_eq!(cb_tx.best_cl_height(), Some(997));
+
+        // A version 3 coinbase special transaction carrying the payload.
+        let mut raw = vec![3, 0, 5, 0];
+        raw.extend(encode::serialize(&Vec::<dashcore::TxIn>::new()));
+        raw.extend(encode::serialize(&Vec::<TxOut>::new()));
+        raw.extend(encode::serialize(&0u32));
+        raw.extend(encode::serialize(&payload));
+        assert_eq!(decode_special_transaction(&raw).unwrap(), (5, payload.clone()));
+        assert_eq!(CbTx::from_coinbase(&raw).unwrap(), cb_tx);
+        // A classic transaction has no payload.
+        raw[2] = 0;
+        assert_eq!(decode_special_transaction(&raw).unwrap(), (0, vec![]));
+
+        let v1 = CbTx {
+            version: 1,
+            merkle_root_quorums: None,
+            best_cl_height_diff: None,
+            best_cl_signature: None,
+            credit_pool_balance: None,
+            ..cb_tx.clone()
+        };
+        assert_eq!(CbTx::from_bytes(&v1.to_bytes()).unwrap(), v1);
+
+        let json = format!(
+            r#"{{"version": 3, "height": 1000, "merkleRootMNList": "{}",
+                "merkleRootQuorums": "{}", "bestCLHeightDiff": 2, "bestCLSignature": "{}",
+                "creditPoolBalance": 0.00005}}"#,
+            cb_tx.merkle_root_mn_list,
+            cb_tx.merkle_root_quorums.unwrap(),
+            "07".repeat(96)
+        );
+        assert_eq!(serde_json::from_str::<CbTx>(&json).unwrap(), cb_tx);
+    }
+
+    #[test]
+    fn test_hash_newtypes() {
+        let hex = "000000000c9eddd5d2a707281b7e30d5aac974dac600ff10f01937e1ca36066f";
+        let hash: QuorumHash = hex.parse().unwrap();
+        assert_eq!(hash.to_string(), hex);
+        // Shown in reverse byte order, like a block hash.
+        assert_eq!(encode::serialize(&hash)[31], 0);
+        assert_eq!(serde_json::to_string(&hash).unwrap(), format!("\"{}\"", hex));
+
+        let member: QuorumSessionStatusMember =
+            serde_json::from_str(&format!(r#"{{"memberIndex": 1, "proTxHash": "{}"}}"#, hex))
+                .unwrap();
+        assert_eq!(member.pro_tx_hash.0, hash.0);
+        assert!("00".parse::<ProTxHash>().is_err());
+    }
+
+    #[test]
+    fn test_pro_reg_tx_hash() {
+        let hex = "000000000c9eddd5d2a707281b7e30d5aac974dac600ff10f01937e1ca36066f";
+        let submitted: ProRegTxHash = serde_json::from_str(&format!("\"{}\"", hex)).unwrap();
+        assert_eq!(submitted, ProRegTxHash::Hash(hex.parse().unwrap()));
+
+        let tx = format!("\"{}\"", "03000100".repeat(20));
+        match serde_json::from_str::<ProRegTxHash>(&tx).unwrap() {
+            ProRegTxHash::Transaction(bytes) => assert_eq!(bytes.len(), 80),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_masternode_payments() {
+        let txid = "b5d2f3bde4c4e5f2a7d6ae0aa2e1b0f0fbd4fcd1a0e6a9bc2a1b8f0c0d3e4f5a";
+        let outputs: GetMasternodeOutputsResult =
+            serde_json::from_str(&format!(r#"["{}-0", "{}-12"]"#, txid, txid)).unwrap();
+        assert_eq!(outputs.0[1].vout, 12);
+        assert!(serde_json::from_str::<GetMasternodeOutputsResult>(r#"["00-x"]"#).is_err());
+
+        let payments: Vec<GetMasternodePaymentsResult> = serde_json::from_str(&format!(
+            r#"[{{"height": 10, "blockhash": "{}", "amount": 150000000, "masternodes": [
+                {{"proTxHash": "{}", "amount": 150000000, "payees": [{{
+                    "address": "yikEZUFcNj9zzCsvHGPr1SJwwDyF2ZvDgw",
+                    "script": "76a914f602e88b2b5901d8aab15ebe4a97cf92ec6e03b388ac",
+                    "amount": 150000000
+                }}]}}
+            ]}}]"#,
+            txid, txid
+        ))
+        .unwrap();
+        let mn = &payments[0].masternodes[0];
+        assert_eq!(payments[0].amount, Amount::from_sat(150_000_000));
+        assert_eq!(mn.pro_tx_hash.to_string(), txid);
+        assert_eq!(mn.payees[0].amount, mn.amount);
+        assert_eq!(mn.payees[0].address.script_pubkey(), mn.payees[0].script);
+    }
+
+    #[test]
+    fn test_masternode_payee() {
+        let hash = "b5d2f3bde4c4e5f2a7d6ae0aa2e1b0f0fbd4fcd1a0e6a9bc2a1b8f0c0d3e4f5a";
+        let json = format!(
+            r#"{{"height": 1001, "IP:port": "127.0.0.1:19999", "proTxHash": "{}",
+                "outpoint": "{}-1", "payee": "yikEZUFcNj9zzCsvHGPr1SJwwDyF2ZvDgw"}}"#,
+            hash, hash
+        );
+        let payee: GetMasternodePayeeResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(payee.height, 1001);
+        assert_eq!(payee.service, "127.0.0.1:19999".parse().unwrap());
+        assert_eq!(payee.pro_tx_hash.to_string(), hash);
+        assert_eq!(payee.outpoint.vout, 1);
+        assert_eq!(payee.payee.unwrap().to_string(), "yikEZUFcNj9zzCsvHGPr1SJwwDyF2ZvDgw");
+
+        let unknown = json.replace("yikEZUFcNj9zzCsvHGPr1SJwwDyF2ZvDgw", "UNKNOWN");
+        let payee: GetMasternodePayeeResult = serde_json::from_str(&unknown).unwrap();
+        assert_eq!(payee.payee, None);
+        let invalid = json.replace("yikEZUFcNj9zzCsvHGPr1SJwwDyF2ZvDgw", "x");
+        assert!(serde_json::from_str::<GetMasternodePayeeResult>(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_block_header_from_info() {
+        let genesis = dashcore::blockdata::constants::genesis_block(dashcore::Network::Dash).header;
+        let info: GetBlockHeaderResult = serde_json::from_str(&format!(
+            r#"{{"hash": "{}", "confirmations": 1, "height": 0, "version": {},
+                "merkleroot": "{}", "time": {}, "nonce": {}, "bits": "{:08x}",
+                "difficulty": 1.0, "chainwork": "00", "nTx": 1}}"#,
+            genesis.block_hash(),
+            genesis.version,
+            genesis.merkle_root,
+            genesis.time,
+            genesis.nonce,
+            genesis.bits,
+        ))
+        .unwrap();
+        assert_eq!(info.block_header(), Some(genesis));
+
+        let info = GetBlockHeaderResult {
+            bits: "zz".into(),
+            ..info
+        };
+        assert_eq!(info.block_header(), None);
+    }
+
+    #[test]
+    fn test_block_filter_match() {
+        let block = dashcore::blockdata::constants::genesis_block(dashcore::Network::Dash);
+        let hash = block.block_hash();
+        let filter = bip158::BlockFilter::new_script_filter(&block, |o| {
+            Err(bip158::Error::UtxoMissing(*o))
+        })
+        .unwrap();
+        let result = GetBlockFilterResult {
+            header: Default::default(),
+            filter: filter.content,
+        };
+        let script = &block.txdata[0].output[0].script_pubkey;
+        let other = Script::from(vec![0x51]);
+        assert!(result.match_any_script(&hash, vec![&other, script]).unwrap());
+        assert!(!result.match_all_scripts(&hash, vec![&other, script]).unwrap());
+        assert!(result.match_all_scripts(&hash, Some(script)).unwrap());
+        assert!(!result.match_any_script(&hash, Some(&other)).unwrap());
+        assert_eq!(serde_json::to_string(&BlockFilterType::Basic).unwrap(), "\"basic\"");
+    }
+
+    #[test]
+    fn test_list_unspent_query_options() {
+        let options = ListUnspentQueryOptions::new()
+            .minimum_amount(Amount::from_sat(50_000_000))
+            .maximum_count(3)
+            .minimum_sum_amount(Amount::from_sat(200_000_000));
+        assert_eq!(options.maximum_amount, None);
+        assert_eq!(
+            serde_json::to_string(&options).unwrap(),
+            r#"{"minimumAmount":0.5,"maximumCount":3,"minimumSumAmount":2.0}"#
+        );
+        assert_eq!(serde_json::to_string(&ListUnspentQueryOptions::new()).unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_script_kind() {
+        use dashcore::blockdata::opcodes::all::OP_RETURN;
+        use dashcore::blockdata::script::Builder;
+        use dashcore::Network;
+
+        let key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let address = Address::p2pkh(&PublicKey::from_str(key).unwrap(), Network::Dash);
+        let kind = ScriptKind::from_script(&address.script_pubkey(), Network::Dash).unwrap();
+        assert_eq!(kind, ScriptKind::P2pkh(address.clone()));
+        assert_eq!(kind.address(), Some(&address));
+
+        let p2sh = Address::p2sh(&address.script_pubkey(), Network::Dash).unwrap();
+        let kind = ScriptKind::from_script(&p2sh.script_pubkey(), Network::Dash).unwrap();
+        assert_eq!(kind, ScriptKind::P2sh(p2sh));
+
+        let op_return = Builder::new().push_opcode(OP_RETURN).push_slice(b"anchor").into_script();
+        let kind = ScriptKind::from_script(&op_return, Network::Dash).unwrap();
+        assert_eq!(kind, ScriptKind::OpReturn(b"anchor".to_vec()));
+        assert_eq!(kind.address(), None);
+
+        let script_pub_key: GetRawTransactionResultVoutScriptPubKey = serde_json::from_str(
+            r#"{"asm": "", "hex": "51", "type": "nonstandard"}"#,
+        )
+        .unwrap();
+        assert_eq!(script_pub_key.hex, Script::from(vec![0x51]));
+        assert_eq!(script_pub_key.kind, ScriptKind::Nonstandard);
+
+        let hex = address.script_pubkey().as_bytes().to_hex();
+        let json = format!(
+            r#"{{"asm": "", "hex": "{}", "reqSigs": 1, "type": "pubkeyhash",
+                 "addresses": ["{}"]}}"#,
+            hex, address,
+        );
+        let script_pub_key: GetRawTransactionResultVoutScriptPubKey =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(script_pub_key.hex, address.script_pubkey());
+        assert_eq!(script_pub_key.kind, ScriptKind::P2pkh(address.clone()));
+        let reserialized = serde_json::to_string(&script_pub_key).unwrap();
+        assert_eq!(serde_json::from_str(&reserialized).ok(), Some(script_pub_key));
+
+        // Without the address reported by the node the script is not classified.
+        let json = format!(r#"{{"asm": "", "hex": "{}"}}"#, hex);
+        let script_pub_key: GetRawTransactionResultVoutScriptPubKey =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(script_pub_key.kind, ScriptKind::Nonstandard);
+    }
+
+    #[test]
+    fn test_get_node_addresses_result() {
+        let json = r#"[{"time": 1665654321, "services": 1029, "address": "192.0.2.1",
+                        "port": 9999, "network": "ipv4"},
+                       {"time": 1665654322, "services": 1, "address": "2001:db8::1",
+                        "port": 9999}]"#;
+        let addresses: Vec<GetNodeAddressesResult> = serde_json::from_str(json).unwrap();
+        assert_eq!(addresses[0].services, 1029);
+        assert_eq!(addresses[0].network, Some(GetPeerInfoResultNetwork::Ipv4));
+        assert_eq!(addresses[1].address, "2001:db8::1");
+        assert_eq!(addresses[1].network, None);
+    }
+
+    #[test]
+    fn test_get_governance_info_result() {
+        let json = r#"{"governanceminquorum": 10, "proposalfee": 1.00000000,
+                       "superblockcycle": 16616, "superblockmaturitywindow": 1662,
+                       "lastsuperblock": 1895208, "nextsuperblock": 1911824,
+                       "fundingthreshold": 347, "governancebudget": 36815.28540911}"#;
+        let info: GetGovernanceInfoResult = serde_json::from_str(json).unwrap();
+        assert_eq!(info.proposal_fee, Amount::from_sat(100_000_000));
+        assert_eq!(info.next_superblock - info.last_superblock, info.superblock_cycle);
+        assert_eq!(info.governance_budget, Some(Amount::from_sat(3_681_528_540_911)));
+
+        let json = r#"{"governanceminquorum": 1, "proposalfee": 5.00000000,
+                       "superblockcycle": 24, "lastsuperblock": 96, "nextsuperblock": 120}"#;
+        let info: GetGovernanceInfoResult = serde_json::from_str(json).unwrap();
+        assert_eq!(info.superblock_maturity_window, None);
+        assert_eq!(info.governance_budget, None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_list_since_block_result() {
+        let tx = r#"{"category": "receive", "amount": 1.5, "label": "", "vout": 1,
+                     "confirmations": 0,
+                     "txid": "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+                     "time": 1600000000, "timereceived": 1600000000,
+                     "bip125-replaceable": "no", "walletconflicts": [],
+                     "instantlock": false, "chainlock": false, "involvesWatchonly": true}"#;
+        let lastblock = "00".repeat(31) + "0a";
+        let json = format!(
+            r#"{{"transactions": [], "removed": [{}], "lastblock": "{}"}}"#,
+            tx,
+            lastblock
+        );
+        let result: ListSinceBlockResult = serde_json::from_str(&json).unwrap();
+        assert!(result.transactions.is_empty());
+        assert_eq!(result.removed[0].detail.amount, SignedAmount::from_sat(150_000_000));
+        assert_eq!(result.removed[0].info.chainlock, Some(false));
+        assert!(result.removed[0].detail.involved_watch_only);
+
+        let json = format!(r#"{{"transactions": [], "lastblock": "{}"}}"#, lastblock);
+        let result: ListSinceBlockResult = serde_json::from_str(&json).unwrap();
+        assert!(result.removed.is_empty());
+    }
+
+    #[test]
+    fn test_import_descriptors_request() {
+        let request = ImportDescriptorsRequest {
+            descriptor: "wpkh(xpub/0/*)#abcdefgh",
+            active: Some(true),
+            range: Some([0, 99]),
+            timestamp: ImportMultiRescanSince::Now,
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(
+            r#"{"desc": "wpkh(xpub/0/*)#abcdefgh", "active": true, "range": [0, 99],
+                "timestamp": "now"}"#,
+        )
+        .unwrap();
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn test_list_descriptors_result() {
+        let json = r#"{
+            "wallet_name": "descriptors",
+            "descriptors": [
+                {
+                    "desc": "pkh([d34db33f/44'/1'/0']tpub/0/*)#2mflgrsq",
+                    "timestamp": 1650000000,
+                    "active": true,
+                    "internal": false,
+                    "range": [0, 999],
+                    "next": 3
+                },
+                {
+                    "desc": "raw(deadbeef)#89f8spxm",
+                    "timestamp": 1650000001,
+                    "active": false
+                }
+            ]
+        }"#;
+        let result: ListDescriptorsResult = serde_json::from_str(json).unwrap();
+        assert_eq!(result.wallet_name, "descriptors");
+        let ranged = &result.descriptors[0];
+        assert_eq!(ranged.internal, Some(false));
+        assert_eq!(ranged.range, Some([0, 999]));
+        assert_eq!(ranged.next, Some(3));
+        let single = &result.descriptors[1];
+        assert_eq!(single.descriptor, "raw(deadbeef)#89f8spxm");
+        assert!(!single.active);
+        assert_eq!(single.range, None);
+    }
+
+    #[test]
+    fn test_get_tx_chain_locks_result() {
+        let json = r#"[
+            {"height": 120, "chainlock": true, "mempool": false},
+            {"height": -1, "chainlock": false, "mempool": true},
+            {"height": -1, "chainlock": false, "mempool": false}
+        ]"#;
+        let result: Vec<GetTxChainLocksResultEntry> = serde_json::from_str(json).unwrap();
+        assert_eq!(result[0].block_height(), Some(120));
+        assert!(result[0].chainlock);
+        assert_eq!(result[1].block_height(), None);
+        assert!(result[1].is_known());
+        assert!(!result[2].is_known());
+    }
+
+    #[test]
+    fn test_asset_unlock_status_result() {
+        let json = r#"[
+            {"index": 1, "status": "chainlocked"},
+            {"index": 2, "status": "mined"},
+            {"index": 3, "status": "mempooled"},
+            {"index": 4, "status": "unknown"}
+        ]"#;
+        let result: Vec<AssetUnlockStatusResult> = serde_json::from_str(json).unwrap();
+        let statuses: Vec<_> = result.iter().map(|r| (r.index, r.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (1, AssetUnlockStatus::Chainlocked),
+                (2, AssetUnlockStatus::Mined),
+                (3, AssetUnlockStatus::Mempooled),
+                (4, AssetUnlockStatus::Unknown),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extra_fields() {
+        let tx: GetTransactionResult = serde_json::from_str(
+            r#"{"txid": "0000000000000000000000000000000000000000000000000000000000000000",
+                "amount": 1.0, "confirmations": 1, "time": 0, "timereceived": 0,
+                "bip125-replaceable": "no", "walletconflicts": [], "details": [],
+                "hex": "00", "newfield": {"a": 1}}"#,
+        )
+        .unwrap();
+        assert_eq!(tx.info.confirmations, 1);
+        assert_eq!(tx.extra.len(), 1);
+        assert_eq!(tx.extra["newfield"]["a"], 1);
+
+        // Unknown fields survive a round trip.
+        let entry = r#"{"vsize": 1, "time": 0, "height": 1, "descendantcount": 1,
+            "descendantsize": 1, "ancestorcount": 1, "ancestorsize": 1,
+            "fees": {"base": 0.0, "modified": 0.0, "ancestor": 0.0, "descendant": 0.0},
+            "depends": [], "spentby": [], "bip125-replaceable": false, "newfield": true}"#;
+        let entry: GetMempoolEntryResult = serde_json::from_str(entry).unwrap();
+        assert_eq!(entry.extra["newfield"], true);
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(serde_json::from_value::<GetMempoolEntryResult>(json).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_governance_vote() {
+        let txid = "b5d2f3bde4c4e5f2a7d6ae0aa2e1b0f0fbd4fcd1a0e6a9bc2a1b8f0c0d3e4f5a";
+        let s = format!("{}-1:1700000000:yes:funding", txid);
+        let vote: GovernanceVote = serde_json::from_value(s.clone().into()).unwrap();
+        assert_eq!(vote.outpoint.txid.to_string(), txid);
+        assert_eq!(vote.outpoint.vout, 1);
+        assert_eq!(vote.time, 1700000000);
+        assert_eq!(vote.outcome, VoteOutcome::Yes);
+        assert_eq!(vote.signal, VoteSignal::Funding);
+        assert_eq!(vote.to_string(), s);
+        assert_eq!(serde_json::to_value(&vote).unwrap(), s);
+
+        let extra = format!("{}-0:1:abstain:delete:{}", txid, "00".repeat(20));
+        assert_eq!(extra.parse::<GovernanceVote>().unwrap().outcome, VoteOutcome::Abstain);
+        assert!(format!("{}-0:1:maybe:funding", txid).parse::<GovernanceVote>().is_err());
+        assert!(format!("{}:1:yes:funding", txid).parse::<GovernanceVote>().is_err());
+    }
+
+    #[test]
+    fn test_llmq_type() {
+        assert_eq!(LLMQType::from(4), LLMQType::Llmq100_67);
+        assert_eq!(u8::from(LLMQType::LlmqTestInstantSend), 104);
+        assert_eq!(LLMQType::from(200), LLMQType::Unknown(200));
+        assert_eq!(u8::from(LLMQType::Unknown(200)), 200);
+        assert_eq!(LLMQType::LlmqDevnet.to_string(), "llmq_devnet");
+        assert_eq!(LLMQType::Unknown(200).to_string(), "200");
+
+        assert_eq!(serde_json::to_string(&LLMQType::Llmq400_60).unwrap(), "2");
+        let from_number: LLMQType = serde_json::from_str("106").unwrap();
+        assert_eq!(from_number, LLMQType::LlmqTestPlatform);
+        let from_name: LLMQType = serde_json::from_str("\"llmq_test_dip0024\"").unwrap();
+        assert_eq!(from_name, LLMQType::LlmqTestDip0024);
+        let from_unknown_name: LLMQType = serde_json::from_str("\"llmq_1_1\"").unwrap();
+        assert_eq!(from_unknown_name, LLMQType::Unknown(255));
+        assert!(serde_json::from_str::<LLMQType>("256").is_err());
+    }
+}